@@ -0,0 +1,93 @@
+#ifndef PATH_PLANNER_H
+#define PATH_PLANNER_H
+
+/* generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define PLANNER_OK 0
+
+#define PLANNER_NULL_WORLD -1
+
+// the planner failed unexpectedly, the world may be left half planned
+#define PLANNER_PANIC -2
+
+#define PLANNER_GOAL_IN_OBSTACLE 1
+
+// the path was kept but may pass too close to obstacles
+#define PLANNER_NO_CONVERGENCE 2
+
+#define PLANNER_NO_PATH 3
+
+#define PLANNER_MOVING_OBSTACLE 4
+
+#define PLANNER_DEADLINE_MISSED 5
+
+// a robot and the circle obstacles it plans around, without any models loaded from files
+typedef struct PlannerWorld PlannerWorld;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// reads the planner config (robot radius, buffer, planner tuning) from a toml file, must be called
+// before the first world is created, defaults are used without it or when path is null
+//
+// # Safety
+// path must be null or a nul terminated string
+void planner_load_config(const char *path);
+
+// a world with the robot at a start position and no obstacles, free it with `planner_world_destroy`,
+// null if it could not be created
+PlannerWorld *planner_world_create(float start_x, float start_y);
+
+// # Safety
+// world must be null or from `planner_world_create`, it can not be used afterwards
+void planner_world_destroy(PlannerWorld *world);
+
+// moves the robot the next plan starts from
+//
+// # Safety
+// world must be null or a live world from `planner_world_create`
+void planner_set_start(PlannerWorld *world, float x, float y);
+
+// adds a circle obstacle, returning its index or a negative error code
+//
+// # Safety
+// world must be null or a live world from `planner_world_create`
+int32_t planner_add_circle_obstacle(PlannerWorld *world, float x, float y, float radius);
+
+// removes every obstacle
+//
+// # Safety
+// world must be null or a live world from `planner_world_create`
+void planner_clear_obstacles(PlannerWorld *world);
+
+// plans from the start to a target with the gradient planner, returning `PLANNER_OK` or why the path
+// is not safe to drive, a path is still there to fetch unless the world was null
+//
+// # Safety
+// world must be null or a live world from `planner_world_create`
+int32_t planner_plan(PlannerWorld *world, float target_x, float target_y, uintptr_t segments);
+
+// number of points on the last planned path, the spline runs through them
+//
+// # Safety
+// world must be null or a live world from `planner_world_create`
+uintptr_t planner_point_count(const PlannerWorld *world);
+
+// writes up to capacity path points as x, y pairs into points, returning how many were written
+//
+// # Safety
+// world must be null or a live world from `planner_world_create`, points must have room for
+// 2 * capacity floats
+uintptr_t planner_get_points(const PlannerWorld *world, float *points, uintptr_t capacity);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PATH_PLANNER_H */
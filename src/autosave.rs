@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant; // std clocks panic in the browser
+
+use crate::scene::{self, Scene};
+
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+fn autosave_directory() -> PathBuf {
+    std::env::temp_dir().join("object-avoidant-path-gen")
+}
+
+fn autosave_path() -> PathBuf {
+    autosave_directory().join("autosave.json")
+}
+
+// exists while the app runs, left behind when it does not exit cleanly
+fn running_marker_path() -> PathBuf {
+    autosave_directory().join("running")
+}
+
+/// periodically saves the scene to a temp file so it can be restored after a crash
+pub struct Autosave {
+    last_save: Instant,
+    pub recovered: Option<Scene>, // scene from a session that did not exit cleanly, until restored or discarded
+}
+
+impl Autosave {
+    /// picks up the autosave of an unclean previous session and marks this one as running
+    pub fn start() -> Self {
+        let recovered = if running_marker_path().exists() && autosave_path().exists() {
+            match scene::load(autosave_path()) {
+                Ok(scene) => {
+                    println!("Found an autosaved scene from a session that did not exit cleanly");
+                    Some(scene)
+                },
+                Err(e) => {
+                    eprintln!("Failed to read autosave {}: {}", autosave_path().display(), e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
+        let marked = std::fs::create_dir_all(autosave_directory())
+            .and_then(|_| std::fs::write(running_marker_path(), std::process::id().to_string()));
+        if let Err(e) = marked {
+            eprintln!("Failed to create {}: {}", running_marker_path().display(), e);
+        }
+        
+        Self {
+            last_save: Instant::now(),
+            recovered,
+        }
+    }
+    
+    pub fn is_due(&self) -> bool {
+        self.last_save.elapsed() >= AUTOSAVE_INTERVAL
+    }
+    
+    pub fn save(&mut self, scene: &Scene) {
+        self.last_save = Instant::now();
+        if let Err(e) = scene::save(scene, autosave_path()) {
+            eprintln!("Failed to autosave scene to {}: {}", autosave_path().display(), e);
+        }
+    }
+    
+    /// removes the autosave and running marker on a clean exit, there is nothing to recover
+    pub fn finish(&self) {
+        for path in [autosave_path(), running_marker_path()] {
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    eprintln!("Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::obstacle::Obstacle;
+use crate::robot::Robot;
+
+/// when the stepped optimization stops to be looked at
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BreakCondition {
+    Iteration(usize), // once this many iterations have run
+    HeightAbove(f32), // any free path point higher on the obstacle fields than this
+    InsideObstacle, // any path point closer to a hard obstacle than the robot radius
+}
+
+impl BreakCondition {
+    pub const ALL: [BreakCondition; 3] = [BreakCondition::Iteration(100), BreakCondition::HeightAbove(0.5), BreakCondition::InsideObstacle];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            BreakCondition::Iteration(_) => "At Iteration",
+            BreakCondition::HeightAbove(_) => "Height Above",
+            BreakCondition::InsideObstacle => "Inside Obstacle",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Breakpoint {
+    pub condition: BreakCondition,
+    pub enabled: bool,
+}
+
+/// where and why the optimization stopped
+#[derive(Debug, Clone)]
+pub struct BreakpointHit {
+    pub iteration: usize,
+    pub point: usize, // index of the offending path point
+    pub obstacles: Vec<(usize, f32)>, // the obstacles whose fields the point is on, highest first
+    pub reason: String,
+}
+
+/// the first enabled breakpoint the path meets after an iteration, with the path point it is about
+pub fn check(breakpoints: &[Breakpoint], robot: &Robot, obstacles: &[Obstacle], iteration: usize) -> Option<BreakpointHit> {
+    let points = &robot.path_points;
+    if points.len() <= 2 {
+        return None;
+    }
+    // the ends never move, so only the points in between can be offending
+    let highest = (1..points.len() - 1).max_by(|&a, &b| points[a].get_height().total_cmp(&points[b].get_height()))?;
+    
+    for breakpoint in breakpoints.iter().filter(|breakpoint| breakpoint.enabled) {
+        let hit = match breakpoint.condition {
+            BreakCondition::Iteration(at) => (iteration == at)
+                .then(|| (highest, format!("reached iteration {}", at))),
+            BreakCondition::HeightAbove(limit) => (1..points.len() - 1)
+                .find(|&i| !points[i].is_constrained() && points[i].get_height() > limit)
+                .map(|i| (i, format!("point {} is {:.3} high, above {:.3}", i, points[i].get_height(), limit))),
+            BreakCondition::InsideObstacle => (1..points.len() - 1)
+                .find_map(|i| {
+                    let position = points[i].position;
+                    obstacles.iter()
+                        .filter(|obstacle| obstacle.is_hard())
+                        .find(|obstacle| obstacle.edge_distance(position) < robot.footprint_radius())
+                        .map(|obstacle| (i, format!("point {} is inside {}", i, obstacle.name)))
+                }),
+        };
+        
+        if let Some((point, reason)) = hit {
+            return Some(BreakpointHit { iteration, point, obstacles: robot.field_contributions(point, obstacles), reason });
+        }
+    }
+    None
+}
@@ -0,0 +1,154 @@
+use std::f32::consts::PI;
+
+use crate::field::{field_length, field_width};
+use crate::position::{wrap_angle, Position};
+
+pub const BOOKMARK_TRANSITION_TIME: f32 = 0.8;
+pub const DEFAULT_CAMERA_DAMPING: f32 = 0.15; // seconds the camera takes to pick up most of a speed or lose it
+pub const MAX_PITCH: f32 = 89.0 * PI / 180.0; // radians, short of straight up or down where the direction flips
+const FRAMING_PITCH: f32 = -0.9; // radians a framed scene is looked down at
+const FRAMING_MARGIN: f32 = 1.1; // of the distance that just fits the scene in the view
+
+/// position and orientation of the camera
+#[derive(Debug, Copy, Clone)]
+pub struct CameraPose {
+    pub position: Position,
+    pub direction: f32,
+    pub rotation_y: f32,
+}
+
+impl CameraPose {
+    /// interpolates between two poses, taking the short way around for the direction
+    pub fn lerp(&self, other: &CameraPose, t: f32) -> CameraPose {
+        CameraPose {
+            position: Position::new(
+                self.position.x + (other.position.x - self.position.x) * t,
+                self.position.y + (other.position.y - self.position.y) * t,
+                self.position.z + (other.position.z - self.position.z) * t,
+            ),
+            direction: self.direction + wrap_angle(other.direction - self.direction) * t,
+            rotation_y: self.rotation_y + (other.rotation_y - self.rotation_y) * t,
+        }
+    }
+    
+    /// the pose with its direction wrapped into -PI to PI and its tilt kept within a pitch either way, so the
+    /// camera can not tilt over the top and see the world upside down
+    pub fn constrained(&self, max_pitch: f32) -> CameraPose {
+        CameraPose {
+            position: self.position,
+            direction: wrap_angle(self.direction),
+            rotation_y: self.rotation_y.clamp(-max_pitch, max_pitch),
+        }
+    }
+}
+
+/// speeds the camera keeps moving and turning with, easing towards the ones the keys ask for so it glides
+/// to a stop instead of halting the frame a key is let go
+#[derive(Debug, Default, Copy, Clone)]
+pub struct CameraMotion {
+    pub velocity: [f32; 3], // m/s in field coordinates
+    pub turn: [f32; 2], // rad/s of the direction and the tilt
+}
+
+impl CameraMotion {
+    /// eases the speeds towards the wanted ones, a damping of 0 takes them at once
+    pub fn step(&mut self, velocity: [f32; 3], turn: [f32; 2], damping: f32, dt: f32) {
+        let blend = if damping <= 0.0 { 1.0 } else { 1.0 - (-dt / damping).exp() };
+        for (current, wanted) in self.velocity.iter_mut().zip(velocity) {
+            *current += (wanted - *current) * blend;
+        }
+        for (current, wanted) in self.turn.iter_mut().zip(turn) {
+            *current += (wanted - *current) * blend;
+        }
+    }
+    
+    pub fn stop(&mut self) {
+        *self = CameraMotion::default();
+    }
+}
+
+/// pose a distance back from a point, facing it along a direction and tilted down to it by a pitch
+pub fn looking_at(point: Position, distance: f32, direction: f32, rotation_y: f32) -> CameraPose {
+    CameraPose {
+        position: Position::new(
+            point.x - distance * rotation_y.cos() * direction.cos(),
+            point.y - distance * rotation_y.cos() * direction.sin(),
+            point.z - distance * rotation_y.sin(),
+        ),
+        direction,
+        rotation_y,
+    }
+}
+
+/// pose facing a direction that fits a rectangle on the ground into a field of view
+pub fn framing(min: [f32; 2], max: [f32; 2], direction: f32, fov: f32) -> CameraPose {
+    let center = Position::new((min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0, 0.0);
+    let radius = (max[0] - min[0]).hypot(max[1] - min[1]) / 2.0;
+    let distance = radius / (fov / 2.0).tan().max(1e-3) * FRAMING_MARGIN;
+    looking_at(center, distance, direction, FRAMING_PITCH)
+}
+
+pub struct CameraBookmark {
+    pub name: String,
+    pub pose: CameraPose,
+}
+
+impl CameraBookmark {
+    pub fn new(name: &str, pose: CameraPose) -> Self {
+        CameraBookmark {
+            name: name.to_string(),
+            pose,
+        }
+    }
+}
+
+pub fn default_bookmarks() -> Vec<CameraBookmark> {
+    vec![
+        CameraBookmark::new("Blue alliance view", CameraPose {
+            position: Position::new(-3.0, field_width() / 2.0, 4.0),
+            direction: 0.0,
+            rotation_y: -0.55,
+        }),
+        CameraBookmark::new("Red alliance view", CameraPose {
+            position: Position::new(field_length() + 3.0, field_width() / 2.0, 4.0),
+            direction: PI,
+            rotation_y: -0.55,
+        }),
+        CameraBookmark::new("Overhead", CameraPose {
+            position: Position::new(field_length() / 2.0, field_width() / 2.0, 10.0),
+            direction: PI / 2.0,
+            rotation_y: -MAX_PITCH,
+        }),
+    ]
+}
+
+/// animated move of the camera from one pose to another
+pub struct CameraTransition {
+    from: CameraPose,
+    to: CameraPose,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl CameraTransition {
+    pub fn new(from: CameraPose, to: CameraPose, duration: f32) -> Self {
+        CameraTransition {
+            from,
+            to,
+            elapsed: 0.0,
+            duration,
+        }
+    }
+    
+    /// advances the transition and returns the current pose
+    pub fn step(&mut self, dt: f32) -> CameraPose {
+        self.elapsed += dt;
+        let t = if self.duration <= 0.0 { 1.0 } else { (self.elapsed / self.duration).min(1.0) };
+        let eased = t * t * (3.0 - 2.0 * t); // smoothstep
+        self.from.lerp(&self.to, eased)
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
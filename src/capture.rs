@@ -0,0 +1,212 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+use web_time::{Instant, SystemTime, UNIX_EPOCH}; // std clocks panic in the browser
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame};
+
+// how long to wait for nannou to finish writing the captured frames before encoding
+const FRAME_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+/// a new png path in the directory for a single screenshot, creating the directory if needed
+pub fn screenshot_path(directory: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(directory)?;
+    
+    let mut path = Path::new(directory).join(format!("screenshot_{}.png", timestamp()));
+    let mut count = 1;
+    while path.exists() {
+        count += 1;
+        path = Path::new(directory).join(format!("screenshot_{}_{}.png", timestamp(), count));
+    }
+    Ok(path)
+}
+
+fn frame_path(run_directory: &Path, frame: usize) -> PathBuf {
+    run_directory.join(format!("frame_{:05}.png", frame))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VideoFormat {
+    Gif,
+    Mp4,
+}
+
+impl VideoFormat {
+    pub const ALL: [VideoFormat; 2] = [VideoFormat::Gif, VideoFormat::Mp4];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "GIF",
+            VideoFormat::Mp4 => "MP4",
+        }
+    }
+    
+    fn extension(&self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "gif",
+            VideoFormat::Mp4 => "mp4",
+        }
+    }
+}
+
+/// the frames of a finished recording and the rate they were captured at
+pub struct RecordedRun {
+    pub directory: PathBuf,
+    pub frames: usize,
+    pub fps: f32,
+}
+
+/// writes numbered png frames while the robot follows its path, one folder per run
+pub struct FrameRecorder {
+    pub enabled: bool,
+    pub directory: String,
+    pub video_format: Option<VideoFormat>, // encoded from the frames when a run finishes
+    single_run: bool, // stop recording after the current run
+    run_directory: Option<PathBuf>,
+    run_start: Instant,
+    frame: usize,
+}
+
+impl FrameRecorder {
+    pub fn new(directory: &str) -> Self {
+        Self {
+            enabled: false,
+            directory: directory.to_string(),
+            video_format: None,
+            single_run: false,
+            run_directory: None,
+            run_start: Instant::now(),
+            frame: 0,
+        }
+    }
+    
+    /// path for the next frame of the current run, starting a new run folder if none is active
+    pub fn next_frame_path(&mut self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let run_directory = match &self.run_directory {
+            Some(run_directory) => run_directory.clone(),
+            None => {
+                let run_directory = Path::new(&self.directory).join(format!("run_{}", timestamp()));
+                std::fs::create_dir_all(&run_directory)?;
+                println!("Recording frames to {}", run_directory.display());
+                self.run_directory = Some(run_directory.clone());
+                self.run_start = Instant::now();
+                self.frame = 0;
+                run_directory
+            }
+        };
+        
+        self.frame += 1;
+        Ok(frame_path(&run_directory, self.frame))
+    }
+    
+    /// records only the next run and encodes it as a video
+    pub fn record_run(&mut self, format: VideoFormat) {
+        self.enabled = true;
+        self.single_run = true;
+        self.video_format = Some(format);
+    }
+    
+    /// ends the current run, encoding it in the background if a video format is chosen
+    pub fn finish_run(&mut self) -> Option<RecordedRun> {
+        let directory = self.run_directory.take()?;
+        println!("Recorded {} frames to {}", self.frame, directory.display());
+        
+        if self.single_run {
+            self.enabled = false;
+            self.single_run = false;
+        }
+        
+        let elapsed = self.run_start.elapsed().as_secs_f32();
+        let fps = if elapsed > 0.0 { self.frame as f32 / elapsed } else { 30.0 };
+        let run = RecordedRun { directory, frames: self.frame, fps };
+        
+        if let Some(format) = self.video_format {
+            encode_video(&run, format);
+        }
+        Some(run)
+    }
+    
+    pub fn is_recording(&self) -> bool {
+        self.run_directory.is_some()
+    }
+    
+    pub fn frame_count(&self) -> usize {
+        self.frame
+    }
+}
+
+/// encodes the frames of a run into a video next to its folder on a background thread
+pub fn encode_video(run: &RecordedRun, format: VideoFormat) {
+    let output = run.directory.with_extension(format.extension());
+    let (directory, frames, fps) = (run.directory.clone(), run.frames, run.fps);
+    if cfg!(target_arch = "wasm32") {
+        eprintln!("Recordings can not be encoded in the browser");
+        return;
+    }
+    
+    std::thread::spawn(move || {
+        let result = wait_for_frames(&directory, frames).and_then(|_| match format {
+            VideoFormat::Gif => encode_gif(&directory, frames, fps, &output),
+            VideoFormat::Mp4 => encode_mp4(&directory, fps, &output),
+        });
+        
+        match result {
+            Ok(()) => println!("Saved {} recording to {}", format.label(), output.display()),
+            Err(e) => eprintln!("Failed to encode {}: {}", output.display(), e),
+        }
+    });
+}
+
+/// frames are written asynchronously by nannou, so the last ones may not exist yet when a run ends
+fn wait_for_frames(directory: &Path, frames: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    while !(1..=frames).all(|frame| frame_path(directory, frame).exists()) {
+        if start.elapsed() > FRAME_WAIT_TIMEOUT {
+            return Err("timed out waiting for captured frames".into());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    
+    // give the last frames time to be fully written
+    std::thread::sleep(Duration::from_millis(500));
+    Ok(())
+}
+
+fn encode_gif(directory: &Path, frames: usize, fps: f32, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::fs::File::create(output)?;
+    let mut encoder = GifEncoder::new_with_speed(file, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    
+    let delay = Delay::from_numer_denom_ms(1000, fps.round().max(1.0) as u32);
+    for frame in 1..=frames {
+        let image = image::open(frame_path(directory, frame))?.to_rgba8();
+        encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+    }
+    Ok(())
+}
+
+/// mp4 encoding is left to ffmpeg, which has to be installed and on the path
+fn encode_mp4(directory: &Path, fps: f32, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-framerate").arg(format!("{:.2}", fps))
+        .arg("-i").arg(directory.join("frame_%05d.png"))
+        .args(["-vf", "scale=trunc(iw/2)*2:trunc(ih/2)*2", "-pix_fmt", "yuv420p"]) // h264 needs even dimensions
+        .arg(output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("could not run ffmpeg, is it installed? ({})", e))?;
+    
+    if !status.success() {
+        return Err(format!("ffmpeg exited with {}", status).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use clap::Parser;
+
+use crate::planner::Planner;
+
+/// startup options, anything left out falls back to the saved settings
+#[derive(Debug, Parser)]
+#[command(about = "Object avoidant path generation visualizer")]
+pub struct Cli {
+    /// scene (json, ron or yaml) or older field layout json to load on startup
+    #[arg(long)]
+    pub scene: Option<String>,
+    
+    /// window size as WIDTHxHEIGHT, e.g. 1280x720
+    #[arg(long, value_parser = parse_window_size)]
+    pub window: Option<(u32, u32)>,
+    
+    #[arg(long, value_enum, default_value_t = Planner::Gradient)]
+    pub planner: Planner,
+    
+    /// number of path segments
+    #[arg(long, value_parser = parse_segments)]
+    pub segments: Option<usize>,
+    
+    /// start the websocket remote control server on this port
+    #[arg(long)]
+    pub remote_port: Option<u16>,
+}
+
+fn parse_segments(segments: &str) -> Result<usize, String> {
+    let segments = segments.parse::<usize>().map_err(|e| format!("invalid segment count {}: {}", segments, e))?;
+    if segments < 2 {
+        return Err(String::from("the path needs at least 2 segments"));
+    }
+    Ok(segments)
+}
+
+fn parse_window_size(size: &str) -> Result<(u32, u32), String> {
+    let (width, height) = size.split_once('x')
+        .ok_or(format!("expected WIDTHxHEIGHT, got {}", size))?;
+    let width = width.trim().parse::<u32>().map_err(|e| format!("invalid width {}: {}", width, e))?;
+    let height = height.trim().parse::<u32>().map_err(|e| format!("invalid height {}: {}", height, e))?;
+    
+    if width == 0 || height == 0 {
+        return Err(String::from("window size must be greater than zero"));
+    }
+    Ok((width, height))
+}
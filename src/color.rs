@@ -0,0 +1,26 @@
+// colors the planning types carry for drawing. with the `window` feature they are nannou's, which the visualizer
+// draws as they are, and without it a plain rgb triple, so the planner library builds without nannou
+#[cfg(feature = "window")]
+pub use nannou::color::{rgb, Rgb, BLUE, RED, WHITE, YELLOW};
+
+#[cfg(not(feature = "window"))]
+pub use self::plain::*;
+
+#[cfg(not(feature = "window"))]
+mod plain {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+    pub struct Rgb<T = u8> {
+        pub red: T,
+        pub green: T,
+        pub blue: T,
+    }
+    
+    pub const fn rgb<T>(red: T, green: T, blue: T) -> Rgb<T> {
+        Rgb { red, green, blue }
+    }
+    
+    pub const BLUE: Rgb<u8> = rgb(0, 0, 255);
+    pub const RED: Rgb<u8> = rgb(255, 0, 0);
+    pub const WHITE: Rgb<u8> = rgb(255, 255, 255);
+    pub const YELLOW: Rgb<u8> = rgb(255, 255, 0);
+}
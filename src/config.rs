@@ -0,0 +1,372 @@
+use crate::color::Rgb;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+use crate::obstacle::FieldCombination;
+
+pub const CONFIG_PATH: &str = "visualizer.toml";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+static CUSTOM_COLORS: RwLock<Option<ColorConfig>> = RwLock::new(None); // chosen in the ui over the configured ones
+
+/// startup defaults read from `visualizer.toml`, every section and field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub field: FieldConfig,
+    pub robot: RobotConfig,
+    pub planner: PlannerConfig,
+    pub colors: ColorConfig,
+    pub models: ModelsConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FieldConfig {
+    pub width: f32,
+    pub length: f32,
+}
+
+impl Default for FieldConfig {
+    fn default() -> Self {
+        Self {
+            width: 8.23,
+            length: 16.46,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RobotConfig {
+    pub model: String,
+    pub scale: f32,
+    pub radius: f32, // added to every obstacle radius when planning
+    pub buffer: f32, // extra clearance kept on top of the robot radius
+}
+
+impl Default for RobotConfig {
+    fn default() -> Self {
+        Self {
+            model: String::from("robot_base"),
+            scale: 1.0,
+            radius: 0.5,
+            buffer: 0.8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PlannerConfig {
+    pub max_iterations: usize,
+    pub optimization_threshold: f32, // path points higher than this are still inside an obstacle field
+    pub adjust_rate: f32, // gradient step scale, the default learning rate
+    pub min_adjust_rate: f32, // smallest step a point that still needs moving takes
+    pub field_combination: FieldCombination, // how overlapping obstacle fields add up, changed at runtime from the ui
+}
+
+impl Default for PlannerConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 2000,
+            optimization_threshold: 0.001,
+            adjust_rate: 0.001,
+            min_adjust_rate: 0.0001,
+            field_combination: FieldCombination::Sum,
+        }
+    }
+}
+
+/// rgb colors, written as `[r, g, b]`
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorConfig {
+    pub background: [u8; 3],
+    pub robot: [u8; 3],
+    pub obstacle: [u8; 3], // hard obstacles
+    pub soft_obstacle: [u8; 3],
+    pub passable_obstacle: [u8; 3], // obstacles the robot may drive through right now
+    pub collision: [u8; 3], // obstacles the robot is touching
+    pub via_point: [u8; 3],
+    pub path: [u8; 3],
+    pub path_slow: [u8; 3], // path colored by speed where the robot drives slowest, it is the path color at the target speed
+    pub initial_path: [u8; 3], // straight line seed before optimization
+    pub optimized_path: [u8; 3], // control polygon after optimization, before smoothing
+    pub pruned_points: [u8; 3], // points removed by curvature pruning
+    pub roadmap: [u8; 3],
+    pub roadmap_search: [u8; 3], // roadmap edges explored by the last search
+    pub costmap_lethal: [u8; 3],
+    pub costmap_inflated: [u8; 3],
+    pub wavefront: [u8; 3], // cells the wavefront reached, at the goal
+    pub wavefront_front: [u8; 3], // newest wave while the expansion is animated
+    pub harmonic_field: [u8; 3], // wires of the harmonic planner's potential
+    pub hybrid_expanded: [u8; 3], // steps the hybrid a* search expanded
+    pub distance_near: [u8; 3], // distance heatmap at an obstacle surface
+    pub distance_far: [u8; 3], // distance heatmap at the max distance
+    pub distance_contour: [u8; 3],
+    pub avoidance_velocity: [u8; 3], // velocity chosen by the local avoidance
+    pub breakpoint: [u8; 3], // path point a breakpoint paused the optimization on
+    pub point_cost: [u8; 3], // lines from the inspected path point to the obstacles lifting it
+    pub probe: [u8; 3], // field probe markers and their gradient lines
+    pub cross_section: [u8; 3], // line the cross section plot samples the field along
+    pub speed_zone: [u8; 3], // outline of the slow zones, they are filled with a darker tint of it
+    pub clearance_marker: [u8; 3], // spots of the clearance report
+    pub gradient: [u8; 3],
+    pub gradient_surface: [u8; 3], // shaded field surface, fully lit
+    pub grid: [u8; 3],
+    pub target: [u8; 3],
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self {
+            background: [0, 0, 0],
+            robot: [0, 255, 0],
+            obstacle: [0, 255, 0],
+            soft_obstacle: [255, 165, 0],
+            passable_obstacle: [0, 100, 0],
+            collision: [255, 0, 0],
+            via_point: [0, 255, 255],
+            path: [0, 255, 0],
+            path_slow: [255, 60, 0],
+            initial_path: [150, 150, 150],
+            optimized_path: [0, 120, 255],
+            pruned_points: [255, 80, 80],
+            roadmap: [50, 50, 110],
+            roadmap_search: [255, 170, 0],
+            costmap_lethal: [140, 0, 40],
+            costmap_inflated: [120, 70, 0],
+            wavefront: [0, 150, 200],
+            wavefront_front: [255, 255, 255],
+            harmonic_field: [180, 120, 255],
+            hybrid_expanded: [90, 90, 160],
+            distance_near: [200, 40, 0],
+            distance_far: [0, 30, 60],
+            distance_contour: [255, 230, 120],
+            avoidance_velocity: [255, 80, 200],
+            breakpoint: [255, 60, 60],
+            point_cost: [255, 200, 0],
+            probe: [120, 255, 160],
+            cross_section: [255, 140, 255],
+            speed_zone: [255, 190, 0],
+            clearance_marker: [255, 40, 120],
+            gradient: [0, 255, 255],
+            gradient_surface: [40, 140, 220],
+            grid: [60, 60, 60],
+            target: [255, 0, 0],
+        }
+    }
+}
+
+impl ColorConfig {
+    /// every color with a name for the ui, in the order of the configuration file
+    pub fn entries_mut(&mut self) -> Vec<(&'static str, &mut [u8; 3])> {
+        vec![
+            ("Background", &mut self.background),
+            ("Robot", &mut self.robot),
+            ("Obstacle", &mut self.obstacle),
+            ("Soft Obstacle", &mut self.soft_obstacle),
+            ("Passable Obstacle", &mut self.passable_obstacle),
+            ("Collision", &mut self.collision),
+            ("Via Point", &mut self.via_point),
+            ("Path", &mut self.path),
+            ("Path Slow", &mut self.path_slow),
+            ("Initial Path", &mut self.initial_path),
+            ("Optimized Path", &mut self.optimized_path),
+            ("Pruned Points", &mut self.pruned_points),
+            ("Roadmap", &mut self.roadmap),
+            ("Roadmap Search", &mut self.roadmap_search),
+            ("Costmap Lethal", &mut self.costmap_lethal),
+            ("Costmap Inflated", &mut self.costmap_inflated),
+            ("Wavefront", &mut self.wavefront),
+            ("Wavefront Front", &mut self.wavefront_front),
+            ("Harmonic Field", &mut self.harmonic_field),
+            ("Hybrid Expanded", &mut self.hybrid_expanded),
+            ("Distance Near", &mut self.distance_near),
+            ("Distance Far", &mut self.distance_far),
+            ("Distance Contour", &mut self.distance_contour),
+            ("Avoidance Velocity", &mut self.avoidance_velocity),
+            ("Breakpoint", &mut self.breakpoint),
+            ("Point Cost", &mut self.point_cost),
+            ("Probe", &mut self.probe),
+            ("Cross Section", &mut self.cross_section),
+            ("Speed Zone", &mut self.speed_zone),
+            ("Clearance Marker", &mut self.clearance_marker),
+            ("Gradient", &mut self.gradient),
+            ("Gradient Surface", &mut self.gradient_surface),
+            ("Grid", &mut self.grid),
+            ("Target", &mut self.target),
+        ]
+    }
+}
+
+/// ready made sets of colors to start from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorTheme {
+    Classic,
+    ColorblindSafe,
+    HighContrast,
+}
+
+impl ColorTheme {
+    pub const ALL: [ColorTheme; 3] = [ColorTheme::Classic, ColorTheme::ColorblindSafe, ColorTheme::HighContrast];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorTheme::Classic => "Classic",
+            ColorTheme::ColorblindSafe => "Colorblind Safe",
+            ColorTheme::HighContrast => "High Contrast",
+        }
+    }
+    
+    pub fn colors(&self) -> ColorConfig {
+        let classic = ColorConfig::default();
+        match self {
+            ColorTheme::Classic => classic,
+            // the okabe-ito palette, told apart with any of the common kinds of color blindness
+            ColorTheme::ColorblindSafe => ColorConfig {
+                robot: [255, 255, 255],
+                obstacle: [230, 159, 0],
+                soft_obstacle: [240, 228, 66],
+                passable_obstacle: [110, 110, 110],
+                collision: [204, 121, 167],
+                via_point: [86, 180, 233],
+                path: [86, 180, 233],
+                path_slow: [213, 94, 0],
+                optimized_path: [0, 158, 115],
+                pruned_points: [204, 121, 167],
+                roadmap_search: [230, 159, 0],
+                costmap_lethal: [213, 94, 0],
+                costmap_inflated: [120, 90, 0],
+                distance_near: [213, 94, 0],
+                distance_contour: [240, 228, 66],
+                breakpoint: [213, 94, 0],
+                point_cost: [240, 228, 66],
+                probe: [0, 158, 115],
+                speed_zone: [240, 228, 66],
+                clearance_marker: [204, 121, 167],
+                gradient: [0, 114, 178],
+                gradient_surface: [0, 114, 178],
+                target: [213, 94, 0],
+                ..classic
+            },
+            // a bright path over a dim field mesh
+            ColorTheme::HighContrast => ColorConfig {
+                robot: [0, 255, 255],
+                obstacle: [255, 255, 255],
+                soft_obstacle: [255, 200, 0],
+                passable_obstacle: [90, 90, 90],
+                via_point: [255, 0, 255],
+                path: [255, 255, 0],
+                path_slow: [255, 0, 0],
+                gradient: [0, 70, 90],
+                gradient_surface: [0, 60, 90],
+                grid: [35, 35, 35],
+                target: [255, 0, 255],
+                ..classic
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ModelsConfig {
+    pub search_paths: Vec<String>, // searched in order for model files
+}
+
+impl Default for ModelsConfig {
+    fn default() -> Self {
+        Self {
+            search_paths: vec![String::from("models")],
+        }
+    }
+}
+
+impl Config {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let config: Config = toml::from_str(&text)?;
+        config.field.validate()?;
+        Ok(config)
+    }
+}
+
+impl FieldConfig {
+    /// the gradient and costmap grids divide by the field size, so it has to be positive and finite
+    fn validate(&self) -> Result<(), String> {
+        for (name, value) in [("length", self.length), ("width", self.width)] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(format!("field {} must be positive, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// reads the config file if there is one, must be called before anything uses `get`
+pub fn init(path: &str) {
+    let config = if Path::new(path).exists() {
+        match Config::load(path) {
+            Ok(config) => {
+                println!("Loaded configuration from {}", path);
+                config
+            },
+            Err(e) => {
+                eprintln!("Failed to load configuration {}, using defaults: {}", path, e);
+                Config::default()
+            }
+        }
+    } else {
+        Config::default()
+    };
+    
+    if CONFIG.set(config).is_err() {
+        eprintln!("Configuration was already initialized");
+    }
+}
+
+/// the loaded configuration, defaults if `init` was never called
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// the colors in use, the configured ones unless others were chosen in the ui
+pub fn colors() -> ColorConfig {
+    CUSTOM_COLORS.read().ok().and_then(|colors| *colors).unwrap_or(get().colors)
+}
+
+/// colors used in place of the configured ones, none goes back to them
+pub fn set_colors(colors: Option<ColorConfig>) {
+    if let Ok(mut custom) = CUSTOM_COLORS.write() {
+        *custom = colors;
+    }
+}
+
+pub fn color(rgb: [u8; 3]) -> Rgb<u8> {
+    crate::color::rgb(rgb[0], rgb[1], rgb[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn non_positive_field_sizes_are_rejected() {
+        assert!(FieldConfig::default().validate().is_ok());
+        assert!(FieldConfig { length: 0.0, ..FieldConfig::default() }.validate().is_err());
+        assert!(FieldConfig { width: -8.0, ..FieldConfig::default() }.validate().is_err());
+        assert!(FieldConfig { width: f32::NAN, ..FieldConfig::default() }.validate().is_err());
+    }
+    
+    #[test]
+    fn a_config_with_a_zero_field_fails_to_load() {
+        let path = std::env::temp_dir().join("visualizer_zero_field.toml");
+        std::fs::write(&path, "[field]\nlength = 0.0\n").unwrap();
+        let result = Config::load(&path);
+        std::fs::remove_file(&path).ok();
+        assert!(result.is_err());
+    }
+}
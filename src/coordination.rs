@@ -0,0 +1,146 @@
+use crate::error::PlanError;
+use crate::model::{Model, ModelConfig};
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::robot::Robot;
+use crate::target_position::TargetPosition;
+use crate::trajectory::{Trajectory, TrajectoryWaypoint};
+
+pub const LEAD_ROBOT_NAME: &str = "Robot 1"; // the main robot, always planned first
+const RESERVATION_STEP: f32 = 0.1; // seconds between the waypoints of a reserved path
+const CONFLICT_STEP: f32 = 0.05; // seconds between the positions checked for conflicts
+const TEAM_COLORS: [[u8; 3]; 4] = [[80, 160, 255], [255, 200, 0], [200, 100, 255], [255, 120, 60]];
+
+/// how the paths of several robots are planned against each other
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CoordinationMode {
+    Independent, // every robot plans around the obstacles alone, conflicts are only shown
+    Priority, // robots plan in order, each keeping clear of where the robots before it will be
+}
+
+impl CoordinationMode {
+    pub const ALL: [CoordinationMode; 2] = [CoordinationMode::Independent, CoordinationMode::Priority];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoordinationMode::Independent => "Independent",
+            CoordinationMode::Priority => "Priority Order",
+        }
+    }
+}
+
+/// a robot planned after the main one, with its own target
+pub struct TeamRobot {
+    pub name: String,
+    pub robot: Robot,
+    pub target: TargetPosition,
+    pub color: nannou::color::Rgb<u8>, // of its model, path and target
+}
+
+impl TeamRobot {
+    /// the robot model from the config at a start, numbered after the robots before it
+    pub fn new(index: usize, start: Position, target: Position) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut robot = Robot::create_default()?;
+        robot.model.move_to(start);
+        let [r, g, b] = TEAM_COLORS[index % TEAM_COLORS.len()];
+        let color = nannou::color::rgb(r, g, b);
+        let mut target = TargetPosition::new(target);
+        target.color = color;
+        Ok(TeamRobot { name: format!("Robot {}", index + 2), robot, target, color })
+    }
+}
+
+/// two robots that will be closer than their footprints allow
+#[derive(Debug, Clone)]
+pub struct RobotConflict {
+    pub robots: (String, String),
+    pub time: f32, // seconds after both leave their starts
+    pub position: Position, // halfway between them
+}
+
+/// plans every team robot after the lead, returning the robots whose plans failed and why
+pub fn plan_team(lead: &Robot, team: &mut [TeamRobot], obstacles: &[Obstacle], segments: usize, mode: CoordinationMode) -> Vec<(String, PlanError)> {
+    let mut planning: Vec<Obstacle> = obstacles.iter().filter(|obstacle| obstacle.is_planned()).cloned().collect();
+    if mode == CoordinationMode::Priority {
+        planning.push(reservation(LEAD_ROBOT_NAME, lead));
+    }
+    
+    let mut errors = Vec::new();
+    for member in team.iter_mut() {
+        member.robot.planner = lead.planner;
+        member.robot.pipeline = lead.pipeline.clone();
+        if let Err(e) = member.robot.generate_path(&member.target.get_position(), segments, &planning) {
+            errors.push((member.name.clone(), e));
+        }
+        if mode == CoordinationMode::Priority {
+            planning.push(reservation(&member.name, &member.robot));
+        }
+    }
+    errors
+}
+
+/// the first time each pair of robots comes closer than their footprints, driving their paths at their
+/// target speeds from now and waiting at the end
+pub fn find_conflicts(robots: &[(&str, &Robot)]) -> Vec<RobotConflict> {
+    let timed: Vec<Trajectory> = robots.iter().map(|(_, robot)| timed_trajectory(robot)).collect();
+    let end = timed.iter().map(|trajectory| trajectory.duration()).fold(0.0, f32::max);
+    let steps = (end / CONFLICT_STEP).ceil() as usize;
+    
+    let mut conflicts = Vec::new();
+    for a in 0..robots.len() {
+        for b in (a + 1)..robots.len() {
+            let radius = robots[a].1.footprint_radius() + robots[b].1.footprint_radius();
+            for step in 0..=steps {
+                let time = step as f32 * CONFLICT_STEP;
+                let (pa, pb) = (position_at(&timed[a], robots[a].1, time), position_at(&timed[b], robots[b].1, time));
+                if ((pa[0] - pb[0]).powi(2) + (pa[1] - pb[1]).powi(2)).sqrt() < radius {
+                    conflicts.push(RobotConflict {
+                        robots: (robots[a].0.to_string(), robots[b].0.to_string()),
+                        time,
+                        position: Position::new((pa[0] + pb[0]) / 2.0, (pa[1] + pb[1]) / 2.0, 0.0),
+                    });
+                    break;
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+/// where a robot will be, on its path or where it stands without one
+fn position_at(trajectory: &Trajectory, robot: &Robot, time: f32) -> [f32; 2] {
+    let position = robot.model.config.position;
+    trajectory.position_at(time).unwrap_or([position.x, position.y])
+}
+
+/// the planned path of a robot as a trajectory starting now
+fn timed_trajectory(robot: &Robot) -> Trajectory {
+    let mut waypoints: Vec<TrajectoryWaypoint> = Vec::new();
+    let timed = robot.timed_path();
+    for (i, &(time, position)) in timed.iter().enumerate() {
+        let due = waypoints.last().is_none_or(|last| time - last.time >= RESERVATION_STEP);
+        if due || i == timed.len() - 1 {
+            waypoints.push(TrajectoryWaypoint { time, x: position.x, y: position.y });
+        }
+    }
+    Trajectory { waypoints, looping: false }
+}
+
+/// an obstacle the size of a planned robot moving along its path, so robots planned after it keep clear of
+/// where it will be, it stands where the robot is when it has no path
+fn reservation(name: &str, robot: &Robot) -> Obstacle {
+    let trajectory = timed_trajectory(robot);
+    let model = Model {
+        wires: Vec::new(),
+        config: ModelConfig { name: name.to_string(), position: robot.model.config.position, scale: robot.footprint_radius() * 2.0 },
+    };
+    
+    let mut obstacle = Obstacle::new(model);
+    obstacle.name = name.to_string();
+    obstacle.set_buffer(0.0);
+    if !trajectory.waypoints.is_empty() {
+        obstacle.trajectory = Some(trajectory);
+        obstacle.follow_trajectory(0.0);
+    }
+    obstacle
+}
@@ -0,0 +1,354 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::color::Rgb;
+
+use crate::dubins::{PathSample, segment_samples};
+use crate::field::{field_length, field_width};
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+
+pub const DEFAULT_COSTMAP_RESOLUTION: f32 = 0.1; // meters per cell
+const INFLATION_WEIGHT: f32 = 4.0; // extra cost of crossing an inflated cell next to the lethal region, per meter
+
+/// how safe it is for the robot center to be in a cell
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CellCost {
+    Free,
+    Inflated(f32), // inside the buffer, 1 next to the lethal region falling to 0 at its edge, or 1 over a soft obstacle
+    Lethal, // closer to an obstacle footprint than the robot radius, the robot would hit it
+}
+
+impl CellCost {
+    /// cost per meter of driving through the cell, None when it can not be entered
+    pub(crate) fn traversal_cost(&self) -> Option<f32> {
+        match self {
+            CellCost::Free => Some(1.0),
+            CellCost::Inflated(inflation) => Some(1.0 + INFLATION_WEIGHT * inflation),
+            CellCost::Lethal => None,
+        }
+    }
+}
+
+/// grid over the field marking where the robot center may go, built from the obstacle
+/// footprints, the robot radius and the buffer kept around them
+pub struct Costmap {
+    pub(crate) resolution: f32,
+    pub(crate) columns: usize,
+    pub(crate) rows: usize,
+    pub(crate) cells: Vec<CellCost>,
+}
+
+impl Costmap {
+    pub fn build(obstacles: &[Obstacle], resolution: f32) -> Self {
+        let resolution = resolution.max(0.01);
+        let columns = (field_length() / resolution).ceil() as usize;
+        let rows = (field_width() / resolution).ceil() as usize;
+        let planned: Vec<&Obstacle> = obstacles.iter().filter(|obstacle| obstacle.is_planned()).collect();
+        
+        let mut cells = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let center = Position::new((column as f32 + 0.5) * resolution, (row as f32 + 0.5) * resolution, 0.0);
+                let mut cost = CellCost::Free;
+                for obstacle in &planned {
+                    let distance = obstacle.edge_distance(center);
+                    // soft obstacles may be crossed, at the cost of the most inflated cell
+                    if obstacle.soft && distance < obstacle.robot_radius() {
+                        cost = CellCost::Inflated(1.0);
+                        continue;
+                    }
+                    if distance < obstacle.robot_radius() {
+                        cost = CellCost::Lethal;
+                        break;
+                    }
+                    if distance < obstacle.clearance() {
+                        let buffer = obstacle.clearance() - obstacle.robot_radius();
+                        let inflation = 1.0 - (distance - obstacle.robot_radius()) / buffer;
+                        if !matches!(cost, CellCost::Inflated(current) if current >= inflation) {
+                            cost = CellCost::Inflated(inflation);
+                        }
+                    }
+                }
+                cells.push(cost);
+            }
+        }
+        
+        Costmap { resolution, columns, rows, cells }
+    }
+    
+    pub(crate) fn cell_of(&self, point: [f32; 2]) -> Option<usize> {
+        if point[0] < 0.0 || point[1] < 0.0 {
+            return None;
+        }
+        let column = (point[0] / self.resolution) as usize;
+        let row = (point[1] / self.resolution) as usize;
+        (column < self.columns && row < self.rows).then(|| row * self.columns + column)
+    }
+    
+    fn center_of(&self, cell: usize) -> [f32; 2] {
+        let (row, column) = (cell / self.columns, cell % self.columns);
+        [(column as f32 + 0.5) * self.resolution, (row as f32 + 0.5) * self.resolution]
+    }
+    
+    /// the cost of the cell under a field position, None off the field
+    pub fn cost_at(&self, point: [f32; 2]) -> Option<CellCost> {
+        self.cell_of(point).map(|cell| self.cells[cell])
+    }
+    
+    /// cheapest route over the grid from start to goal, returns the points of the path after the start
+    /// and the heading the robot arrives with. any angle routes are searched with theta*
+    pub fn query(&self, start: [f32; 2], goal: [f32; 2], any_angle: bool) -> Option<(Vec<PathSample>, f32)> {
+        let corners = self.route(start, goal, any_angle)?;
+        
+        let mut samples = Vec::new();
+        let mut from = start;
+        for &corner in &corners {
+            samples.extend(segment_samples(from, corner).into_iter()
+                .map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed: false }));
+            from = corner;
+        }
+        
+        let before_goal = if corners.len() > 1 { corners[corners.len() - 2] } else { start };
+        let heading = (goal[1] - before_goal[1]).atan2(goal[0] - before_goal[0]);
+        Some((samples, heading))
+    }
+    
+    /// corner points after the start of the a* route over the 8 connected grid, straightened where
+    /// a line between two corners crosses no costlier cell than the cells it skips. with any angle
+    /// every cell may instead be reached straight from its predecessor's parent when that line is
+    /// cheaper (theta*), so the route is made of any angle segments as it is searched
+    fn route(&self, start: [f32; 2], goal: [f32; 2], any_angle: bool) -> Option<Vec<[f32; 2]>> {
+        let start_cell = self.cell_of(start)?;
+        let goal_cell = self.cell_of(goal)?;
+        self.cells[goal_cell].traversal_cost()?;
+        if start_cell == goal_cell {
+            return Some(vec![goal]);
+        }
+        
+        let (cells, _) = self.search(start_cell, goal_cell, any_angle)?;
+        let mut points: Vec<[f32; 2]> = cells.iter().map(|&cell| self.center_of(cell)).collect();
+        points[0] = start;
+        *points.last_mut().unwrap() = goal;
+        if any_angle {
+            return Some(points[1..].to_vec());
+        }
+        Some(self.straighten(&points))
+    }
+    
+    /// the cells a* steps through from the start cell to the goal cell and the cost of the route, with any angle
+    /// only the cells the route turns at
+    pub(crate) fn search(&self, start_cell: usize, goal_cell: usize, any_angle: bool) -> Option<(Vec<usize>, f32)> {
+        let goal = self.center_of(goal_cell);
+        let heuristic = |cell: usize| {
+            let [x, y] = self.center_of(cell);
+            ((x - goal[0]).powi(2) + (y - goal[1]).powi(2)).sqrt()
+        };
+        
+        let mut cost = vec![f32::MAX; self.cells.len()];
+        let mut previous = vec![usize::MAX; self.cells.len()];
+        let mut queue = BinaryHeap::new();
+        cost[start_cell] = 0.0;
+        queue.push(Reverse((heuristic(start_cell).to_bits(), start_cell)));
+        
+        while let Some(Reverse((_, cell))) = queue.pop() {
+            if cell == goal_cell {
+                break;
+            }
+            
+            let (row, column) = ((cell / self.columns) as i64, (cell % self.columns) as i64);
+            for (d_column, d_row) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let Some(next) = self.neighbor(column + d_column, row + d_row) else { continue; };
+                let Some(cell_cost) = self.cells[next].traversal_cost() else { continue; };
+                // diagonal steps may not squeeze between two lethal cells
+                if d_column != 0 && d_row != 0 {
+                    let beside = [self.neighbor(column + d_column, row), self.neighbor(column, row + d_row)];
+                    if beside.iter().any(|side| side.is_none_or(|side| self.cells[side] == CellCost::Lethal)) {
+                        continue;
+                    }
+                }
+                
+                let step = if d_column != 0 && d_row != 0 { std::f32::consts::SQRT_2 } else { 1.0 } * self.resolution;
+                let mut next_cost = cost[cell] + step * cell_cost;
+                let mut parent = cell;
+                let grandparent = previous[cell];
+                if any_angle && grandparent != usize::MAX {
+                    if let Some(line_cost) = self.line_cost(self.center_of(grandparent), self.center_of(next)) {
+                        if cost[grandparent] + line_cost <= next_cost {
+                            next_cost = cost[grandparent] + line_cost;
+                            parent = grandparent;
+                        }
+                    }
+                }
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    previous[next] = parent;
+                    queue.push(Reverse(((next_cost + heuristic(next)).to_bits(), next)));
+                }
+            }
+        }
+        
+        if cost[goal_cell] == f32::MAX {
+            return None;
+        }
+        let mut cells = vec![goal_cell];
+        while *cells.last().unwrap() != start_cell {
+            cells.push(previous[*cells.last().unwrap()]);
+        }
+        cells.reverse();
+        Some((cells, cost[goal_cell]))
+    }
+    
+    /// skips ahead from every corner to the furthest point it can reach in a straight line
+    fn straighten(&self, points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+        let mut corners = Vec::new();
+        let mut from = 0;
+        while from < points.len() - 1 {
+            let mut to = from + 1;
+            let mut worst = self.worst_cost(points[from], points[to]);
+            for next in (from + 2)..points.len() {
+                worst = worst.max(self.worst_cost(points[next - 1], points[next]));
+                if self.worst_cost(points[from], points[next]) <= worst {
+                    to = next;
+                }
+            }
+            corners.push(points[to]);
+            from = to;
+        }
+        corners
+    }
+    
+    /// cost of driving a straight line, its length times the mean traversal cost of the cells along it, None if it
+    /// crosses a lethal cell or leaves the field
+    fn line_cost(&self, from: [f32; 2], to: [f32; 2]) -> Option<f32> {
+        let length = ((to[0] - from[0]).powi(2) + (to[1] - from[1]).powi(2)).sqrt();
+        let steps = (length / (self.resolution / 2.0)).ceil().max(1.0) as usize;
+        let mut total = 0.0;
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            let point = [from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t];
+            total += self.cost_at(point)?.traversal_cost()?;
+        }
+        Some(length * total / (steps + 1) as f32)
+    }
+    
+    /// highest traversal cost of the cells along a line, infinite if it crosses a lethal cell or leaves the field
+    fn worst_cost(&self, from: [f32; 2], to: [f32; 2]) -> f32 {
+        let length = ((to[0] - from[0]).powi(2) + (to[1] - from[1]).powi(2)).sqrt();
+        let steps = (length / (self.resolution / 2.0)).ceil().max(1.0) as usize;
+        (0..=steps)
+            .map(|i| {
+                let t = i as f32 / steps as f32;
+                let point = [from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t];
+                self.cost_at(point).and_then(|cost| cost.traversal_cost()).unwrap_or(f32::INFINITY)
+            })
+            .fold(0.0, f32::max)
+    }
+    
+    fn neighbor(&self, column: i64, row: i64) -> Option<usize> {
+        (column >= 0 && row >= 0 && (column as usize) < self.columns && (row as usize) < self.rows)
+            .then(|| row as usize * self.columns + column as usize)
+    }
+    
+    /// corners of every lethal and inflated cell on the ground plane with its color, inflated cells
+    /// fade out towards the free space
+    pub fn cells(&self, lethal: Rgb<u8>, inflated: Rgb<u8>) -> Vec<([Position; 4], Rgb<u8>)> {
+        let scale = |c: u8, amount: f32| (c as f32 * amount) as u8;
+        let mut result = Vec::new();
+        for (cell, cost) in self.cells.iter().enumerate() {
+            let color = match *cost {
+                CellCost::Free => continue,
+                CellCost::Lethal => lethal,
+                CellCost::Inflated(inflation) => {
+                    let amount = 0.3 + 0.7 * inflation;
+                    crate::color::rgb(scale(inflated.red, amount), scale(inflated.green, amount), scale(inflated.blue, amount))
+                },
+            };
+            
+            let [x, y] = self.center_of(cell);
+            let half = self.resolution / 2.0;
+            let corners = [
+                Position::new(x - half, y - half, 0.0),
+                Position::new(x + half, y - half, 0.0),
+                Position::new(x + half, y + half, 0.0),
+                Position::new(x - half, y + half, 0.0),
+            ];
+            result.push((corners, color));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const START: [f32; 2] = [0.25, 1.05];
+    const GOAL: [f32; 2] = [2.75, 1.05];
+    
+    /// a free 3 by 2 meter grid with a wall across most of it, so the route has to bend
+    fn walled_costmap() -> Costmap {
+        let (columns, rows) = (30, 20);
+        let mut cells = vec![CellCost::Free; columns * rows];
+        for row in 4..rows {
+            cells[row * columns + 12] = CellCost::Lethal;
+        }
+        for column in 18..22 {
+            cells[8 * columns + column] = CellCost::Inflated(0.5);
+        }
+        Costmap { resolution: 0.1, columns, rows, cells }
+    }
+    
+    fn search(costmap: &Costmap, any_angle: bool) -> Option<(Vec<usize>, f32)> {
+        costmap.search(costmap.cell_of(START).unwrap(), costmap.cell_of(GOAL).unwrap(), any_angle)
+    }
+    
+    #[test]
+    fn any_angle_routes_cost_no_more_than_grid_routes() {
+        let costmap = walled_costmap();
+        let (_, grid_cost) = search(&costmap, false).unwrap();
+        let (_, any_angle_cost) = search(&costmap, true).unwrap();
+        assert!(any_angle_cost <= grid_cost + 1e-4, "any angle {} over the grid's {}", any_angle_cost, grid_cost);
+        assert!(any_angle_cost < grid_cost, "the line of sight shortcuts should cut the corners of the grid route");
+    }
+    
+    #[test]
+    fn neither_route_crosses_a_lethal_cell() {
+        let costmap = walled_costmap();
+        let (grid_cells, _) = search(&costmap, false).unwrap();
+        assert!(grid_cells.iter().all(|&cell| costmap.cells[cell] != CellCost::Lethal));
+        
+        // any angle routes only keep their corners, every straight line between them has to be clear
+        let (corners, _) = search(&costmap, true).unwrap();
+        for pair in corners.windows(2) {
+            assert!(costmap.line_cost(costmap.center_of(pair[0]), costmap.center_of(pair[1])).is_some());
+        }
+        for any_angle in [false, true] {
+            let (samples, _) = costmap.query(START, GOAL, any_angle).unwrap();
+            for sample in samples {
+                let cost = costmap.cost_at([sample.position.x, sample.position.y]).unwrap();
+                assert_ne!(cost, CellCost::Lethal, "sample at ({}, {})", sample.position.x, sample.position.y);
+            }
+        }
+    }
+    
+    #[test]
+    fn a_walled_off_goal_is_not_reached() {
+        let mut costmap = walled_costmap();
+        let goal = costmap.cell_of(GOAL).unwrap();
+        let (row, column) = ((goal / costmap.columns) as i64, (goal % costmap.columns) as i64);
+        for d_row in -2i64..=2 {
+            for d_column in -2i64..=2 {
+                if d_row.abs().max(d_column.abs()) == 2 {
+                    let cell = costmap.neighbor(column + d_column, row + d_row).unwrap();
+                    costmap.cells[cell] = CellCost::Lethal;
+                }
+            }
+        }
+        
+        for any_angle in [false, true] {
+            assert!(search(&costmap, any_angle).is_none());
+            assert!(costmap.query(START, GOAL, any_angle).is_none());
+        }
+    }
+}
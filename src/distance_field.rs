@@ -0,0 +1,224 @@
+// euclidean distance transform of the field, the signed distance from every grid cell to the nearest hard
+// obstacle surface, so clearance queries read the grid instead of looping over the obstacles
+use crate::color::Rgb;
+
+use crate::field::{field_length, field_width};
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::wire::Wire;
+
+pub const DEFAULT_DISTANCE_RESOLUTION: f32 = 0.05; // meters per cell
+
+/// signed distance to the nearest hard obstacle footprint at the cell centers, negative inside one, accurate to
+/// about half a cell
+pub struct DistanceField {
+    pub resolution: f32,
+    columns: usize,
+    rows: usize,
+    distances: Vec<f32>,
+    key: Vec<f32>, // positions, radii and hulls of the obstacles it was built from
+    empty: bool, // no hard obstacles, every distance is unbounded
+}
+
+/// what the distances depend on, changes whenever a hard obstacle moves, grows or is toggled
+fn obstacles_key(obstacles: &[Obstacle]) -> Vec<f32> {
+    let mut key = Vec::new();
+    for obstacle in obstacles.iter().filter(|obstacle| obstacle.is_hard()) {
+        let center = obstacle.model.config.position;
+        key.extend([center.x, center.y, obstacle.get_radius()]);
+        if let Some(polygon) = obstacle.footprint_polygon() {
+            key.extend(polygon.into_iter().flatten());
+        }
+        key.push(f32::NAN); // separates the obstacles, keys are compared by bits
+    }
+    key
+}
+
+impl DistanceField {
+    pub fn build(obstacles: &[Obstacle], resolution: f32) -> Self {
+        let resolution = resolution.max(0.01);
+        let columns = (field_length() / resolution).ceil() as usize;
+        let rows = (field_width() / resolution).ceil() as usize;
+        let hard: Vec<&Obstacle> = obstacles.iter().filter(|obstacle| obstacle.is_hard()).collect();
+        
+        let mut inside = vec![false; columns * rows];
+        for row in 0..rows {
+            for column in 0..columns {
+                let center = Position::new((column as f32 + 0.5) * resolution, (row as f32 + 0.5) * resolution, 0.0);
+                inside[row * columns + column] = hard.iter().any(|obstacle| obstacle.edge_distance(center) <= 0.0);
+            }
+        }
+        // obstacles smaller than a cell still cover the cell of their center
+        for obstacle in &hard {
+            let center = obstacle.model.config.position;
+            let (column, row) = ((center.x / resolution).floor(), (center.y / resolution).floor());
+            if column >= 0.0 && row >= 0.0 && (column as usize) < columns && (row as usize) < rows {
+                inside[row as usize * columns + column as usize] = true;
+            }
+        }
+        
+        // the distance outside is to the nearest covered cell and inside to the nearest free one, both measured
+        // between cell centers so half a cell is taken off to put the surface between them
+        let unbounded = field_length() + field_width();
+        let outside_distance = squared_distance_transform(&inside, columns, rows);
+        let inside_distance = squared_distance_transform(&inside.iter().map(|covered| !covered).collect::<Vec<bool>>(), columns, rows);
+        let distances = (0..columns * rows)
+            .map(|cell| {
+                let (squared, sign) = if inside[cell] { (inside_distance[cell], -1.0) } else { (outside_distance[cell], 1.0) };
+                let distance = if squared.is_finite() { (squared.sqrt() - 0.5).max(0.0) * resolution } else { unbounded };
+                sign * distance
+            })
+            .collect();
+        
+        DistanceField { resolution, columns, rows, distances, key: obstacles_key(obstacles), empty: hard.is_empty() }
+    }
+    
+    /// whether the field was built from these obstacles, bit for bit
+    pub fn matches(&self, obstacles: &[Obstacle]) -> bool {
+        let key = obstacles_key(obstacles);
+        key.len() == self.key.len() && key.iter().zip(&self.key).all(|(a, b)| a.to_bits() == b.to_bits())
+    }
+    
+    fn center_of(&self, column: usize, row: usize) -> [f32; 2] {
+        [(column as f32 + 0.5) * self.resolution, (row as f32 + 0.5) * self.resolution]
+    }
+    
+    /// signed distance to the nearest hard obstacle surface, interpolated between the four surrounding cell
+    /// centers, f32::MAX without hard obstacles like the per obstacle loops give
+    pub fn distance_at(&self, position: Position) -> f32 {
+        if self.empty {
+            return f32::MAX;
+        }
+        let x = (position.x / self.resolution - 0.5).clamp(0.0, (self.columns - 1) as f32);
+        let y = (position.y / self.resolution - 0.5).clamp(0.0, (self.rows - 1) as f32);
+        let (column, row) = ((x as usize).min(self.columns.saturating_sub(2)), (y as usize).min(self.rows.saturating_sub(2)));
+        let (tx, ty) = (x - column as f32, y - row as f32);
+        let at = |column: usize, row: usize| self.distances[row.min(self.rows - 1) * self.columns + column.min(self.columns - 1)];
+        
+        let bottom = at(column, row) * (1.0 - tx) + at(column + 1, row) * tx;
+        let top = at(column, row + 1) * (1.0 - tx) + at(column + 1, row + 1) * tx;
+        bottom * (1.0 - ty) + top * ty
+    }
+    
+    /// ground quads of the cells up to max distance from an obstacle, colored from near at the surface to far
+    pub fn heatmap(&self, max_distance: f32, near: Rgb<u8>, far: Rgb<u8>) -> Vec<([Position; 4], Rgb<u8>)> {
+        let mix = |a: u8, b: u8, t: f32| (a as f32 + (b as f32 - a as f32) * t) as u8;
+        let half = self.resolution / 2.0;
+        let mut quads = Vec::new();
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let distance = self.distances[row * self.columns + column];
+                if self.empty || distance > max_distance {
+                    continue;
+                }
+                let t = (distance / max_distance).clamp(0.0, 1.0);
+                let color = crate::color::rgb(mix(near.red, far.red, t), mix(near.green, far.green, t), mix(near.blue, far.blue, t));
+                
+                let [x, y] = self.center_of(column, row);
+                let corners = [
+                    Position::new(x - half, y - half, 0.0),
+                    Position::new(x + half, y - half, 0.0),
+                    Position::new(x + half, y + half, 0.0),
+                    Position::new(x - half, y + half, 0.0),
+                ];
+                quads.push((corners, color));
+            }
+        }
+        quads
+    }
+    
+    /// ground lines where the distance is every multiple of spacing up to max distance, traced with marching squares
+    pub fn contours(&self, spacing: f32, max_distance: f32, color: Rgb<u8>) -> Vec<Wire> {
+        let mut wires = Vec::new();
+        if self.empty || spacing <= 0.0 || self.columns < 2 || self.rows < 2 {
+            return wires;
+        }
+        
+        let levels: Vec<f32> = (1..).map(|i| i as f32 * spacing).take_while(|&level| level <= max_distance).collect();
+        for row in 0..self.rows - 1 {
+            for column in 0..self.columns - 1 {
+                // corners counter clockwise from the bottom left
+                let corners = [(column, row), (column + 1, row), (column + 1, row + 1), (column, row + 1)];
+                let values = corners.map(|(column, row)| self.distances[row * self.columns + column]);
+                for &level in &levels {
+                    let mut crossings = Vec::with_capacity(4);
+                    for edge in 0..4 {
+                        let (a, b) = (values[edge], values[(edge + 1) % 4]);
+                        if (a < level) != (b < level) {
+                            let t = (level - a) / (b - a);
+                            let start = self.center_of(corners[edge].0, corners[edge].1);
+                            let end = self.center_of(corners[(edge + 1) % 4].0, corners[(edge + 1) % 4].1);
+                            crossings.push(Position::new(start[0] + (end[0] - start[0]) * t, start[1] + (end[1] - start[1]) * t, 0.0));
+                        }
+                    }
+                    // two crossings make one line, a saddle with four makes two
+                    for pair in crossings.chunks_exact(2) {
+                        wires.push(Wire::with_color(pair[0], pair[1], color));
+                    }
+                }
+            }
+        }
+        wires
+    }
+}
+
+/// squared distance in cells from every cell to the nearest set one, infinite when none is set. separable exact
+/// transform of felzenszwalb and huttenlocher, along the rows and then along the columns
+fn squared_distance_transform(set: &[bool], columns: usize, rows: usize) -> Vec<f32> {
+    let mut distances: Vec<f32> = set.iter().map(|&set| if set { 0.0 } else { f32::INFINITY }).collect();
+    
+    let mut line = Vec::with_capacity(columns.max(rows));
+    for row in 0..rows {
+        line.clear();
+        line.extend_from_slice(&distances[row * columns..(row + 1) * columns]);
+        let transformed = transform_line(&line);
+        distances[row * columns..(row + 1) * columns].copy_from_slice(&transformed);
+    }
+    for column in 0..columns {
+        line.clear();
+        line.extend((0..rows).map(|row| distances[row * columns + column]));
+        for (row, distance) in transform_line(&line).into_iter().enumerate() {
+            distances[row * columns + column] = distance;
+        }
+    }
+    distances
+}
+
+/// one dimensional squared distance transform, the lower envelope of the parabolas rooted at every sample
+fn transform_line(f: &[f32]) -> Vec<f32> {
+    let n = f.len();
+    let mut result = vec![f32::INFINITY; n];
+    let roots: Vec<usize> = (0..n).filter(|&q| f[q].is_finite()).collect();
+    if roots.is_empty() {
+        return result;
+    }
+    
+    // parabolas of the envelope and where each starts to be the lowest
+    let mut envelope: Vec<usize> = Vec::with_capacity(roots.len());
+    let mut starts: Vec<f32> = Vec::with_capacity(roots.len());
+    for &q in &roots {
+        let mut start = f32::NEG_INFINITY;
+        while let Some(&p) = envelope.last() {
+            start = ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2.0 * (q as f32 - p as f32));
+            if start <= *starts.last().unwrap() {
+                envelope.pop();
+                starts.pop();
+                start = f32::NEG_INFINITY;
+            } else {
+                break;
+            }
+        }
+        envelope.push(q);
+        starts.push(start);
+    }
+    
+    let mut k = 0;
+    for (q, value) in result.iter_mut().enumerate() {
+        while k + 1 < envelope.len() && starts[k + 1] < q as f32 {
+            k += 1;
+        }
+        let p = envelope[k];
+        *value = (q as f32 - p as f32).powi(2) + f[p];
+    }
+    result
+}
@@ -0,0 +1,329 @@
+// d* lite incremental replanner over the costmap grid (koenig and likhachev). the search runs from the goal
+// back to the robot, so when the robot moves or a few cells change cost only the cells whose distance to the
+// goal changed are expanded again instead of searching the whole grid
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::costmap::{CellCost, Costmap};
+use crate::dubins::{PathSample, segment_samples};
+use crate::position::Position;
+
+type Key = (u32, u32); // bits of the two non negative key values, which order like the floats
+const KEY_TOLERANCE: f32 = 1e-4;
+
+/// costs to the goal of the cells searched so far, kept between plans to the same goal
+pub struct DStarLite {
+    resolution: f32,
+    columns: usize,
+    rows: usize,
+    costs: Vec<Option<f32>>, // cost per meter of entering each cell, None for lethal cells
+    g: Vec<f32>,
+    rhs: Vec<f32>, // one step lookahead of g, the cell is consistent when they agree
+    queue: BinaryHeap<Reverse<(Key, usize)>>,
+    queued: Vec<Option<Key>>, // key of the cells in the queue, entries with another key are stale
+    goal: usize,
+    goal_point: [f32; 2],
+    start: Option<usize>, // robot cell of the last plan
+    km: f32, // heuristic the keys already in the queue are short of since the robot moved
+    pub expanded: usize, // cells expanded by the last plan
+    pub plans: usize, // plans answered by this search, the first one searches from scratch
+}
+
+impl DStarLite {
+    /// an empty search towards the goal over the costmap, None when the goal is off the grid
+    pub fn new(costmap: &Costmap, goal: [f32; 2]) -> Option<Self> {
+        let cells = costmap.cells.len();
+        let mut search = DStarLite {
+            resolution: costmap.resolution,
+            columns: costmap.columns,
+            rows: costmap.rows,
+            costs: costmap.cells.iter().map(CellCost::traversal_cost).collect(),
+            g: vec![f32::INFINITY; cells],
+            rhs: vec![f32::INFINITY; cells],
+            queue: BinaryHeap::new(),
+            queued: vec![None; cells],
+            goal: 0,
+            goal_point: goal,
+            start: None,
+            km: 0.0,
+            expanded: 0,
+            plans: 0,
+        };
+        search.goal = search.cell_of(goal)?;
+        search.rhs[search.goal] = 0.0;
+        Some(search)
+    }
+    
+    /// whether the search can be carried on for a costmap and goal, the grid must be the same size
+    pub fn continues(&self, costmap: &Costmap, goal: [f32; 2]) -> bool {
+        self.goal_point == goal && self.resolution == costmap.resolution && self.costs.len() == costmap.cells.len()
+    }
+    
+    fn cell_of(&self, point: [f32; 2]) -> Option<usize> {
+        if point[0] < 0.0 || point[1] < 0.0 {
+            return None;
+        }
+        let column = (point[0] / self.resolution) as usize;
+        let row = (point[1] / self.resolution) as usize;
+        (column < self.columns && row < self.rows).then(|| row * self.columns + column)
+    }
+    
+    fn center_of(&self, cell: usize) -> [f32; 2] {
+        let (row, column) = (cell / self.columns, cell % self.columns);
+        [(column as f32 + 0.5) * self.resolution, (row as f32 + 0.5) * self.resolution]
+    }
+    
+    /// octile distance, never more than the cost of the cheapest route since no cell costs less than 1 per meter
+    fn heuristic(&self, a: usize, b: usize) -> f32 {
+        let d_column = (a % self.columns).abs_diff(b % self.columns) as f32;
+        let d_row = (a / self.columns).abs_diff(b / self.columns) as f32;
+        (d_column.max(d_row) + (std::f32::consts::SQRT_2 - 1.0) * d_column.min(d_row)) * self.resolution
+    }
+    
+    /// the 8 cells around a cell with the cost of stepping into each, infinite into lethal cells and diagonally
+    /// past one
+    fn neighbors(&self, cell: usize) -> Vec<(usize, f32)> {
+        let (row, column) = ((cell / self.columns) as i64, (cell % self.columns) as i64);
+        let at = |column: i64, row: i64| (column >= 0 && row >= 0 && (column as usize) < self.columns && (row as usize) < self.rows)
+            .then(|| row as usize * self.columns + column as usize);
+        
+        let mut neighbors = Vec::with_capacity(8);
+        for (d_column, d_row) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let Some(next) = at(column + d_column, row + d_row) else { continue; };
+            let diagonal = d_column != 0 && d_row != 0;
+            let squeezed = diagonal && [at(column + d_column, row), at(column, row + d_row)].iter()
+                .any(|side| side.is_none_or(|side| self.costs[side].is_none()));
+            let cost = match self.costs[next] {
+                Some(cost) if !squeezed => cost * if diagonal { std::f32::consts::SQRT_2 } else { 1.0 } * self.resolution,
+                _ => f32::INFINITY,
+            };
+            neighbors.push((next, cost));
+        }
+        neighbors
+    }
+    
+    fn key(&self, cell: usize, start: usize) -> Key {
+        let best = self.g[cell].min(self.rhs[cell]);
+        ((best + self.heuristic(start, cell) + self.km).to_bits(), best.to_bits())
+    }
+    
+    /// recomputes the lookahead of a cell and queues it while it is inconsistent
+    fn update_cell(&mut self, cell: usize, start: usize) {
+        if cell != self.goal {
+            self.rhs[cell] = self.neighbors(cell).into_iter()
+                .map(|(next, cost)| cost + self.g[next])
+                .fold(f32::INFINITY, f32::min);
+        }
+        if self.g[cell] != self.rhs[cell] {
+            let key = self.key(cell, start);
+            self.queued[cell] = Some(key);
+            self.queue.push(Reverse((key, cell)));
+        } else {
+            self.queued[cell] = None;
+        }
+    }
+    
+    /// expands cells until the robot cell is consistent and no queued cell could still lower its cost
+    fn compute_shortest_path(&mut self, start: usize) {
+        while let Some(&Reverse((key, cell))) = self.queue.peek() {
+            if self.queued[cell] != Some(key) {
+                self.queue.pop();
+                continue;
+            }
+            // keys summed in another order can differ in the last bits, a tie keeps searching so the
+            // robot cell is not settled early
+            let start_key = self.key(start, start);
+            if f32::from_bits(key.0) > f32::from_bits(start_key.0) + KEY_TOLERANCE && self.rhs[start] == self.g[start] {
+                break;
+            }
+            self.queue.pop();
+            self.queued[cell] = None;
+            self.expanded += 1;
+            
+            let current = self.key(cell, start);
+            if key < current {
+                self.queued[cell] = Some(current);
+                self.queue.push(Reverse((current, cell)));
+            } else if self.g[cell] > self.rhs[cell] {
+                self.g[cell] = self.rhs[cell];
+                for (neighbor, _) in self.neighbors(cell) {
+                    self.update_cell(neighbor, start);
+                }
+            } else {
+                self.g[cell] = f32::INFINITY;
+                self.update_cell(cell, start);
+                for (neighbor, _) in self.neighbors(cell) {
+                    self.update_cell(neighbor, start);
+                }
+            }
+        }
+    }
+    
+    /// plans from start with the costs of the costmap, repairing the previous search where cells changed cost,
+    /// returns the points of the path after the start and the heading the robot arrives with
+    pub fn plan(&mut self, costmap: &Costmap, start: [f32; 2]) -> Option<(Vec<PathSample>, f32)> {
+        let start_cell = self.cell_of(start)?;
+        self.expanded = 0;
+        self.plans += 1;
+        
+        // keys queued before the robot moved are short by the distance it moved
+        if let Some(previous) = self.start {
+            self.km += self.heuristic(previous, start_cell);
+        } else {
+            let key = self.key(self.goal, start_cell);
+            self.queued[self.goal] = Some(key);
+            self.queue.push(Reverse((key, self.goal)));
+        }
+        self.start = Some(start_cell);
+        
+        let changed: Vec<usize> = (0..self.costs.len())
+            .filter(|&cell| self.costs[cell] != costmap.cells[cell].traversal_cost())
+            .collect();
+        for &cell in &changed {
+            self.costs[cell] = costmap.cells[cell].traversal_cost();
+        }
+        // the steps into a changed cell and diagonally past it all start at one of its neighbors
+        for &cell in &changed {
+            self.update_cell(cell, start_cell);
+            for (neighbor, _) in self.neighbors(cell) {
+                self.update_cell(neighbor, start_cell);
+            }
+        }
+        
+        self.compute_shortest_path(start_cell);
+        let corners = self.descend(start, start_cell)?;
+        
+        let mut samples = Vec::new();
+        let mut from = start;
+        for &corner in &corners {
+            samples.extend(segment_samples(from, corner).into_iter()
+                .map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed: false }));
+            from = corner;
+        }
+        
+        let goal = self.goal_point;
+        let before_goal = if corners.len() > 1 { corners[corners.len() - 2] } else { start };
+        let heading = (goal[1] - before_goal[1]).atan2(goal[0] - before_goal[0]);
+        Some((samples, heading))
+    }
+    
+    /// follows the cheapest step to the goal from the robot cell, keeping only the cells the direction changes at
+    fn descend(&self, start: [f32; 2], start_cell: usize) -> Option<Vec<[f32; 2]>> {
+        if !self.g[start_cell].is_finite() {
+            return None;
+        }
+        
+        let mut cells = vec![start_cell];
+        let mut cell = start_cell;
+        while cell != self.goal {
+            cell = self.neighbors(cell).into_iter()
+                .map(|(next, cost)| (next, cost + self.g[next]))
+                .filter(|(_, total)| total.is_finite())
+                .min_by(|a, b| a.1.total_cmp(&b.1))?
+                .0;
+            cells.push(cell);
+            if cells.len() > self.costs.len() {
+                return None;
+            }
+        }
+        
+        if cells.len() == 1 {
+            return Some(vec![self.goal_point]);
+        }
+        let mut points: Vec<[f32; 2]> = cells.iter().map(|&cell| self.center_of(cell)).collect();
+        points[0] = start;
+        *points.last_mut().unwrap() = self.goal_point;
+        
+        let mut corners = Vec::new();
+        for i in 1..points.len() {
+            let straight = points.get(i + 1).is_some_and(|next| {
+                let (a, b) = ([points[i][0] - points[i - 1][0], points[i][1] - points[i - 1][1]], [next[0] - points[i][0], next[1] - points[i][1]]);
+                (a[0] * b[1] - a[1] * b[0]).abs() < 1e-6 && a[0] * b[0] + a[1] * b[1] > 0.0
+            });
+            if !straight {
+                corners.push(points[i]);
+            }
+        }
+        Some(corners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const START: [f32; 2] = [0.25, 1.05];
+    const GOAL: [f32; 2] = [2.75, 1.05];
+    
+    /// a free 3 by 2 meter grid with a wall across most of it, so the route has to bend
+    fn walled_costmap() -> Costmap {
+        let (columns, rows) = (30, 20);
+        let mut cells = vec![CellCost::Free; columns * rows];
+        for row in 4..rows {
+            cells[row * columns + 12] = CellCost::Lethal;
+        }
+        for column in 18..22 {
+            cells[8 * columns + column] = CellCost::Inflated(0.5);
+        }
+        Costmap { resolution: 0.1, columns, rows, cells }
+    }
+    
+    fn a_star_cost(costmap: &Costmap) -> f32 {
+        let (start, goal) = (costmap.cell_of(START).unwrap(), costmap.cell_of(GOAL).unwrap());
+        costmap.search(start, goal, false).unwrap().1
+    }
+    
+    /// the cells the plan steps through from the start, following the cheapest step to the goal
+    fn route_cells(search: &DStarLite) -> Vec<usize> {
+        let mut cells = vec![search.cell_of(START).unwrap()];
+        while *cells.last().unwrap() != search.goal {
+            let cell = *cells.last().unwrap();
+            let (next, _) = search.neighbors(cell).into_iter()
+                .map(|(next, cost)| (next, cost + search.g[next]))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            cells.push(next);
+        }
+        cells
+    }
+    
+    #[test]
+    fn first_plan_matches_a_star() {
+        let costmap = walled_costmap();
+        let mut search = DStarLite::new(&costmap, GOAL).unwrap();
+        let (samples, _) = search.plan(&costmap, START).unwrap();
+        
+        let start = search.cell_of(START).unwrap();
+        assert!((search.g[start] - a_star_cost(&costmap)).abs() < 1e-4);
+        let end = samples.last().unwrap().position;
+        assert!((end.x - GOAL[0]).abs() < 1e-4 && (end.y - GOAL[1]).abs() < 1e-4);
+        assert!(route_cells(&search).iter().all(|&cell| costmap.cells[cell] != CellCost::Lethal));
+    }
+    
+    #[test]
+    fn repair_matches_a_plan_from_scratch() {
+        let mut costmap = walled_costmap();
+        let mut repaired = DStarLite::new(&costmap, GOAL).unwrap();
+        repaired.plan(&costmap, START).unwrap();
+        
+        // block a cell the first route passes through, away from the start and goal
+        let route = route_cells(&repaired);
+        let blocked = route[route.len() / 2];
+        costmap.cells[blocked] = CellCost::Lethal;
+        
+        let (repaired_samples, repaired_heading) = repaired.plan(&costmap, START).unwrap();
+        let mut scratch = DStarLite::new(&costmap, GOAL).unwrap();
+        let (scratch_samples, scratch_heading) = scratch.plan(&costmap, START).unwrap();
+        
+        let start = scratch.cell_of(START).unwrap();
+        assert!((repaired.g[start] - scratch.g[start]).abs() < 1e-4);
+        assert!((scratch.g[start] - a_star_cost(&costmap)).abs() < 1e-4);
+        assert!(!route_cells(&repaired).contains(&blocked));
+        assert_eq!(repaired_samples.len(), scratch_samples.len());
+        for (a, b) in repaired_samples.iter().zip(&scratch_samples) {
+            assert!(a.position.distance_to(&b.position) < 1e-4);
+        }
+        assert!((repaired_heading - scratch_heading).abs() < 1e-4);
+        assert!(repaired.expanded < scratch.expanded, "repair expanded {} cells, a new search {}", repaired.expanded, scratch.expanded);
+    }
+}
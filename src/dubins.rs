@@ -0,0 +1,454 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::f32::consts::{PI, TAU};
+
+use crate::position::Position;
+
+pub const DEFAULT_TURN_RADIUS: f32 = 0.5;
+const SAMPLE_SPACING: f32 = 0.05; // meters between the points of the planned path
+const CUSP_STEP: f32 = PI / 12.0; // the arcs driven before changing direction are tried in these steps, up to half a turn
+const REVERSE_COST: f32 = 1.5; // driving backwards counts this much more than driving forwards
+const CUSP_COST: f32 = 0.5; // meters added for stopping and changing direction
+const TOLERANCE: f32 = 1e-3;
+
+/// inflated obstacle the path may touch but not enter
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Circle {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+/// where a leg starts, heading is the direction the front of the robot faces
+#[derive(Debug, Copy, Clone)]
+pub struct Pose {
+    pub x: f32,
+    pub y: f32,
+    pub heading: f32,
+}
+
+/// a point of the planned path, reversed while the robot drives backwards to it
+#[derive(Debug, Copy, Clone)]
+pub struct PathSample {
+    pub position: Position,
+    pub reversed: bool,
+}
+
+/// circle driven counter clockwise (turn 1, left) or clockwise (turn -1, right), a point when the radius is 0
+#[derive(Debug, Copy, Clone)]
+struct TurnCircle {
+    x: f32,
+    y: f32,
+    radius: f32,
+    turn: f32,
+}
+
+impl TurnCircle {
+    /// the circle turning left or right from a point moving in a direction
+    fn from_motion(x: f32, y: f32, direction: f32, radius: f32, turn: f32) -> Self {
+        Self {
+            x: x - turn * radius * direction.sin(),
+            y: y + turn * radius * direction.cos(),
+            radius,
+            turn,
+        }
+    }
+    
+    fn angle_of(&self, point: [f32; 2]) -> f32 {
+        (point[1] - self.y).atan2(point[0] - self.x)
+    }
+    
+    fn point_at(&self, angle: f32) -> [f32; 2] {
+        [self.x + self.radius * angle.cos(), self.y + self.radius * angle.sin()]
+    }
+    
+    /// angle swept driving along the circle from one point angle to another
+    fn sweep(&self, from: f32, to: f32) -> f32 {
+        if self.radius <= 0.0 {
+            return 0.0;
+        }
+        if self.turn > 0.0 { (to - from).rem_euclid(TAU) } else { (from - to).rem_euclid(TAU) }
+    }
+    
+    /// direction of motion at a point angle
+    fn direction_at(&self, angle: f32) -> f32 {
+        angle + self.turn * PI / 2.0
+    }
+    
+    fn arc_samples(&self, from: f32, sweep: f32) -> Vec<[f32; 2]> {
+        if self.radius * sweep < TOLERANCE {
+            return Vec::new();
+        }
+        let steps = ((self.radius * sweep / SAMPLE_SPACING).ceil() as usize).max(1);
+        (1..=steps)
+            .map(|i| self.point_at(from + self.turn * sweep * i as f32 / steps as f32))
+            .collect()
+    }
+}
+
+/// the straight segment leaving one circle and arriving at the next in their driving directions,
+/// None when one circle lies inside the other so there is no such tangent
+fn tangent(from: &TurnCircle, to: &TurnCircle) -> Option<([f32; 2], [f32; 2])> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let distance = (dx * dx + dy * dy).sqrt();
+    let offset = to.turn * to.radius - from.turn * from.radius;
+    if distance < TOLERANCE || offset.abs() > distance {
+        return None;
+    }
+    
+    let direction = dy.atan2(dx) - (offset / distance).asin();
+    // the center of a circle is on the side it turns to
+    let (nx, ny) = (-direction.sin(), direction.cos());
+    let start = [from.x - from.turn * from.radius * nx, from.y - from.turn * from.radius * ny];
+    let end = [to.x - to.turn * to.radius * nx, to.y - to.turn * to.radius * ny];
+    Some((start, end))
+}
+
+/// points every few centimeters along a segment, after its start
+pub fn segment_samples(start: [f32; 2], end: [f32; 2]) -> Vec<[f32; 2]> {
+    let length = segment_length(start, end);
+    if length < TOLERANCE {
+        return Vec::new();
+    }
+    let steps = ((length / SAMPLE_SPACING).ceil() as usize).max(1);
+    (1..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            [start[0] + (end[0] - start[0]) * t, start[1] + (end[1] - start[1]) * t]
+        })
+        .collect()
+}
+
+pub fn segment_length(start: [f32; 2], end: [f32; 2]) -> f32 {
+    ((end[0] - start[0]).powi(2) + (end[1] - start[1]).powi(2)).sqrt()
+}
+
+/// whether a segment stays out of every circle
+pub fn segment_is_clear(start: [f32; 2], end: [f32; 2], obstacles: &[Circle]) -> bool {
+    let (ex, ey) = (end[0] - start[0], end[1] - start[1]);
+    let length_squared = ex * ex + ey * ey;
+    obstacles.iter().all(|circle| {
+        let t = if length_squared > 0.0 {
+            (((circle.x - start[0]) * ex + (circle.y - start[1]) * ey) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (px, py) = (start[0] + ex * t - circle.x, start[1] + ey * t - circle.y);
+        (px * px + py * py).sqrt() >= circle.radius - TOLERANCE
+    })
+}
+
+fn points_are_clear(points: &[[f32; 2]], obstacles: &[Circle]) -> bool {
+    points.iter().all(|point| {
+        obstacles.iter().all(|circle| {
+            ((point[0] - circle.x).powi(2) + (point[1] - circle.y).powi(2)).sqrt() >= circle.radius - TOLERANCE
+        })
+    })
+}
+
+/// a circle the search can start on, after an optional arc driven in the other direction
+struct Source {
+    circle: TurnCircle,
+    reversed: bool,
+    cost: f32,
+    prefix: Vec<PathSample>, // the arc before changing direction
+}
+
+/// arrival on a circle, keyed by the circle it came from so every tangent is one node
+struct Node {
+    from: usize, // circle the node came from, itself for a source
+    circle: usize,
+    arrival: f32, // point angle the circle is entered at
+    reversed: bool,
+    parent: Option<usize>,
+    departure: [f32; 2], // where the parent circle was left
+    source: Option<usize>,
+}
+
+#[derive(PartialEq)]
+struct Queued(f32, usize);
+
+impl Eq for Queued {}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // lowest cost first
+        other.0.total_cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// shortest path of straight segments and arcs of at least the turn radius from the pose to the goal,
+/// tangent to the obstacle circles. with reversing the robot may back up, once, before or after driving forwards.
+/// returns the points of the path after the start and the heading the robot arrives with
+pub fn plan(start: Pose, goal: [f32; 2], obstacles: &[Circle], turn_radius: f32, reversing: bool) -> Option<(Vec<PathSample>, f32)> {
+    let turn_radius = turn_radius.max(SAMPLE_SPACING);
+    // obstacles the robot already is in, or has to drive into, are left out
+    let obstacles: Vec<Circle> = obstacles.iter()
+        .filter(|circle| {
+            let inside = |point: [f32; 2]| ((point[0] - circle.x).powi(2) + (point[1] - circle.y).powi(2)).sqrt() < circle.radius;
+            !inside([start.x, start.y]) && !inside(goal)
+        })
+        .copied()
+        .collect();
+    
+    let sources = start_sources(start, turn_radius, reversing, &obstacles);
+    
+    // the circles driven around the obstacles, then the goal as a point
+    let mut circles: Vec<TurnCircle> = Vec::with_capacity(obstacles.len() * 2 + 1);
+    for circle in &obstacles {
+        for turn in [1.0, -1.0] {
+            circles.push(TurnCircle { x: circle.x, y: circle.y, radius: circle.radius.max(turn_radius), turn });
+        }
+    }
+    let goal_index = circles.len();
+    circles.push(TurnCircle { x: goal[0], y: goal[1], radius: 0.0, turn: 1.0 });
+    
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut best: HashMap<(usize, usize, bool), f32> = HashMap::new(); // by (from, to, reversed), sources come from usize::MAX - i
+    let mut queue = BinaryHeap::new();
+    
+    for (i, source) in sources.iter().enumerate() {
+        nodes.push(Node {
+            from: usize::MAX - i,
+            circle: usize::MAX - i,
+            arrival: source.circle.angle_of(source_point(source, start)),
+            reversed: source.reversed,
+            parent: None,
+            departure: source_point(source, start),
+            source: Some(i),
+        });
+        queue.push(Queued(source.cost, nodes.len() - 1));
+    }
+    
+    let circle_of = |node: &Node| -> TurnCircle {
+        match node.source {
+            Some(i) => sources[i].circle,
+            None => circles[node.circle],
+        }
+    };
+    
+    let mut reached = None;
+    while let Some(Queued(cost, index)) = queue.pop() {
+        // a cheaper way into the same tangent was found after this one was queued
+        let node = &nodes[index];
+        if best.get(&(node.from, node.circle, node.reversed)).is_some_and(|&known| known < cost) {
+            continue;
+        }
+        if nodes[index].circle == goal_index {
+            reached = Some(index);
+            break;
+        }
+        
+        let from = circle_of(&nodes[index]);
+        let reversed = nodes[index].reversed;
+        let scale = if reversed { REVERSE_COST } else { 1.0 };
+        
+        for (next, to) in circles.iter().enumerate() {
+            // the same obstacle driven the other way round has no tangent
+            if next == nodes[index].circle || (from.x == to.x && from.y == to.y) {
+                continue;
+            }
+            let Some((departure, arrival)) = tangent(&from, to) else {
+                continue;
+            };
+            
+            let sweep = from.sweep(nodes[index].arrival, from.angle_of(departure));
+            if !segment_is_clear(departure, arrival, &obstacles)
+                || !points_are_clear(&from.arc_samples(nodes[index].arrival, sweep), &obstacles) {
+                continue;
+            }
+            
+            let next_cost = cost + (from.radius * sweep + segment_length(departure, arrival)) * scale;
+            let key = (nodes[index].circle, next, reversed);
+            if best.get(&key).is_some_and(|&known| known <= next_cost) {
+                continue;
+            }
+            best.insert(key, next_cost);
+            
+            nodes.push(Node {
+                from: nodes[index].circle,
+                circle: next,
+                arrival: to.angle_of(arrival),
+                reversed,
+                parent: Some(index),
+                departure,
+                source: None,
+            });
+            queue.push(Queued(next_cost, nodes.len() - 1));
+        }
+    }
+    
+    // walk back from the goal, collecting the arc and segment that led to each node
+    let mut chain = vec![reached?];
+    while let Some(parent) = nodes[*chain.last()?].parent {
+        chain.push(parent);
+    }
+    chain.reverse();
+    
+    let first = &nodes[chain[0]];
+    let reversed = first.reversed;
+    let mut samples: Vec<PathSample> = first.source.map(|i| sources[i].prefix.clone()).unwrap_or_default();
+    let mut heading = 0.0;
+    for pair in chain.windows(2) {
+        let (node, next) = (&nodes[pair[0]], &nodes[pair[1]]);
+        let circle = circle_of(node);
+        let departure_angle = circle.angle_of(next.departure);
+        let sweep = circle.sweep(node.arrival, departure_angle);
+        
+        let mut points = circle.arc_samples(node.arrival, sweep);
+        points.extend(segment_samples(next.departure, circle_of(next).point_at(next.arrival)));
+        samples.extend(points.into_iter().map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed }));
+        
+        // the tangent segment leaves in the direction of motion at the departure point
+        let motion = circle.direction_at(departure_angle);
+        heading = if reversed { motion + PI } else { motion };
+    }
+    
+    Some((samples, heading))
+}
+
+/// the point a source circle is entered at, the start or the cusp after its prefix
+fn source_point(source: &Source, start: Pose) -> [f32; 2] {
+    source.prefix.last()
+        .map(|sample| [sample.position.x, sample.position.y])
+        .unwrap_or([start.x, start.y])
+}
+
+/// circles the robot can turn onto from the start pose, forwards, and with reversing also backwards
+/// and after an arc driven the other way
+fn start_sources(start: Pose, turn_radius: f32, reversing: bool, obstacles: &[Circle]) -> Vec<Source> {
+    let gears: &[bool] = if reversing { &[false, true] } else { &[false] };
+    let mut sources = Vec::new();
+    
+    for &reversed in gears {
+        let direction = if reversed { start.heading + PI } else { start.heading };
+        for turn in [1.0, -1.0] {
+            let circle = TurnCircle::from_motion(start.x, start.y, direction, turn_radius, turn);
+            sources.push(Source { circle, reversed, cost: 0.0, prefix: Vec::new() });
+            
+            if !reversing {
+                continue;
+            }
+            
+            // drive part of the way round, stop, and carry on in the other direction
+            let start_angle = circle.angle_of([start.x, start.y]);
+            let scale = if reversed { REVERSE_COST } else { 1.0 };
+            let mut sweep = CUSP_STEP;
+            while sweep <= PI + TOLERANCE {
+                let arc = circle.arc_samples(start_angle, sweep);
+                if !points_are_clear(&arc, obstacles) {
+                    break;
+                }
+                
+                let [x, y] = circle.point_at(start_angle + turn * sweep);
+                let cusp_direction = circle.direction_at(start_angle + turn * sweep) + PI;
+                let prefix: Vec<PathSample> = arc.iter()
+                    .map(|&[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed })
+                    .collect();
+                for next_turn in [1.0, -1.0] {
+                    sources.push(Source {
+                        circle: TurnCircle::from_motion(x, y, cusp_direction, turn_radius, next_turn),
+                        reversed: !reversed,
+                        cost: turn_radius * sweep * scale + CUSP_COST,
+                        prefix: prefix.clone(),
+                    });
+                }
+                sweep += CUSP_STEP;
+            }
+        }
+    }
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const TURN_RADIUS: f32 = 0.5;
+    const ORIGIN_POSE: Pose = Pose { x: 0.0, y: 0.0, heading: 0.0 };
+    
+    /// the start and every planned point after it
+    fn points(samples: &[PathSample]) -> Vec<[f32; 2]> {
+        std::iter::once([0.0, 0.0])
+            .chain(samples.iter().map(|sample| [sample.position.x, sample.position.y]))
+            .collect()
+    }
+    
+    fn length(points: &[[f32; 2]]) -> f32 {
+        points.windows(2).map(|pair| segment_length(pair[0], pair[1])).sum()
+    }
+    
+    /// radius of the circle through three points, infinite when they are in a line
+    fn circumradius(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+        let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        if cross.abs() < 1e-9 {
+            return f32::INFINITY;
+        }
+        segment_length(a, b) * segment_length(b, c) * segment_length(a, c) / (2.0 * cross.abs())
+    }
+    
+    #[test]
+    fn straight_ahead_is_a_single_segment() {
+        let (samples, heading) = plan(ORIGIN_POSE, [5.0, 0.0], &[], TURN_RADIUS, false).unwrap();
+        assert!((length(&points(&samples)) - 5.0).abs() < 1e-3);
+        assert!(heading.abs() < 1e-3);
+    }
+    
+    #[test]
+    fn lengths_match_turn_then_straight_words() {
+        // goal to the left: a left turn about (0, 0.5) until the goal 1.5 m from its center is straight ahead,
+        // then the tangent of sqrt(1.5^2 - 0.5^2) m. the turn sweeps pi/2 + asin(1/3)
+        let sweep = PI / 2.0 + (1.0f32 / 3.0).asin();
+        let (samples, heading) = plan(ORIGIN_POSE, [0.0, 2.0], &[], TURN_RADIUS, false).unwrap();
+        assert!((length(&points(&samples)) - (TURN_RADIUS * sweep + 2.0f32.sqrt())).abs() < 0.01);
+        assert!((heading - sweep).abs() < 0.01);
+        assert!(samples.iter().all(|sample| !sample.reversed));
+        
+        // goal behind: either turn, here the left one, sweeps from straight below its center round to where the goal
+        // sqrt(4.25) m away is ahead, leaving a tangent of 2 m
+        let distance = 4.25f32.sqrt();
+        let sweep = 1.5 * PI + 0.25f32.atan() - (TURN_RADIUS / distance).acos();
+        let (samples, _) = plan(ORIGIN_POSE, [-2.0, 0.0], &[], TURN_RADIUS, false).unwrap();
+        assert!((length(&points(&samples)) - (TURN_RADIUS * sweep + 2.0)).abs() < 0.01);
+    }
+    
+    #[test]
+    fn no_arc_is_tighter_than_the_turn_radius() {
+        let obstacles = [Circle { x: 3.0, y: 0.2, radius: 1.0 }, Circle { x: 1.5, y: 2.5, radius: 0.3 }];
+        for goal in [[0.0, 2.0], [-2.0, 0.0], [1.0, -1.5], [6.0, 0.0], [3.0, 3.0]] {
+            let (samples, _) = plan(ORIGIN_POSE, goal, &obstacles, TURN_RADIUS, false).unwrap();
+            for triple in points(&samples).windows(3) {
+                let radius = circumradius(triple[0], triple[1], triple[2]);
+                assert!(radius > TURN_RADIUS * 0.98, "turn of radius {} at {:?} on the way to {:?}", radius, triple[1], goal);
+            }
+        }
+    }
+    
+    #[test]
+    fn path_wraps_tangent_around_an_inflated_circle() {
+        let circle = Circle { x: 3.0, y: 0.2, radius: 1.0 };
+        let (samples, _) = plan(ORIGIN_POSE, [6.0, 0.0], &[circle], TURN_RADIUS, false).unwrap();
+        let points = points(&samples);
+        let distance = |point: [f32; 2]| segment_length(point, [circle.x, circle.y]);
+        
+        assert!(points.iter().all(|&point| distance(point) >= circle.radius - TOLERANCE));
+        // the path touches the circle and runs along it there, never cutting in or bouncing off at an angle
+        let touching: Vec<usize> = (1..points.len() - 1)
+            .filter(|&i| (distance(points[i]) - circle.radius).abs() < TOLERANCE)
+            .collect();
+        assert!(!touching.is_empty());
+        for i in touching {
+            let (before, point, after) = (points[i - 1], points[i], points[i + 1]);
+            let motion = [after[0] - before[0], after[1] - before[1]];
+            let radial = [point[0] - circle.x, point[1] - circle.y];
+            let cosine = (motion[0] * radial[0] + motion[1] * radial[1]) / (segment_length(before, after) * distance(point));
+            assert!(cosine.abs() < 0.05, "path crosses the circle at {:?} with cosine {}", point, cosine);
+        }
+    }
+}
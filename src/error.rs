@@ -0,0 +1,53 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// why a path could not be planned, or was planned but is not safe to drive
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanError {
+    GoalInObstacle(String), // name of the obstacle covering the target
+    NoConvergence { iterations: usize }, // the path was kept but may still pass too close to obstacles
+    NoPath, // the planner found no way around the obstacles, a straight line is shown instead
+    MovingObstacle { name: String, time: f32 }, // a moving obstacle will be on the path when the robot gets there
+    DeadlineMissed { arrival: f32, deadline: f32 }, // the earliest the robot can arrive is after the deadline, in seconds
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::GoalInObstacle(name) => write!(f, "the target is inside obstacle {}", name),
+            PlanError::NoConvergence { iterations } => write!(f, "path optimization did not converge after {} iterations", iterations),
+            PlanError::NoPath => write!(f, "no path around the obstacles reaches the target"),
+            PlanError::MovingObstacle { name, time } => write!(f, "obstacle {} will be on the path {:.1}s after leaving", name, time),
+            PlanError::DeadlineMissed { arrival, deadline } => write!(f, "the robot can not arrive before {:.1}s, {:.1}s after the deadline", arrival, arrival - deadline),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+/// why a model file could not be loaded
+#[derive(Debug)]
+pub enum ModelError {
+    FileNotFound(PathBuf),
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::FileNotFound(path) => write!(f, "model file {} not found", path.display()),
+            ModelError::Io { path, source } => write!(f, "could not read {}: {}", path.display(), source),
+            ModelError::Parse { path, message } => write!(f, "could not parse {}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModelError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant; // std clocks panic in the browser
+
+use crate::config;
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::robot::Robot;
+use crate::planner::Planner;
+use crate::scene_generator::{RandomSceneConfig, generate_random_scene};
+
+/// outcome of planning through one random scene
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    pub seed: u64,
+    pub success: bool, // planned without an error and the spline keeps the robot radius from every hard obstacle
+    pub length: f32,
+    pub clearance: f32,
+    pub planning_time_ms: f32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EvaluationSummary {
+    pub runs: Vec<RunResult>,
+}
+
+impl EvaluationSummary {
+    pub fn success_rate(&self) -> f32 {
+        if self.runs.is_empty() {
+            return 0.0;
+        }
+        self.successful().count() as f32 / self.runs.len() as f32
+    }
+    
+    fn successful(&self) -> impl Iterator<Item = &RunResult> {
+        self.runs.iter().filter(|run| run.success)
+    }
+    
+    /// averaged over successful runs only
+    pub fn average_length(&self) -> f32 {
+        average(self.successful().map(|run| run.length))
+    }
+    
+    /// averaged over successful runs only
+    pub fn average_clearance(&self) -> f32 {
+        average(self.successful().map(|run| run.clearance))
+    }
+    
+    pub fn average_planning_time_ms(&self) -> f32 {
+        average(self.runs.iter().map(|run| run.planning_time_ms))
+    }
+    
+    pub fn max_planning_time_ms(&self) -> f32 {
+        self.runs.iter().map(|run| run.planning_time_ms).fold(0.0, f32::max)
+    }
+}
+
+fn average(values: impl Iterator<Item = f32>) -> f32 {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 { 0.0 } else { sum / count as f32 }
+}
+
+/// plans from start to goal through `runs` random scenes seeded from scene.seed upwards, one scene
+/// at a time so it can be spread over frames
+pub struct Evaluation {
+    robot: Robot,
+    scene: RandomSceneConfig,
+    runs: usize,
+    goal: Position,
+    path_segments: usize,
+    pub summary: EvaluationSummary,
+}
+
+impl Evaluation {
+    pub fn new(
+        scene: &RandomSceneConfig,
+        runs: usize,
+        start: Position,
+        goal: Position,
+        path_segments: usize,
+        planner: Planner,
+    ) -> Result<Evaluation, Box<dyn std::error::Error>> {
+        let mut robot = Robot::create_default()?;
+        robot.planner = planner;
+        robot.model.move_to(start);
+        
+        Ok(Evaluation { robot, scene: scene.clone(), runs, goal, path_segments, summary: EvaluationSummary::default() })
+    }
+    
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.summary.runs.len() >= self.runs
+    }
+    
+    /// plans through the next scene, doing nothing once every run is done
+    pub fn run_next(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_finished() {
+            return Ok(());
+        }
+        
+        let start = self.robot.model.config.position;
+        let seed = self.scene.seed.wrapping_add(self.summary.runs.len() as u64);
+        let config = RandomSceneConfig { seed, ..self.scene.clone() };
+        
+        let obstacles = generate_random_scene(&config, start, self.goal).iter()
+            .map(Obstacle::from_config)
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let started = Instant::now();
+        let planned = self.robot.generate_path(&self.goal, self.path_segments, &obstacles);
+        let planning_time_ms = started.elapsed().as_secs_f32() * 1000.0;
+        
+        // measured on the spline the robot drives, not only the points it runs through
+        let clearance = self.robot.spline_clearance(&obstacles);
+        
+        self.summary.runs.push(RunResult {
+            seed,
+            success: planned.is_ok() && clearance >= config::get().robot.radius,
+            length: self.robot.path_length(),
+            clearance: if obstacles.is_empty() { 0.0 } else { clearance },
+            planning_time_ms,
+        });
+        Ok(())
+    }
+}
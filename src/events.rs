@@ -0,0 +1,43 @@
+/// something that changed in the world, published when it happens and handled once per frame
+#[derive(Debug, Clone, Copy)]
+pub enum WorldEvent {
+    ObstacleAdded,
+    ObstacleRemoved(usize), // later obstacles move down one index
+    ObstacleMoved,
+    ObstaclesEdited, // edited in place, such as a new radius, layer or footprint
+    ObstaclesCleared,
+    TargetChanged,
+    PathInvalidated, // the path has to be planned again, such as after a via point changed
+    SpeedZonesChanged, // only the speeds along the path change, it stays as planned
+}
+
+impl WorldEvent {
+    /// whether the obstacle field changed, so the gradient overlay is out of date
+    pub fn changes_field(&self) -> bool {
+        matches!(self,
+            WorldEvent::ObstacleAdded | WorldEvent::ObstacleRemoved(_) | WorldEvent::ObstacleMoved |
+            WorldEvent::ObstaclesEdited | WorldEvent::ObstaclesCleared)
+    }
+    
+    /// whether the current path may no longer reach the target or avoid the obstacles
+    pub fn invalidates_path(&self) -> bool {
+        self.changes_field() || matches!(self, WorldEvent::TargetChanged | WorldEvent::PathInvalidated)
+    }
+}
+
+/// queue of world events, published during a frame and drained by the subscribers after it
+#[derive(Debug, Default)]
+pub struct EventBus {
+    queue: Vec<WorldEvent>,
+}
+
+impl EventBus {
+    pub fn publish(&mut self, event: WorldEvent) {
+        self.queue.push(event);
+    }
+    
+    /// the events published since the last drain, oldest first
+    pub fn drain(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.queue)
+    }
+}
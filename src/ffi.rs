@@ -0,0 +1,198 @@
+// c api over the planner, see `include/path_planner.h`, every function taking a world pointer
+// expects one returned by `planner_world_create` that was not destroyed yet. a panic in the planner
+// is caught at the boundary, unwinding into the caller would abort it
+
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::error::PlanError;
+use crate::model::{Model, ModelConfig};
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::robot::Robot;
+use crate::config;
+
+pub const PLANNER_OK: i32 = 0;
+pub const PLANNER_NULL_WORLD: i32 = -1;
+pub const PLANNER_PANIC: i32 = -2; // the planner failed unexpectedly, the world may be left half planned
+pub const PLANNER_GOAL_IN_OBSTACLE: i32 = 1;
+pub const PLANNER_NO_CONVERGENCE: i32 = 2; // the path was kept but may pass too close to obstacles
+pub const PLANNER_NO_PATH: i32 = 3;
+pub const PLANNER_MOVING_OBSTACLE: i32 = 4;
+pub const PLANNER_DEADLINE_MISSED: i32 = 5;
+
+/// a robot and the circle obstacles it plans around, without any models loaded from files
+pub struct PlannerWorld {
+    robot: Robot,
+    obstacles: Vec<Obstacle>,
+}
+
+/// a model without a mesh, only its position and size matter for planning
+fn circle_model(name: &str, x: f32, y: f32, radius: f32) -> Model {
+    Model {
+        wires: Vec::new(),
+        config: ModelConfig { name: name.to_string(), position: Position::new(x, y, 0.0), scale: radius * 2.0 },
+    }
+}
+
+/// runs the body of an entry point, returning `failed` if it panics
+fn guarded<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+/// reads the planner config (robot radius, buffer, planner tuning) from a toml file, must be called
+/// before the first world is created, defaults are used without it or when path is null
+///
+/// # Safety
+/// path must be null or a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn planner_load_config(path: *const c_char) {
+    if path.is_null() {
+        return;
+    }
+    guarded((), || config::init(&CStr::from_ptr(path).to_string_lossy()));
+}
+
+/// a world with the robot at a start position and no obstacles, free it with `planner_world_destroy`,
+/// null if it could not be created
+#[no_mangle]
+pub extern "C" fn planner_world_create(start_x: f32, start_y: f32) -> *mut PlannerWorld {
+    guarded(std::ptr::null_mut(), || {
+        let robot = Robot::new(circle_model("robot", start_x, start_y, config::get().robot.scale / 2.0));
+        Box::into_raw(Box::new(PlannerWorld { robot, obstacles: Vec::new() }))
+    })
+}
+
+/// # Safety
+/// world must be null or from `planner_world_create`, it can not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn planner_world_destroy(world: *mut PlannerWorld) {
+    if !world.is_null() {
+        guarded((), || drop(Box::from_raw(world)));
+    }
+}
+
+/// moves the robot the next plan starts from
+///
+/// # Safety
+/// world must be null or a live world from `planner_world_create`
+#[no_mangle]
+pub unsafe extern "C" fn planner_set_start(world: *mut PlannerWorld, x: f32, y: f32) {
+    let Some(world) = world.as_mut() else { return; };
+    guarded((), || world.robot.model.move_to(Position::new(x, y, 0.0)));
+}
+
+/// adds a circle obstacle, returning its index or a negative error code
+///
+/// # Safety
+/// world must be null or a live world from `planner_world_create`
+#[no_mangle]
+pub unsafe extern "C" fn planner_add_circle_obstacle(world: *mut PlannerWorld, x: f32, y: f32, radius: f32) -> i32 {
+    let Some(world) = world.as_mut() else { return PLANNER_NULL_WORLD; };
+    guarded(PLANNER_PANIC, || {
+        let name = format!("obstacle {}", world.obstacles.len() + 1);
+        world.obstacles.push(Obstacle::new(circle_model(&name, x, y, radius)));
+        world.obstacles.len() as i32 - 1
+    })
+}
+
+/// removes every obstacle
+///
+/// # Safety
+/// world must be null or a live world from `planner_world_create`
+#[no_mangle]
+pub unsafe extern "C" fn planner_clear_obstacles(world: *mut PlannerWorld) {
+    if let Some(world) = world.as_mut() {
+        guarded((), || world.obstacles.clear());
+    }
+}
+
+/// plans from the start to a target with the gradient planner, returning `PLANNER_OK` or why the path
+/// is not safe to drive, a path is still there to fetch unless the world was null
+///
+/// # Safety
+/// world must be null or a live world from `planner_world_create`
+#[no_mangle]
+pub unsafe extern "C" fn planner_plan(world: *mut PlannerWorld, target_x: f32, target_y: f32, segments: usize) -> i32 {
+    let Some(world) = world.as_mut() else { return PLANNER_NULL_WORLD; };
+    guarded(PLANNER_PANIC, || {
+        match world.robot.generate_path(&Position::new(target_x, target_y, 0.0), segments.max(2), &world.obstacles) {
+            Ok(()) => PLANNER_OK,
+            Err(PlanError::GoalInObstacle(_)) => PLANNER_GOAL_IN_OBSTACLE,
+            Err(PlanError::NoConvergence { .. }) => PLANNER_NO_CONVERGENCE,
+            Err(PlanError::NoPath) => PLANNER_NO_PATH,
+            Err(PlanError::MovingObstacle { .. }) => PLANNER_MOVING_OBSTACLE,
+            Err(PlanError::DeadlineMissed { .. }) => PLANNER_DEADLINE_MISSED,
+        }
+    })
+}
+
+/// number of points on the last planned path, the spline runs through them
+///
+/// # Safety
+/// world must be null or a live world from `planner_world_create`
+#[no_mangle]
+pub unsafe extern "C" fn planner_point_count(world: *const PlannerWorld) -> usize {
+    world.as_ref().map_or(0, |world| world.robot.path_points.len())
+}
+
+/// writes up to capacity path points as x, y pairs into points, returning how many were written
+///
+/// # Safety
+/// world must be null or a live world from `planner_world_create`, points must have room for
+/// 2 * capacity floats
+#[no_mangle]
+pub unsafe extern "C" fn planner_get_points(world: *const PlannerWorld, points: *mut f32, capacity: usize) -> usize {
+    let Some(world) = world.as_ref() else { return 0; };
+    if points.is_null() {
+        return 0;
+    }
+    
+    guarded(0, || {
+        let count = world.robot.path_points.len().min(capacity);
+        let out = std::slice::from_raw_parts_mut(points, count * 2);
+        for (pair, point) in out.chunks_exact_mut(2).zip(&world.robot.path_points) {
+            pair[0] = point.position.x;
+            pair[1] = point.position.y;
+        }
+        count
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn planned_points_stay_clear_of_an_obstacle() {
+        let (center, radius) = ([8.0, 4.5], 1.0);
+        unsafe {
+            let world = planner_world_create(2.0, 4.0);
+            assert!(!world.is_null());
+            assert_eq!(planner_add_circle_obstacle(world, center[0], center[1], radius), 0);
+            assert_eq!(planner_plan(world, 14.0, 4.0, 40), PLANNER_OK);
+            
+            let count = planner_point_count(world);
+            let mut points = vec![0.0f32; count * 2];
+            assert_eq!(planner_get_points(world, points.as_mut_ptr(), count), count);
+            planner_world_destroy(world);
+            
+            assert!(count >= 2);
+            assert!((points[0] - 2.0).abs() < 1e-3 && (points[1] - 4.0).abs() < 1e-3);
+            assert!((points[count * 2 - 2] - 14.0).abs() < 1e-3 && (points[count * 2 - 1] - 4.0).abs() < 1e-3);
+            for pair in points.chunks_exact(2) {
+                let distance = (pair[0] - center[0]).hypot(pair[1] - center[1]);
+                assert!(distance > radius, "point ({}, {}) is inside the obstacle", pair[0], pair[1]);
+            }
+        }
+    }
+    
+    #[test]
+    fn null_world_is_reported() {
+        unsafe {
+            assert_eq!(planner_plan(std::ptr::null_mut(), 1.0, 1.0, 10), PLANNER_NULL_WORLD);
+            assert_eq!(planner_add_circle_obstacle(std::ptr::null_mut(), 1.0, 1.0, 1.0), PLANNER_NULL_WORLD);
+            assert_eq!(planner_point_count(std::ptr::null()), 0);
+        }
+    }
+}
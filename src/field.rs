@@ -1,62 +1,70 @@
-use nannou::color::*;
+use crate::color::{BLUE, RED, WHITE, YELLOW};
 
 use crate::wire::Wire;
 use crate::position::Position;
+use crate::config;
 
 
-pub const FIELD_WIDTH: f32 = 8.23;
-pub const FIELD_LENGTH: f32 = 16.46;
+/// field size across the alliance walls, from the config file
+pub fn field_width() -> f32 {
+    config::get().field.width
+}
+
+/// field size between the alliance walls, from the config file
+pub fn field_length() -> f32 {
+    config::get().field.length
+}
 
 pub fn field_border() -> [Wire; 9] {
 
     [
         // top edge
         Wire {
-            start: Position { x: 0.0, y: FIELD_WIDTH, z: 0.0 },
-            end: Position { x: FIELD_LENGTH/2.0, y: FIELD_WIDTH, z: 0.0 },
+            start: Position { x: 0.0, y: field_width(), z: 0.0 },
+            end: Position { x: field_length()/2.0, y: field_width(), z: 0.0 },
             color: BLUE
         },
         Wire {
-            start: Position { x: FIELD_LENGTH/2.0, y: FIELD_WIDTH, z: 0.0 },
-            end: Position { x: FIELD_LENGTH, y: FIELD_WIDTH, z: 0.0 },
+            start: Position { x: field_length()/2.0, y: field_width(), z: 0.0 },
+            end: Position { x: field_length(), y: field_width(), z: 0.0 },
             color: RED
         },
 
         // bottom edge
         Wire {
             start: Position { x: 0.0, y: 0.0, z: 0.0 },
-            end: Position { x: FIELD_LENGTH/2.0, y: 0.0, z: 0.0 },
+            end: Position { x: field_length()/2.0, y: 0.0, z: 0.0 },
             color: BLUE
         },
         Wire {
-            start: Position { x: FIELD_LENGTH/2.0, y: 0.0, z: 0.0 },
-            end: Position { x: FIELD_LENGTH, y: 0.0, z: 0.0 },
+            start: Position { x: field_length()/2.0, y: 0.0, z: 0.0 },
+            end: Position { x: field_length(), y: 0.0, z: 0.0 },
             color: RED
         },
 
         // left edge
         Wire {
             start: Position { x: 0.0, y: 0.0, z: 0.0 },
-            end: Position { x: 0.0, y: FIELD_WIDTH, z: 0.0 },
+            end: Position { x: 0.0, y: field_width(), z: 0.0 },
             color: BLUE
         },
 
         // right edge
         Wire {
-            start: Position { x: FIELD_LENGTH, y: 0.0, z: 0.0 },
-            end: Position { x: FIELD_LENGTH, y: FIELD_WIDTH, z: 0.0 },
+            start: Position { x: field_length(), y: 0.0, z: 0.0 },
+            end: Position { x: field_length(), y: field_width(), z: 0.0 },
             color: RED
         },
         Wire {
-            start: Position { x: FIELD_LENGTH, y: FIELD_WIDTH, z: 0.0 },
-            end: Position { x: FIELD_LENGTH, y: FIELD_WIDTH, z: 0.0 },
+            start: Position { x: field_length(), y: field_width(), z: 0.0 },
+            end: Position { x: field_length(), y: field_width(), z: 0.0 },
             color: YELLOW
         },
 
         // middle divider
         Wire {
-            start: Position { x: FIELD_LENGTH/2.0, y: 0.0, z: 0.0 },
-            end: Position { x: FIELD_LENGTH/2.0, y: FIELD_WIDTH, z: 0.0 },
+            start: Position { x: field_length()/2.0, y: 0.0, z: 0.0 },
+            end: Position { x: field_length()/2.0, y: field_width(), z: 0.0 },
             color: WHITE
         },
 
@@ -67,4 +75,30 @@ pub fn field_border() -> [Wire; 9] {
             color: WHITE
         },
     ]
-}
\ No newline at end of file
+}
+
+/// ground grid lines with the given spacing, extending past the field border by extent
+pub fn ground_grid(spacing: f32, extent: f32) -> Vec<Wire> {
+    let spacing = spacing.max(0.05);
+    let extent = extent.max(0.0);
+    let (min_x, max_x) = (-extent, field_length() + extent);
+    let (min_y, max_y) = (-extent, field_width() + extent);
+    
+    let grid_color = config::color(config::colors().grid);
+    let mut wires = Vec::new();
+    
+    // lines are anchored on the field origin so they stay aligned with the border
+    let mut x = (min_x / spacing).ceil() * spacing;
+    while x <= max_x {
+        wires.push(Wire::with_color(Position::new(x, min_y, 0.0), Position::new(x, max_y, 0.0), grid_color));
+        x += spacing;
+    }
+    
+    let mut y = (min_y / spacing).ceil() * spacing;
+    while y <= max_y {
+        wires.push(Wire::with_color(Position::new(min_x, y, 0.0), Position::new(max_x, y, 0.0), grid_color));
+        y += spacing;
+    }
+    
+    wires
+}
@@ -0,0 +1,59 @@
+use nannou::image::{self, imageops::FilterType};
+use std::path::Path;
+
+use crate::field::{field_length, field_width};
+use crate::position::Position;
+
+/// top down field drawing stretched over the field, stored as a grid of colored cells
+pub struct FieldImage {
+    cells: Vec<[u8; 3]>,
+    columns: usize,
+    rows: usize,
+}
+
+impl FieldImage {
+    /// loads an image and downsamples it to the given number of columns along the field length
+    pub fn load<P: AsRef<Path>>(path: P, columns: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let columns = columns.max(1);
+        let rows = ((columns as f32 * field_width() / field_length()).round() as usize).max(1);
+        
+        let image = image::open(path)?
+            .resize_exact(columns as u32, rows as u32, FilterType::Triangle)
+            .to_rgb8();
+        
+        let mut cells = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                // image rows go top to bottom, field y goes bottom to top
+                let pixel = image.get_pixel(column as u32, (rows - 1 - row) as u32);
+                cells.push(pixel.0);
+            }
+        }
+        
+        Ok(FieldImage { cells, columns, rows })
+    }
+    
+    /// corners of every cell on the ground plane with its color, scaled by brightness
+    pub fn cells(&self, brightness: f32) -> Vec<([Position; 4], nannou::color::Rgb<u8>)> {
+        let cell_length = field_length() / self.columns as f32;
+        let cell_width = field_width() / self.rows as f32;
+        let scale = |c: u8| (c as f32 * brightness).clamp(0.0, 255.0) as u8;
+        
+        let mut result = Vec::with_capacity(self.cells.len());
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let [r, g, b] = self.cells[row * self.columns + column];
+                let x0 = column as f32 * cell_length;
+                let y0 = row as f32 * cell_width;
+                let corners = [
+                    Position::new(x0, y0, 0.0),
+                    Position::new(x0 + cell_length, y0, 0.0),
+                    Position::new(x0 + cell_length, y0 + cell_width, 0.0),
+                    Position::new(x0, y0 + cell_width, 0.0),
+                ];
+                result.push((corners, nannou::color::rgb(scale(r), scale(g), scale(b))));
+            }
+        }
+        result
+    }
+}
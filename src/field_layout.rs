@@ -0,0 +1,66 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::model::ModelConfig;
+use crate::position::Position;
+
+/// bundled season layouts, name and json contents
+const PRESETS: [(&str, &str); 2] = [
+    ("2024 Crescendo", include_str!("../fields/2024_crescendo.json")),
+    ("2023 Charged Up", include_str!("../fields/2023_charged_up.json")),
+];
+
+fn default_model() -> String {
+    String::from("cube")
+}
+
+/// a season field description listing the fixed game elements to avoid
+#[derive(Debug, Deserialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub elements: Vec<FieldElement>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FieldElement {
+    pub name: String,
+    #[serde(default = "default_model")]
+    pub model: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub z: f32,
+    pub radius: f32,
+}
+
+impl FieldElement {
+    pub fn model_config(&self) -> ModelConfig {
+        ModelConfig {
+            name: self.model.clone(),
+            position: Position::new(self.x, self.y, self.z),
+            scale: self.radius * 2.0, // scale is diameter, radius*2
+        }
+    }
+}
+
+impl FieldLayout {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(json)?)
+    }
+    
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+    
+    pub fn preset_names() -> Vec<&'static str> {
+        PRESETS.iter().map(|(name, _)| *name).collect()
+    }
+    
+    pub fn preset(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let (_, json) = PRESETS.iter()
+            .find(|(preset_name, _)| *preset_name == name)
+            .ok_or_else(|| format!("unknown field preset {}", name))?;
+        Self::from_json(json)
+    }
+}
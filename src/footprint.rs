@@ -0,0 +1,156 @@
+/// 2d convex hull of a set of points in counter clockwise order, using the monotone chain algorithm
+pub fn convex_hull(points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup_by(|a, b| (a[0] - b[0]).abs() < 1e-6 && (a[1] - b[1]).abs() < 1e-6);
+    
+    if points.len() < 3 {
+        return points;
+    }
+    
+    let cross = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0]);
+    
+    let mut hull: Vec<[f32; 2]> = Vec::with_capacity(points.len() * 2);
+    // lower hull left to right, then upper hull right to left
+    for pass in [points.clone(), points.iter().rev().copied().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop(); // the last point starts the other half
+    }
+    
+    hull
+}
+
+/// distance from a point to the edge of a convex polygon, negative inside, and the unit direction
+/// that leads away from the polygon
+pub fn signed_distance(hull: &[[f32; 2]], point: [f32; 2]) -> (f32, [f32; 2]) {
+    let mut min_distance = f32::MAX;
+    let mut nearest_offset = [0.0, 0.0]; // from the nearest point on the edge to the point
+    let mut nearest_normal = [0.0, 0.0]; // outward normal of the nearest edge
+    let mut inside = hull.len() >= 3;
+    
+    for i in 0..hull.len() {
+        let a = hull[i];
+        let b = hull[(i + 1) % hull.len()];
+        let edge = [b[0] - a[0], b[1] - a[1]];
+        let to_point = [point[0] - a[0], point[1] - a[1]];
+        let length_squared = edge[0] * edge[0] + edge[1] * edge[1];
+        
+        // counter clockwise, so the outside of every edge is on its right
+        if edge[0] * to_point[1] - edge[1] * to_point[0] < 0.0 {
+            inside = false;
+        }
+        
+        let t = if length_squared > 0.0 {
+            ((to_point[0] * edge[0] + to_point[1] * edge[1]) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let offset = [to_point[0] - edge[0] * t, to_point[1] - edge[1] * t];
+        let distance = (offset[0] * offset[0] + offset[1] * offset[1]).sqrt();
+        
+        if distance < min_distance {
+            min_distance = distance;
+            nearest_offset = offset;
+            let length = length_squared.sqrt();
+            nearest_normal = if length > 0.0 { [edge[1] / length, -edge[0] / length] } else { [0.0, 0.0] };
+        }
+    }
+    
+    if inside || min_distance < 1e-6 {
+        let distance = if inside { -min_distance } else { min_distance };
+        (distance, nearest_normal)
+    } else {
+        (min_distance, [nearest_offset[0] / min_distance, nearest_offset[1] / min_distance])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const SQUARE: [[f32; 2]; 4] = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    
+    fn assert_near(a: [f32; 2], b: [f32; 2]) {
+        assert!((a[0] - b[0]).abs() < 1e-5 && (a[1] - b[1]).abs() < 1e-5, "{:?} is not {:?}", a, b);
+    }
+    
+    /// twice the signed area, positive for counter clockwise polygons
+    fn area(polygon: &[[f32; 2]]) -> f32 {
+        (0..polygon.len())
+            .map(|i| {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                a[0] * b[1] - b[0] * a[1]
+            })
+            .sum()
+    }
+    
+    #[test]
+    fn hull_keeps_only_the_corners_counter_clockwise() {
+        let points = [[0.5, 0.5], [1.0, 1.0], [0.0, 0.0], [0.5, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 0.5], [0.2, 0.7]];
+        let hull = convex_hull(&points);
+        assert_eq!(hull.len(), 4);
+        assert!(SQUARE.iter().all(|corner| hull.contains(corner)));
+        assert!((area(&hull) - 2.0).abs() < 1e-5);
+    }
+    
+    #[test]
+    fn duplicate_points_are_counted_once() {
+        let points: Vec<[f32; 2]> = SQUARE.iter().chain(SQUARE.iter()).chain([[1.0, 1.0], [1.0 + 1e-7, 1.0]].iter()).copied().collect();
+        let hull = convex_hull(&points);
+        assert_eq!(hull.len(), 4);
+        assert!((area(&hull) - 2.0).abs() < 1e-5);
+        
+        assert_eq!(convex_hull(&[[2.0, 3.0], [2.0, 3.0], [2.0, 3.0]]), vec![[2.0, 3.0]]);
+    }
+    
+    #[test]
+    fn collinear_points_leave_no_footprint() {
+        let hull = convex_hull(&[[0.0, 0.0], [1.0, 1.0], [3.0, 3.0], [2.0, 2.0]]);
+        assert_eq!(hull.len(), 2);
+        assert!(hull.contains(&[0.0, 0.0]) && hull.contains(&[3.0, 3.0]));
+    }
+    
+    #[test]
+    fn distance_is_negative_inside_the_hull() {
+        let hull = convex_hull(&SQUARE);
+        let (distance, direction) = signed_distance(&hull, [0.1, 0.5]);
+        assert!((distance + 0.1).abs() < 1e-5);
+        assert_near(direction, [-1.0, 0.0]);
+        
+        let (distance, direction) = signed_distance(&hull, [0.5, 0.5]);
+        assert!((distance + 0.5).abs() < 1e-5);
+        assert!((direction[0].hypot(direction[1]) - 1.0).abs() < 1e-5);
+    }
+    
+    #[test]
+    fn distance_is_positive_outside_the_hull() {
+        let hull = convex_hull(&SQUARE);
+        let (distance, direction) = signed_distance(&hull, [2.0, 0.5]);
+        assert!((distance - 1.0).abs() < 1e-5);
+        assert_near(direction, [1.0, 0.0]);
+        
+        // past a corner the nearest point is the corner itself
+        let (distance, direction) = signed_distance(&hull, [2.0, 2.0]);
+        assert!((distance - 2.0f32.sqrt()).abs() < 1e-5);
+        assert_near(direction, [0.5f32.sqrt(), 0.5f32.sqrt()]);
+    }
+    
+    #[test]
+    fn distance_is_zero_on_the_hull() {
+        let hull = convex_hull(&SQUARE);
+        let (distance, direction) = signed_distance(&hull, [1.0, 0.5]);
+        assert!(distance.abs() < 1e-5);
+        assert_near(direction, [1.0, 0.0]);
+        
+        let (distance, direction) = signed_distance(&hull, [0.0, 0.0]);
+        assert!(distance.abs() < 1e-5);
+        assert!((direction[0].hypot(direction[1]) - 1.0).abs() < 1e-5);
+        assert!(direction[0] <= 0.0 && direction[1] <= 0.0, "{:?} points into the hull", direction);
+    }
+}
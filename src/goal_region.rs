@@ -0,0 +1,102 @@
+// tolerance region around the target, the way real autonomous goals are given. the path ends at the nearest
+// point of the region the robot fits at and the follower stops once it is inside
+use crate::color::Rgb;
+use serde::{Deserialize, Serialize};
+
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::wire::Wire;
+
+const SAMPLE_SPACING: f32 = 0.05; // meters between the points of the region tried when the nearest one is blocked
+
+/// where around the target the robot counts as arrived
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum GoalRegion {
+    #[default]
+    Point,
+    Disc { radius: f32 },
+    Rectangle { width: f32, height: f32 }, // along x and y, centered on the target
+}
+
+impl GoalRegion {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GoalRegion::Point => "Point",
+            GoalRegion::Disc { .. } => "Disc",
+            GoalRegion::Rectangle { .. } => "Rectangle",
+        }
+    }
+    
+    pub fn contains(&self, target: Position, point: Position) -> bool {
+        let (dx, dy) = (point.x - target.x, point.y - target.y);
+        match *self {
+            GoalRegion::Point => dx == 0.0 && dy == 0.0,
+            GoalRegion::Disc { radius } => dx * dx + dy * dy <= radius * radius,
+            GoalRegion::Rectangle { width, height } => dx.abs() <= width / 2.0 && dy.abs() <= height / 2.0,
+        }
+    }
+    
+    /// the point of the region nearest to a position, the position itself when it is inside
+    pub fn nearest(&self, target: Position, point: Position) -> Position {
+        let (dx, dy) = (point.x - target.x, point.y - target.y);
+        match *self {
+            GoalRegion::Point => target,
+            GoalRegion::Disc { radius } => {
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance <= radius {
+                    return Position::new(point.x, point.y, target.z);
+                }
+                Position::new(target.x + dx / distance * radius, target.y + dy / distance * radius, target.z)
+            },
+            GoalRegion::Rectangle { width, height } => Position::new(
+                target.x + dx.clamp(-width / 2.0, width / 2.0),
+                target.y + dy.clamp(-height / 2.0, height / 2.0),
+                target.z,
+            ),
+        }
+    }
+    
+    /// where a path coming from a position should end, the nearest point of the region the robot fits at
+    /// between the hard obstacles, or the target when there is none
+    pub fn goal_from(&self, target: Position, from: Position, obstacles: &[Obstacle]) -> Position {
+        let blocked = |point: Position| obstacles.iter()
+            .any(|obstacle| obstacle.is_hard() && obstacle.edge_distance(point) < obstacle.robot_radius());
+        let nearest = self.nearest(target, from);
+        if *self == GoalRegion::Point || !blocked(nearest) {
+            return nearest;
+        }
+        
+        let (half_x, half_y) = match *self {
+            GoalRegion::Disc { radius } => (radius, radius),
+            GoalRegion::Rectangle { width, height } => (width / 2.0, height / 2.0),
+            GoalRegion::Point => (0.0, 0.0),
+        };
+        let (columns, rows) = ((half_x / SAMPLE_SPACING) as i32, (half_y / SAMPLE_SPACING) as i32);
+        (-rows..=rows)
+            .flat_map(|row| (-columns..=columns).map(move |column| (column, row)))
+            .map(|(column, row)| Position::new(target.x + column as f32 * SAMPLE_SPACING, target.y + row as f32 * SAMPLE_SPACING, target.z))
+            .filter(|&point| self.contains(target, point) && !blocked(point))
+            .min_by(|a, b| a.distance_to(&from).total_cmp(&b.distance_to(&from)))
+            .unwrap_or(target)
+    }
+    
+    /// outline of the region on the ground around the target
+    pub fn wires(&self, target: Position, color: Rgb<u8>) -> Vec<Wire> {
+        let ground = Position::new(target.x, target.y, 0.0);
+        match *self {
+            GoalRegion::Point => Vec::new(),
+            GoalRegion::Disc { radius } => Wire::circle(ground, radius, 32, color),
+            GoalRegion::Rectangle { width, height } => {
+                let (half_x, half_y) = (width / 2.0, height / 2.0);
+                let corners = [
+                    Position::new(ground.x - half_x, ground.y - half_y, 0.0),
+                    Position::new(ground.x + half_x, ground.y - half_y, 0.0),
+                    Position::new(ground.x + half_x, ground.y + half_y, 0.0),
+                    Position::new(ground.x - half_x, ground.y + half_y, 0.0),
+                ];
+                (0..4).map(|i| Wire::with_color(corners[i], corners[(i + 1) % 4], color)).collect()
+            },
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// summed obstacle field evaluated on the gpu with a compute shader, for gradient overlays at resolutions
+// the cpu can not rebuild interactively
+#[cfg(feature = "native")]
+use wgpu::util::DeviceExt;
+
+use crate::obstacle::Obstacle;
+#[cfg(feature = "native")]
+use crate::obstacle::{field_combination, FieldCombination};
+
+#[cfg(feature = "native")]
+const WORKGROUP_SIZE: u32 = 64;
+#[cfg(feature = "native")]
+const MAX_WORKGROUPS: u32 = 65535; // per dispatch dimension
+
+// one invocation per grid point, the circle obstacles' cosine fields as in `cosine_field_function` combined as in
+// `FieldCombination::combine`
+#[cfg(feature = "native")]
+const SHADER: &str = r#"
+struct Params {
+    count_x: u32,
+    count_y: u32,
+    obstacle_count: u32,
+    row_length: u32,
+    combination: u32, // 0 sum, 1 max, 2 smooth max, as in `FieldCombination`
+}
+
+struct Circle {
+    x: f32,
+    y: f32,
+    calculation_radius: f32,
+    b: f32,
+    weight: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> xs: array<f32>;
+@group(0) @binding(2) var<storage, read> ys: array<f32>;
+@group(0) @binding(3) var<storage, read> circles: array<Circle>;
+@group(0) @binding(4) var<storage, read_write> heights: array<f32>;
+
+const PI: f32 = 3.14159265;
+const SMOOTH_MAX_POWER: f32 = 4.0;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.y * params.row_length + id.x;
+    if index >= params.count_x * params.count_y {
+        return;
+    }
+    let point = vec2<f32>(xs[index / params.count_y], ys[index % params.count_y]);
+    
+    var height = 0.0;
+    for (var i = 0u; i < params.obstacle_count; i++) {
+        let circle = circles[i];
+        let dist = distance(point, vec2<f32>(circle.x, circle.y));
+        if dist <= circle.calculation_radius {
+            let field = max(circle.weight * circle.b / 2.0 * cos(PI * dist / circle.b), 0.0);
+            switch params.combination {
+                case 1u: { height = max(height, field); }
+                case 2u: { height += pow(field, SMOOTH_MAX_POWER); }
+                default: { height += field; }
+            }
+        }
+    }
+    if params.combination == 2u {
+        height = pow(height, 1.0 / SMOOTH_MAX_POWER);
+    }
+    heights[index] = height;
+}
+"#;
+
+/// a gpu device of its own with the field shader compiled for it
+#[cfg(feature = "native")]
+pub struct GpuField {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    adapter: String, // name of the gpu the field is computed on
+}
+
+#[cfg(feature = "native")]
+impl GpuField {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .ok_or("no gpu adapter was found")?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))?;
+        
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("field shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("field pipeline"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        });
+        
+        Ok(Self { device, queue, pipeline, adapter: adapter.get_info().name })
+    }
+    
+    pub fn adapter(&self) -> &str {
+        &self.adapter
+    }
+    
+    /// field heights at every crossing of xs and ys, indexed x major, hull obstacles are added on the cpu
+    /// since only circles are uploaded
+    pub fn heights(&self, obstacles: &[Obstacle], xs: &[f32], ys: &[f32]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let count = xs.len() * ys.len();
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let (circles, hulls): (Vec<&Obstacle>, Vec<&Obstacle>) = obstacles.iter()
+            .filter(|obstacle| obstacle.is_planned())
+            .partition(|obstacle| obstacle.footprint_polygon().is_none());
+        // storage bindings can not be empty, the padding circle is never read
+        let mut circle_data: Vec<f32> = circles.iter().flat_map(|obstacle| obstacle.field_parameters()).collect();
+        circle_data.resize(circle_data.len().max(5), 0.0);
+        
+        let groups = (count as u32).div_ceil(WORKGROUP_SIZE);
+        let groups_x = groups.min(MAX_WORKGROUPS);
+        let groups_y = groups.div_ceil(groups_x);
+        let combination = field_combination();
+        let combination_index = FieldCombination::ALL.iter().position(|&c| c == combination).unwrap_or(0) as u32;
+        let params = [xs.len() as u32, ys.len() as u32, circles.len() as u32, groups_x * WORKGROUP_SIZE, combination_index];
+        
+        let storage = |label: &str, data: &[u8]| self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: data,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let params_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("field params"),
+            contents: &params.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let xs_buffer = storage("field xs", &bytes(xs));
+        let ys_buffer = storage("field ys", &bytes(ys));
+        let circle_buffer = storage("field circles", &bytes(&circle_data));
+        
+        let size = (count * std::mem::size_of::<f32>()) as wgpu::BufferAddress;
+        let heights_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("field heights"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("field readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("field bindings"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: xs_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: ys_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: circle_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: heights_buffer.as_entire_binding() },
+            ],
+        });
+        
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("field") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: Some("field pass") });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+        encoder.copy_buffer_to_buffer(&heights_buffer, 0, &readback, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+        
+        // waits for the gpu, the overlay is only rebuilt after an edit
+        let slice = readback.slice(..);
+        let (sender, mapped) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| { let _ = sender.send(result); });
+        self.device.poll(wgpu::Maintain::Wait);
+        mapped.recv()??;
+        
+        let mut heights: Vec<f32> = slice.get_mapped_range()
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        readback.unmap();
+        
+        if !hulls.is_empty() {
+            for (i, height) in heights.iter_mut().enumerate() {
+                let position = crate::position::Position::new(xs[i / ys.len()], ys[i % ys.len()], 0.0);
+                // combining the circles' result with the hull fields gives the same as combining them all
+                let hull_fields = hulls.iter().map(|obstacle| obstacle.cosine_field_function(position));
+                *height = combination.combine(std::iter::once(*height).chain(hull_fields));
+            }
+        }
+        Ok(heights)
+    }
+}
+
+#[cfg(feature = "native")]
+fn bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+/// stands in without the native feature, the field is always computed on the cpu
+#[cfg(not(feature = "native"))]
+pub struct GpuField;
+
+#[cfg(not(feature = "native"))]
+impl GpuField {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Err("gpu compute needs the native feature".into())
+    }
+    
+    pub fn adapter(&self) -> &str {
+        ""
+    }
+    
+    pub fn heights(&self, _obstacles: &[Obstacle], _xs: &[f32], _ys: &[f32]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        Err("gpu compute needs the native feature".into())
+    }
+}
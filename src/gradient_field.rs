@@ -1,12 +1,14 @@
+use serde::{Deserialize, Serialize};
+
 use crate::position::Position;
 use crate::wire::Wire;
-use crate::field::{FIELD_LENGTH,FIELD_WIDTH};
+use crate::field::{field_length, field_width};
 
-pub static mut OBSTACLES_REF: Option<*const Vec<crate::obstacle::Obstacle>> = None;
+pub static mut OBSTACLES_REF: Option<*const crate::world::Obstacles> = None;
 
-pub fn set_obstacles_ref(obstacles: &Vec<crate::obstacle::Obstacle>) {
+pub fn set_obstacles_ref(obstacles: &crate::world::Obstacles) {
     unsafe {
-        OBSTACLES_REF = Some(obstacles as *const Vec<crate::obstacle::Obstacle>);
+        OBSTACLES_REF = Some(obstacles as *const crate::world::Obstacles);
     }
 }
 
@@ -15,18 +17,228 @@ pub fn obstacle_sum(x: f32, y: f32) -> f32 {
         if let Some(obstacles_ptr) = OBSTACLES_REF {
             let obstacles = &*obstacles_ptr;
             let pos = crate::position::Position::new(x, y, 0.0);
-            let mut sum = 0.0;
-            
-            for obstacle in obstacles {
-                sum += obstacle.cosine_field_function(pos);
-            }
-            
-            return sum;
+            return crate::obstacle::combined_field(obstacles, pos);
         }
         0.0
     }
 }
 
+/// circles around the planned obstacles of `OBSTACLES_REF` their fields reach, grown by a margin, outside them the
+/// field is flat at zero
+pub struct Influence {
+    circles: Vec<([f32; 2], f32)>,
+}
+
+impl Influence {
+    pub fn of_obstacles(margin: f32) -> Influence {
+        let mut circles = Vec::new();
+        unsafe {
+            if let Some(obstacles_ptr) = OBSTACLES_REF {
+                for obstacle in (*obstacles_ptr).iter().filter(|obstacle| obstacle.is_planned()) {
+                    let center = obstacle.model.config.position;
+                    circles.push(([center.x, center.y], obstacle.inflated_radius() + margin));
+                }
+            }
+        }
+        Influence { circles }
+    }
+    
+    /// whether the segment from a to b passes through any of the circles
+    fn touches_segment(&self, a: [f32; 2], b: [f32; 2]) -> bool {
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let length_squared = (dx * dx + dy * dy).max(f32::EPSILON);
+        self.circles.iter().any(|&(center, radius)| {
+            let t = (((center[0] - a[0]) * dx + (center[1] - a[1]) * dy) / length_squared).clamp(0.0, 1.0);
+            let (x, y) = (a[0] + dx * t - center[0], a[1] + dy * t - center[1]);
+            x * x + y * y <= radius * radius
+        })
+    }
+    
+    /// whether the rectangle between the corners overlaps any of the circles
+    fn touches_cell(&self, min: [f32; 2], max: [f32; 2]) -> bool {
+        self.circles.iter().any(|&(center, radius)| {
+            let x = center[0].clamp(min[0], max[0]) - center[0];
+            let y = center[1].clamp(min[1], max[1]) - center[1];
+            x * x + y * y <= radius * radius
+        })
+    }
+}
+
+/// the obstacle field sampled at one point, the gradient points uphill so the path is pushed against it
+#[derive(Debug, Copy, Clone)]
+pub struct FieldProbe {
+    pub position: Position,
+    pub potential: f32,
+    pub gradient: [f32; 2],
+}
+
+impl FieldProbe {
+    pub fn at(obstacles: &[crate::obstacle::Obstacle], position: Position) -> FieldProbe {
+        let position = Position::new(position.x, position.y, 0.0);
+        let combination = crate::obstacle::field_combination();
+        let heights: Vec<f32> = obstacles.iter().map(|obstacle| obstacle.cosine_field_function(position)).collect();
+        let mut probe = FieldProbe { position, potential: combination.combine(heights.iter().copied()), gradient: [0.0, 0.0] };
+        for (obstacle, weight) in obstacles.iter().zip(combination.gradient_weights(&heights)) {
+            // the descent step with a unit learning rate is the slope itself
+            let [dx, dy] = obstacle.cosine_gradient_function(position, 1.0);
+            probe.gradient[0] += dx * weight;
+            probe.gradient[1] += dy * weight;
+        }
+        probe
+    }
+    
+    pub fn slope(&self) -> f32 {
+        (self.gradient[0] * self.gradient[0] + self.gradient[1] * self.gradient[1]).sqrt()
+    }
+    
+    pub fn readout(&self) -> String {
+        format!("h = {:.3}, grad = ({:.3}, {:.3}), |grad| = {:.3}", self.potential, self.gradient[0], self.gradient[1], self.slope())
+    }
+}
+
+/// the obstacle field sampled evenly along a line on the ground, in total and per obstacle
+pub struct CrossSection {
+    pub start: Position,
+    pub end: Position,
+    pub cosine: Vec<f32>, // the field the planner descends
+    pub gaussian: Vec<f32>,
+    pub obstacles: Vec<(usize, Vec<f32>)>, // cosine field of every obstacle the line passes through
+}
+
+impl CrossSection {
+    pub fn sample(obstacles: &[crate::obstacle::Obstacle], start: Position, end: Position, samples: usize) -> CrossSection {
+        let samples = samples.max(2);
+        let positions: Vec<Position> = (0..samples)
+            .map(|i| {
+                let t = i as f32 / (samples - 1) as f32;
+                Position::new(start.x + (end.x - start.x) * t, start.y + (end.y - start.y) * t, 0.0)
+            })
+            .collect();
+        
+        // both totals are combined the way the planner combines the cosine fields
+        let combination = crate::obstacle::field_combination();
+        let cosine = positions.iter()
+            .map(|&position| combination.combine(obstacles.iter().map(|obstacle| obstacle.cosine_field_function(position))))
+            .collect();
+        let gaussian = positions.iter()
+            .map(|&position| combination.combine(obstacles.iter().map(|obstacle| obstacle.gaussian_field_function(position))))
+            .collect();
+        let mut contributing = Vec::new();
+        for (index, obstacle) in obstacles.iter().enumerate() {
+            let heights: Vec<f32> = positions.iter().map(|&position| obstacle.cosine_field_function(position)).collect();
+            if heights.iter().any(|&height| height > 0.0) {
+                contributing.push((index, heights));
+            }
+        }
+        
+        CrossSection { start, end, cosine, gaussian, obstacles: contributing }
+    }
+    
+    pub fn length(&self) -> f32 {
+        self.start.distance_to(&self.end)
+    }
+    
+    /// distances along the line where two or more obstacle fields overlap, as start and end pairs
+    pub fn overlaps(&self) -> Vec<(f32, f32)> {
+        let samples = self.cosine.len();
+        let step = self.length() / (samples - 1) as f32;
+        let mut overlaps: Vec<(f32, f32)> = Vec::new();
+        let mut open = None;
+        for i in 0..samples {
+            let fields = self.obstacles.iter().filter(|(_, heights)| heights[i] > 0.0).count();
+            match (fields > 1, open) {
+                (true, None) => open = Some(i),
+                (false, Some(first)) => {
+                    overlaps.push((first as f32 * step, (i - 1) as f32 * step));
+                    open = None;
+                },
+                _ => {},
+            }
+        }
+        if let Some(first) = open {
+            overlaps.push((first as f32 * step, self.length()));
+        }
+        overlaps
+    }
+}
+
+const LIGHT_DIRECTION: [f32; 3] = [-0.4, -0.3, 0.866]; // unit vector towards the light, high over the field
+const AMBIENT_LIGHT: f32 = 0.35;
+
+/// how the field overlay is drawn
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldRendering {
+    Wires,
+    Surface, // flat shaded triangles, readable where the wires crowd together
+    Both,
+}
+
+impl FieldRendering {
+    pub const ALL: [FieldRendering; 3] = [FieldRendering::Wires, FieldRendering::Surface, FieldRendering::Both];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldRendering::Wires => "Wire Mesh",
+            FieldRendering::Surface => "Shaded Surface",
+            FieldRendering::Both => "Both",
+        }
+    }
+    
+    pub fn wires(&self) -> bool {
+        *self != FieldRendering::Surface
+    }
+    
+    pub fn surface(&self) -> bool {
+        *self != FieldRendering::Wires
+    }
+}
+
+/// a triangle of the field surface with the brightness its slope catches
+#[derive(Debug, Copy, Clone)]
+pub struct SurfaceTriangle {
+    pub corners: [Position; 3],
+    pub shade: f32,
+}
+
+impl SurfaceTriangle {
+    fn new(corners: [Position; 3]) -> SurfaceTriangle {
+        let [a, b, c] = corners;
+        let (u, v) = ([b.x - a.x, b.y - a.y, b.z - a.z], [c.x - a.x, c.y - a.y, c.z - a.z]);
+        let normal = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt().max(f32::EPSILON);
+        // the normal is turned upwards, the surface is lit from above whichever way the corners wind
+        let facing = (normal[0] * LIGHT_DIRECTION[0] + normal[1] * LIGHT_DIRECTION[1] + normal[2] * LIGHT_DIRECTION[2]) / length * normal[2].signum();
+        SurfaceTriangle { corners, shade: AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * facing.max(0.0) }
+    }
+    
+    pub fn center(&self) -> Position {
+        let [a, b, c] = self.corners;
+        Position::new((a.x + b.x + c.x) / 3.0, (a.y + b.y + c.y) / 3.0, (a.z + b.z + c.z) / 3.0)
+    }
+}
+
+/// two triangles per cell of the grid through the x and y line crossings
+fn generate_surface(gradient_func: fn(f32, f32) -> f32, x_resolution: f32, y_resolution: f32, influence: Option<&Influence>) -> Vec<SurfaceTriangle> {
+    let x_line_count = (field_length() * x_resolution.max(0.01)).max(1.0) as usize;
+    let y_line_count = (field_width() * y_resolution.max(0.01)).max(1.0) as usize;
+    let xs = offsets(x_line_count, field_length() / x_line_count as f32, field_length());
+    let ys = offsets(y_line_count, field_width() / y_line_count as f32, field_width());
+    
+    let mut grid = HeightGrid::new(gradient_func, &xs, &ys);
+    let mut triangles = Vec::with_capacity(x_line_count * y_line_count * 2);
+    for x in xs.windows(2) {
+        for y in ys.windows(2) {
+            if influence.is_some_and(|influence| !influence.touches_cell([x[0], y[0]], [x[1], y[1]])) {
+                continue;
+            }
+            let corners = [grid.point(x[0], y[0]), grid.point(x[1], y[0]), grid.point(x[1], y[1]), grid.point(x[0], y[1])];
+            triangles.push(SurfaceTriangle::new([corners[0], corners[1], corners[2]]));
+            triangles.push(SurfaceTriangle::new([corners[0], corners[2], corners[3]]));
+        }
+    }
+    triangles
+}
+
 pub struct GradientWire {
     x_wires: Vec<Vec<Wire>>,
     y_wires: Vec<Vec<Wire>>,
@@ -34,12 +246,16 @@ pub struct GradientWire {
     pub x_resolution: f32,
     pub y_resolution: f32,
     pub line_resolution: f32,
+    pub adaptive: Option<f32>, // height tolerance the grid is refined to near rims, none for the uniform grid
+    pub influence_margin: Option<f32>, // wires are only built this far around the obstacle fields, none for the whole field
+    pub surface: Vec<SurfaceTriangle>, // only built while `shaded` is set
+    pub shaded: bool,
     color: Option<nannou::color::Rgb<u8>>,
 }
 
 impl GradientWire {
     pub fn new(gradient_func: fn(f32,f32) -> f32, x_resolution: f32, y_resolution: f32, line_resolution: f32) -> GradientWire {
-        let gradient_field: [Vec<Vec<Wire>>; 2] = generate_gradient_wires(gradient_func, x_resolution, y_resolution, line_resolution);
+        let gradient_field: [Vec<Vec<Wire>>; 2] = generate_gradient_wires(gradient_func, x_resolution, y_resolution, line_resolution, None, |_, _| None);
         GradientWire { 
             x_wires: gradient_field[0].clone(),
             y_wires: gradient_field[1].clone(),
@@ -47,6 +263,10 @@ impl GradientWire {
             x_resolution: x_resolution,
             y_resolution: y_resolution,
             line_resolution: line_resolution,
+            adaptive: None,
+            influence_margin: None,
+            surface: Vec::new(),
+            shaded: false,
             color: None,
         }
     }
@@ -57,6 +277,33 @@ impl GradientWire {
         self
     }
     
+    /// rebuilds the grid refined to the tolerance, the uniform grid is kept for none
+    pub fn with_adaptive(mut self, tolerance: Option<f32>) -> Self {
+        if tolerance.is_some() {
+            self.adaptive = tolerance;
+            self.update();
+        }
+        self
+    }
+    
+    /// rebuilds the grid within the margin around the obstacle fields, the whole field is kept for none
+    pub fn with_influence(mut self, margin: Option<f32>) -> Self {
+        if margin.is_some() {
+            self.influence_margin = margin;
+            self.update();
+        }
+        self
+    }
+    
+    /// builds the shaded surface along with the wires from now on
+    pub fn with_surface(mut self, shaded: bool) -> Self {
+        if shaded {
+            self.shaded = true;
+            self.update();
+        }
+        self
+    }
+    
     pub fn set_color(&mut self, color: nannou::color::Rgb<u8>) {
         self.color = Some(color);
         self.apply_color();
@@ -80,7 +327,7 @@ impl GradientWire {
     
     pub fn get_all_wires(&self) -> Vec<Wire> {
         let mut all_wires = Vec::new();
-
+        
         for wire_set in &self.x_wires {
             for wire in wire_set {
                 all_wires.push(*wire);
@@ -96,16 +343,52 @@ impl GradientWire {
         all_wires
     }
     
+    pub fn wire_count(&self) -> usize {
+        self.x_wires.iter().chain(&self.y_wires).map(|wire_set| wire_set.len()).sum()
+    }
+    
     pub fn update(&mut self) {
-        let gradient_field = generate_gradient_wires(
-            self.gradient_function, 
-            self.x_resolution, 
-            self.y_resolution, 
-            self.line_resolution
-        );
+        self.update_with_heights(|_, _| None);
+    }
+    
+    /// rebuilds the wires with heights computed elsewhere, such as on the gpu, given the x and y offsets of the
+    /// uniform grid, the adaptive grid always samples the gradient function
+    pub fn update_with_heights(&mut self, heights: impl FnOnce(&[f32], &[f32]) -> Option<Vec<f32>>) {
+        let influence = self.influence_margin.map(Influence::of_obstacles);
+        let mut gradient_field = match self.adaptive {
+            Some(tolerance) => generate_adaptive_wires(
+                self.gradient_function,
+                self.x_resolution,
+                self.y_resolution,
+                self.line_resolution,
+                tolerance
+            ),
+            None => generate_gradient_wires(
+                self.gradient_function, 
+                self.x_resolution, 
+                self.y_resolution, 
+                self.line_resolution,
+                influence.as_ref(),
+                heights
+            ),
+        };
+        if let (Some(influence), Some(_)) = (&influence, self.adaptive) {
+            // the adaptive grid is already sparse where the field is flat, only the wires outside are dropped
+            for wire_set in gradient_field.iter_mut().flatten() {
+                wire_set.retain(|wire| influence.touches_segment([wire.start.x, wire.start.y], [wire.end.x, wire.end.y]));
+            }
+            for wires in gradient_field.iter_mut() {
+                wires.retain(|wire_set| !wire_set.is_empty());
+            }
+        }
         
         self.x_wires = gradient_field[0].clone();
         self.y_wires = gradient_field[1].clone();
+        self.surface = if self.shaded {
+            generate_surface(self.gradient_function, self.x_resolution, self.y_resolution, influence.as_ref())
+        } else {
+            Vec::new()
+        };
         
         if self.color.is_some() {
             self.apply_color();
@@ -114,117 +397,189 @@ impl GradientWire {
 }
 
 
-fn generate_gradient_wires(gradient_func: fn(f32,f32) -> f32, x_resolution: f32, y_resolution: f32, line_resolution: f32) -> [Vec<Vec<Wire>>; 2] {
-    let mut x_wires: Vec<Vec<Wire>> = Vec::new();
-    let mut y_wires: Vec<Vec<Wire>> = Vec::new();
+/// heights of the field at the crossings of a set of x and y offsets, each evaluated once on first use so the x and
+/// y wire families share the points they have in common
+struct HeightGrid {
+    gradient_func: fn(f32, f32) -> f32,
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    heights: Vec<Option<f32>>,
+}
+
+impl HeightGrid {
+    fn new(gradient_func: fn(f32, f32) -> f32, xs: &[f32], ys: &[f32]) -> HeightGrid {
+        let merged = |values: &[f32]| {
+            let mut values = values.to_vec();
+            values.sort_by(f32::total_cmp);
+            values.dedup();
+            values
+        };
+        let (xs, ys) = (merged(xs), merged(ys));
+        let heights = vec![None; xs.len() * ys.len()];
+        HeightGrid { gradient_func, xs, ys, heights }
+    }
+    
+    fn fill(&mut self, heights: Vec<f32>) {
+        if heights.len() == self.heights.len() {
+            self.heights = heights.into_iter().map(Some).collect();
+        }
+    }
     
+    fn point(&mut self, x: f32, y: f32) -> Position {
+        // every offset asked for was passed to `new`, so the searches always hit
+        let ix = self.xs.partition_point(|&value| value < x);
+        let iy = self.ys.partition_point(|&value| value < y);
+        let gradient_func = self.gradient_func;
+        let z = *self.heights[ix * self.ys.len() + iy].get_or_insert_with(|| gradient_func(x, y));
+        Position::new(x, y, z)
+    }
+}
+
+/// offsets from zero to the extent, the last one exactly at the extent
+fn offsets(count: usize, spacing: f32, extent: f32) -> Vec<f32> {
+    (0..=count).map(|i| if i == count { extent } else { i as f32 * spacing }).collect()
+}
+
+/// the uniform grid, with heights from `precomputed` when it gives one for every crossing of the x and y offsets
+/// it is passed, otherwise from the gradient function
+fn generate_gradient_wires(gradient_func: fn(f32,f32) -> f32, x_resolution: f32, y_resolution: f32, line_resolution: f32, influence: Option<&Influence>, precomputed: impl FnOnce(&[f32], &[f32]) -> Option<Vec<f32>>) -> [Vec<Vec<Wire>>; 2] {
     let safe_x_resolution = x_resolution.max(0.01);
     let safe_y_resolution = y_resolution.max(0.01);
     let safe_line_resolution = line_resolution.max(0.01);
     
-    let x_line_count = (FIELD_LENGTH * safe_x_resolution).max(1.0) as usize;
-    let y_line_count = (FIELD_WIDTH * safe_y_resolution).max(1.0) as usize;
+    let x_line_count = (field_length() * safe_x_resolution).max(1.0) as usize;
+    let y_line_count = (field_width() * safe_y_resolution).max(1.0) as usize;
     
-    let x_spacing = FIELD_LENGTH / x_line_count as f32;
-    let y_spacing = FIELD_WIDTH / y_line_count as f32;
+    let x_spacing = field_length() / x_line_count as f32;
+    let y_spacing = field_width() / y_line_count as f32;
     
     let max_segment_length = safe_line_resolution * 5.0;
+    let y_segments = (field_width() / max_segment_length).ceil() as usize;
+    let x_segments = (field_length() / max_segment_length).ceil() as usize;
     
-    for i in 0..x_line_count {
-        let x = i as f32 * x_spacing;
-        let mut x_wire_set: Vec<Wire> = Vec::new();
-        
-        let y_segments = (FIELD_WIDTH / max_segment_length).ceil() as usize;
-        let y_segment_size = FIELD_WIDTH / y_segments as f32;
-        
-        for j in 0..y_segments {
-            let y_start = j as f32 * y_segment_size;
-            let y_end = if j == y_segments - 1 { FIELD_WIDTH } else { (j + 1) as f32 * y_segment_size };
-            
-            let z_start = gradient_func(x, y_start);
-            let z_end = gradient_func(x, y_end);
-            
-            x_wire_set.push(Wire::new(
-                Position::new(x, y_start, z_start),
-                Position::new(x, y_end, z_end)
-            ));
-        }
-        
-        if !x_wire_set.is_empty() {
-            x_wires.push(x_wire_set);
-        }
-    }
+    // x wires run along y at each x line, y wires along x at each y line, both include the far edge of the field
+    let x_lines = offsets(x_line_count, x_spacing, field_length());
+    let y_lines = offsets(y_line_count, y_spacing, field_width());
+    let y_ends = offsets(y_segments, field_width() / y_segments as f32, field_width());
+    let x_ends = offsets(x_segments, field_length() / x_segments as f32, field_length());
     
-    for i in 0..y_line_count {
-        let y = i as f32 * y_spacing;
-        let mut y_wire_set: Vec<Wire> = Vec::new();
-        
-        let x_segments = (FIELD_LENGTH / max_segment_length).ceil() as usize;
-        let x_segment_size = FIELD_LENGTH / x_segments as f32;
-        
-        for j in 0..x_segments {
-            let x_start = j as f32 * x_segment_size;
-            let x_end = if j == x_segments - 1 { FIELD_LENGTH } else { (j + 1) as f32 * x_segment_size };
-            
-            let z_start = gradient_func(x_start, y);
-            let z_end = gradient_func(x_end, y);
-            
-            y_wire_set.push(Wire::new(
-                Position::new(x_start, y, z_start),
-                Position::new(x_end, y, z_end)
-            ));
-        }
-        
-        if !y_wire_set.is_empty() {
-            y_wires.push(y_wire_set);
-        }
+    let mut grid = HeightGrid::new(gradient_func, &[x_lines.as_slice(), &x_ends].concat(), &[y_lines.as_slice(), &y_ends].concat());
+    if let Some(heights) = precomputed(&grid.xs, &grid.ys) {
+        grid.fill(heights);
     }
     
-    let mut final_x_wire_set: Vec<Wire> = Vec::new();
-    let x = FIELD_LENGTH;
-    
-    let y_segments = (FIELD_WIDTH / max_segment_length).ceil() as usize;
-    let y_segment_size = FIELD_WIDTH / y_segments as f32;
+    // segments outside the influence are skipped before their heights are looked up
+    let inside = |a: [f32; 2], b: [f32; 2]| influence.is_none_or(|influence| influence.touches_segment(a, b));
+    let x_wires: Vec<Vec<Wire>> = x_lines.iter()
+        .map(|&x| y_ends.windows(2)
+            .filter(|pair| inside([x, pair[0]], [x, pair[1]]))
+            .map(|pair| Wire::new(grid.point(x, pair[0]), grid.point(x, pair[1])))
+            .collect())
+        .filter(|wire_set: &Vec<Wire>| !wire_set.is_empty())
+        .collect();
+    let y_wires: Vec<Vec<Wire>> = y_lines.iter()
+        .map(|&y| x_ends.windows(2)
+            .filter(|pair| inside([pair[0], y], [pair[1], y]))
+            .map(|pair| Wire::new(grid.point(pair[0], y), grid.point(pair[1], y)))
+            .collect())
+        .filter(|wire_set: &Vec<Wire>| !wire_set.is_empty())
+        .collect();
     
-    for j in 0..y_segments {
-        let y_start = j as f32 * y_segment_size;
-        let y_end = if j == y_segments - 1 { FIELD_WIDTH } else { (j + 1) as f32 * y_segment_size };
-        
-        let z_start = gradient_func(x, y_start);
-        let z_end = gradient_func(x, y_end);
-        
-        final_x_wire_set.push(Wire::new(
-            Position::new(x, y_start, z_start),
-            Position::new(x, y_end, z_end)
-        ));
+    [x_wires, y_wires]
+}
+
+
+const ADAPTIVE_COARSENING: f32 = 4.0; // flat stretches get segments this many times the uniform length
+const BEND_SAMPLES: usize = 8; // samples checked against the straight wire between two endpoints
+
+/// whether f strays from the straight line between (a, fa) and (b, fb) by more than the tolerance,
+/// where the field's second derivative is large
+fn bends(f: &dyn Fn(f32) -> f32, a: f32, fa: f32, b: f32, fb: f32, tolerance: f32) -> bool {
+    (1..BEND_SAMPLES).any(|i| {
+        let t = i as f32 / BEND_SAMPLES as f32;
+        (f(a + (b - a) * t) - (fa + (fb - fa) * t)).abs() > tolerance
+    })
+}
+
+/// halves the span until it is straight within the tolerance or as short as allowed, pushing the far end of
+/// every piece
+fn subdivide(f: &dyn Fn(f32) -> f32, (a, fa): (f32, f32), (b, fb): (f32, f32), min_length: f32, tolerance: f32, points: &mut Vec<(f32, f32)>) {
+    if b - a >= min_length * 2.0 && bends(f, a, fa, b, fb, tolerance) {
+        let m = (a + b) / 2.0;
+        let fm = f(m);
+        subdivide(f, (a, fa), (m, fm), min_length, tolerance, points);
+        subdivide(f, (m, fm), (b, fb), min_length, tolerance, points);
+    } else {
+        points.push((b, fb));
     }
-    
-    if !final_x_wire_set.is_empty() {
-        x_wires.push(final_x_wire_set);
+}
+
+/// points along a line of the given length with their heights, long steps where f is flat and short ones where it bends
+fn adaptive_points(f: &dyn Fn(f32) -> f32, length: f32, coarse: f32, fine: f32, tolerance: f32) -> Vec<(f32, f32)> {
+    let spans = (length / coarse).ceil().max(1.0) as usize;
+    let mut points = vec![(0.0, f(0.0))];
+    for i in 0..spans {
+        let start = points[points.len() - 1];
+        let end = if i == spans - 1 { length } else { (i + 1) as f32 * length / spans as f32 };
+        subdivide(f, start, (end, f(end)), fine, tolerance, &mut points);
     }
+    points
+}
+
+/// offsets of the lines across one direction, the uniform spacing with lines added in between wherever the
+/// field along the middle bends away from the average of its neighbours
+fn adaptive_lines(f: &dyn Fn(f32, f32) -> f32, extent: f32, resolution: f32, length: f32, step: f32, fine: f32, tolerance: f32) -> Vec<f32> {
+    let count = (extent * resolution.max(0.01)).max(1.0) as usize;
+    let base: Vec<f32> = (0..=count).map(|i| if i == count { extent } else { i as f32 * extent / count as f32 }).collect();
+    let samples = (length / step).ceil().max(1.0) as usize;
+    let along: Vec<f32> = (0..=samples).map(|i| i as f32 * length / samples as f32).collect();
     
-    let mut final_y_wire_set: Vec<Wire> = Vec::new();
-    let y = FIELD_WIDTH;
-    
-    let x_segments = (FIELD_LENGTH / max_segment_length).ceil() as usize;
-    let x_segment_size = FIELD_LENGTH / x_segments as f32;
-    
-    for j in 0..x_segments {
-        let x_start = j as f32 * x_segment_size;
-        let x_end = if j == x_segments - 1 { FIELD_LENGTH } else { (j + 1) as f32 * x_segment_size };
-        
-        let z_start = gradient_func(x_start, y);
-        let z_end = gradient_func(x_end, y);
-        
-        final_y_wire_set.push(Wire::new(
-            Position::new(x_start, y, z_start),
-            Position::new(x_end, y, z_end)
-        ));
+    fn refine(f: &dyn Fn(f32, f32) -> f32, a: f32, b: f32, along: &[f32], fine: f32, tolerance: f32, lines: &mut Vec<f32>) {
+        let m = (a + b) / 2.0;
+        let curved = b - a >= fine * 2.0 && along.iter().any(|&s| (f(m, s) - (f(a, s) + f(b, s)) / 2.0).abs() > tolerance);
+        if curved {
+            refine(f, a, m, along, fine, tolerance, lines);
+            refine(f, m, b, along, fine, tolerance, lines);
+        } else {
+            lines.push(b);
+        }
     }
     
-    if !final_y_wire_set.is_empty() {
-        y_wires.push(final_y_wire_set);
+    let mut lines = vec![base[0]];
+    for pair in base.windows(2) {
+        refine(f, pair[0], pair[1], &along, fine, tolerance, &mut lines);
     }
+    lines
+}
+
+/// wire sets along a line at each offset, joining the adaptive points of that line
+fn adaptive_wire_sets(f: &dyn Fn(f32, f32) -> f32, lines: &[f32], length: f32, coarse: f32, fine: f32, tolerance: f32, position: fn(f32, f32, f32) -> Position) -> Vec<Vec<Wire>> {
+    lines.iter()
+        .map(|&offset| {
+            let points = adaptive_points(&|s| f(offset, s), length, coarse, fine, tolerance);
+            points.windows(2)
+                .map(|pair| Wire::new(position(offset, pair[0].0, pair[0].1), position(offset, pair[1].0, pair[1].1)))
+                .collect()
+        })
+        .filter(|wire_set: &Vec<Wire>| !wire_set.is_empty())
+        .collect()
+}
+
+/// the same grid as `generate_gradient_wires` refined where the field bends, near obstacle rims, and coarsened where
+/// it is flat, the line resolution is the shortest segment and closest line spacing allowed
+fn generate_adaptive_wires(gradient_func: fn(f32,f32) -> f32, x_resolution: f32, y_resolution: f32, line_resolution: f32, tolerance: f32) -> [Vec<Vec<Wire>>; 2] {
+    let fine = line_resolution.max(0.01);
+    let coarse = fine * 5.0 * ADAPTIVE_COARSENING;
+    let tolerance = tolerance.max(0.001);
+    let step = coarse / BEND_SAMPLES as f32;
+    
+    let along_y = |x: f32, y: f32| gradient_func(x, y);
+    let along_x = |y: f32, x: f32| gradient_func(x, y);
     
+    let x_lines = adaptive_lines(&along_y, field_length(), x_resolution, field_width(), step, fine, tolerance);
+    let y_lines = adaptive_lines(&along_x, field_width(), y_resolution, field_length(), step, fine, tolerance);
+    
+    let x_wires = adaptive_wire_sets(&along_y, &x_lines, field_width(), coarse, fine, tolerance, Position::new);
+    let y_wires = adaptive_wire_sets(&along_x, &y_lines, field_length(), coarse, fine, tolerance, |y, x, z| Position::new(x, y, z));
     [x_wires, y_wires]
-}
\ No newline at end of file
+}
@@ -0,0 +1,254 @@
+// harmonic navigation function over a grid of the field, the solution of laplace's equation with the
+// obstacles and field border held at 1 and the goal at 0. every free cell is the average of its neighbors,
+// so the only minimum it has is the goal and descending it always arrives there
+use crate::color::Rgb;
+
+use crate::dubins::{PathSample, segment_samples};
+use crate::field::{field_length, field_width};
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::wire::Wire;
+
+pub const DEFAULT_HARMONIC_RESOLUTION: f32 = 0.15; // meters per cell
+pub const HARMONIC_HEIGHT: f32 = 1.0; // meters the wires of the boundary are drawn above the goal
+const MAX_SWEEPS: usize = 20_000;
+const CONVERGENCE: f64 = 1e-12; // largest change of a sweep the potential counts as solved at
+
+/// potential of every cell for one goal, solved by successive over-relaxation
+pub struct HarmonicField {
+    pub resolution: f32,
+    columns: usize,
+    rows: usize,
+    potential: Vec<f64>, // f64 since the potential is very flat far from the goal
+    fixed: Vec<bool>, // boundary cells, the obstacles, the field border and the goal
+    goal_cell: Option<usize>,
+    pub sweeps: usize,
+    pub converged: bool,
+}
+
+impl HarmonicField {
+    pub fn solve(obstacles: &[Obstacle], resolution: f32, goal: [f32; 2]) -> Self {
+        let resolution = resolution.max(0.05);
+        let columns = ((field_length() / resolution).ceil() as usize).max(3);
+        let rows = ((field_width() / resolution).ceil() as usize).max(3);
+        let hard: Vec<&Obstacle> = obstacles.iter().filter(|obstacle| obstacle.is_planned() && obstacle.is_hard()).collect();
+        
+        let mut fixed = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let border = row == 0 || column == 0 || row == rows - 1 || column == columns - 1;
+                let center = Position::new((column as f32 + 0.5) * resolution, (row as f32 + 0.5) * resolution, 0.0);
+                fixed.push(border || hard.iter().any(|obstacle| obstacle.edge_distance(center) < obstacle.robot_radius()));
+            }
+        }
+        
+        let mut field = HarmonicField {
+            resolution,
+            columns,
+            rows,
+            potential: vec![1.0; columns * rows],
+            fixed,
+            goal_cell: None,
+            sweeps: 0,
+            converged: false,
+        };
+        // a goal inside an obstacle or on the border can not be reached, the potential stays 1 everywhere
+        field.goal_cell = field.cell_of(goal).filter(|&cell| !field.fixed[cell]);
+        if let Some(goal_cell) = field.goal_cell {
+            field.potential[goal_cell] = 0.0;
+            field.fixed[goal_cell] = true;
+            field.relax();
+        }
+        field
+    }
+    
+    /// gauss-seidel sweeps over the free cells, over-relaxed by the optimal factor for the grid size
+    fn relax(&mut self) {
+        let omega = 2.0 / (1.0 + (std::f64::consts::PI / self.columns.max(self.rows) as f64).sin());
+        for sweep in 1..=MAX_SWEEPS {
+            let mut largest_change: f64 = 0.0;
+            for row in 1..self.rows - 1 {
+                for column in 1..self.columns - 1 {
+                    let cell = row * self.columns + column;
+                    if self.fixed[cell] {
+                        continue;
+                    }
+                    let average = (self.potential[cell - 1] + self.potential[cell + 1]
+                        + self.potential[cell - self.columns] + self.potential[cell + self.columns]) / 4.0;
+                    let change = omega * (average - self.potential[cell]);
+                    self.potential[cell] += change;
+                    largest_change = largest_change.max(change.abs());
+                }
+            }
+            self.sweeps = sweep;
+            if largest_change < CONVERGENCE {
+                self.converged = true;
+                return;
+            }
+        }
+    }
+    
+    fn cell_of(&self, point: [f32; 2]) -> Option<usize> {
+        if point[0] < 0.0 || point[1] < 0.0 {
+            return None;
+        }
+        let column = (point[0] / self.resolution) as usize;
+        let row = (point[1] / self.resolution) as usize;
+        (column < self.columns && row < self.rows).then(|| row * self.columns + column)
+    }
+    
+    fn center_of(&self, cell: usize) -> [f32; 2] {
+        let (row, column) = (cell / self.columns, cell % self.columns);
+        [(column as f32 + 0.5) * self.resolution, (row as f32 + 0.5) * self.resolution]
+    }
+    
+    fn neighbor(&self, column: i64, row: i64) -> Option<usize> {
+        (column >= 0 && row >= 0 && (column as usize) < self.columns && (row as usize) < self.rows)
+            .then(|| row as usize * self.columns + column as usize)
+    }
+    
+    /// descends the potential from start to the goal it was solved for, returns the points of the path after
+    /// the start and the heading the robot arrives with. None when the start is blocked or walled off from the goal
+    pub fn query(&self, start: [f32; 2], goal: [f32; 2]) -> Option<(Vec<PathSample>, f32)> {
+        let corners = self.descend(start, goal)?;
+        
+        let mut samples = Vec::new();
+        let mut from = start;
+        for &corner in &corners {
+            samples.extend(segment_samples(from, corner).into_iter()
+                .map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed: false }));
+            from = corner;
+        }
+        
+        let before_goal = if corners.len() > 1 { corners[corners.len() - 2] } else { start };
+        let heading = (goal[1] - before_goal[1]).atan2(goal[0] - before_goal[0]);
+        Some((samples, heading))
+    }
+    
+    /// cells down the steepest slope of the 8 neighbors until the goal, keeping only the cells the direction
+    /// changes at
+    fn descend(&self, start: [f32; 2], goal: [f32; 2]) -> Option<Vec<[f32; 2]>> {
+        let goal_cell = self.goal_cell?;
+        let mut cell = self.cell_of(start)?;
+        if cell == goal_cell {
+            return Some(vec![goal]);
+        }
+        if self.fixed[cell] {
+            return None;
+        }
+        
+        let mut cells = vec![cell];
+        while cell != goal_cell {
+            let (row, column) = ((cell / self.columns) as i64, (cell % self.columns) as i64);
+            let mut steepest: Option<(f64, usize)> = None;
+            for (d_column, d_row) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let Some(next) = self.neighbor(column + d_column, row + d_row) else { continue; };
+                if self.fixed[next] && next != goal_cell {
+                    continue;
+                }
+                // diagonal steps may not squeeze between two boundary cells
+                if d_column != 0 && d_row != 0 {
+                    let beside = [self.neighbor(column + d_column, row), self.neighbor(column, row + d_row)];
+                    if beside.iter().any(|side| side.is_none_or(|side| self.fixed[side] && side != goal_cell)) {
+                        continue;
+                    }
+                }
+                let step = if d_column != 0 && d_row != 0 { std::f64::consts::SQRT_2 } else { 1.0 };
+                let slope = (self.potential[next] - self.potential[cell]) / step;
+                if slope < 0.0 && steepest.is_none_or(|(steepest, _)| slope < steepest) {
+                    steepest = Some((slope, next));
+                }
+            }
+            // a flat cell is walled off from the goal, the potential there never left 1
+            cell = steepest?.1;
+            cells.push(cell);
+            if cells.len() > self.potential.len() {
+                return None;
+            }
+        }
+        
+        let mut points: Vec<[f32; 2]> = cells.iter().map(|&cell| self.center_of(cell)).collect();
+        points[0] = start;
+        *points.last_mut().unwrap() = goal;
+        
+        let mut corners = Vec::new();
+        for i in 1..points.len() {
+            let straight = points.get(i + 1).is_some_and(|next| {
+                let (a, b) = ([points[i][0] - points[i - 1][0], points[i][1] - points[i - 1][1]], [next[0] - points[i][0], next[1] - points[i][1]]);
+                (a[0] * b[1] - a[1] * b[0]).abs() < 1e-6 && a[0] * b[0] + a[1] * b[1] > 0.0
+            });
+            if !straight {
+                corners.push(points[i]);
+            }
+        }
+        Some(corners)
+    }
+    
+    /// lines along the cell rows and columns every spacing meters, lifted by the potential like the gradient
+    /// wires are by the obstacle field
+    pub fn wires(&self, x_spacing: f32, y_spacing: f32, color: Rgb<u8>) -> Vec<Wire> {
+        let point = |cell: usize| {
+            let [x, y] = self.center_of(cell);
+            Position::new(x, y, self.potential[cell] as f32 * HARMONIC_HEIGHT)
+        };
+        let row_stride = ((y_spacing / self.resolution).round() as usize).max(1);
+        let column_stride = ((x_spacing / self.resolution).round() as usize).max(1);
+        
+        let mut wires = Vec::new();
+        for row in (0..self.rows).step_by(row_stride) {
+            for column in 1..self.columns {
+                let cell = row * self.columns + column;
+                wires.push(Wire::with_color(point(cell - 1), point(cell), color));
+            }
+        }
+        for column in (0..self.columns).step_by(column_stride) {
+            for row in 1..self.rows {
+                let cell = row * self.columns + column;
+                wires.push(Wire::with_color(point(cell - self.columns), point(cell), color));
+            }
+        }
+        wires
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Model, ModelConfig};
+    
+    const START: [f32; 2] = [3.0, 4.1];
+    const GOAL: [f32; 2] = [13.0, 4.1];
+    
+    fn circle(x: f32, y: f32, radius: f32) -> Obstacle {
+        let config = ModelConfig { name: String::from("circle"), position: Position::new(x, y, 0.0), scale: radius * 2.0 };
+        Obstacle::new(Model { wires: Vec::new(), config })
+    }
+    
+    #[test]
+    fn descending_the_potential_reaches_the_goal_around_an_obstacle() {
+        let obstacles = vec![circle(8.0, 4.1, 1.0)];
+        let field = HarmonicField::solve(&obstacles, DEFAULT_HARMONIC_RESOLUTION, GOAL);
+        assert!(field.converged);
+        let (samples, _) = field.query(START, GOAL).unwrap();
+        
+        let end = samples.last().unwrap().position;
+        assert!((end.x - GOAL[0]).abs() < 1e-4 && (end.y - GOAL[1]).abs() < 1e-4);
+        for sample in &samples {
+            let cell = field.cell_of([sample.position.x, sample.position.y]).unwrap();
+            assert!(!field.fixed[cell] || Some(cell) == field.goal_cell, "sample at ({}, {}) is in an obstacle", sample.position.x, sample.position.y);
+        }
+    }
+    
+    #[test]
+    fn an_enclosed_goal_is_unreachable() {
+        // a ring of posts around the goal, close enough that the robot fits between none of them
+        let obstacles: Vec<Obstacle> = (0..12)
+            .map(|i| {
+                let angle = i as f32 * std::f32::consts::TAU / 12.0;
+                circle(GOAL[0] + 2.0 * angle.cos(), GOAL[1] + 2.0 * angle.sin(), 0.3)
+            })
+            .collect();
+        let field = HarmonicField::solve(&obstacles, DEFAULT_HARMONIC_RESOLUTION, GOAL);
+        assert!(field.query(START, GOAL).is_none());
+    }
+}
@@ -0,0 +1,309 @@
+// hybrid a* over the costmap (dolgov et al). the search is over continuous poses binned into grid cells and
+// heading slices, and every step drives a short arc no tighter than the turn radius, so the path it returns can
+// be driven by a car-like robot as it is without smoothing it into shape
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::f32::consts::TAU;
+
+use crate::costmap::{CellCost, Costmap};
+use crate::dubins::{PathSample, Pose, segment_samples};
+use crate::position::Position;
+
+pub const HEADING_BINS: usize = 72; // heading slices a cell is split into, 5 degrees each
+const STEERING: [f32; 5] = [-1.0, -0.5, 0.0, 0.5, 1.0]; // curvature of the primitives, as a fraction of the tightest
+const SAMPLE_SPACING: f32 = 0.05; // meters between the points of the planned path
+const REVERSE_COST: f32 = 1.5; // driving backwards counts this much more than driving forwards
+const CUSP_COST: f32 = 0.5; // meters added for stopping and changing direction
+const STEERING_COST: f32 = 0.05; // meters added per meter driven at the tightest curvature, so the path prefers straights
+const MAX_EXPANSIONS: usize = 100_000;
+
+/// a pose reached by the search and the primitive that drove to it from its parent
+#[derive(Copy, Clone)]
+struct Node {
+    pose: Pose,
+    cost: f32,
+    parent: Option<usize>,
+    curvature: f32,
+    distance: f32, // driven from the parent, negative backwards
+}
+
+/// the pose after driving a signed distance along an arc of a curvature, straight when it is 0
+fn drive(pose: Pose, curvature: f32, distance: f32) -> Pose {
+    let heading = pose.heading + curvature * distance;
+    if curvature.abs() < 1e-6 {
+        return Pose { x: pose.x + distance * pose.heading.cos(), y: pose.y + distance * pose.heading.sin(), heading };
+    }
+    Pose {
+        x: pose.x + (heading.sin() - pose.heading.sin()) / curvature,
+        y: pose.y - (heading.cos() - pose.heading.cos()) / curvature,
+        heading,
+    }
+}
+
+/// poses every few centimeters along a primitive, after its start
+fn primitive_poses(from: Pose, curvature: f32, distance: f32) -> Vec<Pose> {
+    let steps = ((distance.abs() / SAMPLE_SPACING).ceil() as usize).max(1);
+    (1..=steps).map(|i| drive(from, curvature, distance * i as f32 / steps as f32)).collect()
+}
+
+fn is_free(costmap: &Costmap, point: [f32; 2]) -> bool {
+    costmap.cost_at(point).is_some_and(|cost| cost != CellCost::Lethal)
+}
+
+/// cost of driving from every cell to the goal over the 8 connected grid, ignoring the turn radius, which the
+/// search is guided by so it does not wander into dead ends the straight line distance can not see
+fn cost_to_go(costmap: &Costmap, goal: usize) -> Vec<f32> {
+    let columns = costmap.columns;
+    let mut costs = vec![f32::INFINITY; costmap.cells.len()];
+    let mut queue = BinaryHeap::new();
+    costs[goal] = 0.0;
+    queue.push(Reverse((0.0f32.to_bits(), goal)));
+    
+    while let Some(Reverse((bits, cell))) = queue.pop() {
+        let cost = f32::from_bits(bits);
+        if cost > costs[cell] {
+            continue;
+        }
+        let (row, column) = ((cell / columns) as i64, (cell % columns) as i64);
+        for (d_column, d_row) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let (next_column, next_row) = (column + d_column, row + d_row);
+            if next_column < 0 || next_row < 0 || next_column as usize >= columns || next_row as usize >= costmap.rows {
+                continue;
+            }
+            let next = next_row as usize * columns + next_column as usize;
+            // the cost of leaving a cell, so the goal cell is free to arrive at whatever it holds
+            let Some(per_meter) = costmap.cells[next].traversal_cost() else { continue; };
+            let step = if d_column != 0 && d_row != 0 { std::f32::consts::SQRT_2 } else { 1.0 };
+            let total = cost + per_meter * step * costmap.resolution;
+            if total < costs[next] {
+                costs[next] = total;
+                queue.push(Reverse((total.to_bits(), next)));
+            }
+        }
+    }
+    costs
+}
+
+/// the shortest way to drive from a pose to a point with one arc of the turn radius and a straight, None when
+/// the point is inside both turning circles. returns the arc's curvature and length and the straight's length
+fn turn_then_straight(pose: Pose, goal: [f32; 2], turn_radius: f32) -> Option<(f32, f32, f32)> {
+    let mut best: Option<(f32, f32, f32)> = None;
+    for turn in [1.0f32, -1.0] {
+        let center = [pose.x - turn * turn_radius * pose.heading.sin(), pose.y + turn * turn_radius * pose.heading.cos()];
+        let (dx, dy) = (goal[0] - center[0], goal[1] - center[1]);
+        let distance = dx.hypot(dy);
+        if distance < turn_radius {
+            continue;
+        }
+        // the straight leaves the circle where its radius is square to the line on to the goal
+        let leave = dy.atan2(dx) - turn * (turn_radius / distance).acos();
+        let start = (pose.y - center[1]).atan2(pose.x - center[0]);
+        let sweep = (turn * (leave - start)).rem_euclid(TAU);
+        let straight = (distance * distance - turn_radius * turn_radius).sqrt();
+        let shot = (turn / turn_radius, sweep * turn_radius, straight);
+        if best.is_none_or(|best| shot.1 + shot.2 < best.1 + best.2) {
+            best = Some(shot);
+        }
+    }
+    best
+}
+
+/// searches drivable poses from start to the goal over the costmap, every step an arc of the primitive length
+/// with one of a few curvatures up to the tightest the turn radius allows, backing up too when reversing. the
+/// search ends once a turn and a straight reach the goal without entering a lethal cell. returns the points of
+/// the path after the start and the heading the robot arrives with, and adds the steps it expanded to expanded
+pub fn plan(costmap: &Costmap, start: Pose, goal: [f32; 2], turn_radius: f32, reversing: bool, expanded: &mut Vec<[[f32; 2]; 2]>) -> Option<(Vec<PathSample>, f32)> {
+    let turn_radius = turn_radius.max(SAMPLE_SPACING);
+    let goal_cell = costmap.cell_of(goal).filter(|&cell| costmap.cells[cell] != CellCost::Lethal)?;
+    let to_go = cost_to_go(costmap, goal_cell);
+    
+    // a step must leave its cell so a node never lands in the bin it came from, even diagonally
+    let step = costmap.resolution * 1.5;
+    let state_of = |pose: &Pose| {
+        let bin = ((pose.heading.rem_euclid(TAU) / TAU * HEADING_BINS as f32) as usize).min(HEADING_BINS - 1);
+        costmap.cell_of([pose.x, pose.y]).unwrap_or(0) * HEADING_BINS + bin
+    };
+    let heuristic = |pose: &Pose| costmap.cell_of([pose.x, pose.y])
+        .map_or(f32::INFINITY, |cell| to_go[cell].max((goal[0] - pose.x).hypot(goal[1] - pose.y)));
+    
+    if !is_free(costmap, [start.x, start.y]) || !heuristic(&start).is_finite() {
+        return None;
+    }
+    let mut nodes = vec![Node { pose: start, cost: 0.0, parent: None, curvature: 0.0, distance: 0.0 }];
+    let mut best = vec![f32::INFINITY; costmap.cells.len() * HEADING_BINS];
+    let mut closed = vec![false; best.len()];
+    let mut open = BinaryHeap::new();
+    best[state_of(&start)] = 0.0;
+    open.push(Reverse((heuristic(&start).to_bits(), 0usize)));
+    let directions: &[f32] = if reversing { &[1.0, -1.0] } else { &[1.0] };
+    
+    let mut expansions = 0;
+    while let Some(Reverse((_, index))) = open.pop() {
+        let node = nodes[index];
+        let state = state_of(&node.pose);
+        if closed[state] || node.cost > best[state] {
+            continue;
+        }
+        closed[state] = true;
+        expansions += 1;
+        if expansions > MAX_EXPANSIONS {
+            return None;
+        }
+        if let Some(parent) = node.parent {
+            let from = nodes[parent].pose;
+            expanded.push([[from.x, from.y], [node.pose.x, node.pose.y]]);
+        }
+        
+        if let Some(leg) = shot(costmap, &nodes, index, goal, turn_radius) {
+            return Some(leg);
+        }
+        
+        for &direction in directions {
+            for fraction in STEERING {
+                let curvature = fraction / turn_radius;
+                let poses = primitive_poses(node.pose, curvature, direction * step);
+                if !poses.iter().all(|pose| is_free(costmap, [pose.x, pose.y])) {
+                    continue;
+                }
+                let pose = *poses.last().unwrap();
+                let next_state = state_of(&pose);
+                if closed[next_state] {
+                    continue;
+                }
+                
+                let per_meter = poses.iter()
+                    .filter_map(|pose| costmap.cost_at([pose.x, pose.y]).and_then(|cost| cost.traversal_cost()))
+                    .sum::<f32>() / poses.len() as f32;
+                let mut cost = node.cost + step * (per_meter + STEERING_COST * fraction.abs());
+                if direction < 0.0 {
+                    cost += step * (REVERSE_COST - 1.0) * per_meter;
+                }
+                if node.distance * direction < 0.0 {
+                    cost += CUSP_COST;
+                }
+                if cost >= best[next_state] {
+                    continue;
+                }
+                
+                let estimate = heuristic(&pose);
+                if !estimate.is_finite() {
+                    continue;
+                }
+                best[next_state] = cost;
+                nodes.push(Node { pose, cost, parent: Some(index), curvature, distance: direction * step });
+                open.push(Reverse(((cost + estimate).to_bits(), nodes.len() - 1)));
+            }
+        }
+    }
+    None
+}
+
+/// the path to a node with a turn and a straight on to the goal, when they stay out of the lethal cells
+fn shot(costmap: &Costmap, nodes: &[Node], index: usize, goal: [f32; 2], turn_radius: f32) -> Option<(Vec<PathSample>, f32)> {
+    let from = nodes[index].pose;
+    let (curvature, arc, straight) = turn_then_straight(from, goal, turn_radius)?;
+    let arc_poses = if arc > 1e-3 { primitive_poses(from, curvature, arc) } else { Vec::new() };
+    let leave = arc_poses.last().copied().unwrap_or(from);
+    let straight_points = segment_samples([leave.x, leave.y], goal);
+    let clear = arc_poses.iter().map(|pose| [pose.x, pose.y]).chain(straight_points.iter().copied())
+        .all(|point| is_free(costmap, point));
+    if !clear {
+        return None;
+    }
+    
+    // the primitives back to the start, replayed from each parent
+    let mut chain = vec![index];
+    while let Some(parent) = nodes[*chain.last().unwrap()].parent {
+        chain.push(parent);
+    }
+    let mut samples = Vec::new();
+    for &node in chain.iter().rev().skip(1) {
+        let node = nodes[node];
+        let parent = nodes[node.parent.unwrap()].pose;
+        samples.extend(primitive_poses(parent, node.curvature, node.distance).into_iter()
+            .map(|pose| PathSample { position: Position::new(pose.x, pose.y, 0.0), reversed: node.distance < 0.0 }));
+    }
+    samples.extend(arc_poses.iter().map(|pose| [pose.x, pose.y]).chain(straight_points)
+        .map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed: false }));
+    
+    let heading = if straight > 1e-3 { (goal[1] - leave.y).atan2(goal[0] - leave.x) } else { leave.heading };
+    Some((samples, heading))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const TURN_RADIUS: f32 = 0.5;
+    const START: Pose = Pose { x: 0.5, y: 0.5, heading: 0.0 };
+    const GOAL: [f32; 2] = [3.5, 0.5];
+    
+    /// a free 4 by 2 meter grid with a block across the straight line from the start to the goal, leaving a
+    /// corridor above it
+    fn blocked_corridor() -> Costmap {
+        let (columns, rows) = (40, 20);
+        let mut cells = vec![CellCost::Free; columns * rows];
+        for row in 0..10 {
+            for column in 18..22 {
+                cells[row * columns + column] = CellCost::Lethal;
+            }
+        }
+        Costmap { resolution: 0.1, columns, rows, cells }
+    }
+    
+    fn circumradius(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+        let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        if cross.abs() < 1e-9 {
+            return f32::INFINITY;
+        }
+        let length = |p: [f32; 2], q: [f32; 2]| (q[0] - p[0]).hypot(q[1] - p[1]);
+        length(a, b) * length(b, c) * length(a, c) / (2.0 * cross.abs())
+    }
+    
+    #[test]
+    fn the_path_drives_around_the_block() {
+        let costmap = blocked_corridor();
+        let (samples, _) = plan(&costmap, START, GOAL, TURN_RADIUS, false, &mut Vec::new()).unwrap();
+        
+        let points: Vec<[f32; 2]> = std::iter::once([START.x, START.y])
+            .chain(samples.iter().map(|sample| [sample.position.x, sample.position.y]))
+            .collect();
+        let end = *points.last().unwrap();
+        assert!((end[0] - GOAL[0]).abs() < 1e-3 && (end[1] - GOAL[1]).abs() < 1e-3);
+        for point in &points {
+            assert!(is_free(&costmap, *point), "({}, {}) is in the block", point[0], point[1]);
+        }
+        assert!(points.iter().any(|point| point[1] > 1.0), "the path has to pass above the block");
+        assert!(samples.iter().all(|sample| !sample.reversed));
+    }
+    
+    #[test]
+    fn the_path_turns_no_tighter_than_the_turn_radius() {
+        let costmap = blocked_corridor();
+        let (samples, _) = plan(&costmap, START, GOAL, TURN_RADIUS, false, &mut Vec::new()).unwrap();
+        
+        let points: Vec<[f32; 2]> = samples.iter().map(|sample| [sample.position.x, sample.position.y]).collect();
+        for triple in points.windows(3) {
+            let radius = circumradius(triple[0], triple[1], triple[2]);
+            assert!(radius > TURN_RADIUS * 0.95, "turns with a radius of {} at ({}, {})", radius, triple[1][0], triple[1][1]);
+        }
+    }
+    
+    #[test]
+    fn an_enclosed_goal_is_unreachable() {
+        let mut costmap = blocked_corridor();
+        let goal = costmap.cell_of(GOAL).unwrap();
+        let (row, column) = (goal / costmap.columns, goal % costmap.columns);
+        for d_row in 0..5 {
+            for d_column in 0..5 {
+                if d_row == 0 || d_row == 4 || d_column == 0 || d_column == 4 {
+                    costmap.cells[(row + d_row - 2) * costmap.columns + column + d_column - 2] = CellCost::Lethal;
+                }
+            }
+        }
+        
+        assert!(plan(&costmap, START, GOAL, TURN_RADIUS, false, &mut Vec::new()).is_none());
+        assert!(plan(&costmap, START, GOAL, TURN_RADIUS, true, &mut Vec::new()).is_none());
+    }
+}
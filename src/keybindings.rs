@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use nannou::prelude::{App, Key};
+use serde::{Deserialize, Serialize};
+
+pub const KEYBINDINGS_PATH: &str = "keybindings.json";
+
+/// something a key can be bound to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    CameraForward,
+    CameraBack,
+    CameraLeft,
+    CameraRight,
+    CameraUp,
+    CameraDown,
+    RotateLeft,
+    RotateRight,
+    RotateUp,
+    RotateDown,
+    RobotForward,
+    RobotBack,
+    RobotLeft,
+    RobotRight,
+    ToggleMoveFocus,
+    ToggleFollowRobot,
+    ToggleFullscreen,
+    FollowPath,
+    GeneratePath,
+    ToggleGradient,
+    ToggleMinimap,
+    ToggleLabels,
+    ToggleGrid,
+    FrameScene,
+    Screenshot,
+}
+
+impl Action {
+    pub const ALL: [Action; 25] = [
+        Action::CameraForward, Action::CameraBack, Action::CameraLeft, Action::CameraRight,
+        Action::CameraUp, Action::CameraDown,
+        Action::RotateLeft, Action::RotateRight, Action::RotateUp, Action::RotateDown,
+        Action::RobotForward, Action::RobotBack, Action::RobotLeft, Action::RobotRight,
+        Action::ToggleMoveFocus, Action::ToggleFollowRobot, Action::ToggleFullscreen,
+        Action::FollowPath, Action::GeneratePath,
+        Action::ToggleGradient, Action::ToggleMinimap, Action::ToggleLabels, Action::ToggleGrid,
+        Action::FrameScene, Action::Screenshot,
+    ];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::CameraForward => "Camera Forward",
+            Action::CameraBack => "Camera Back",
+            Action::CameraLeft => "Camera Left",
+            Action::CameraRight => "Camera Right",
+            Action::CameraUp => "Camera Up",
+            Action::CameraDown => "Camera Down",
+            Action::RotateLeft => "Rotate Camera Left",
+            Action::RotateRight => "Rotate Camera Right",
+            Action::RotateUp => "Tilt Camera Up",
+            Action::RotateDown => "Tilt Camera Down",
+            Action::RobotForward => "Drive Robot Forward",
+            Action::RobotBack => "Drive Robot Back",
+            Action::RobotLeft => "Drive Robot Left",
+            Action::RobotRight => "Drive Robot Right",
+            Action::ToggleMoveFocus => "Camera Movement Keys Drive Robot",
+            Action::ToggleFollowRobot => "Toggle Follow Robot",
+            Action::ToggleFullscreen => "Toggle Fullscreen",
+            Action::FollowPath => "Follow Path (hold)",
+            Action::GeneratePath => "Generate Path",
+            Action::ToggleGradient => "Toggle Gradient",
+            Action::ToggleMinimap => "Toggle Minimap",
+            Action::ToggleLabels => "Toggle Labels",
+            Action::ToggleGrid => "Toggle Ground Grid",
+            Action::FrameScene => "Frame Scene",
+            Action::Screenshot => "Save Screenshot",
+        }
+    }
+    
+    fn default_key(&self) -> Option<Key> {
+        Some(match self {
+            Action::CameraForward => Key::W,
+            Action::CameraBack => Key::S,
+            Action::CameraLeft => Key::A,
+            Action::CameraRight => Key::D,
+            Action::CameraUp => Key::E,
+            Action::CameraDown => Key::Q,
+            Action::RotateLeft => Key::Left,
+            Action::RotateRight => Key::Right,
+            Action::RotateUp => Key::Up,
+            Action::RotateDown => Key::Down,
+            Action::RobotForward => Key::I,
+            Action::RobotBack => Key::K,
+            Action::RobotLeft => Key::J,
+            Action::RobotRight => Key::L,
+            Action::ToggleMoveFocus => Key::T,
+            Action::ToggleFollowRobot => Key::F,
+            Action::ToggleFullscreen => Key::F11,
+            Action::FollowPath => Key::Space,
+            Action::GeneratePath => Key::G,
+            Action::ToggleGradient => Key::H,
+            Action::ToggleMinimap => Key::M,
+            Action::ToggleLabels => Key::N,
+            Action::FrameScene => Key::Z,
+            Action::Screenshot => Key::F12,
+            Action::ToggleGrid => return None,
+        })
+    }
+}
+
+// keys that can be bound, F1-F9 are kept for camera bookmarks
+const BINDABLE_KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9, Key::Key0,
+    Key::F10, Key::F11, Key::F12,
+    Key::Left, Key::Right, Key::Up, Key::Down,
+    Key::Space, Key::Tab, Key::Return, Key::Back, Key::Delete, Key::Insert, Key::Home, Key::End, Key::PageUp, Key::PageDown,
+    Key::LShift, Key::RShift, Key::LControl, Key::RControl, Key::LAlt, Key::RAlt,
+    Key::Minus, Key::Equals, Key::Comma, Key::Period, Key::Slash, Key::Semicolon, Key::Apostrophe,
+    Key::LBracket, Key::RBracket, Key::Backslash, Key::Grave,
+];
+
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    BINDABLE_KEYS.iter().copied().find(|key| key_name(*key) == name)
+}
+
+pub fn is_bindable(key: Key) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+/// which key triggers each action, actions can also be left unbound
+pub struct KeyBindings {
+    keys: BTreeMap<Action, Option<Key>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL.iter().map(|action| (*action, action.default_key())).collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// reads bindings saved by `save`, actions missing from the file keep their default key
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let saved: BTreeMap<Action, Option<String>> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let mut bindings = Self::default();
+        for (action, name) in saved {
+            let key = match name {
+                Some(name) => Some(key_from_name(&name).ok_or(format!("unknown key {} for {:?}", name, action))?),
+                None => None,
+            };
+            bindings.keys.insert(action, key);
+        }
+        Ok(bindings)
+    }
+    
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let saved: BTreeMap<Action, Option<String>> = self.keys.iter()
+            .map(|(action, key)| (*action, key.map(key_name)))
+            .collect();
+        std::fs::write(path, serde_json::to_string_pretty(&saved)?)?;
+        Ok(())
+    }
+    
+    pub fn key(&self, action: Action) -> Option<Key> {
+        self.keys.get(&action).copied().flatten()
+    }
+    
+    /// binds a key to an action, unbinding it from any other action
+    pub fn bind(&mut self, action: Action, key: Option<Key>) {
+        if key.is_some() {
+            for bound in self.keys.values_mut() {
+                if *bound == key {
+                    *bound = None;
+                }
+            }
+        }
+        self.keys.insert(action, key);
+    }
+    
+    /// the action bound to a key, if any
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.keys.iter().find(|(_, bound)| **bound == Some(key)).map(|(action, _)| *action)
+    }
+    
+    pub fn is_down(&self, action: Action, app: &App) -> bool {
+        self.key(action).is_some_and(|key| app.keys.down.contains(&key))
+    }
+    
+    /// key name for display, "-" when unbound
+    pub fn describe(&self, action: Action) -> String {
+        self.key(action).map_or(String::from("-"), key_name)
+    }
+}
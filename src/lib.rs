@@ -0,0 +1,33 @@
+// the planning code of the visualizer without its window, used by the visualizer itself and by the
+// c api in `ffi` for running the planner on a coprocessor
+
+pub mod model;
+pub mod wire;
+pub mod field;
+pub mod obstacle;
+pub mod position;
+pub mod color;
+pub mod planner;
+pub mod robot;
+pub mod path_event;
+pub mod noise;
+pub mod pid;
+pub mod config;
+pub mod footprint;
+pub mod error;
+pub mod pipeline;
+pub mod dubins;
+pub mod prm;
+pub mod costmap;
+pub mod harmonic;
+pub mod wavefront;
+pub mod dstar_lite;
+pub mod hybrid_astar;
+pub mod space_time;
+pub mod goal_region;
+pub mod tour;
+pub mod distance_field;
+pub mod trajectory;
+pub mod local_avoidance;
+pub mod speed_limit;
+pub mod ffi;
@@ -0,0 +1,148 @@
+use crate::obstacle::Obstacle;
+
+pub const DEFAULT_TIME_HORIZON: f32 = 2.0; // seconds ahead a collision changes the velocity
+pub const DEFAULT_TRACKING_GAIN: f32 = 1.5; // per second, pull of the preferred velocity back to the path
+const MAX_LAG: f32 = 0.5; // meters behind the path reference the follower stops advancing along the path
+const SPEED_SAMPLES: usize = 5;
+const HEADING_SAMPLES: usize = 24;
+const COLLISION_WEIGHT: f32 = 2.0; // m/s of extra deviation worth one second sooner collision
+
+/// a moving obstacle as the avoidance layer sees it, a disc of the robot and obstacle radii together
+#[derive(Debug, Copy, Clone)]
+pub struct MovingDisc {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub radius: f32,
+}
+
+/// reactive layer between the path follower and the drive, picks the velocity closest to the one the
+/// follower wants that stays out of the velocity obstacles of the moving obstacles, while the global
+/// path stays as planned
+#[derive(Debug, Clone)]
+pub struct LocalAvoidance {
+    pub enabled: bool,
+    pub time_horizon: f32,
+    pub tracking_gain: f32,
+    pub preferred: [f32; 2], // velocity the follower asked for, with the pull back to the path
+    pub chosen: [f32; 2], // velocity sent to the drive
+    pub lagging: bool, // the robot fell behind the path reference while avoiding
+    discs: Vec<MovingDisc>,
+}
+
+impl LocalAvoidance {
+    pub fn new() -> Self {
+        LocalAvoidance {
+            enabled: false,
+            time_horizon: DEFAULT_TIME_HORIZON,
+            tracking_gain: DEFAULT_TRACKING_GAIN,
+            preferred: [0.0, 0.0],
+            chosen: [0.0, 0.0],
+            lagging: false,
+            discs: Vec::new(),
+        }
+    }
+    
+    /// takes the moving hard obstacles the next velocities are chosen against
+    pub fn observe(&mut self, obstacles: &[Obstacle]) {
+        self.discs = obstacles.iter()
+            .filter(|obstacle| obstacle.is_hard() && obstacle.trajectory.is_some())
+            .map(|obstacle| {
+                let center = obstacle.model.config.position;
+                MovingDisc { position: [center.x, center.y], velocity: obstacle.velocity(), radius: obstacle.inflated_radius() }
+            })
+            .collect();
+    }
+    
+    /// true when the avoidance changed the last command
+    pub fn is_avoiding(&self) -> bool {
+        let dx = self.chosen[0] - self.preferred[0];
+        let dy = self.chosen[1] - self.preferred[1];
+        self.enabled && (dx * dx + dy * dy).sqrt() > 0.01
+    }
+    
+    /// the velocity to drive from a position given the follower command and the point on the path the robot
+    /// should be at, no faster than max speed
+    pub fn choose_velocity(&mut self, position: [f32; 2], command: [f32; 2], reference: [f32; 2], max_speed: f32) -> [f32; 2] {
+        let offset = [reference[0] - position[0], reference[1] - position[1]];
+        self.lagging = (offset[0] * offset[0] + offset[1] * offset[1]).sqrt() > MAX_LAG;
+        self.preferred = [command[0] + self.tracking_gain * offset[0], command[1] + self.tracking_gain * offset[1]];
+        let max_speed = max_speed.max(length(command));
+        let preferred_speed = length(self.preferred);
+        if preferred_speed > max_speed {
+            self.preferred = [self.preferred[0] * max_speed / preferred_speed, self.preferred[1] * max_speed / preferred_speed];
+        }
+        
+        let mut candidates = vec![self.preferred, [0.0, 0.0]];
+        for speed_step in 1..=SPEED_SAMPLES {
+            let speed = max_speed * speed_step as f32 / SPEED_SAMPLES as f32;
+            for heading_step in 0..HEADING_SAMPLES {
+                let angle = heading_step as f32 / HEADING_SAMPLES as f32 * std::f32::consts::TAU;
+                candidates.push([speed * angle.cos(), speed * angle.sin()]);
+            }
+        }
+        
+        let mut best = self.preferred;
+        let mut best_cost = f32::INFINITY;
+        for candidate in candidates {
+            let cost = length([candidate[0] - self.preferred[0], candidate[1] - self.preferred[1]])
+                + self.collision_penalty(position, candidate);
+            if cost < best_cost {
+                best_cost = cost;
+                best = candidate;
+            }
+        }
+        self.chosen = best;
+        best
+    }
+    
+    /// cost of a velocity for how soon it runs into a moving obstacle within the time horizon
+    fn collision_penalty(&self, position: [f32; 2], velocity: [f32; 2]) -> f32 {
+        let mut penalty: f32 = 0.0;
+        for disc in &self.discs {
+            let relative_position = [disc.position[0] - position[0], disc.position[1] - position[1]];
+            let relative_velocity = [velocity[0] - disc.velocity[0], velocity[1] - disc.velocity[1]];
+            
+            // already overlapping, only velocities moving apart are allowed
+            if length(relative_position) < disc.radius {
+                let closing = relative_position[0] * relative_velocity[0] + relative_position[1] * relative_velocity[1];
+                if closing > 0.0 {
+                    penalty = f32::INFINITY;
+                }
+                continue;
+            }
+            
+            let time = time_to_collision(relative_position, relative_velocity, disc.radius);
+            if time < self.time_horizon {
+                penalty = penalty.max(COLLISION_WEIGHT * (self.time_horizon / time.max(0.01) - 1.0));
+            }
+        }
+        penalty
+    }
+}
+
+impl Default for LocalAvoidance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn length(v: [f32; 2]) -> f32 {
+    (v[0] * v[0] + v[1] * v[1]).sqrt()
+}
+
+/// seconds until a disc at a relative position comes within radius when closing at a relative
+/// velocity, infinite if it never does
+fn time_to_collision(relative_position: [f32; 2], relative_velocity: [f32; 2], radius: f32) -> f32 {
+    // |p - v t| = r, a t^2 - 2 b t + c = 0
+    let a = relative_velocity[0] * relative_velocity[0] + relative_velocity[1] * relative_velocity[1];
+    let b = relative_position[0] * relative_velocity[0] + relative_position[1] * relative_velocity[1];
+    let c = relative_position[0] * relative_position[0] + relative_position[1] * relative_position[1] - radius * radius;
+    if a <= f32::EPSILON || b <= 0.0 {
+        return f32::INFINITY;
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return f32::INFINITY;
+    }
+    (b - discriminant.sqrt()) / a
+}
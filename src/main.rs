@@ -1,782 +1,5345 @@
 use nannou::prelude::*;
 use nannou_egui::{self, egui, Egui};
 
-mod model;
-mod wire;
-mod field;
-mod obstacle;
-mod position;
+use object_avoidant_path_gen::{config, error, field, goal_region, model, obstacle, path_event, pid, pipeline, planner, position, robot, speed_limit, tour, trajectory, wire};
+
+mod camera;
+mod cli;
 mod gradient_field;
-mod robot;
 mod target_position;
+mod minimap;
+mod field_layout;
+mod field_image;
+mod obstacle_palette;
+mod scene_generator;
+mod evaluation;
+mod path_export;
+mod keybindings;
+mod settings;
+mod model_watcher;
+mod capture;
+mod svg_export;
+mod scene;
+mod autosave;
+mod world;
+mod events;
+mod path_markers;
+mod coordination;
+mod ros_export;
+mod setpoint_export;
+mod path_import;
+mod remote;
+mod scripting;
+mod session;
+mod breakpoint;
+mod gpu_field;
 
 use model::{Model, ModelConfig};
-use crate::position::Position;
+use crate::config::{ColorConfig, ColorTheme};
+use crate::position::{wrap_angle, Position};
 use crate::field::*;
-use crate::obstacle::Obstacle;
-use crate::robot::Robot;
-use crate::target_position::TargetPosition;
+use crate::obstacle::{FieldCombination, Obstacle, ObstacleGroup, ObstacleLayer, DEFAULT_LAYER, ensure_group, ensure_layer, group_center};
+use crate::robot::{Robot, ViaPoint, ROBOT_INITIAL_POSITION};
+use crate::speed_limit::{SpeedZone, DEFAULT_ZONE_SPEED};
+use crate::goal_region::GoalRegion;
+use crate::target_position::{NamedTarget, TargetPosition, TARGET_PALETTE};
+use crate::gradient_field::{CrossSection, FieldProbe, FieldRendering};
+use crate::gpu_field::GpuField;
+use crate::wire::Wire;
+use crate::minimap::Minimap;
+use crate::field_layout::FieldLayout;
+use crate::field_image::FieldImage;
+use crate::obstacle_palette::OBSTACLE_PALETTE;
+use crate::scene_generator::{RandomSceneConfig, generate_random_scene};
+use crate::evaluation::Evaluation;
+use crate::path_event::{PathAction, PathEvent};
+use crate::path_export::export_path;
+use crate::ros_export::export_ros_path;
+use crate::setpoint_export::export_setpoints;
+use crate::path_import::{check_path, import_path, worst_clearances, ClearancePoint, PathCheck, DEFAULT_WORST_POINTS};
+use crate::svg_export::export_svg;
+use crate::scene::{Scene, SceneGroup, SceneLayer, SceneModel, SceneObstacle, ScenePoint, SceneRobot, SceneTarget, SceneViaPoint};
+use crate::pid::FollowerMode;
+use crate::keybindings::{Action, KeyBindings, KEYBINDINGS_PATH};
+use crate::settings::{LineWeights, Settings, SETTINGS_PATH};
+use crate::cli::Cli;
+use crate::planner::Planner;
+use crate::model_watcher::ModelWatcher;
+use crate::capture::{FrameRecorder, VideoFormat};
+use crate::autosave::Autosave;
+use crate::world::World;
+use crate::events::WorldEvent;
+use crate::error::ModelError;
+use crate::path_markers::{MarkerColoring, MarkerSampling, PathMarkers};
+use crate::pipeline::PipelineStage;
+use crate::trajectory::TrajectoryWaypoint;
+use crate::remote::{RemoteCommand, RemoteServer};
+use crate::scripting::ScriptConsole;
+use crate::breakpoint::{BreakCondition, Breakpoint, BreakpointHit};
+use crate::session::{RobotState, SessionEntry, SessionRecorder, SessionReplay, SessionSettings};
+use crate::coordination::{CoordinationMode, RobotConflict, TeamRobot, LEAD_ROBOT_NAME, find_conflicts, plan_team};
+use clap::Parser;
+use crate::camera::{CameraBookmark, CameraMotion, CameraPose, CameraTransition};
 
-const SPEED: f64 = 3.0;
-const FOV: f32 = PI / 2.0;
+const MIN_FOV: f32 = PI / 6.0;
+const MAX_FOV: f32 = PI * 5.0 / 6.0;
+const FOLLOW_MIN_HEADING_SPEED: f32 = 0.05;
+const AXIS_GIZMO_LENGTH: f32 = 30.0;
+const AXIS_GIZMO_MARGIN: f32 = 50.0;
+const STATUS_BAR_HEIGHT: f32 = 24.0;
+const MAX_STEPS_PER_FRAME: usize = 20; // fixed steps a slow frame may catch up, the rest of the time is dropped
+const PATH_HANDLE_RADIUS: f32 = 4.0;
+const PATH_HANDLE_PICK_RADIUS: f32 = 10.0; // pixels
+const COLLISION_FLASH_TIME: f32 = 1.5;
+const LABEL_HEIGHT: f32 = 0.3; // meters above the top of a model
+const HORIZON_MARGIN: f32 = 0.9; // of half the field of view the camera may tilt with the horizon kept level
+const POINT_VIEW_DISTANCE: f32 = 2.5; // meters the camera stays from a spot it is sent to
+const POINT_VIEW_PITCH: f32 = -0.7; // radians the camera looks down at a spot it is sent to
+const CROSS_SECTION_SAMPLES: usize = 200;
+const PROBE_ARROW_LENGTH: f32 = 0.5; // meters the gradient line of a probe reaches at a slope of one or more
+const FIELD_ELEMENTS_LAYER: &str = "field elements";
+const MSAA_SAMPLES: u32 = 4; // anti-aliases the lines
 const SCREENWIDTH: u32 = 640;
 const SCREENHEIGHT: u32 = 480;
 
+/// current window dimensions and field of view used to project camera space onto the canvas
+struct Projection {
+    width: f32,
+    height: f32,
+    fov: f32, // field of view across the shorter window side, in radians
+}
+
+impl Projection {
+    fn from_window(window_rect: Rect, fov: f32) -> Self {
+        Projection {
+            width: window_rect.w(),
+            height: window_rect.h(),
+            fov,
+        }
+    }
+    
+    fn focal_length(&self) -> f32 {
+        (self.width.min(self.height) / 2.0) / (self.fov / 2.0).tan()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    config::init(config::CONFIG_PATH);
+    
     nannou::app(model)
         .update(update)
+        .exit(exit)
         .run();
 }
 
+// in the browser the window is created asynchronously, wasm-bindgen calls main once the module is loaded
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    config::init(config::CONFIG_PATH);
+    
+    wasm_bindgen_futures::spawn_local(async {
+        nannou::app::Builder::new_async(|app| Box::new(model_async(app)))
+            .update(update)
+            .exit(exit)
+            .run_async()
+            .await;
+    });
+}
+
+/// what a left click on the field does
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ClickAction {
+    None,
+    PlaceObstacle,
+    MoveTarget,
+    Measure,
+    EditPath,
+    AddViaPoint,
+    AddPathEvent,
+    Probe,
+    CrossSection,
+    SpeedZone,
+}
+
 struct AppModel {
     _window: window::Id,
     camera_position: Position,
     direction: f32,
     rotation_y: f32,
-    models: Vec<Model>,
-    obstacles: Vec<Obstacle>,
-    robot: Option<Robot>,
-    target_position: TargetPosition,
+    fov: f32,
+    fullscreen: bool,
+    follow_robot: bool,
+    follow_distance: f32,
+    follow_height: f32,
+    follow_smoothing: f32,
+    follow_heading: f32,
+    camera_bookmarks: Vec<CameraBookmark>,
+    camera_transition: Option<CameraTransition>,
+    camera_transition_time: f32, // seconds a jump to a bookmark or framing the scene takes
+    camera_motion: CameraMotion,
+    camera_damping: f32,
+    keep_horizon_level: bool, // tilt no further than keeps the horizon in view
+    new_bookmark_name: String,
+    new_target_name: String,
+    tour: Option<(Vec<usize>, f32)>, // order of the named targets in the last pickup tour and its length
+    cursor_field_position: Option<Position>,
+    click_action: ClickAction,
+    snap_to_grid: bool,
+    ruler_start: Option<Position>,
+    ruler_end: Option<Position>,
+    probes: Vec<Position>, // pinned field probes, their readouts follow the obstacles as they move
+    section_start: Option<Position>,
+    zone_corner: Option<Position>, // first corner of the speed zone being drawn
+    new_zone_speed: f32,
+    section_end: Option<Position>,
+    section_gaussian: bool, // overlay the gaussian field on the cross section plot
+    dragging_path_point: Option<usize>,
+    inspected_path_point: Option<usize>, // its obstacle field contributions are drawn and listed
+    new_via_tolerance: f32,
+    new_event_action: PathAction,
+    path_export_path: String,
+    ros_export_path: String,
+    setpoint_export_path: String,
+    path_import_path: String,
+    path_check: Option<PathCheck>, // last check of the current path
+    clearance_report: Vec<ClearancePoint>, // lowest clearance spots of the path when the report was made
+    worst_point_count: usize,
+    control_rate: f32,
+    svg_export_path: String,
+    scene_path: String,
+    session_path: String,
+    session_recorder: Option<SessionRecorder>,
+    session_replay: Option<SessionReplay>,
+    world: World,
     egui: Egui,
-
+    
     camera_speed: f32,
     rotation_speed: f32,
     show_path: bool,
+    show_initial_path: bool,
+    show_optimized_path: bool,
+    show_pruned_points: bool,
+    show_roadmap: bool,
+    animate_roadmap: bool,
+    roadmap_growth_speed: f32, // edges drawn per second
+    show_wavefront: bool,
+    animate_wavefront: bool,
+    wavefront_speed: f32, // waves drawn per second
+    replan_moving_obstacles: bool, // d* lite replans every step while obstacles move
+    show_hybrid_expanded: bool,
+    color_path_by_speed: bool,
     show_points: bool,
+    path_markers: PathMarkers,
+    line_weights: LineWeights,
+    custom_colors: Option<ColorConfig>, // chosen in the colors panel, none keeps the configured ones
     show_gradient_function: bool,
+    show_influence_rings: bool,
+    show_minimap: bool,
+    show_grid: bool,
+    show_axis_gizmo: bool,
+    show_labels: bool,
+    grid_spacing: f32,
+    grid_extent: f32,
+    field_image: Option<FieldImage>,
+    field_image_path: String,
+    field_image_columns: usize,
+    field_image_brightness: f32,
+    show_field_image: bool,
+    show_costmap: bool,
+    show_distance_heatmap: bool,
+    show_distance_contours: bool,
+    distance_contour_spacing: f32,
+    distance_max: f32,
+    show_harmonic_field: bool,
     path_segments: usize,
+    fixed_timestep: bool, // advance the simulation in equal steps so runs do not depend on the frame rate
+    timestep: f32, // seconds per fixed step
+    sim_time: f32, // seconds simulated, runs slower than the wall clock when frames take too long
+    time_accumulator: f32, // frame time not yet simulated with the fixed timestep
+    play_optimization: bool, // runs the stepped optimization every frame
+    optimization_steps_per_frame: usize,
+    breakpoints: Vec<Breakpoint>, // checked after every stepped optimization iteration
+    new_break_condition: BreakCondition,
+    breakpoint_hit: Option<BreakpointHit>, // highlighted until the optimization goes on
+    obstacle_time: f32, // seconds the obstacle trajectories have run
+    animate_obstacles: bool, // moves obstacles along their trajectories
+    coordination_mode: CoordinationMode,
+    robot_conflicts: Vec<RobotConflict>, // found after the last plan of the team robots
     
     robot_velocity_x: f32,
     robot_velocity_y: f32,
     robot_target_speed: f32,
-
+    wasd_drives_robot: bool, // keyboard focus, the camera movement keys move the robot instead
+    teleop_active: bool,
+    key_bindings: KeyBindings,
+    show_settings: bool,
+    rebinding_action: Option<Action>, // waiting for a key press to bind
+    
     new_model_name: String,
     new_model_scale: f32,
     new_model_position: Position,
     selected_model_index: Option<usize>,
     selected_obstacle_index: Option<usize>,
+    selected_obstacle_indices: Vec<usize>, // multi-selection, includes the selected obstacle
+    group_move_offset: Position,
+    duplicate_offset: Position,
     create_as_obstacle: bool,
+    show_add_entity: bool,
+    show_script_console: bool,
+    script_console: ScriptConsole,
+    entity_preview: Result<Model, String>, // the model the add entity dialog would create
     
     new_obstacle_name: String,
+    new_obstacle_label: String, // display name from the palette, empty to use the model name
     new_obstacle_radius: f32,
     new_obstacle_position: Position,
+    new_obstacle_layer: String,
+    
+    obstacle_layers: Vec<ObstacleLayer>,
+    new_layer_name: String,
+    obstacle_groups: Vec<ObstacleGroup>,
+    new_group_name: String,
+    selected_group: Option<String>,
+    
+    field_layout_preset: String,
+    field_layout_path: String,
+    replace_obstacles_on_load: bool,
+    robot_height: f32, // the robot drives under obstacles whose underside is at least this high
+    step_height: f32, // and over obstacles no taller than this
+    random_scene: RandomSceneConfig,
+    collision_count: usize,
+    pause_on_collision: bool,
+    auto_replan: bool,
+    replan_threshold: f32,
+    replan_count: usize,
+    evaluation_runs: usize,
+    evaluation: Option<Evaluation>, // the last evaluation, planned one scene per frame until it finishes
     
     gradient_field: Option<gradient_field::GradientWire>,
     gradient_x_resolution: f32,
     gradient_y_resolution: f32,
     gradient_line_resolution: f32,
+    gradient_adaptive: bool,
+    gradient_tolerance: f32,
+    gradient_gpu: bool,
+    gradient_rendering: FieldRendering,
+    gradient_influence_only: bool,
+    gradient_influence_margin: f32,
+    gpu_field: Option<GpuField>, // created the first time the gpu is asked for
+    panel_width: f32,
+    model_watcher: Option<ModelWatcher>,
+    remote: Option<RemoteServer>, // running until the app exits once started
+    remote_port: u16,
+    screenshot_directory: String,
+    frame_recorder: FrameRecorder,
+    autosave: Autosave,
+    errors: Vec<String>, // shown in dialogs until dismissed
 }
 
 
 
-fn update(app: &App, model: &mut AppModel, update: Update) {
-    if let Some(robot) = &mut model.robot {
-        robot.update_position(update.since_last.as_secs_f32());
+impl AppModel {
+    /// the ui state that is kept between sessions
+    fn settings(&self) -> Settings {
+        Settings {
+            camera_speed: self.camera_speed,
+            rotation_speed: self.rotation_speed,
+            camera_damping: self.camera_damping,
+            keep_horizon_level: self.keep_horizon_level,
+            camera_transition_time: self.camera_transition_time,
+            fov: self.fov,
+            follow_distance: self.follow_distance,
+            follow_height: self.follow_height,
+            follow_smoothing: self.follow_smoothing,
+            show_path: self.show_path,
+            show_initial_path: self.show_initial_path,
+            show_optimized_path: self.show_optimized_path,
+            show_pruned_points: self.show_pruned_points,
+            show_roadmap: self.show_roadmap,
+            animate_roadmap: self.animate_roadmap,
+            roadmap_growth_speed: self.roadmap_growth_speed,
+            show_wavefront: self.show_wavefront,
+            animate_wavefront: self.animate_wavefront,
+            wavefront_speed: self.wavefront_speed,
+            replan_moving_obstacles: self.replan_moving_obstacles,
+            show_hybrid_expanded: self.show_hybrid_expanded,
+            color_path_by_speed: self.color_path_by_speed,
+            show_points: self.show_points,
+            path_markers: self.path_markers.clone(),
+            line_weights: self.line_weights,
+            colors: self.custom_colors,
+            show_gradient_function: self.show_gradient_function,
+            show_influence_rings: self.show_influence_rings,
+            show_minimap: self.show_minimap,
+            show_grid: self.show_grid,
+            show_axis_gizmo: self.show_axis_gizmo,
+            show_labels: self.show_labels,
+            show_field_image: self.show_field_image,
+            show_costmap: self.show_costmap,
+            show_distance_heatmap: self.show_distance_heatmap,
+            show_distance_contours: self.show_distance_contours,
+            distance_contour_spacing: self.distance_contour_spacing,
+            distance_max: self.distance_max,
+            show_harmonic_field: self.show_harmonic_field,
+            grid_spacing: self.grid_spacing,
+            grid_extent: self.grid_extent,
+            gradient_x_resolution: self.gradient_x_resolution,
+            gradient_y_resolution: self.gradient_y_resolution,
+            gradient_line_resolution: self.gradient_line_resolution,
+            gradient_adaptive: self.gradient_adaptive,
+            gradient_tolerance: self.gradient_tolerance,
+            gradient_gpu: self.gradient_gpu,
+            gradient_rendering: self.gradient_rendering,
+            gradient_influence_only: self.gradient_influence_only,
+            gradient_influence_margin: self.gradient_influence_margin,
+            path_segments: self.path_segments,
+            fixed_timestep: self.fixed_timestep,
+            timestep: self.timestep,
+            field_image_path: self.field_image_path.clone(),
+            field_image_columns: self.field_image_columns,
+            field_image_brightness: self.field_image_brightness,
+            field_layout_path: self.field_layout_path.clone(),
+            path_export_path: self.path_export_path.clone(),
+            ros_export_path: self.ros_export_path.clone(),
+            setpoint_export_path: self.setpoint_export_path.clone(),
+            path_import_path: self.path_import_path.clone(),
+            control_rate: self.control_rate,
+            remote_port: self.remote_port,
+            svg_export_path: self.svg_export_path.clone(),
+            scene_path: self.scene_path.clone(),
+            session_path: self.session_path.clone(),
+            script_path: self.script_console.path.clone(),
+            panel_width: self.panel_width,
+        }
     }
     
-    let ctx = model.egui.begin_frame();
+    fn camera_pose(&self) -> CameraPose {
+        CameraPose {
+            position: self.camera_position,
+            direction: self.direction,
+            rotation_y: self.rotation_y,
+        }
+    }
     
-    // ui side panel
-    egui::SidePanel::right("controls_panel")
-        .default_width(200.0)
-        .resizable(true)
-        .show(&ctx, |ui| {
-            ui.collapsing("Visualization", |ui| {
-                ui.checkbox(&mut model.show_path, "Show Path");
-                ui.checkbox(&mut model.show_points, "Show Points");
-                ui.checkbox(&mut model.show_gradient_function, "Show Gradient Function");
-                
-                if model.show_gradient_function {
-                    ui.separator();
-                    ui.heading("Gradient Field Settings");
-                                        
-                    ui.add(egui::Slider::new(&mut model.gradient_x_resolution, 0.01..=10.0).text("X Resolution"));
-                    ui.add(egui::Slider::new(&mut model.gradient_y_resolution, 0.01..=10.0).text("Y Resolution"));
-                    ui.add(egui::Slider::new(&mut model.gradient_line_resolution, 0.01..=1.0).text("Line Resolution"));
-                    
-                    if ui.button("Update Gradient Field").clicked() {
-                        let gradient_function = gradient_field::obstacle_sum;
-                        
-                        gradient_field::set_obstacles_ref(&model.obstacles);
-                        
-                        if let Some(gradient_field) = &mut model.gradient_field {
-                            gradient_field.gradient_function = gradient_function;
-                            gradient_field.x_resolution = model.gradient_x_resolution;
-                            gradient_field.y_resolution = model.gradient_y_resolution;
-                            gradient_field.line_resolution = model.gradient_line_resolution;
-                            gradient_field.update();
-                        } else {
-                            gradient_field::set_obstacles_ref(&model.obstacles);
-                            
-                            model.gradient_field = Some(gradient_field::GradientWire::new(
-                                gradient_function,
-                                model.gradient_x_resolution,
-                                model.gradient_y_resolution,
-                                model.gradient_line_resolution
-                            ).with_color(nannou::color::rgb(0, 255, 255)));
-                        }
-                    }
+    fn set_camera_pose(&mut self, pose: CameraPose) {
+        self.camera_position = pose.position;
+        self.direction = pose.direction;
+        self.rotation_y = pose.rotation_y;
+    }
+    
+    /// rounds a field position to the nearest ground grid intersection when snapping is enabled
+    fn snap_position(&self, position: Position) -> Position {
+        if !self.snap_to_grid || self.grid_spacing <= 0.0 {
+            return position;
+        }
+        
+        Position::new(
+            (position.x / self.grid_spacing).round() * self.grid_spacing,
+            (position.y / self.grid_spacing).round() * self.grid_spacing,
+            position.z,
+        )
+    }
+    
+    fn new_obstacle_config(&self, position: Position) -> ModelConfig {
+        ModelConfig {
+            name: self.new_obstacle_name.clone(),
+            position,
+            scale: self.new_obstacle_radius * 2.0 // scale is diameter, radius*2
+        }
+    }
+    
+    /// loads an obstacle model and adds it to the scene, selecting it
+    fn add_obstacle(&mut self, config: ModelConfig) {
+        match Obstacle::from_config(&config) {
+            Ok(mut obstacle) => {
+                ensure_layer(&mut self.obstacle_layers, &self.new_obstacle_layer);
+                obstacle.layer = self.new_obstacle_layer.clone();
+                if !self.new_obstacle_label.is_empty() {
+                    obstacle.name = self.new_obstacle_label.clone();
                 }
+                obstacle.apply_layer(&self.obstacle_layers);
+                let index = self.world.add_obstacle(obstacle);
+                println!("Successfully created obstacle: {}", config.name);
+                self.selected_obstacle_index = Some(index);
+                self.selected_obstacle_indices = vec![index];
+                self.selected_model_index = None;
+            },
+            Err(e) => { self.report_error(format!("Failed to create obstacle {}: {}", config.name, e)); }
+        }
+    }
+    
+    /// loads a decorative model and adds it to the scene, selecting it
+    fn add_model(&mut self, config: ModelConfig) {
+        match Model::from_config(&config) {
+            Ok(new_model) => {
+                let index = self.world.add_model(new_model);
+                println!("Successfully created model: {}", config.name);
+                self.selected_model_index = Some(index);
+                self.selected_obstacle_index = None;
+                self.selected_obstacle_indices.clear();
+            },
+            Err(e) => { self.report_error(format!("Failed to create model {}: {}", config.name, e)); }
+        }
+    }
+    
+    fn new_entity_config(&self) -> ModelConfig {
+        ModelConfig {
+            name: self.new_model_name.clone(),
+            position: self.new_model_position,
+            scale: self.new_model_scale,
+        }
+    }
+    
+    /// reloads the add entity preview after its model or scale changed
+    fn refresh_entity_preview(&mut self) {
+        self.entity_preview = Model::from_config(&self.new_entity_config()).map_err(|e| e.to_string());
+    }
+    
+    /// adds the add entity dialog's model as a decorative model, or as an obstacle sized to its footprint
+    fn add_entity(&mut self) {
+        let config = self.new_entity_config();
+        if !self.create_as_obstacle {
+            self.add_model(config);
+            return;
+        }
+        
+        let count = self.world.obstacles.len();
+        self.add_obstacle(config.clone());
+        if self.world.obstacles.len() > count {
+            self.world.edit_obstacle(count, |obstacle| {
+                obstacle.name = config.name;
+                let radius = obstacle.model.footprint_radius();
+                obstacle.set_radius(radius);
             });
-                        
-            // obstacles section
-            ui.collapsing("Obstacles", |ui| {
-                ui.heading("Create New Obstacle");
-                
-                ui.horizontal(|ui| {
-                    ui.label("Obstacle Name:");
-                    ui.text_edit_singleline(&mut model.new_obstacle_name);
-                });
-                
-                ui.add(egui::Slider::new(&mut model.new_obstacle_radius, 0.1..=5.0).text("Radius"));
-                
-                // obstacle position controls
-                ui.label("Position:");
-                ui.horizontal(|ui| {
-                    ui.label("X:");
-                    ui.add(egui::DragValue::new(&mut model.new_obstacle_position.x).speed(0.1));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Y:");
-                    ui.add(egui::DragValue::new(&mut model.new_obstacle_position.y).speed(0.1));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Z:");
-                    ui.add(egui::DragValue::new(&mut model.new_obstacle_position.z).speed(0.1));
-                });
-                
-                if ui.button("Add Obstacle").clicked() {
-                    let config = ModelConfig {
-                        name: model.new_obstacle_name.clone(),
-                        position: model.new_obstacle_position,
-                        scale: model.new_obstacle_radius * 2.0 // scale is diameter, radius*2
-                    };
-                    
-                    match Obstacle::from_config(&config) {
-                        Ok(obstacle) => {
-                            model.obstacles.push(obstacle);
-                            println!("Successfully created obstacle: {}", config.name);
-                            model.selected_obstacle_index = Some(model.obstacles.len() - 1);
-                            model.selected_model_index = None;
-                            
-                            if model.show_gradient_function {
-                                gradient_field::set_obstacles_ref(&model.obstacles);
-                                
-                                if let Some(gradient_field) = &mut model.gradient_field {
-                                    gradient_field.update();
-                                }
-                            }
-                        },
-                        Err(e) => { eprintln!("Failed to create obstacle {}: {}", config.name, e); }
+        }
+    }
+    
+    /// adds every game element of a field layout as an obstacle
+    fn load_field_layout(&mut self, layout: &FieldLayout) {
+        if self.replace_obstacles_on_load {
+            self.world.clear_obstacles();
+        }
+        
+        ensure_layer(&mut self.obstacle_layers, FIELD_ELEMENTS_LAYER);
+        for element in &layout.elements {
+            match Obstacle::from_config(&element.model_config()) {
+                Ok(mut obstacle) => {
+                    obstacle.name = element.name.clone();
+                    obstacle.layer = FIELD_ELEMENTS_LAYER.to_string();
+                    obstacle.apply_layer(&self.obstacle_layers);
+                    self.world.add_obstacle(obstacle);
+                },
+                Err(e) => { self.report_error(format!("Failed to create field element {}: {}", element.name, e)); }
+            }
+        }
+        println!("Loaded field layout {} with {} elements", layout.name, layout.elements.len());
+    }
+    
+    /// the robot, target and everything placed on the field, as written to a scene file
+    fn scene(&self) -> Scene {
+        let (robot_position, heading) = match &self.world.robot {
+            Some(robot) => (robot.model.config.position, robot.heading),
+            None => (position::ORIGIN, 0.0),
+        };
+        let target = self.world.target();
+        
+        Scene {
+            robot: SceneRobot { x: robot_position.x, y: robot_position.y, heading },
+            target: ScenePoint { x: target.x, y: target.y },
+            target_region: self.world.robot.as_ref().map_or(GoalRegion::Point, |robot| robot.goal_region),
+            layers: self.obstacle_layers.iter()
+                .map(|layer| SceneLayer { name: layer.name.clone(), visible: layer.visible, enabled: layer.enabled })
+                .collect(),
+            groups: self.obstacle_groups.iter()
+                .map(|group| SceneGroup { name: group.name.clone(), rotation: group.rotation })
+                .collect(),
+            obstacles: self.world.obstacles.iter()
+                .map(|obstacle| {
+                    let config = &obstacle.model.config;
+                    SceneObstacle {
+                        name: obstacle.name.clone(),
+                        model: config.name.clone(),
+                        x: config.position.x,
+                        y: config.position.y,
+                        z: config.position.z,
+                        scale: config.scale,
+                        radius: obstacle.get_radius(),
+                        layer: obstacle.layer.clone(),
+                        use_hull: obstacle.use_hull,
+                        buffer: obstacle.get_buffer(),
+                        weight: obstacle.weight,
+                        soft: obstacle.soft,
+                        group: obstacle.group.clone(),
+                        rotation: obstacle.rotation,
+                        trajectory: obstacle.trajectory.clone(),
                     }
-                }
-                
-                if !model.obstacles.is_empty() {
-                    ui.separator();
-                    ui.heading("Existing Obstacles");
-                    
-                    for (i, obstacle) in model.obstacles.iter().enumerate() {
-                        let is_selected = model.selected_obstacle_index == Some(i);
-                        let label = format!("Obstacle {}: {} (radius: {:.2})", 
-                            i + 1, 
-                            obstacle.model.config.name,
-                            obstacle.get_radius()
-                        );
-                        
-                        if ui.selectable_label(is_selected, label).clicked() {
-                            model.selected_obstacle_index = Some(i);
-                            model.selected_model_index = None;
-                        }
+                })
+                .collect(),
+            models: self.world.models.iter()
+                .map(|model| {
+                    let config = &model.config;
+                    SceneModel { model: config.name.clone(), x: config.position.x, y: config.position.y, z: config.position.z, scale: config.scale }
+                })
+                .collect(),
+            via_points: self.world.robot.iter()
+                .flat_map(|robot| &robot.via_points)
+                .map(|via| SceneViaPoint { x: via.position.x, y: via.position.y, tolerance: via.tolerance, reversed: via.reversed })
+                .collect(),
+            targets: self.world.targets.iter()
+                .map(|target| SceneTarget { name: target.name.clone(), x: target.marker.position.x, y: target.marker.position.y, color: target.color() })
+                .collect(),
+            speed_zones: self.world.speed_zones().to_vec(),
+        }
+    }
+    
+    /// moves a group so its members are centered on a position and turns them about it to a rotation,
+    /// keeping their layout
+    fn transform_group(&mut self, name: &str, center: Position, rotation: f32) {
+        let Some(old_center) = group_center(&self.world.obstacles, name) else { return; };
+        let Some(group) = self.obstacle_groups.iter_mut().find(|group| group.name == name) else { return; };
+        let turn = rotation - group.rotation;
+        group.rotation = rotation;
+        
+        let (sin, cos) = turn.sin_cos();
+        self.world.edit_obstacles(|obstacles| {
+            for obstacle in obstacles.iter_mut().filter(|obstacle| obstacle.group.as_deref() == Some(name)) {
+                let position = obstacle.model.config.position;
+                let (dx, dy) = (position.x - old_center.x, position.y - old_center.y);
+                obstacle.model.move_to(Position::new(center.x + dx * cos - dy * sin, center.y + dx * sin + dy * cos, position.z));
+                obstacle.rotate(turn);
+            }
+        });
+    }
+    
+    /// dissolves a group, its members stay where they are
+    fn ungroup(&mut self, name: &str) {
+        self.world.edit_obstacles(|obstacles| {
+            for obstacle in obstacles.iter_mut().filter(|obstacle| obstacle.group.as_deref() == Some(name)) {
+                obstacle.group = None;
+            }
+        });
+        self.obstacle_groups.retain(|group| group.name != name);
+        if self.selected_group.as_deref() == Some(name) {
+            self.selected_group = None;
+        }
+    }
+    
+    /// replaces the field contents with a loaded scene and plans a path through it
+    fn load_scene(&mut self, scene: &Scene) {
+        self.obstacle_time = 0.0;
+        self.load_field(scene);
+        
+        if let Some(robot) = &mut self.world.robot {
+            robot.follow_path = false;
+            robot.set_velocity(0.0, 0.0);
+            let z = robot.model.config.position.z;
+            robot.model.move_to(Position::new(scene.robot.x, scene.robot.y, z));
+            robot.set_heading(scene.robot.heading);
+        }
+        println!("Loaded scene with {} obstacles and {} models", self.world.obstacles.len(), self.world.models.len());
+    }
+    
+    /// replaces the layers, obstacles, models, via points and target with those of a scene, leaving the robot where it is
+    fn load_field(&mut self, scene: &Scene) {
+        self.obstacle_layers = scene.layers.iter()
+            .map(|layer| ObstacleLayer { name: layer.name.clone(), visible: layer.visible, enabled: layer.enabled })
+            .collect();
+        ensure_layer(&mut self.obstacle_layers, DEFAULT_LAYER);
+        self.obstacle_groups = scene.groups.iter()
+            .map(|group| ObstacleGroup { name: group.name.clone(), rotation: group.rotation })
+            .collect();
+        self.selected_group = None;
+        
+        self.world.clear_obstacles();
+        self.world.clear_models();
+        self.selected_model_index = None;
+        
+        for entry in &scene.obstacles {
+            let config = ModelConfig { name: entry.model.clone(), position: Position::new(entry.x, entry.y, entry.z), scale: entry.scale };
+            match Obstacle::from_config(&config) {
+                Ok(mut obstacle) => {
+                    obstacle.name = entry.name.clone();
+                    obstacle.layer = entry.layer.clone();
+                    obstacle.use_hull = entry.use_hull;
+                    obstacle.weight = entry.weight;
+                    obstacle.soft = entry.soft;
+                    if let Some(group) = &entry.group {
+                        ensure_group(&mut self.obstacle_groups, group);
                     }
+                    obstacle.group = entry.group.clone();
+                    obstacle.rotate(entry.rotation);
+                    obstacle.trajectory = entry.trajectory.clone();
+                    obstacle.follow_trajectory(0.0);
+                    obstacle.set_buffer(entry.buffer);
+                    obstacle.set_radius(entry.radius);
+                    ensure_layer(&mut self.obstacle_layers, &entry.layer);
+                    obstacle.apply_layer(&self.obstacle_layers);
+                    obstacle.update_passable(self.robot_height, self.step_height);
+                    self.world.add_obstacle(obstacle);
+                },
+                Err(e) => { self.report_error(format!("Failed to create obstacle {}: {}", entry.name, e)); }
+            }
+        }
+        
+        for entry in &scene.models {
+            let config = ModelConfig { name: entry.model.clone(), position: Position::new(entry.x, entry.y, entry.z), scale: entry.scale };
+            match Model::from_config(&config) {
+                Ok(model) => { self.world.add_model(model); },
+                Err(e) => { self.report_error(format!("Failed to create model {}: {}", entry.model, e)); }
+            }
+        }
+        
+        if let Some(robot) = &mut self.world.robot {
+            robot.via_points = scene.via_points.iter()
+                .map(|via| ViaPoint { position: Position::new(via.x, via.y, 0.0), tolerance: via.tolerance, reversed: via.reversed })
+                .collect();
+            robot.goal_region = scene.target_region;
+        }
+        self.world.set_speed_zones(scene.speed_zones.clone());
+        self.world.targets = scene.targets.iter()
+            .map(|target| NamedTarget::new(&target.name, Position::new(target.x, target.y, 0.0), target.color))
+            .collect();
+        
+        let target_z = self.world.target().z;
+        self.world.set_target(Position::new(scene.target.x, scene.target.y, target_z));
+    }
+    
+    /// writes the scene to the autosave file every interval, unless an older autosave is waiting to be restored
+    fn autosave_scene(&mut self) {
+        if self.autosave.recovered.is_none() && self.autosave.is_due() {
+            let scene = self.scene();
+            self.autosave.save(&scene);
+        }
+    }
+    
+    /// replaces the obstacles with a seeded random scene that keeps the robot and target clear
+    fn generate_random_scene(&mut self) {
+        let start = match &self.world.robot {
+            Some(robot) => robot.model.config.position,
+            None => position::ORIGIN,
+        };
+        let configs = generate_random_scene(&self.random_scene, start, self.world.target());
+        
+        self.world.clear_obstacles();
+        
+        for config in &configs {
+            match Obstacle::from_config(config) {
+                Ok(mut obstacle) => {
+                    obstacle.apply_layer(&self.obstacle_layers);
+                    self.world.add_obstacle(obstacle);
+                },
+                Err(e) => {
+                    self.report_error(format!("Failed to create random obstacle {}: {}", config.name, e));
+                    break;
                 }
-                
-                if let Some(index) = model.selected_obstacle_index {
-                    if index < model.obstacles.len() {
-                        let mut current_radius = 1.0;
-                        let mut current_position = Position::new(0.0, 0.0, 0.0);
-                        
-                        if let Some(selected_obstacle) = model.obstacles.get(index) {
-                            current_radius = selected_obstacle.get_radius();
-                            current_position = selected_obstacle.model.config.position;
-                        }
-                        
-                        ui.separator();
-                        ui.heading("Edit Selected Obstacle");
-                        
-                        // radius controls
-                        let mut radius = current_radius;
-                        let radius_changed = ui.add(egui::Slider::new(&mut radius, 0.1..=5.0).text("Radius")).changed();
-                        
-                        // position controls
-                        ui.label("Position:");
-                        let mut position = current_position;
-                        let mut position_changed = false;
-                        
-                        ui.horizontal(|ui| {
-                            ui.label("X:");
-                            if ui.add(egui::DragValue::new(&mut position.x).speed(0.1)).changed() {
-                                position_changed = true;
-                            }
-                        });
-                        ui.horizontal(|ui| {
-                            ui.label("Y:");
-                            if ui.add(egui::DragValue::new(&mut position.y).speed(0.1)).changed() {
-                                position_changed = true;
-                            }
-                        });
-                        ui.horizontal(|ui| {
-                            ui.label("Z:");
-                            if ui.add(egui::DragValue::new(&mut position.z).speed(0.1)).changed() {
-                                position_changed = true;
-                            }
-                        });
-                        
-                        let delete_clicked = ui.button("Delete Obstacle").clicked();
-                        
-                        if let Some(selected_obstacle) = model.obstacles.get_mut(index) {
-                            let mut properties_changed = false;
-                            
-                            if radius_changed {
-                                selected_obstacle.set_radius(radius);
-                                
-                                // update the model scale to match the new radius (diameter = 2*radius)
-                                let old_position = selected_obstacle.model.config.position;
-                                selected_obstacle.model.config.scale = radius * 2.0;
-                                
-                                selected_obstacle.model.position_at(Position::new(-old_position.x, -old_position.y, -old_position.z));
-                                selected_obstacle.model.scale(radius * 2.0);
-                                selected_obstacle.model.position_at(old_position);
-                                
-                                properties_changed = true;
-                            }
-                            
-                            if position_changed {
-                                let delta_x = position.x - selected_obstacle.model.config.position.x;
-                                let delta_y = position.y - selected_obstacle.model.config.position.y;
-                                let delta_z = position.z - selected_obstacle.model.config.position.z;
-                            
-                                selected_obstacle.model.config.position = position;
-                                selected_obstacle.model.position_at(Position::new(delta_x, delta_y, delta_z));
-                                
-                                properties_changed = true;
-                            }
-                            
-                            if properties_changed {
-                                if model.show_gradient_function {
-                                    gradient_field::set_obstacles_ref(&model.obstacles);
-                                    
-                                    if let Some(gradient_field) = &mut model.gradient_field {
-                                        gradient_field.update();
-                                    }
-                                }
-                                
-                                if let Some(robot) = &mut model.robot {
-                                    robot.generate_path(&model.target_position.get_position(), model.path_segments, &model.obstacles);
-                                }
-                            }
-                        }
-                        
-                        if delete_clicked {
-                            model.obstacles.remove(index);
-                            model.selected_obstacle_index = None;
-                        }
-                        
-                        if (radius_changed || position_changed || delete_clicked) && model.show_gradient_function {
-                            gradient_field::set_obstacles_ref(&model.obstacles);
-                            
-                            if let Some(gradient_field) = &mut model.gradient_field {
-                                gradient_field.update();
-                            }
+            }
+        }
+        println!("Generated random scene with {} obstacles (seed {})", self.world.obstacles.len(), self.random_scene.seed);
+    }
+    
+    /// starts planning through a batch of random scenes without touching the current scene, the scenes
+    /// are planned by step_evaluation
+    fn run_evaluation(&mut self) {
+        let (start, planner) = match &self.world.robot {
+            Some(robot) => (robot.model.config.position, robot.planner),
+            None => (position::ORIGIN, Planner::Gradient),
+        };
+        
+        match Evaluation::new(&self.random_scene, self.evaluation_runs, start, self.world.target(), self.path_segments, planner) {
+            Ok(evaluation) => self.evaluation = Some(evaluation),
+            Err(e) => self.report_error(format!("Failed to run planner evaluation: {}", e)),
+        }
+    }
+    
+    /// plans through the next scene of a running evaluation, one per frame so the ui keeps drawing
+    fn step_evaluation(&mut self) {
+        let Some(evaluation) = self.evaluation.as_mut().filter(|evaluation| !evaluation.is_finished()) else { return; };
+        if let Err(e) = evaluation.run_next() {
+            self.evaluation = None;
+            self.report_error(format!("Failed to run planner evaluation: {}", e));
+            return;
+        }
+        if evaluation.is_finished() {
+            let summary = &evaluation.summary;
+            println!("Evaluated planner over {} scenes: {:.0}% success", summary.runs.len(), summary.success_rate() * 100.0);
+        }
+    }
+    
+    /// the steps to advance the simulation by for a frame, equal ones with the fixed timestep where time
+    /// left over carries to the next frame, or the whole frame otherwise
+    fn simulation_steps(&mut self, frame_time: f32) -> Vec<f32> {
+        if !self.fixed_timestep {
+            return vec![frame_time];
+        }
+        
+        self.time_accumulator += frame_time;
+        let mut steps = Vec::new();
+        while self.time_accumulator >= self.timestep && steps.len() < MAX_STEPS_PER_FRAME {
+            self.time_accumulator -= self.timestep;
+            steps.push(self.timestep);
+        }
+        if steps.len() == MAX_STEPS_PER_FRAME {
+            self.time_accumulator = 0.0;
+        }
+        steps
+    }
+    
+    /// moves the robots and obstacles on by a step of simulated time
+    fn simulate(&mut self, dt: f32) {
+        self.log_robot_control();
+        self.sim_time += dt;
+        if let Some(robot) = &mut self.world.robot {
+            robot.update_position(dt, &self.world.obstacles);
+        }
+        for member in &mut self.world.team {
+            member.robot.update_position(dt, &self.world.obstacles);
+        }
+        if self.animate_obstacles {
+            // moving obstacles are planned around where they will be, so the path is not replanned as they move
+            self.obstacle_time += dt;
+            self.world.follow_trajectories(self.obstacle_time);
+        }
+        self.detect_collisions(dt);
+        
+        if let (Some(recorder), Some(robot)) = (&mut self.session_recorder, &self.world.robot) {
+            recorder.tick += 1;
+            recorder.log(&SessionEntry::Tick { tick: recorder.tick, dt, robot: RobotState::of(robot) });
+            recorder.control = (robot.follow_path, robot.commanded_velocity());
+        }
+    }
+    
+    /// logs the robot being started, stopped or driven by hand since the last tick
+    fn log_robot_control(&mut self) {
+        let (Some(recorder), Some(robot)) = (&mut self.session_recorder, &self.world.robot) else { return; };
+        let control = (robot.follow_path, robot.commanded_velocity());
+        if control != recorder.control {
+            recorder.log(&SessionEntry::Control { tick: recorder.tick, following: control.0, velocity: control.1 });
+            recorder.control = control;
+        }
+    }
+    
+    /// the settings a session log needs to play the run out the same way
+    fn session_settings(&self) -> SessionSettings {
+        let robot = self.world.robot.as_ref();
+        let noise = robot.map(|robot| &robot.noise);
+        SessionSettings {
+            planner: robot.map_or(Planner::Gradient, |robot| robot.planner).label().to_string(),
+            path_segments: self.path_segments,
+            target_speed: robot.map_or(0.0, |robot| robot.target_speed),
+            noise_enabled: noise.is_some_and(|noise| noise.enabled),
+            noise_std_dev: noise.map_or(0.0, |noise| noise.velocity_std_dev),
+            noise_scale_error: noise.map_or(0.0, |noise| noise.scale_error),
+            noise_seed: noise.map_or(0, |noise| noise.seed),
+            field_combination: obstacle::field_combination(),
+        }
+    }
+    
+    /// starts logging from a fresh plan and noise sequence, which the replay starts from too
+    fn start_session_recording(&mut self) {
+        self.session_replay = None;
+        self.plan_path();
+        if let Some(robot) = &mut self.world.robot {
+            robot.noise.reseed();
+        }
+        self.sim_time = 0.0;
+        
+        let control = self.world.robot.as_ref().map_or((false, [0.0, 0.0]), |robot| (robot.follow_path, robot.commanded_velocity()));
+        match SessionRecorder::create(&self.session_path, self.session_settings(), self.scene(), control) {
+            Ok(recorder) => {
+                println!("Recording session to {}", self.session_path);
+                self.session_recorder = Some(recorder);
+            },
+            Err(e) => self.report_error(format!("Failed to record session to {}: {}", self.session_path, e)),
+        }
+    }
+    
+    /// loads a session log and puts the field, robot and settings back to where its recording started
+    fn start_session_replay(&mut self) {
+        self.session_recorder = None;
+        let (replay, settings, scene) = match SessionReplay::load(&self.session_path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.report_error(format!("Failed to load session {}: {}", self.session_path, e));
+                return;
+            }
+        };
+        
+        self.path_segments = settings.path_segments;
+        obstacle::set_field_combination(settings.field_combination);
+        if let Some(robot) = &mut self.world.robot {
+            if let Some(planner) = Planner::ALL.into_iter().find(|planner| planner.label() == settings.planner) {
+                robot.planner = planner;
+            }
+            robot.set_target_speed(settings.target_speed);
+            robot.noise.enabled = settings.noise_enabled;
+            robot.noise.velocity_std_dev = settings.noise_std_dev;
+            robot.noise.scale_error = settings.noise_scale_error;
+            robot.noise.seed = settings.noise_seed;
+            robot.noise.reseed();
+        }
+        self.load_scene(&scene);
+        self.dispatch_world_events();
+        self.sim_time = 0.0;
+        
+        println!("Replaying {} ticks from {}", replay.ticks, self.session_path);
+        self.session_replay = Some(replay);
+    }
+    
+    /// plays the next recorded tick, applying what was logged before it
+    fn step_session_replay(&mut self) {
+        let Some(replay) = &mut self.session_replay else { return; };
+        let Some((entries, dt, recorded)) = replay.next_tick() else {
+            replay.playing = false;
+            return;
+        };
+        
+        for entry in entries {
+            match entry {
+                SessionEntry::Edit { scene, .. } => {
+                    self.load_field(&scene);
+                    self.dispatch_world_events();
+                },
+                SessionEntry::Plan { .. } => self.plan_path(),
+                SessionEntry::Optimize { locked, .. } => {
+                    if let Some(robot) = &mut self.world.robot {
+                        for (i, point) in robot.path_points.iter_mut().enumerate() {
+                            point.locked = locked.contains(&i);
                         }
                     }
+                    self.reoptimize_path();
+                },
+                SessionEntry::PathPointMoved { index, x, y, .. } => {
+                    if let Some(robot) = &mut self.world.robot {
+                        robot.move_path_point(index, Position::new(x, y, 0.0), &self.world.obstacles);
+                    }
+                },
+                SessionEntry::Control { following, velocity, .. } => {
+                    if let Some(robot) = &mut self.world.robot {
+                        robot.follow_path = following;
+                        robot.set_velocity(velocity[0], velocity[1]);
+                    }
+                },
+                SessionEntry::Start { .. } | SessionEntry::Tick { .. } => {},
+            }
+        }
+        
+        self.simulate(dt);
+        if let (Some(replay), Some(robot)) = (&mut self.session_replay, &self.world.robot) {
+            replay.check(&RobotState::of(robot), &recorded);
+            if replay.is_finished() {
+                replay.playing = false;
+            }
+        }
+    }
+    
+    /// counts each new contact between the robot footprint and a planned obstacle, flashing the obstacle
+    fn detect_collisions(&mut self, dt: f32) {
+        let Some(robot) = &self.world.robot else { return; };
+        let robot_position = robot.model.config.position;
+        let footprint_radius = robot.footprint_radius();
+        let new_collisions = self.world.update_contacts(robot_position, footprint_radius, dt, COLLISION_FLASH_TIME);
+        self.collision_count += new_collisions;
+        
+        let Some(robot) = &mut self.world.robot else { return; };
+        if new_collisions > 0 && self.pause_on_collision {
+            robot.follow_path = false;
+            robot.set_velocity(0.0, 0.0);
+        }
+    }
+    
+    /// plans a new path from where the robot is when it strays too far from the one it is following
+    fn replan_on_deviation(&mut self) {
+        if !self.auto_replan {
+            return;
+        }
+        let Some(robot) = &self.world.robot else { return; };
+        if !robot.follow_path || robot.path_points.len() < 2 {
+            return;
+        }
+        
+        let error = robot.cross_track_error();
+        if error > self.replan_threshold {
+            println!("Cross-track error {:.2} m exceeded {:.2} m, re-planning", error, self.replan_threshold);
+            self.plan_path();
+            if let Some(robot) = &mut self.world.robot {
+                robot.follow_path = true;
+            }
+            self.replan_count += 1;
+        }
+    }
+    
+    /// plans the followed path again from where the robot is as the obstacles move, cheap with d* lite since
+    /// it only repairs its last search. the robot stops when no path is left
+    fn replan_around_obstacles(&mut self) {
+        let moving = self.world.obstacles.iter().any(|obstacle| obstacle.trajectory.is_some());
+        if !(self.animate_obstacles && self.replan_moving_obstacles && moving) {
+            return;
+        }
+        let target = self.world.target();
+        let Some(robot) = &mut self.world.robot else { return; };
+        if !robot.follow_path || robot.planner != Planner::DStarLite {
+            return;
+        }
+        let Err(e) = robot.replan(&target, self.path_segments, &self.world.obstacles) else { return; };
+        robot.follow_path = false;
+        robot.set_velocity(0.0, 0.0);
+        self.report_error(format!("Re-planning around moving obstacles: {}", e));
+    }
+    
+    /// plans a new path from the robot to the target, showing why when it is not safe to drive
+    fn plan_path(&mut self) {
+        if let Some(recorder) = &mut self.session_recorder {
+            recorder.log(&SessionEntry::Plan { tick: recorder.tick });
+        }
+        let target = self.world.target();
+        let Some(robot) = &mut self.world.robot else { return; };
+        if let Err(e) = robot.generate_path(&target, self.path_segments, &self.world.obstacles) {
+            self.report_error(format!("Path planning: {}", e));
+        }
+        self.plan_team();
+    }
+    
+    /// optimizes the current path again around its locked points, logging which those are so a replay does the same
+    fn reoptimize_path(&mut self) {
+        let Some(robot) = &mut self.world.robot else { return; };
+        if let Some(recorder) = &mut self.session_recorder {
+            let locked = robot.path_points.iter().enumerate()
+                .filter(|(_, point)| point.locked)
+                .map(|(i, _)| i)
+                .collect();
+            recorder.log(&SessionEntry::Optimize { tick: recorder.tick, locked });
+        }
+        if let Err(e) = robot.optimize_path(&self.world.obstacles) {
+            self.report_error(format!("Path planning: {}", e));
+        }
+    }
+    
+    /// records where a dragged path point was let go
+    fn log_path_point_move(&mut self, index: usize) {
+        let (Some(recorder), Some(robot)) = (&mut self.session_recorder, &self.world.robot) else { return; };
+        let Some(point) = robot.path_points.get(index) else { return; };
+        recorder.log(&SessionEntry::PathPointMoved { tick: recorder.tick, index, x: point.position.x, y: point.position.y });
+    }
+    
+    /// starts every robot driving its path
+    fn start_following(&mut self) {
+        // the path was planned for obstacles moving from when it was generated, so plan it again from now
+        if self.world.obstacles.iter().any(|obstacle| obstacle.trajectory.is_some()) {
+            self.plan_path();
+        }
+        if let Some(robot) = &mut self.world.robot {
+            robot.follow_path = true;
+        }
+        for member in &mut self.world.team {
+            member.robot.follow_path = true;
+        }
+    }
+    
+    /// runs the console script on the world, the obstacles it added join their layers afterwards
+    fn run_script(&mut self) {
+        let world = std::mem::replace(&mut self.world, World::new(None, TargetPosition::create_default()));
+        self.world = self.script_console.run(world, self.path_segments);
+        let layers = &self.obstacle_layers;
+        self.world.edit_obstacles(|obstacles| {
+            for obstacle in obstacles {
+                obstacle.apply_layer(layers);
+            }
+        });
+    }
+    
+    fn start_remote_server(&mut self) {
+        match RemoteServer::start(self.remote_port) {
+            Ok(server) => self.remote = Some(server),
+            Err(e) => self.report_error(format!("Failed to start remote control on port {}: {}", self.remote_port, e)),
+        }
+    }
+    
+    /// runs the commands remote clients sent since the last frame, replying to each
+    fn handle_remote_commands(&mut self) {
+        let Some(remote) = &self.remote else { return; };
+        for request in remote.requests() {
+            let reply = match &request.command {
+                Ok(command) => self.run_remote_command(command.clone()),
+                Err(e) => serde_json::json!({ "ok": false, "error": e }),
+            };
+            request.reply(reply);
+        }
+    }
+    
+    fn run_remote_command(&mut self, command: RemoteCommand) -> serde_json::Value {
+        let failed = |error: String| serde_json::json!({ "ok": false, "error": error });
+        match command {
+            RemoteCommand::AddObstacle { model, x, y, radius } => {
+                let config = ModelConfig { name: model, position: Position::new(x, y, 0.0), scale: radius * 2.0 };
+                match Obstacle::from_config(&config) {
+                    Ok(mut obstacle) => {
+                        obstacle.apply_layer(&self.obstacle_layers);
+                        let index = self.world.add_obstacle(obstacle);
+                        serde_json::json!({ "ok": true, "index": index })
+                    },
+                    Err(e) => failed(format!("failed to create obstacle {}: {}", config.name, e)),
                 }
-            });
+            },
+            RemoteCommand::MoveObstacle { index, x, y } => {
+                if index >= self.world.obstacles.len() {
+                    return failed(format!("no obstacle {}, there are {}", index, self.world.obstacles.len()));
+                }
+                self.world.move_obstacle(index, Position::new(x, y, 0.0));
+                serde_json::json!({ "ok": true })
+            },
+            RemoteCommand::SetTarget { x, y } => {
+                self.world.set_target(Position::new(x, y, 0.0));
+                serde_json::json!({ "ok": true })
+            },
+            RemoteCommand::Plan => {
+                let target = self.world.target();
+                let Some(robot) = &mut self.world.robot else { return failed(String::from("no robot is loaded")); };
+                let result = robot.generate_path(&target, self.path_segments, &self.world.obstacles);
+                let points = robot.path_points.len();
+                self.plan_team();
+                match result {
+                    Ok(()) => serde_json::json!({ "ok": true, "points": points }),
+                    Err(e) => serde_json::json!({ "ok": false, "error": e.to_string(), "points": points }),
+                }
+            },
+            RemoteCommand::StartFollowing => {
+                self.start_following();
+                serde_json::json!({ "ok": true })
+            },
+            RemoteCommand::GetPose => {
+                let Some(robot) = &self.world.robot else { return failed(String::from("no robot is loaded")); };
+                let position = robot.model.config.position;
+                serde_json::json!({
+                    "ok": true,
+                    "x": position.x,
+                    "y": position.y,
+                    "heading": robot.heading,
+                    "velocity": [robot.velocity_x, robot.velocity_y],
+                    "following": robot.follow_path,
+                    "progress": robot.current_path_progress,
+                    "sim_time": self.sim_time,
+                })
+            },
+        }
+    }
+    
+    /// plans the team robots after the main one and finds where any two of them would meet
+    fn plan_team(&mut self) {
+        self.robot_conflicts.clear();
+        let Some(lead) = &self.world.robot else { return; };
+        if self.world.team.is_empty() {
+            return;
+        }
+        
+        let errors = plan_team(lead, &mut self.world.team, &self.world.obstacles, self.path_segments, self.coordination_mode);
+        let mut robots = vec![(LEAD_ROBOT_NAME, lead)];
+        robots.extend(self.world.team.iter().map(|member| (member.name.as_str(), &member.robot)));
+        self.robot_conflicts = find_conflicts(&robots);
+        for (name, e) in errors {
+            self.report_error(format!("Path planning for {}: {}", name, e));
+        }
+    }
+    
+    /// adds a team robot beside the main robot and its target
+    fn add_team_robot(&mut self) {
+        let offset = 1.5 * (self.world.team.len() + 1) as f32;
+        let start = self.world.robot.as_ref().map_or(ROBOT_INITIAL_POSITION, |robot| robot.model.config.position);
+        let target = self.world.target();
+        let start = Position::new(start.x, (start.y + offset).min(field_width()), 0.0);
+        let target = Position::new(target.x, (target.y - offset).max(0.0), 0.0);
+        match TeamRobot::new(self.world.team.len(), start, target) {
+            Ok(member) => self.world.add_team_robot(member),
+            Err(e) => self.report_error(format!("Failed to load robot model: {}", e)),
+        }
+    }
+    
+    /// rebuilds the shown costmap, the planners searching it rebuild their own when they plan
+    fn refresh_costmap(&mut self) {
+        let Some(robot) = &mut self.world.robot else { return; };
+        if self.show_costmap && !robot.planner.uses_costmap() {
+            robot.update_costmap(&self.world.obstacles);
+        }
+    }
+    
+    /// rebuilds the distance field while it is shown or read for clearance, planning rebuilds it as well
+    fn refresh_distance_field(&mut self) {
+        let Some(robot) = &mut self.world.robot else { return; };
+        if self.show_distance_heatmap || self.show_distance_contours || robot.use_distance_field {
+            robot.update_distance_field(&self.world.obstacles);
+        }
+    }
+    
+    /// runs iterations of the stepped optimization until one meets a breakpoint, reporting it if the path
+    /// ends up not converging
+    fn step_optimization(&mut self, steps: usize) {
+        self.breakpoint_hit = None;
+        let Some(robot) = &mut self.world.robot else { return; };
+        for _ in 0..steps {
+            match robot.step_optimization(&self.world.obstacles, 1) {
+                Some(Err(e)) => {
+                    self.report_error(format!("Path planning: {}", e));
+                    return;
+                },
+                Some(Ok(())) => return,
+                None => {},
+            }
             
-            ui.collapsing("Target Position", |ui| {
-                ui.heading("Edit Target Position");
+            let iteration = robot.stepped_optimization.map_or(0, |stepped| stepped.iterations);
+            if let Some(hit) = breakpoint::check(&self.breakpoints, robot, &self.world.obstacles, iteration) {
+                println!("Optimization paused at iteration {}: {}", hit.iteration, hit.reason);
+                self.breakpoint_hit = Some(hit);
+                self.play_optimization = false;
+                return;
+            }
+        }
+    }
+    
+    /// shows an error in a dialog until it is dismissed, an error that is already shown is not repeated
+    fn report_error(&mut self, message: String) {
+        eprintln!("{}", message);
+        if !self.errors.contains(&message) {
+            self.errors.push(message);
+        }
+    }
+    
+    /// rebuilds the gradient overlay for the current obstacles, on the gpu when it is enabled and one is found
+    fn update_gradient_field(&mut self) {
+        gradient_field::set_obstacles_ref(&self.world.obstacles);
+        if self.gradient_gpu && self.gpu_field.is_none() {
+            match GpuField::new() {
+                Ok(gpu_field) => self.gpu_field = Some(gpu_field),
+                Err(e) => {
+                    eprintln!("Computing the gradient field on the CPU: {}", e);
+                    self.gradient_gpu = false;
+                },
+            }
+        }
+        
+        let Some(gradient_field) = &mut self.gradient_field else { return; };
+        match self.gpu_field.as_ref().filter(|_| self.gradient_gpu) {
+            Some(gpu_field) => {
+                let obstacles = &self.world.obstacles;
+                gradient_field.update_with_heights(|xs, ys| {
+                    gpu_field.heights(obstacles, xs, ys)
+                        .map_err(|e| eprintln!("GPU field failed, using the CPU: {}", e))
+                        .ok()
+                });
+            },
+            None => gradient_field.update(),
+        }
+    }
+    
+    /// delivers the world events of this frame to the gradient overlay, the planner and the ui
+    fn dispatch_world_events(&mut self) {
+        let events = self.world.take_events();
+        if events.is_empty() {
+            return;
+        }
+        if self.session_recorder.is_some() {
+            let scene = self.scene();
+            if let Some(recorder) = &mut self.session_recorder {
+                recorder.log(&SessionEntry::Edit { tick: recorder.tick, scene });
+            }
+        }
+        
+        if events.iter().any(WorldEvent::changes_field) {
+            self.update_gradient_field();
+            self.refresh_costmap();
+            self.refresh_distance_field();
+        }
+        
+        if events.iter().any(WorldEvent::invalidates_path) {
+            self.plan_path();
+        }
+        
+        for event in &events {
+            self.update_selection(event);
+        }
+    }
+    
+    /// keeps the selected obstacle indices pointing at the same obstacles after removals
+    fn update_selection(&mut self, event: &WorldEvent) {
+        match *event {
+            WorldEvent::ObstacleRemoved(removed) => {
+                let shift = |index: usize| match index.cmp(&removed) {
+                    std::cmp::Ordering::Less => Some(index),
+                    std::cmp::Ordering::Equal => None,
+                    std::cmp::Ordering::Greater => Some(index - 1),
+                };
+                self.selected_obstacle_index = self.selected_obstacle_index.and_then(shift);
+                self.selected_obstacle_indices = self.selected_obstacle_indices.iter().filter_map(|&index| shift(index)).collect();
+            },
+            WorldEvent::ObstaclesCleared => {
+                self.selected_obstacle_index = None;
+                self.selected_obstacle_indices.clear();
+            },
+            _ => {},
+        }
+    }
+    
+    /// reloads the wireframes of models whose stl file changed on disk, keeping their transforms
+    fn reload_changed_models(&mut self) {
+        let Some(watcher) = &self.model_watcher else { return; };
+        let changed = watcher.changed_files();
+        if changed.is_empty() {
+            return;
+        }
+        
+        let was_changed = |config: &ModelConfig| {
+            let file_name = config.file_name();
+            let file_name = std::path::Path::new(&file_name).file_name().and_then(|name| name.to_str());
+            file_name.is_some_and(|file_name| changed.iter().any(|name| name == file_name))
+        };
+        let mut failures = Vec::new();
+        let mut report = |name: &str, result: Result<(), ModelError>| match result {
+            Ok(()) => println!("Reloaded model {}", name),
+            Err(e) => failures.push(format!("Failed to reload model {}: {}", name, e)),
+        };
+        
+        for model in self.world.models.iter_mut().filter(|model| was_changed(&model.config)) {
+            let name = model.config.name.clone();
+            report(&name, model.reload());
+        }
+        if self.world.obstacles.iter().any(|obstacle| was_changed(&obstacle.model.config)) {
+            self.world.edit_obstacles(|obstacles| {
+                for obstacle in obstacles.iter_mut().filter(|obstacle| was_changed(&obstacle.model.config)) {
+                    let result = obstacle.reload_model();
+                    report(&obstacle.name, result);
+                }
+            });
+        }
+        if let Some(robot) = self.world.robot.as_mut().filter(|robot| was_changed(&robot.model.config)) {
+            report("robot", robot.reload_model());
+        }
+        for message in failures {
+            self.report_error(message);
+        }
+    }
+    
+    /// captures the next frame into the current recording while the robot follows its path
+    fn record_frame(&mut self, app: &App) {
+        let following = self.world.robot.as_ref().is_some_and(|robot| robot.follow_path);
+        
+        if self.frame_recorder.enabled && following {
+            match self.frame_recorder.next_frame_path() {
+                Ok(path) => app.main_window().capture_frame(path),
+                Err(e) => {
+                    eprintln!("Failed to record frame: {}", e);
+                    self.frame_recorder.enabled = false;
+                }
+            }
+        } else if self.frame_recorder.is_recording() {
+            self.frame_recorder.finish_run();
+        }
+    }
+    
+    /// generates the path and records the robot following it, encoded as a gif unless mp4 is chosen
+    fn record_run(&mut self) {
+        if self.world.robot.is_none() {
+            return;
+        }
+        self.plan_path();
+        if let Some(robot) = &mut self.world.robot {
+            robot.follow_path = true;
+        }
+        
+        let format = self.frame_recorder.video_format.unwrap_or(VideoFormat::Gif);
+        self.frame_recorder.record_run(format);
+    }
+    
+    /// keeps the camera from tilting past straight up or down, or out of sight of the horizon when it is kept
+    /// level, and its direction within one turn
+    fn constrain_camera(&mut self) {
+        let max_pitch = if self.keep_horizon_level { HORIZON_MARGIN * self.fov / 2.0 } else { camera::MAX_PITCH };
+        let pose = self.camera_pose().constrained(max_pitch);
+        self.set_camera_pose(pose);
+    }
+    
+    /// draws with other colors than the configured ones, none goes back to them. colors kept by what is already
+    /// built are updated too
+    fn set_colors(&mut self, colors: Option<ColorConfig>) {
+        self.custom_colors = colors;
+        config::set_colors(colors);
+        let colors = config::colors();
+        if let Some(gradient_field) = &mut self.gradient_field {
+            gradient_field.set_color(config::color(colors.gradient));
+        }
+        self.world.set_target_color(config::color(colors.target));
+    }
+    
+    /// glides the camera to a pose, it stops following the robot on the way
+    fn move_camera_to(&mut self, pose: CameraPose) {
+        self.follow_robot = false;
+        self.camera_transition = Some(CameraTransition::new(self.camera_pose(), pose, self.camera_transition_time));
+    }
+    
+    /// moves the camera to look down at a spot from its current direction
+    fn go_to_point(&mut self, point: Position) {
+        self.move_camera_to(camera::looking_at(point, POINT_VIEW_DISTANCE, self.direction, POINT_VIEW_PITCH));
+    }
+    
+    /// starts a smooth transition to a saved camera bookmark
+    fn go_to_bookmark(&mut self, index: usize) {
+        if let Some(bookmark) = self.camera_bookmarks.get(index) {
+            self.move_camera_to(bookmark.pose);
+        }
+    }
+    
+    /// moves the camera to see the whole field and every obstacle from its current direction
+    fn frame_scene(&mut self) {
+        let (mut min, mut max) = ([0.0f32, 0.0], [field_length(), field_width()]);
+        for obstacle in &self.world.obstacles {
+            let position = obstacle.model.config.position;
+            min = [min[0].min(position.x), min[1].min(position.y)];
+            max = [max[0].max(position.x), max[1].max(position.y)];
+        }
+        self.move_camera_to(camera::framing(min, max, self.direction, self.fov));
+    }
+}
+
+fn update(app: &App, model: &mut AppModel, update: Update) {
+    model.reload_changed_models();
+    model.handle_remote_commands();
+    model.world.update_passable(model.robot_height, model.step_height);
+    
+    // a replay steps through the recorded ticks instead of the frame time
+    let mut stepped = false;
+    if let Some(replay) = &model.session_replay {
+        let steps = if replay.playing { replay.ticks_per_frame } else { 0 };
+        for _ in 0..steps {
+            model.step_session_replay();
+            stepped = true;
+        }
+    } else {
+        for dt in model.simulation_steps(update.since_last.as_secs_f32()) {
+            model.simulate(dt);
+            stepped = true;
+        }
+    }
+    // once for however many steps the frame took, a replan costs far more than a step
+    if stepped {
+        model.replan_around_obstacles();
+    }
+    if let Some(roadmap) = model.world.robot.as_mut().and_then(|robot| robot.roadmap.as_mut()) {
+        let edges = if model.animate_roadmap { model.roadmap_growth_speed * update.since_last.as_secs_f32() } else { f32::INFINITY };
+        roadmap.grow(edges);
+    }
+    if let Some(wavefront) = model.world.robot.as_mut().and_then(|robot| robot.wavefront.as_mut()) {
+        let waves = if model.animate_wavefront { model.wavefront_speed * update.since_last.as_secs_f32() } else { f32::INFINITY };
+        wavefront.grow(waves);
+    }
+    if model.play_optimization {
+        model.step_optimization(model.optimization_steps_per_frame);
+    }
+    model.step_evaluation();
+    model.record_frame(app);
+    model.replan_on_deviation();
+    model.autosave_scene();
+    
+    let projection = Projection::from_window(app.window_rect(), model.fov);
+    model.cursor_field_position = screen_to_ground(app.mouse.position(), model, &projection);
+    
+    let ctx = model.egui.begin_frame();
+    let mut bookmark_to_open = None;
+    let mut obstacle_to_add = None;
+    let mut field_layout_to_load = None;
+    let mut generate_scene = false;
+    let mut run_evaluation = false;
+    let mut refresh_entity_preview = false;
+    let mut add_entity = false;
+    let mut record_run = false;
+    let mut scene_to_load = None;
+    let mut save_scene = false;
+    let mut restore_autosave = None;
+    let mut reoptimize = false;
+    let mut tour_failed = false;
+    let mut path_import_error = None;
+    let mut go_to_clearance_point = None;
+    let mut frame_scene = false;
+    let mut colors_to_set = None;
+    let mut dismissed_error = None;
+    let mut refresh_costmap = false;
+    let mut refresh_distance_field = false;
+    let mut start_following = false;
+    let mut start_remote = false;
+    let mut run_script = false;
+    let mut record_session = false;
+    let mut replay_session = false;
+    let mut step_replay = false;
+    let mut add_team_robot = false;
+    let mut optimization_steps = None;
+    let mut update_gradient_field = false;
+    let mut group_transform = None;
+    let mut group_to_dissolve = None;
+    
+    egui::TopBottomPanel::bottom("status_bar")
+        .exact_height(STATUS_BAR_HEIGHT)
+        .show(&ctx, |ui| {
+            ui.horizontal_centered(|ui| {
+                match model.cursor_field_position {
+                    Some(cursor) if !ctx.is_pointer_over_area() => {
+                        ui.label(format!("Cursor: x = {:.2}, y = {:.2}", cursor.x, cursor.y));
+                    },
+                    _ => { ui.label("Cursor: -"); },
+                }
                 
-                ui.label("Position:");
-                let mut position = model.target_position.get_position();
-                let mut position_changed = false;
+                if let (Some(start), Some(end)) = (model.ruler_start, model.ruler_end) {
+                    ui.separator();
+                    ui.label(format!("Ruler: {:.3} m", start.distance_to(&end)));
+                }
+                
+                if let (ClickAction::Probe, Some(cursor)) = (model.click_action, model.cursor_field_position) {
+                    ui.separator();
+                    ui.label(format!("Field: {}", FieldProbe::at(&model.world.obstacles, cursor).readout()));
+                }
                 
+                ui.separator();
+                let stepping = if model.fixed_timestep { format!("fixed {:.1} ms", model.timestep * 1000.0) } else { String::from("per frame") };
+                ui.label(format!("Sim time: {:.2} s ({})", model.sim_time, stepping));
+            });
+        });
+    
+    for (i, message) in model.errors.iter().enumerate() {
+        egui::Window::new("Error")
+            .id(egui::Id::new(("error", i)))
+            .collapsible(false)
+            .resizable(false)
+            .show(&ctx, |ui| {
+                ui.colored_label(egui::Color32::RED, message);
+                if ui.button("Dismiss").clicked() {
+                    dismissed_error = Some(i);
+                }
+            });
+    }
+    
+    if model.autosave.recovered.is_some() {
+        egui::Window::new("Restore Scene")
+            .collapsible(false)
+            .resizable(false)
+            .show(&ctx, |ui| {
+                ui.label("The last session did not exit cleanly. Restore its autosaved scene?");
                 ui.horizontal(|ui| {
-                    ui.label("X:");
-                    if ui.add(egui::DragValue::new(&mut position.x).speed(0.1)).changed() {
-                        position_changed = true;
+                    if ui.button("Restore").clicked() {
+                        restore_autosave = Some(true);
                     }
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Y:");
-                    if ui.add(egui::DragValue::new(&mut position.y).speed(0.1)).changed() {
-                        position_changed = true;
+                    if ui.button("Discard").clicked() {
+                        restore_autosave = Some(false);
                     }
                 });
-                
-                if position_changed {
-                    model.target_position.set_position(position);
-                    
-                    if let Some(robot) = &mut model.robot {
-                        robot.generate_path(&position, model.path_segments, &model.obstacles);
+            });
+    }
+    
+    egui::Window::new("Settings")
+        .open(&mut model.show_settings)
+        .resizable(false)
+        .show(&ctx, |ui| {
+            ui.label("Key Bindings (click a key, then press the new key):");
+            egui::Grid::new("key_bindings").striped(true).show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.label());
+                    let text = if model.rebinding_action == Some(action) {
+                        String::from("press a key...")
+                    } else {
+                        model.key_bindings.describe(action)
+                    };
+                    if ui.button(text).clicked() {
+                        model.rebinding_action = Some(action);
                     }
+                    if ui.small_button("x").on_hover_text("Unbind").clicked() {
+                        model.key_bindings.bind(action, None);
+                        model.rebinding_action = None;
+                    }
+                    ui.end_row();
                 }
-                
-                ui.separator();
-                ui.heading("Robot Movement");
-                
-                let mut velocity_changed = false;
-                let mut speed_changed = false;
-                
-                ui.horizontal(|ui| {
-                    ui.label("X Velocity:");
-                    if ui.add(egui::Slider::new(&mut model.robot_velocity_x, -5.0..=5.0).text("X")).changed() {
-                        velocity_changed = true;
+            });
+            
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    match model.key_bindings.save(KEYBINDINGS_PATH) {
+                        Ok(()) => { println!("Saved key bindings to {}", KEYBINDINGS_PATH); },
+                        Err(e) => { eprintln!("Failed to save key bindings to {}: {}", KEYBINDINGS_PATH, e); }
                     }
-                });
-                
-                ui.horizontal(|ui| {
-                    ui.label("Y Velocity:");
-                    if ui.add(egui::Slider::new(&mut model.robot_velocity_y, -5.0..=5.0).text("Y")).changed() {
-                        velocity_changed = true;
+                }
+                if ui.button("Reload").clicked() {
+                    match KeyBindings::load(KEYBINDINGS_PATH) {
+                        Ok(key_bindings) => { model.key_bindings = key_bindings; },
+                        Err(e) => { eprintln!("Failed to load key bindings from {}: {}", KEYBINDINGS_PATH, e); }
                     }
-                });
-                
-                ui.horizontal(|ui| {
-                    ui.label("Target Speed:");
-                    if ui.add(egui::Slider::new(&mut model.robot_target_speed, 0.1..=10.0).text("Speed")).changed() {
-                        speed_changed = true;
+                }
+                if ui.button("Reset Defaults").clicked() {
+                    model.key_bindings = KeyBindings::default();
+                }
+            });
+        });
+    
+    egui::Window::new("Script Console")
+        .open(&mut model.show_script_console)
+        .default_width(420.0)
+        .show(&ctx, |ui| {
+            let console = &mut model.script_console;
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut console.path);
+                if ui.button("Load").clicked() {
+                    if let Err(e) = console.load() {
+                        eprintln!("Failed to load script {}: {}", console.path, e);
                     }
-                });
-                
-                if velocity_changed {
-                    if let Some(robot) = &mut model.robot {
-                        robot.set_velocity(model.robot_velocity_x, model.robot_velocity_y);
+                }
+                if ui.button("Save").clicked() {
+                    match console.save() {
+                        Ok(()) => println!("Saved script to {}", console.path),
+                        Err(e) => eprintln!("Failed to save script to {}: {}", console.path, e),
                     }
                 }
-                
-                if speed_changed {
-                    if let Some(robot) = &mut model.robot {
-                        robot.set_target_speed(model.robot_target_speed);
-                    }
+            });
+            egui::ScrollArea::vertical().id_source("script_source").max_height(260.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut console.source).code_editor().desired_rows(14).desired_width(f32::INFINITY));
+            });
+            
+            ui.horizontal(|ui| {
+                if ui.button("Run").clicked() {
+                    run_script = true;
+                }
+                if ui.button("Clear Output").clicked() {
+                    console.output.clear();
+                    console.metrics.clear();
+                }
+            });
+            
+            ui.separator();
+            egui::ScrollArea::vertical().id_source("script_output").max_height(160.0).stick_to_bottom(true).show(ui, |ui| {
+                for line in &console.output {
+                    ui.monospace(line);
+                }
+            });
+            
+            ui.separator();
+            ui.label(format!("Recorded metrics: {}", console.metrics.len()));
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut console.metrics_path);
+                if ui.button("Export Metrics").clicked() {
+                    match console.export_metrics() {
+                        Ok(()) => println!("Exported script metrics to {}", console.metrics_path),
+                        Err(e) => eprintln!("Failed to export script metrics to {}: {}", console.metrics_path, e),
+                    }
+                }
+            });
+        });
+    
+    egui::Window::new("Add Entity")
+        .open(&mut model.show_add_entity)
+        .resizable(false)
+        .show(&ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Model:");
+                refresh_entity_preview |= ui.text_edit_singleline(&mut model.new_model_name).changed();
+            });
+            refresh_entity_preview |= ui.add(egui::Slider::new(&mut model.new_model_scale, 0.1..=10.0).text("Scale")).changed();
+            
+            ui.label("Position:");
+            let mut position_changed = false;
+            ui.horizontal(|ui| {
+                ui.label("X:");
+                position_changed |= ui.add(egui::DragValue::new(&mut model.new_model_position.x).speed(0.1)).changed();
+                ui.label("Y:");
+                position_changed |= ui.add(egui::DragValue::new(&mut model.new_model_position.y).speed(0.1)).changed();
+                ui.label("Z:");
+                position_changed |= ui.add(egui::DragValue::new(&mut model.new_model_position.z).speed(0.1)).changed();
+            });
+            if position_changed {
+                if let Ok(preview) = &mut model.entity_preview {
+                    preview.move_to(model.new_model_position);
+                }
+            }
+            
+            ui.checkbox(&mut model.create_as_obstacle, "Create As Obstacle");
+            
+            ui.separator();
+            match &model.entity_preview {
+                Ok(preview) => {
+                    let (min, max) = preview.bounds();
+                    ui.label(format!("Size: {:.2} x {:.2} x {:.2} m", max.x - min.x, max.y - min.y, max.z - min.z));
+                    ui.label(format!("Footprint Radius: {:.2} m", preview.footprint_radius()));
+                    if model.create_as_obstacle {
+                        ui.label(format!("Layer: {}", model.new_obstacle_layer));
+                    }
+                    
+                    let text = if model.create_as_obstacle { "Create Obstacle" } else { "Create Model" };
+                    add_entity = ui.button(text).clicked();
+                },
+                Err(e) => { ui.label(format!("Cannot load {}: {}", model.new_model_name, e)); },
+            }
+        });
+    
+    // ui side panel
+    let panel = egui::SidePanel::right("controls_panel")
+        .default_width(model.panel_width)
+        .resizable(true)
+        .show(&ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Settings").clicked() {
+                    model.show_settings = !model.show_settings;
+                }
+                if ui.button("Script Console").clicked() {
+                    model.show_script_console = !model.show_script_console;
+                }
+            });
+            
+            ui.collapsing("Visualization", |ui| {
+                let mut fov_degrees = model.fov.to_degrees();
+                if ui.add(egui::Slider::new(&mut fov_degrees, MIN_FOV.to_degrees()..=MAX_FOV.to_degrees()).text("FOV")).changed() {
+                    model.fov = fov_degrees.to_radians();
+                }
+                let fullscreen_key = model.key_bindings.describe(Action::ToggleFullscreen);
+                if ui.checkbox(&mut model.fullscreen, format!("Fullscreen ({})", fullscreen_key)).changed() {
+                    app.main_window().set_fullscreen(model.fullscreen);
+                }
+                
+                let follow_key = model.key_bindings.describe(Action::ToggleFollowRobot);
+                ui.checkbox(&mut model.follow_robot, format!("Follow Robot ({})", follow_key));
+                if model.follow_robot {
+                    ui.add(egui::Slider::new(&mut model.follow_distance, 0.0..=10.0).text("Follow Distance"));
+                    ui.add(egui::Slider::new(&mut model.follow_height, 0.0..=10.0).text("Follow Height"));
+                    ui.add(egui::Slider::new(&mut model.follow_smoothing, 0.0..=2.0).text("Follow Smoothing (s)"));
+                }
+                egui::CollapsingHeader::new("Line Weights").show(ui, |ui| {
+                    let weights = &mut model.line_weights;
+                    ui.add(egui::Slider::new(&mut weights.field, 0.5..=6.0).text("Field"))
+                        .on_hover_text("the field border and ground grid");
+                    ui.add(egui::Slider::new(&mut weights.obstacles, 0.5..=6.0).text("Obstacles"));
+                    ui.add(egui::Slider::new(&mut weights.path, 0.5..=6.0).text("Path"));
+                    ui.add(egui::Slider::new(&mut weights.gradient, 0.5..=6.0).text("Gradient"))
+                        .on_hover_text("the wire mesh of the obstacle field");
+                    if ui.button("Reset").clicked() {
+                        *weights = LineWeights::default();
+                    }
+                });
+                egui::CollapsingHeader::new("Colors").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        for theme in ColorTheme::ALL {
+                            if ui.button(theme.label()).clicked() {
+                                colors_to_set = Some(Some(theme.colors()));
+                            }
+                        }
+                    });
+                    let mut colors = config::colors();
+                    let mut changed = false;
+                    egui::Grid::new("colors").num_columns(2).show(ui, |ui| {
+                        for (name, color) in colors.entries_mut() {
+                            ui.label(name);
+                            changed |= ui.color_edit_button_srgb(color).changed();
+                            ui.end_row();
+                        }
+                    });
+                    if changed {
+                        colors_to_set = Some(Some(colors));
+                    }
+                    if model.custom_colors.is_some() && ui.button("Use Configured Colors")
+                        .on_hover_text("goes back to the colors of visualizer.toml")
+                        .clicked() {
+                        colors_to_set = Some(None);
+                    }
+                });
+                egui::CollapsingHeader::new("Camera Motion").show(ui, |ui| {
+                    ui.add(egui::Slider::new(&mut model.camera_speed, 0.5..=20.0).text("Camera Speed (m/s)"));
+                    ui.add(egui::Slider::new(&mut model.rotation_speed, 0.1..=5.0).text("Rotation Speed (rad/s)"));
+                    ui.add(egui::Slider::new(&mut model.camera_damping, 0.0..=1.0).text("Camera Damping (s)"))
+                        .on_hover_text("how long the camera takes to speed up and glide to a stop, 0 moves it only while a key is held");
+                    ui.checkbox(&mut model.keep_horizon_level, "Keep Horizon Level")
+                        .on_hover_text("tilts the camera no further up or down than keeps the horizon in view, so turning never spins the view around its center");
+                    ui.add(egui::Slider::new(&mut model.camera_transition_time, 0.0..=3.0).text("Transition Time (s)"))
+                        .on_hover_text("how long jumps to bookmarks, report spots and framing the scene take, 0 jumps at once");
+                    if ui.button(format!("Frame Scene ({})", model.key_bindings.describe(Action::FrameScene))).clicked() {
+                        frame_scene = true;
+                    }
+                });
+                ui.separator();
+                
+                ui.checkbox(&mut model.show_path, "Show Path");
+                ui.checkbox(&mut model.show_points, "Show Points")
+                    .on_hover_text("mark each path point on the obstacle field surface");
+                if model.show_points {
+                    let markers = &mut model.path_markers;
+                    ui.horizontal(|ui| {
+                        ui.label("Sample:");
+                        for sampling in MarkerSampling::ALL {
+                            ui.radio_value(&mut markers.sampling, sampling, sampling.label());
+                        }
+                    });
+                    match markers.sampling {
+                        MarkerSampling::EveryNth => { ui.add(egui::Slider::new(&mut markers.every_nth, 1..=20).text("Every Nth")); },
+                        MarkerSampling::ArcLength => { ui.add(egui::Slider::new(&mut markers.spacing, 0.05..=2.0).text("Spacing (m)")); },
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Color:");
+                        for coloring in MarkerColoring::ALL {
+                            ui.radio_value(&mut markers.coloring, coloring, coloring.label());
+                        }
+                    });
+                    ui.add(egui::Slider::new(&mut markers.size, 0.01..=0.5).text("Marker Size (m)"));
+                }
+                ui.checkbox(&mut model.show_gradient_function, "Show Gradient Function");
+                ui.checkbox(&mut model.show_influence_rings, "Show Influence Rings")
+                    .on_hover_text("dashed outline of the area each obstacle pushes the path out of, its footprint grown by the robot radius and buffer");
+                ui.checkbox(&mut model.show_minimap, "Show Minimap");
+                ui.checkbox(&mut model.show_axis_gizmo, "Show Axis Gizmo");
+                ui.checkbox(&mut model.show_labels, "Show Labels");
+                ui.collapsing("Field Image", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("PNG:");
+                        ui.text_edit_singleline(&mut model.field_image_path);
+                    });
+                    ui.add(egui::Slider::new(&mut model.field_image_columns, 16..=256).text("Image Resolution"));
+                    ui.horizontal(|ui| {
+                        if ui.button("Load Image").clicked() {
+                            match FieldImage::load(&model.field_image_path, model.field_image_columns) {
+                                Ok(image) => model.field_image = Some(image),
+                                Err(e) => eprintln!("Failed to load field image {}: {}", model.field_image_path, e),
+                            }
+                        }
+                        if ui.button("Remove Image").clicked() {
+                            model.field_image = None;
+                        }
+                    });
+                    ui.checkbox(&mut model.show_field_image, "Show Field Image");
+                    ui.add(egui::Slider::new(&mut model.field_image_brightness, 0.1..=1.0).text("Brightness"));
+                });
+                refresh_costmap |= ui.checkbox(&mut model.show_costmap, "Show Costmap")
+                    .on_hover_text("cells the robot center can not enter in the lethal color, the buffer around them in the inflated color")
+                    .changed();
+                if let Some(robot) = &mut model.world.robot {
+                    if model.show_costmap || robot.planner.uses_costmap() {
+                        refresh_costmap |= ui.add(egui::Slider::new(&mut robot.costmap_resolution, 0.02..=0.5).logarithmic(true).text("Costmap Resolution (m)"))
+                            .changed();
+                    }
+                }
+                refresh_distance_field |= ui.checkbox(&mut model.show_distance_heatmap, "Show Distance Heatmap")
+                    .on_hover_text("distance from the nearest hard obstacle surface, from the near color at the surface to the far color")
+                    .changed();
+                refresh_distance_field |= ui.checkbox(&mut model.show_distance_contours, "Show Distance Contours")
+                    .changed();
+                if let Some(robot) = &mut model.world.robot {
+                    if model.show_distance_heatmap || model.show_distance_contours || robot.use_distance_field {
+                        refresh_distance_field |= ui.add(egui::Slider::new(&mut robot.distance_resolution, 0.01..=0.5).logarithmic(true).text("Distance Resolution (m)"))
+                            .changed();
+                    }
+                }
+                if model.show_distance_heatmap || model.show_distance_contours {
+                    ui.add(egui::Slider::new(&mut model.distance_max, 0.1..=5.0).text("Max Distance (m)"));
+                }
+                if model.show_distance_contours {
+                    ui.add(egui::Slider::new(&mut model.distance_contour_spacing, 0.05..=1.0).text("Contour Spacing (m)"));
+                }
+                ui.checkbox(&mut model.show_grid, "Show Ground Grid");
+                if model.show_grid {
+                    ui.add(egui::Slider::new(&mut model.grid_spacing, 0.1..=5.0).text("Grid Spacing"));
+                    ui.add(egui::Slider::new(&mut model.grid_extent, 0.0..=10.0).text("Grid Extent"));
+                }
+                
+                if model.show_gradient_function {
+                    ui.separator();
+                    ui.heading("Gradient Field Settings");
+                    
+                    ui.horizontal(|ui| {
+                        for rendering in FieldRendering::ALL {
+                            if ui.radio_value(&mut model.gradient_rendering, rendering, rendering.label()).changed() {
+                                if let Some(gradient_field) = &mut model.gradient_field {
+                                    // the surface is only built while it is shown
+                                    gradient_field.shaded = rendering.surface();
+                                    if !gradient_field.shaded {
+                                        gradient_field.surface.clear();
+                                    }
+                                    update_gradient_field |= gradient_field.shaded && gradient_field.surface.is_empty();
+                                }
+                            }
+                        }
+                    });
+                    ui.add(egui::Slider::new(&mut model.gradient_x_resolution, 0.01..=10.0).text("X Resolution"));
+                    ui.add(egui::Slider::new(&mut model.gradient_y_resolution, 0.01..=10.0).text("Y Resolution"));
+                    ui.add(egui::Slider::new(&mut model.gradient_line_resolution, 0.01..=1.0).text("Line Resolution"));
+                    ui.checkbox(&mut model.gradient_adaptive, "Adaptive Refinement")
+                        .on_hover_text("add lines and segments where the field bends, near obstacle rims, and use fewer where it is flat");
+                    if model.gradient_adaptive {
+                        ui.add(egui::Slider::new(&mut model.gradient_tolerance, 0.005..=0.5).logarithmic(true).text("Height Tolerance"));
+                    }
+                    ui.checkbox(&mut model.gradient_influence_only, "Only Near Obstacles")
+                        .on_hover_text("skip the flat ground outside every obstacle's field");
+                    if model.gradient_influence_only {
+                        ui.add(egui::Slider::new(&mut model.gradient_influence_margin, 0.0..=2.0).text("Margin (m)"));
+                    }
+                    ui.checkbox(&mut model.gradient_gpu, "Compute on GPU")
+                        .on_hover_text("evaluate the field with a compute shader, for resolutions the CPU can not keep up with, the adaptive grid stays on the CPU");
+                    if let Some(gpu_field) = model.gpu_field.as_ref().filter(|_| model.gradient_gpu) {
+                        ui.label(format!("Computing on {}", gpu_field.adapter()));
+                    }
+                    if let Some(gradient_field) = &model.gradient_field {
+                        ui.label(format!("{} wires", gradient_field.wire_count()));
+                    }
+                    
+                    if ui.button("Update Gradient Field").clicked() {
+                        let gradient_function = gradient_field::obstacle_sum;
+                        
+                        gradient_field::set_obstacles_ref(&model.world.obstacles);
+                        
+                        if let Some(gradient_field) = &mut model.gradient_field {
+                            gradient_field.gradient_function = gradient_function;
+                            gradient_field.x_resolution = model.gradient_x_resolution;
+                            gradient_field.y_resolution = model.gradient_y_resolution;
+                            gradient_field.line_resolution = model.gradient_line_resolution;
+                            gradient_field.adaptive = model.gradient_adaptive.then_some(model.gradient_tolerance);
+                            gradient_field.influence_margin = model.gradient_influence_only.then_some(model.gradient_influence_margin);
+                            update_gradient_field = true;
+                        } else {
+                            gradient_field::set_obstacles_ref(&model.world.obstacles);
+                            
+                            model.gradient_field = Some(gradient_field::GradientWire::new(
+                                gradient_function,
+                                model.gradient_x_resolution,
+                                model.gradient_y_resolution,
+                                model.gradient_line_resolution
+                            ).with_color(config::color(config::colors().gradient))
+                                .with_adaptive(model.gradient_adaptive.then_some(model.gradient_tolerance))
+                                .with_influence(model.gradient_influence_only.then_some(model.gradient_influence_margin))
+                                .with_surface(model.gradient_rendering.surface()));
+                        }
+                    }
+                }
+            });
+                        
+            ui.collapsing("Capture", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Screenshots:");
+                    ui.text_edit_singleline(&mut model.screenshot_directory);
+                });
+                let screenshot_key = model.key_bindings.describe(Action::Screenshot);
+                if ui.button(format!("Save Screenshot ({})", screenshot_key)).clicked() {
+                    save_screenshot(app, &model.screenshot_directory);
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Frames:");
+                    ui.text_edit_singleline(&mut model.frame_recorder.directory);
+                });
+                ui.checkbox(&mut model.frame_recorder.enabled, "Record Frames While Following");
+                ui.horizontal(|ui| {
+                    ui.label("Encode As:");
+                    ui.radio_value(&mut model.frame_recorder.video_format, None, "Frames Only");
+                    for format in VideoFormat::ALL {
+                        ui.radio_value(&mut model.frame_recorder.video_format, Some(format), format.label());
+                    }
+                });
+                if ui.button("Record Run").on_hover_text("generate the path and record the robot following it").clicked() {
+                    record_run = true;
+                }
+                if model.frame_recorder.is_recording() {
+                    ui.label(format!("Recording frame {}", model.frame_recorder.frame_count()));
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut model.svg_export_path);
+                    if ui.button("Export Plan SVG").on_hover_text("writes a top-down drawing of the field, obstacles and path").clicked() {
+                        let target = model.world.target();
+                        match export_svg(model.world.robot.as_ref(), &model.world.obstacles, target, &model.svg_export_path) {
+                            Ok(()) => println!("Exported plan to {}", model.svg_export_path),
+                            Err(e) => eprintln!("Failed to export plan to {}: {}", model.svg_export_path, e),
+                        }
+                    }
+                });
+            });
+            
+            ui.collapsing("Placement", |ui| {
+                ui.label("Left click on the field to:");
+                ui.radio_value(&mut model.click_action, ClickAction::None, "Do Nothing");
+                ui.radio_value(&mut model.click_action, ClickAction::PlaceObstacle, "Place New Obstacle");
+                ui.radio_value(&mut model.click_action, ClickAction::MoveTarget, "Move Target");
+                ui.radio_value(&mut model.click_action, ClickAction::Measure, "Measure (two clicks)");
+                ui.radio_value(&mut model.click_action, ClickAction::EditPath, "Edit Path (drag points)");
+                ui.radio_value(&mut model.click_action, ClickAction::AddViaPoint, "Add Via Point");
+                ui.radio_value(&mut model.click_action, ClickAction::AddPathEvent, "Add Path Event");
+                ui.radio_value(&mut model.click_action, ClickAction::Probe, "Probe Field (pin a readout)");
+                ui.radio_value(&mut model.click_action, ClickAction::CrossSection, "Cross Section (two clicks)");
+                ui.radio_value(&mut model.click_action, ClickAction::SpeedZone, "Speed Zone (two corners)");
+                
+                ui.separator();
+                ui.checkbox(&mut model.snap_to_grid, format!("Snap to Grid ({:.2} m)", model.grid_spacing));
+                
+                if let (Some(start), Some(end)) = (model.ruler_start, model.ruler_end) {
+                    ui.label(format!("Measured: {:.3} m", start.distance_to(&end)));
+                    if ui.button("Clear Measurement").clicked() {
+                        model.ruler_start = None;
+                        model.ruler_end = None;
+                    }
+                }
+                
+                if !model.probes.is_empty() {
+                    ui.separator();
+                    let mut removed = None;
+                    for (i, &position) in model.probes.iter().enumerate() {
+                        let probe = FieldProbe::at(&model.world.obstacles, position);
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}: ({:.2}, {:.2}) {}", i + 1, position.x, position.y, probe.readout()));
+                            if ui.small_button("x").on_hover_text("Unpin").clicked() {
+                                removed = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = removed {
+                        model.probes.remove(i);
+                    }
+                    if ui.button("Clear Probes").clicked() {
+                        model.probes.clear();
+                    }
+                }
+            });
+            
+            ui.collapsing("Cross Section", |ui| {
+                let (Some(start), Some(end)) = (model.section_start, model.section_end) else {
+                    ui.label("Pick two points in Cross Section placement mode to plot the field along the line between them.");
+                    return;
+                };
+                
+                let section = CrossSection::sample(&model.world.obstacles, start, end, CROSS_SECTION_SAMPLES);
+                ui.label(format!("({:.2}, {:.2}) to ({:.2}, {:.2}), {:.2} m through {} obstacle field(s)",
+                    start.x, start.y, end.x, end.y, section.length(), section.obstacles.len()));
+                ui.checkbox(&mut model.section_gaussian, "Overlay Gaussian Field");
+                plot_cross_section(ui, &section, model.section_gaussian);
+                
+                for (from, to) in section.overlaps() {
+                    ui.colored_label(egui::Color32::YELLOW, format!("Fields overlap from {:.2} m to {:.2} m", from, to));
+                }
+                if ui.button("Clear Cross Section").clicked() {
+                    model.section_start = None;
+                    model.section_end = None;
+                }
+            });
+            
+            ui.collapsing("Scene", |ui| {
+                ui.label("Robot, target, obstacles, models and via points (.json, .ron or .yaml):");
+                ui.text_edit_singleline(&mut model.scene_path);
+                ui.horizontal(|ui| {
+                    if ui.button("Save Scene").clicked() {
+                        save_scene = true;
+                    }
+                    if ui.button("Load Scene").clicked() {
+                        match scene::load(&model.scene_path) {
+                            Ok(scene) => scene_to_load = Some(scene),
+                            Err(e) => eprintln!("Failed to load scene {}: {}", model.scene_path, e),
+                        }
+                    }
+                });
+            });
+            
+            ui.collapsing("Session Log", |ui| {
+                ui.label("Session log file (.jsonl):");
+                ui.text_edit_singleline(&mut model.session_path);
+                match &model.session_recorder {
+                    Some(recorder) => {
+                        ui.colored_label(egui::Color32::RED, format!("Recording: {} ticks", recorder.tick));
+                        if ui.button("Stop Recording").clicked() {
+                            model.session_recorder = None;
+                            println!("Saved session log to {}", model.session_path);
+                        }
+                    },
+                    None => {
+                        ui.horizontal(|ui| {
+                            if ui.button("Record Session").on_hover_text("log every edit and simulation tick from a fresh plan").clicked() {
+                                record_session = true;
+                            }
+                            if ui.button("Replay Session").clicked() {
+                                replay_session = true;
+                            }
+                        });
+                    },
+                }
+                
+                let mut stop_replay = false;
+                if let Some(replay) = &mut model.session_replay {
+                    ui.separator();
+                    ui.label(format!("Replay tick {} / {}", replay.tick, replay.ticks));
+                    ui.horizontal(|ui| {
+                        let text = if replay.playing { "Pause" } else { "Play" };
+                        if ui.add_enabled(!replay.is_finished(), egui::Button::new(text)).clicked() {
+                            replay.playing = !replay.playing;
+                        }
+                        if ui.add_enabled(!replay.playing && !replay.is_finished(), egui::Button::new("Step")).clicked() {
+                            step_replay = true;
+                        }
+                        if ui.button("Stop Replay").clicked() {
+                            stop_replay = true;
+                        }
+                    });
+                    ui.add(egui::Slider::new(&mut replay.ticks_per_frame, 1..=20).text("Ticks Per Frame"));
+                    match replay.divergence {
+                        Some((tick, distance)) => { ui.colored_label(egui::Color32::RED, format!("Diverged at tick {} by {:.3} m", tick, distance)); },
+                        None => { ui.colored_label(egui::Color32::GREEN, "Matching the recording"); },
+                    }
+                }
+                if stop_replay {
+                    model.session_replay = None;
+                }
+            });
+            
+            ui.collapsing("Remote Control", |ui| {
+                match &model.remote {
+                    Some(remote) => { ui.label(format!("Listening on ws://127.0.0.1:{}", remote.port)); },
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut model.remote_port).clamp_range(1024..=65535));
+                        });
+                        if ui.button("Start Server").clicked() {
+                            start_remote = true;
+                        }
+                    },
+                }
+            });
+            
+            ui.collapsing("Field Layout", |ui| {
+                egui::ComboBox::from_label("Preset")
+                    .selected_text(model.field_layout_preset.clone())
+                    .show_ui(ui, |ui| {
+                        for name in FieldLayout::preset_names() {
+                            ui.selectable_value(&mut model.field_layout_preset, name.to_string(), name);
+                        }
+                    });
+                
+                ui.checkbox(&mut model.replace_obstacles_on_load, "Replace Existing Obstacles");
+                
+                if ui.button("Load Preset").clicked() {
+                    match FieldLayout::preset(&model.field_layout_preset) {
+                        Ok(layout) => field_layout_to_load = Some(layout),
+                        Err(e) => eprintln!("Failed to load field preset {}: {}", model.field_layout_preset, e),
+                    }
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut model.field_layout_path);
+                });
+                if ui.button("Load File").clicked() {
+                    match FieldLayout::from_file(&model.field_layout_path) {
+                        Ok(layout) => field_layout_to_load = Some(layout),
+                        Err(e) => eprintln!("Failed to load field layout {}: {}", model.field_layout_path, e),
+                    }
+                }
+            });
+            
+            ui.collapsing("Camera Bookmarks", |ui| {
+                let mut go_to_index = None;
+                let mut delete_index = None;
+                
+                for (i, bookmark) in model.camera_bookmarks.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let hotkey = if i < 9 { format!("F{} ", i + 1) } else { String::new() };
+                        if ui.button(format!("{}{}", hotkey, bookmark.name)).clicked() {
+                            go_to_index = Some(i);
+                        }
+                        if ui.small_button("x").clicked() {
+                            delete_index = Some(i);
+                        }
+                    });
+                }
+                
+                bookmark_to_open = go_to_index;
+                if let Some(index) = delete_index {
+                    model.camera_bookmarks.remove(index);
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut model.new_bookmark_name);
+                    if ui.button("Save Current View").clicked() {
+                        let pose = CameraPose {
+                            position: model.camera_position,
+                            direction: model.direction,
+                            rotation_y: model.rotation_y,
+                        };
+                        model.camera_bookmarks.push(CameraBookmark::new(&model.new_bookmark_name, pose));
+                    }
+                });
+            });
+                        
+            // random scene section
+            ui.collapsing("Random Scene", |ui| {
+                let config = &mut model.random_scene;
+                ui.add(egui::Slider::new(&mut config.count, 1..=300).text("Obstacles"));
+                ui.add(egui::Slider::new(&mut config.min_radius, 0.05..=2.0).text("Min Radius"));
+                ui.add(egui::Slider::new(&mut config.max_radius, 0.05..=2.0).text("Max Radius"));
+                ui.add(egui::Slider::new(&mut config.min_spacing, 0.0..=2.0).text("Min Spacing"));
+                ui.add(egui::Slider::new(&mut config.free_radius, 0.0..=3.0).text("Start/Goal Clearance"));
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    ui.add(egui::DragValue::new(&mut config.seed));
+                    if ui.button("Randomize").clicked() {
+                        config.seed = rand::random();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Model:");
+                    ui.text_edit_singleline(&mut config.model);
+                });
+                
+                if ui.button("Generate Random Scene").on_hover_text("replaces all obstacles").clicked() {
+                    generate_scene = true;
+                }
+                
+                ui.separator();
+                ui.heading("Planner Evaluation");
+                ui.add(egui::Slider::new(&mut model.evaluation_runs, 1..=200).text("Runs"));
+                let running = model.evaluation.as_ref().is_some_and(|evaluation| !evaluation.is_finished());
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!running, egui::Button::new("Run Evaluation")).on_hover_text("plans through one random scene per seed, starting at the seed above").clicked() {
+                        run_evaluation = true;
+                    }
+                    if running && ui.button("Stop").clicked() {
+                        model.evaluation = None;
+                    }
+                });
+                
+                if let Some(evaluation) = &model.evaluation {
+                    let summary = &evaluation.summary;
+                    if running {
+                        ui.label(format!("Planning scene {} of {}", summary.runs.len() + 1, evaluation.runs()));
+                    }
+                    ui.label(format!("Success rate: {:.0}% of {} runs", summary.success_rate() * 100.0, summary.runs.len()));
+                    ui.label(format!("Average length: {:.2} m", summary.average_length()));
+                    ui.label(format!("Average clearance: {:.2} m", summary.average_clearance()));
+                    ui.label(format!("Planning time: {:.1} ms average, {:.1} ms max", 
+                        summary.average_planning_time_ms(), 
+                        summary.max_planning_time_ms()
+                    ));
+                    
+                    // path length per run, red for failed runs
+                    let max_length = summary.runs.iter().map(|run| run.length).fold(0.0, f32::max);
+                    let (response, painter) = ui.allocate_painter(egui::vec2(ui.available_width(), 60.0), egui::Sense::hover());
+                    let rect = response.rect;
+                    let bar_width = rect.width() / summary.runs.len().max(1) as f32;
+                    for (i, run) in summary.runs.iter().enumerate() {
+                        let height = if max_length > 0.0 { run.length / max_length * rect.height() } else { 0.0 };
+                        let left = rect.left() + i as f32 * bar_width;
+                        let bar = egui::Rect::from_min_max(
+                            egui::pos2(left, rect.bottom() - height),
+                            egui::pos2(left + (bar_width - 1.0).max(1.0), rect.bottom()),
+                        );
+                        let color = if run.success { egui::Color32::GREEN } else { egui::Color32::RED };
+                        painter.rect_filled(bar, 0.0, color);
+                    }
+                    
+                    egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                        egui::Grid::new("evaluation_runs").striped(true).show(ui, |ui| {
+                            ui.label("Seed");
+                            ui.label("Result");
+                            ui.label("Length");
+                            ui.label("Clearance");
+                            ui.label("Time");
+                            ui.end_row();
+                            
+                            for run in &summary.runs {
+                                ui.label(run.seed.to_string());
+                                ui.label(if run.success { "ok" } else { "failed" });
+                                ui.label(format!("{:.2} m", run.length));
+                                ui.label(format!("{:.2} m", run.clearance));
+                                ui.label(format!("{:.1} ms", run.planning_time_ms));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+            });
+            
+            // obstacle layers section
+            ui.collapsing("Obstacle Layers", |ui| {
+                let mut layers_changed = false;
+                let mut remove_index = None;
+                
+                for (i, layer) in model.obstacle_layers.iter_mut().enumerate() {
+                    let count = model.world.obstacles.iter().filter(|obstacle| obstacle.layer == layer.name).count();
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} ({})", layer.name, count));
+                        layers_changed |= ui.checkbox(&mut layer.visible, "Visible").changed();
+                        layers_changed |= ui.checkbox(&mut layer.enabled, "Enabled").on_hover_text("include in path planning").changed();
+                        if layer.name != DEFAULT_LAYER && ui.small_button("x").clicked() {
+                            remove_index = Some(i);
+                        }
+                    });
+                }
+                
+                // obstacles of a removed layer fall back to the default layer
+                if let Some(index) = remove_index {
+                    let removed = model.obstacle_layers.remove(index);
+                    model.world.edit_obstacles(|obstacles| {
+                        for obstacle in obstacles.iter_mut().filter(|obstacle| obstacle.layer == removed.name) {
+                            obstacle.layer = DEFAULT_LAYER.to_string();
+                        }
+                    });
+                    if model.new_obstacle_layer == removed.name {
+                        model.new_obstacle_layer = DEFAULT_LAYER.to_string();
+                    }
+                    layers_changed = true;
+                }
+                
+                if layers_changed {
+                    model.world.edit_obstacles(|obstacles| {
+                        for obstacle in obstacles {
+                            obstacle.apply_layer(&model.obstacle_layers);
+                        }
+                    });
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut model.new_layer_name);
+                    if ui.button("Add Layer").clicked() && !model.new_layer_name.is_empty() {
+                        ensure_layer(&mut model.obstacle_layers, &model.new_layer_name);
+                    }
+                });
+            });
+            
+            // obstacles section
+            ui.collapsing("Obstacles", |ui| {
+                ui.heading("Create New Obstacle");
+                
+                let selected_text = if model.new_obstacle_label.is_empty() {
+                    String::from("Custom")
+                } else {
+                    model.new_obstacle_label.clone()
+                };
+                egui::ComboBox::from_label("Template")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for template in OBSTACLE_PALETTE {
+                            if ui.selectable_label(model.new_obstacle_label == template.name, template.name).clicked() {
+                                model.new_obstacle_label = template.name.to_string();
+                                model.new_obstacle_name = template.model.to_string();
+                                model.new_obstacle_radius = template.radius;
+                            }
+                        }
+                    });
+                
+                ui.horizontal(|ui| {
+                    ui.label("Custom Model:");
+                    if ui.text_edit_singleline(&mut model.new_obstacle_name).changed() {
+                        model.new_obstacle_label.clear();
+                    }
+                });
+                
+                ui.add(egui::Slider::new(&mut model.new_obstacle_radius, 0.1..=5.0).text("Radius"));
+                
+                // obstacle position controls
+                ui.label("Position:");
+                ui.horizontal(|ui| {
+                    ui.label("X:");
+                    ui.add(egui::DragValue::new(&mut model.new_obstacle_position.x).speed(0.1));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Y:");
+                    ui.add(egui::DragValue::new(&mut model.new_obstacle_position.y).speed(0.1));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Z:");
+                    ui.add(egui::DragValue::new(&mut model.new_obstacle_position.z).speed(0.1));
+                });
+                
+                egui::ComboBox::from_label("Layer")
+                    .selected_text(model.new_obstacle_layer.clone())
+                    .show_ui(ui, |ui| {
+                        for layer in &model.obstacle_layers {
+                            ui.selectable_value(&mut model.new_obstacle_layer, layer.name.clone(), layer.name.as_str());
+                        }
+                    });
+                
+                if ui.button("Add Obstacle").clicked() {
+                    obstacle_to_add = Some(ModelConfig {
+                        name: model.new_obstacle_name.clone(),
+                        position: model.new_obstacle_position,
+                        scale: model.new_obstacle_radius * 2.0 // scale is diameter, radius*2
+                    });
+                }
+                
+                ui.separator();
+                ui.heading("Robot Clearance");
+                ui.add(egui::Slider::new(&mut model.robot_height, 0.0..=3.0).text("Robot Height"))
+                    .on_hover_text("the robot drives under obstacles whose underside is at least this high");
+                ui.add(egui::Slider::new(&mut model.step_height, 0.0..=1.0).text("Step Height"))
+                    .on_hover_text("the robot drives over obstacles no taller than this");
+                
+                ui.separator();
+                ui.heading("Moving Obstacles");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut model.animate_obstacles, "Animate Trajectories")
+                        .on_hover_text("moves obstacles along their trajectories, paths are planned around where they will be");
+                    ui.label(format!("Time: {:.1} s", model.obstacle_time));
+                    if ui.button("Reset Time").clicked() {
+                        model.obstacle_time = 0.0;
+                        model.world.edit_obstacles(|obstacles| {
+                            for obstacle in obstacles {
+                                obstacle.follow_trajectory(0.0);
+                            }
+                        });
+                    }
+                });
+                
+                if !model.world.obstacles.is_empty() {
+                    ui.separator();
+                    ui.heading("Existing Obstacles");
+                    
+                    for (i, obstacle) in model.world.obstacles.iter().enumerate() {
+                        let is_selected = model.selected_obstacle_index == Some(i);
+                        let label = format!("Obstacle {}: {} (radius: {:.2}, {}{})", 
+                            i + 1, 
+                            obstacle.name,
+                            obstacle.get_radius(),
+                            obstacle.layer,
+                            if obstacle.enabled { "" } else { ", disabled" }
+                        );
+                        let label = if obstacle.passable { format!("{}, passable", label) } else { label };
+                        let label = match &obstacle.group {
+                            Some(group) => format!("{}, in {}", label, group),
+                            None => label,
+                        };
+                        
+                        let is_selected = is_selected || model.selected_obstacle_indices.contains(&i);
+                        if ui.selectable_label(is_selected, label).clicked() {
+                            if ui.input(|input| input.modifiers.command) {
+                                // ctrl-click toggles the obstacle in the multi-selection
+                                if let Some(position) = model.selected_obstacle_indices.iter().position(|&index| index == i) {
+                                    model.selected_obstacle_indices.remove(position);
+                                } else {
+                                    model.selected_obstacle_indices.push(i);
+                                }
+                                model.selected_obstacle_index = model.selected_obstacle_indices.last().copied();
+                            } else {
+                                model.selected_obstacle_index = Some(i);
+                                model.selected_obstacle_indices = vec![i];
+                            }
+                            model.selected_model_index = None;
+                        }
+                    }
+                }
+                
+                if let Some(index) = model.selected_obstacle_index {
+                    if index < model.world.obstacles.len() {
+                        let mut current_radius = 1.0;
+                        let mut current_position = Position::new(0.0, 0.0, 0.0);
+                        
+                        if let Some(selected_obstacle) = model.world.obstacles.get(index) {
+                            current_radius = selected_obstacle.get_radius();
+                            current_position = selected_obstacle.model.config.position;
+                        }
+                        
+                        ui.separator();
+                        ui.heading("Edit Selected Obstacle");
+                        
+                        // radius controls
+                        let mut radius = current_radius;
+                        let radius_changed = ui.add(egui::Slider::new(&mut radius, 0.1..=5.0).text("Radius")).changed();
+                        
+                        // position controls
+                        ui.label("Position:");
+                        let mut position = current_position;
+                        let mut position_changed = false;
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("X:");
+                            if ui.add(egui::DragValue::new(&mut position.x).speed(0.1)).changed() {
+                                position_changed = true;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Y:");
+                            if ui.add(egui::DragValue::new(&mut position.y).speed(0.1)).changed() {
+                                position_changed = true;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Z:");
+                            if ui.add(egui::DragValue::new(&mut position.z).speed(0.1)).changed() {
+                                position_changed = true;
+                            }
+                        });
+                        
+                        let selected_obstacle = &model.world.obstacles[index];
+                        let mut layer = selected_obstacle.layer.clone();
+                        let mut height = selected_obstacle.height;
+                        let mut elevation = selected_obstacle.elevation;
+                        let mut buffer = selected_obstacle.get_buffer();
+                        let mut weight = selected_obstacle.weight;
+                        let mut soft = selected_obstacle.soft;
+                        let mut use_hull = selected_obstacle.use_hull;
+                        let has_hull = selected_obstacle.has_hull();
+                        let mut trajectory = selected_obstacle.trajectory.clone().unwrap_or_default();
+                        let obstacle_position = selected_obstacle.model.config.position;
+                        let mut properties_changed = false;
+                        
+                        egui::ComboBox::from_id_source("selected_obstacle_layer")
+                            .selected_text(layer.clone())
+                            .show_ui(ui, |ui| {
+                                for obstacle_layer in &model.obstacle_layers {
+                                    ui.selectable_value(&mut layer, obstacle_layer.name.clone(), obstacle_layer.name.as_str());
+                                }
+                            });
+                        let layer_changed = layer != model.world.obstacles[index].layer;
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Height:");
+                            properties_changed |= ui.add(egui::DragValue::new(&mut height).speed(0.05).clamp_range(0.0..=10.0)).changed();
+                            ui.label("Underside:");
+                            properties_changed |= ui.add(egui::DragValue::new(&mut elevation).speed(0.05).clamp_range(0.0..=10.0)).changed();
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Buffer:");
+                            properties_changed |= ui.add(egui::DragValue::new(&mut buffer).speed(0.01).clamp_range(0.0..=2.0))
+                                .on_hover_text("clearance kept from this obstacle on top of the robot radius")
+                                .changed();
+                            ui.label("Danger Weight:");
+                            properties_changed |= ui.add(egui::DragValue::new(&mut weight).speed(0.05).clamp_range(0.0..=20.0))
+                                .on_hover_text("scales how strongly this obstacle pushes the path away, such as a defending robot over a pillar")
+                                .changed();
+                        });
+                        
+                        let soft_checkbox = egui::Checkbox::new(&mut soft, "Soft (Crossable)");
+                        properties_changed |= ui.add(soft_checkbox).on_hover_text("the path avoids it where it can but may cross it, such as a cable protector").changed();
+                        
+                        if has_hull {
+                            let hull_checkbox = egui::Checkbox::new(&mut use_hull, "Convex Hull Footprint");
+                            properties_changed |= ui.add(hull_checkbox).on_hover_text("plan around the mesh outline instead of the radius circle").changed();
+                        }
+                        
+                        let mut trajectory_changed = false;
+                        ui.collapsing("Trajectory", |ui| {
+                            let mut waypoint_to_remove = None;
+                            
+                            for (i, waypoint) in trajectory.waypoints.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.label("t:");
+                                    trajectory_changed |= ui.add(egui::DragValue::new(&mut waypoint.time).speed(0.1).clamp_range(0.0..=600.0).suffix(" s")).changed();
+                                    ui.label("X:");
+                                    trajectory_changed |= ui.add(egui::DragValue::new(&mut waypoint.x).speed(0.05)).changed();
+                                    ui.label("Y:");
+                                    trajectory_changed |= ui.add(egui::DragValue::new(&mut waypoint.y).speed(0.05)).changed();
+                                    if ui.small_button("Remove").clicked() {
+                                        waypoint_to_remove = Some(i);
+                                    }
+                                });
+                            }
+                            if let Some(i) = waypoint_to_remove {
+                                trajectory.waypoints.remove(i);
+                                trajectory_changed = true;
+                            }
+                            
+                            ui.horizontal(|ui| {
+                                if ui.button("Add Waypoint Here")
+                                    .on_hover_text("adds the obstacle's current position two seconds after the last waypoint, pause the animation to move it first")
+                                    .clicked() {
+                                    let time = trajectory.waypoints.last().map_or(0.0, |waypoint| waypoint.time + 2.0);
+                                    trajectory.waypoints.push(TrajectoryWaypoint { time, x: obstacle_position.x, y: obstacle_position.y });
+                                    trajectory_changed = true;
+                                }
+                                trajectory_changed |= ui.checkbox(&mut trajectory.looping, "Loop").changed();
+                                if ui.button("Clear").clicked() {
+                                    trajectory.waypoints.clear();
+                                    trajectory_changed = true;
+                                }
+                            });
+                        });
+                        
+                        let delete_clicked = ui.button("Delete Obstacle").clicked();
+                        
+                        if layer_changed || properties_changed || trajectory_changed || radius_changed || position_changed {
+                            let layers = &model.obstacle_layers;
+                            model.world.edit_obstacle(index, |selected_obstacle| {
+                                if layer_changed {
+                                    selected_obstacle.layer = layer;
+                                    selected_obstacle.apply_layer(layers);
+                                }
+                                
+                                if properties_changed {
+                                    selected_obstacle.height = height;
+                                    selected_obstacle.elevation = elevation;
+                                    selected_obstacle.set_buffer(buffer);
+                                    selected_obstacle.weight = weight;
+                                    selected_obstacle.soft = soft;
+                                    selected_obstacle.use_hull = use_hull;
+                                }
+                                
+                                if trajectory_changed {
+                                    trajectory.sort();
+                                    selected_obstacle.trajectory = (!trajectory.waypoints.is_empty()).then_some(trajectory);
+                                }
+                                
+                                if radius_changed {
+                                    selected_obstacle.set_radius(radius);
+                                    
+                                    // update the model scale to match the new radius (diameter = 2*radius)
+                                    let old_position = selected_obstacle.model.config.position;
+                                    selected_obstacle.model.config.scale = radius * 2.0;
+                                    
+                                    selected_obstacle.model.position_at(Position::new(-old_position.x, -old_position.y, -old_position.z));
+                                    selected_obstacle.model.scale(radius * 2.0);
+                                    selected_obstacle.model.position_at(old_position);
+                                }
+                                
+                                if position_changed {
+                                    let delta_x = position.x - selected_obstacle.model.config.position.x;
+                                    let delta_y = position.y - selected_obstacle.model.config.position.y;
+                                    let delta_z = position.z - selected_obstacle.model.config.position.z;
+                                    
+                                    selected_obstacle.model.config.position = position;
+                                    selected_obstacle.model.position_at(Position::new(delta_x, delta_y, delta_z));
+                                }
+                            });
+                        }
+                        
+                        if delete_clicked {
+                            model.world.remove_obstacle(index);
+                            model.selected_obstacle_index = None;
+                            model.selected_obstacle_indices.clear();
+                        }
+                    }
+                }
+                
+                let selected: Vec<usize> = model.selected_obstacle_indices.iter()
+                    .copied()
+                    .filter(|&i| i < model.world.obstacles.len())
+                    .collect();
+                
+                if !selected.is_empty() {
+                    ui.separator();
+                    ui.heading(format!("Selection ({} obstacles, ctrl-click to add)", selected.len()));
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Duplicate Offset X:");
+                        ui.add(egui::DragValue::new(&mut model.duplicate_offset.x).speed(0.1));
+                        ui.label("Y:");
+                        ui.add(egui::DragValue::new(&mut model.duplicate_offset.y).speed(0.1));
+                    });
+                    
+                    let mut duplicates = Vec::new();
+                    ui.horizontal(|ui| {
+                        let duplicate_clicked = ui.button("Duplicate").clicked();
+                        let mirror_clicked = ui.button("Duplicate Mirrored").on_hover_text("mirror across the center line").clicked();
+                        
+                        for &i in &selected {
+                            let obstacle = &model.world.obstacles[i];
+                            let position = obstacle.model.config.position;
+                            let new_position = if duplicate_clicked {
+                                Position::new(position.x + model.duplicate_offset.x, position.y + model.duplicate_offset.y, position.z)
+                            } else if mirror_clicked {
+                                Position::new(field_length() - position.x, position.y, position.z)
+                            } else {
+                                continue;
+                            };
+                            
+                            match obstacle.duplicate_at(new_position) {
+                                Ok(duplicate) => duplicates.push(duplicate),
+                                Err(e) => eprintln!("Failed to duplicate obstacle {}: {}", obstacle.name, e),
+                            }
+                        }
+                    });
+                    
+                    if !duplicates.is_empty() {
+                        // select the new copies so they can be moved as a group straight away
+                        model.selected_obstacle_indices = duplicates.into_iter()
+                            .map(|obstacle| model.world.add_obstacle(obstacle))
+                            .collect();
+                        model.selected_obstacle_index = model.selected_obstacle_indices.last().copied();
+                    }
+                    
+                    if selected.len() > 1 {
+                        ui.horizontal(|ui| {
+                            ui.label("Move By X:");
+                            ui.add(egui::DragValue::new(&mut model.group_move_offset.x).speed(0.1));
+                            ui.label("Y:");
+                            ui.add(egui::DragValue::new(&mut model.group_move_offset.y).speed(0.1));
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            if ui.button("Move Selected").clicked() {
+                                for &i in &selected {
+                                    let position = model.world.obstacles[i].model.config.position;
+                                    model.world.move_obstacle(i, Position::new(
+                                        position.x + model.group_move_offset.x,
+                                        position.y + model.group_move_offset.y,
+                                        position.z,
+                                    ));
+                                }
+                            }
+                            
+                            if ui.button("Delete Selected").clicked() {
+                                let mut indices = selected.clone();
+                                indices.sort_unstable();
+                                for &i in indices.iter().rev() {
+                                    model.world.remove_obstacle(i);
+                                }
+                                model.selected_obstacle_index = None;
+                                model.selected_obstacle_indices.clear();
+                            }
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Group Name:");
+                            ui.text_edit_singleline(&mut model.new_group_name);
+                            if ui.button("Group Selected").on_hover_text("move and rotate the selected obstacles as one shape").clicked() {
+                                let name = if model.new_group_name.trim().is_empty() {
+                                    format!("Group {}", model.obstacle_groups.len() + 1)
+                                } else {
+                                    model.new_group_name.trim().to_string()
+                                };
+                                ensure_group(&mut model.obstacle_groups, &name);
+                                model.world.edit_obstacles(|obstacles| {
+                                    for &i in &selected {
+                                        obstacles[i].group = Some(name.clone());
+                                    }
+                                });
+                                model.selected_group = Some(name);
+                            }
+                        });
+                    }
+                }
+                
+                if !model.obstacle_groups.is_empty() {
+                    ui.separator();
+                    ui.heading("Groups");
+                    
+                    for group in &model.obstacle_groups {
+                        let members: Vec<usize> = model.world.obstacles.iter().enumerate()
+                            .filter(|(_, obstacle)| obstacle.group.as_deref() == Some(group.name.as_str()))
+                            .map(|(i, _)| i)
+                            .collect();
+                        let is_selected = model.selected_group.as_deref() == Some(group.name.as_str());
+                        if ui.selectable_label(is_selected, format!("{} ({} obstacles)", group.name, members.len())).clicked() {
+                            // selecting a group selects its members so they are highlighted
+                            model.selected_group = Some(group.name.clone());
+                            model.selected_obstacle_index = members.last().copied();
+                            model.selected_obstacle_indices = members;
+                            model.selected_model_index = None;
+                        }
+                    }
+                    
+                    let selected_group = model.selected_group.as_ref()
+                        .and_then(|name| model.obstacle_groups.iter().find(|group| &group.name == name));
+                    if let Some(group) = selected_group {
+                        let Some(mut center) = group_center(&model.world.obstacles, &group.name) else {
+                            ui.label("The group has no obstacles left");
+                            if ui.button("Remove Group").clicked() {
+                                group_to_dissolve = Some(group.name.clone());
+                            }
+                            return;
+                        };
+                        let mut rotation = group.rotation.to_degrees();
+                        let mut changed = false;
+                        ui.horizontal(|ui| {
+                            ui.label("Center X:");
+                            changed |= ui.add(egui::DragValue::new(&mut center.x).speed(0.05)).changed();
+                            ui.label("Y:");
+                            changed |= ui.add(egui::DragValue::new(&mut center.y).speed(0.05)).changed();
+                        });
+                        changed |= ui.add(egui::Slider::new(&mut rotation, -180.0..=180.0).text("Rotation (deg)")).changed();
+                        if changed {
+                            group_transform = Some((group.name.clone(), center, rotation.to_radians()));
+                        }
+                        if ui.button("Ungroup").on_hover_text("keep the obstacles where they are but move them separately again").clicked() {
+                            group_to_dissolve = Some(group.name.clone());
+                        }
+                    }
+                }
+            });
+            
+            ui.collapsing("Models", |ui| {
+                if ui.button("Add Entity").clicked() {
+                    model.show_add_entity = true;
+                    refresh_entity_preview = true;
+                }
+                
+                if model.world.models.is_empty() {
+                    ui.label("No models, drop an STL or OBJ file onto the window to add one");
+                }
+                
+                for i in 0..model.world.models.len() {
+                    let config = &model.world.models[i].config;
+                    let label = format!("Model {}: {} (scale: {:.2})", i + 1, config.name, config.scale);
+                    if ui.selectable_label(model.selected_model_index == Some(i), label).clicked() {
+                        model.selected_model_index = Some(i);
+                        model.selected_obstacle_index = None;
+                        model.selected_obstacle_indices.clear();
+                    }
+                }
+                
+                if let Some(index) = model.selected_model_index.filter(|&index| index < model.world.models.len()) {
+                    ui.separator();
+                    ui.heading("Edit Selected Model");
+                    
+                    let selected_model = &mut model.world.models[index];
+                    
+                    let mut scale = selected_model.config.scale;
+                    if ui.add(egui::Slider::new(&mut scale, 0.1..=10.0).text("Scale")).changed() {
+                        selected_model.set_scale(scale);
+                    }
+                    
+                    ui.label("Position:");
+                    let mut position = selected_model.config.position;
+                    let mut position_changed = false;
+                    ui.horizontal(|ui| {
+                        ui.label("X:");
+                        position_changed |= ui.add(egui::DragValue::new(&mut position.x).speed(0.1)).changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Y:");
+                        position_changed |= ui.add(egui::DragValue::new(&mut position.y).speed(0.1)).changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Z:");
+                        position_changed |= ui.add(egui::DragValue::new(&mut position.z).speed(0.1)).changed();
+                    });
+                    if position_changed {
+                        selected_model.move_to(position);
+                    }
+                    
+                    if ui.button("Delete Model").clicked() {
+                        model.world.remove_model(index);
+                        model.selected_model_index = None;
+                    }
+                }
+            });
+            
+            ui.collapsing("Via Points", |ui| {
+                ui.add(egui::Slider::new(&mut model.new_via_tolerance, 0.05..=2.0).text("New Tolerance"));
+                
+                let target = model.world.target();
+                if let Some(robot) = &mut model.world.robot {
+                    let mut via_changed = ui.checkbox(&mut robot.start_reversed, "Reverse From Start")
+                        .on_hover_text("drive backwards from the start to the first via point or target")
+                        .changed();
+                    let mut remove_index = None;
+                    
+                    for (i, via) in robot.via_points.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Via {}", i + 1));
+                            ui.label("X:");
+                            via_changed |= ui.add(egui::DragValue::new(&mut via.position.x).speed(0.1)).changed();
+                            ui.label("Y:");
+                            via_changed |= ui.add(egui::DragValue::new(&mut via.position.y).speed(0.1)).changed();
+                            ui.label("Tol:");
+                            via_changed |= ui.add(egui::DragValue::new(&mut via.tolerance).speed(0.05).clamp_range(0.0..=5.0)).changed();
+                            via_changed |= ui.checkbox(&mut via.reversed, "Reverse").on_hover_text("drive backwards from this via point").changed();
+                            if ui.small_button("x").clicked() {
+                                remove_index = Some(i);
+                            }
+                        });
+                    }
+                    
+                    if let Some(index) = remove_index {
+                        robot.via_points.remove(index);
+                        via_changed = true;
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        if ui.button("Add Via Point At Target").clicked() {
+                            robot.via_points.push(ViaPoint::new(target, model.new_via_tolerance));
+                            via_changed = true;
+                        }
+                        if ui.button("Clear").clicked() {
+                            robot.via_points.clear();
+                            via_changed = true;
+                        }
+                    });
+                    
+                    if via_changed {
+                        model.world.invalidate_path();
+                    }
+                }
+            });
+            
+            ui.collapsing("Simulation Stats", |ui| {
+                ui.label(format!("Collisions: {}", model.collision_count));
+                let touching = model.world.obstacles.iter().filter(|obstacle| obstacle.colliding).count();
+                if touching > 0 {
+                    ui.colored_label(egui::Color32::RED, format!("Touching {} obstacle(s)", touching));
+                }
+                ui.checkbox(&mut model.pause_on_collision, "Pause On Collision")
+                    .on_hover_text("stop following the path when the robot hits an obstacle");
+                if ui.button("Reset Collisions").clicked() {
+                    model.collision_count = 0;
+                }
+                
+                ui.separator();
+                if let Some(robot) = &model.world.robot {
+                    if robot.follow_path {
+                        ui.label(format!("Cross-track error: {:.2} m", robot.cross_track_error()));
+                    }
+                }
+                ui.checkbox(&mut model.auto_replan, "Re-plan On Deviation")
+                    .on_hover_text("plan a new path from the robot when it strays too far while following");
+                ui.add(egui::Slider::new(&mut model.replan_threshold, 0.05..=3.0).text("Max Cross-Track Error (m)"));
+                ui.label(format!("Re-plans: {}", model.replan_count));
+                
+                ui.separator();
+                ui.label(format!("Sim time: {:.2} s", model.sim_time));
+                ui.checkbox(&mut model.fixed_timestep, "Fixed Timestep")
+                    .on_hover_text("advance the simulation in equal steps so runs are the same at any frame rate");
+                if model.fixed_timestep {
+                    let mut milliseconds = model.timestep * 1000.0;
+                    if ui.add(egui::Slider::new(&mut milliseconds, 1.0..=50.0).text("Timestep (ms)")).changed() {
+                        model.timestep = milliseconds / 1000.0;
+                    }
+                }
+                if ui.button("Reset Sim Time").clicked() {
+                    model.sim_time = 0.0;
+                    model.time_accumulator = 0.0;
+                }
+            });
+            
+            ui.collapsing("Path Events", |ui| {
+                ui.label("New event (placed with the Add Path Event click action):");
+                let action = &mut model.new_event_action;
+                ui.horizontal(|ui| {
+                    if ui.radio(matches!(action, PathAction::Wait { .. }), "Wait").clicked() {
+                        *action = PathAction::Wait { seconds: 1.0 };
+                    }
+                    if ui.radio(matches!(action, PathAction::RotateTo { .. }), "Rotate To").clicked() {
+                        *action = PathAction::RotateTo { heading: 0.0 };
+                    }
+                    if ui.radio(matches!(action, PathAction::Marker { .. }), "Marker").clicked() {
+                        *action = PathAction::Marker { name: String::from("marker") };
+                    }
+                });
+                match action {
+                    PathAction::Wait { seconds } => {
+                        ui.add(egui::Slider::new(seconds, 0.1..=10.0).text("Seconds"));
+                    },
+                    PathAction::RotateTo { heading } => {
+                        ui.horizontal(|ui| {
+                            ui.label("Heading:");
+                            ui.drag_angle(heading);
+                        });
+                    },
+                    PathAction::Marker { name } => {
+                        ui.horizontal(|ui| {
+                            ui.label("Name:");
+                            ui.text_edit_singleline(name);
+                        });
+                    },
+                }
+                
+                if let Some(robot) = &mut model.world.robot {
+                    ui.separator();
+                    let mut remove_index = None;
+                    for (i, event) in robot.events.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} at {:.0}%: {}", i + 1, event.progress * 100.0, event.action.description()));
+                            if ui.small_button("x").clicked() {
+                                remove_index = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(index) = remove_index {
+                        robot.events.remove(index);
+                        robot.reset_events();
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut model.path_export_path);
+                        if ui.button("Export Path").on_hover_text("writes the path points and events as JSON").clicked() {
+                            match export_path(robot, &model.path_export_path) {
+                                Ok(()) => println!("Exported path to {}", model.path_export_path),
+                                Err(e) => eprintln!("Failed to export path to {}: {}", model.path_export_path, e),
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut model.ros_export_path);
+                        if ui.button("Export ROS Path")
+                            .on_hover_text("writes the spline as a nav_msgs/Path of timed geometry_msgs/PoseStamped, YAML or JSON by extension")
+                            .clicked() {
+                            match export_ros_path(robot, &model.ros_export_path) {
+                                Ok(()) => println!("Exported ROS path to {}", model.ros_export_path),
+                                Err(e) => eprintln!("Failed to export ROS path to {}: {}", model.ros_export_path, e),
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut model.setpoint_export_path);
+                        if ui.button("Export Setpoints")
+                            .on_hover_text("writes t, x, y, heading, vx, vy and omega at the control rate for a trajectory follower, CSV or JSON by extension")
+                            .clicked() {
+                            match export_setpoints(robot, &model.setpoint_export_path, model.control_rate) {
+                                Ok(()) => println!("Exported setpoints to {}", model.setpoint_export_path),
+                                Err(e) => eprintln!("Failed to export setpoints to {}: {}", model.setpoint_export_path, e),
+                            }
+                        }
+                    });
+                    ui.add(egui::Slider::new(&mut model.control_rate, 5.0..=500.0).text("Control Rate (Hz)"));
+                }
+            });
+            
+            ui.collapsing("Path Validation", |ui| {
+                let mut check_requested = false;
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut model.path_import_path);
+                    if ui.button("Import Path")
+                        .on_hover_text("reads a path from another planner as CSV of x,y points, moves the robot to its start and checks it")
+                        .clicked() {
+                        match import_path(&model.path_import_path) {
+                            Ok(points) => {
+                                if let Some(robot) = &mut model.world.robot {
+                                    robot.set_external_path(&points, &model.world.obstacles);
+                                    println!("Imported {} path points from {}", points.len(), model.path_import_path);
+                                }
+                                check_requested = true;
+                            },
+                            Err(e) => { path_import_error = Some(format!("Failed to import path from {}: {}", model.path_import_path, e)); },
+                        }
+                    }
+                });
+                if ui.button("Check Current Path").clicked() {
+                    check_requested = true;
+                }
+                if check_requested {
+                    model.path_check = model.world.robot.as_ref().map(|robot| check_path(robot, &model.world.obstacles));
+                }
+                
+                if let Some(check) = &model.path_check {
+                    let (verdict, color) = if check.passed() { ("Passed", egui::Color32::LIGHT_GREEN) } else { ("Failed", egui::Color32::LIGHT_RED) };
+                    ui.colored_label(color, verdict);
+                    ui.label(format!("Length: {:.2} m", check.length));
+                    ui.label(format!("Clearance: {:.3} m", check.clearance))
+                        .on_hover_text("smallest distance from the spline to the edge of a hard obstacle");
+                    let curvature_color = if check.max_curvature <= check.curvature_limit { ui.visuals().text_color() } else { egui::Color32::LIGHT_RED };
+                    ui.colored_label(curvature_color, format!("Max curvature: {:.2} 1/m (turn radius allows {:.2})", check.max_curvature, check.curvature_limit));
+                    if check.colliding.is_empty() {
+                        ui.label("Collisions: none");
+                    } else {
+                        ui.colored_label(egui::Color32::LIGHT_RED, format!("Collisions: {}", check.colliding.join(", ")));
+                    }
+                    if let Some((name, time)) = &check.conflict {
+                        ui.colored_label(egui::Color32::LIGHT_RED, format!("Moving obstacle {} in the way after {:.1} s", name, time));
+                    }
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Clearance Report")
+                        .on_hover_text("lists the spots of the path closest to the hard obstacles and marks them in the view")
+                        .clicked() {
+                        model.clearance_report = model.world.robot.as_ref()
+                            .map(|robot| worst_clearances(robot, &model.world.obstacles, model.worst_point_count))
+                            .unwrap_or_default();
+                    }
+                    ui.add(egui::Slider::new(&mut model.worst_point_count, 1..=20).text("Points"));
+                });
+                for (i, point) in model.clearance_report.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Go").on_hover_text("moves the camera to the spot").clicked() {
+                            go_to_clearance_point = Some(point.position);
+                        }
+                        ui.label(format!("#{} {:.3} m to {} at {:.2} m", i + 1, point.clearance, point.obstacle, point.distance));
+                    });
+                }
+                if !model.clearance_report.is_empty() && ui.button("Clear Report").clicked() {
+                    model.clearance_report.clear();
+                }
+            });
+            
+            ui.collapsing("Target Position", |ui| {
+                ui.heading("Edit Target Position");
+                
+                ui.label("Position:");
+                let mut position = model.world.target();
+                let mut position_changed = false;
+                
+                ui.horizontal(|ui| {
+                    ui.label("X:");
+                    if ui.add(egui::DragValue::new(&mut position.x).speed(0.1)).changed() {
+                        position_changed = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Y:");
+                    if ui.add(egui::DragValue::new(&mut position.y).speed(0.1)).changed() {
+                        position_changed = true;
+                    }
+                });
+                
+                if position_changed {
+                    model.world.set_target(position);
+                }
+                
+                let mut region_changed = false;
+                if let Some(robot) = &mut model.world.robot {
+                    ui.label("Goal Tolerance:")
+                        .on_hover_text("the path ends at the nearest point of the region the robot fits at, and the follower stops once it is inside");
+                    let mut region = robot.goal_region;
+                    ui.horizontal(|ui| {
+                        for shape in [GoalRegion::Point, GoalRegion::Disc { radius: 0.5 }, GoalRegion::Rectangle { width: 1.0, height: 1.0 }] {
+                            if ui.radio(std::mem::discriminant(&region) == std::mem::discriminant(&shape), shape.label()).clicked() {
+                                region = shape;
+                            }
+                        }
+                    });
+                    match &mut region {
+                        GoalRegion::Point => {},
+                        GoalRegion::Disc { radius } => {
+                            ui.add(egui::Slider::new(radius, 0.05..=3.0).text("Radius (m)"));
+                        },
+                        GoalRegion::Rectangle { width, height } => {
+                            ui.add(egui::Slider::new(width, 0.1..=6.0).text("Width (m)"));
+                            ui.add(egui::Slider::new(height, 0.1..=6.0).text("Height (m)"));
+                        },
+                    }
+                    region_changed = region != robot.goal_region;
+                    robot.goal_region = region;
+                }
+                if region_changed {
+                    model.world.invalidate_path();
+                }
+                
+                ui.label("Named Targets:")
+                    .on_hover_text("the number keys 1-9 send the target to the first nine and replan from where the robot is");
+                let mut go_to_index = None;
+                let mut delete_index = None;
+                for (i, target) in model.world.targets.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        let mut color = target.color();
+                        if ui.color_edit_button_srgb(&mut color).changed() {
+                            target.marker.color = nannou::color::rgb(color[0], color[1], color[2]);
+                        }
+                        let hotkey = if i < 9 { format!("{} ", i + 1) } else { String::new() };
+                        if ui.button(format!("{}{}", hotkey, target.name)).clicked() {
+                            go_to_index = Some(i);
+                        }
+                        if ui.small_button("x").clicked() {
+                            delete_index = Some(i);
+                        }
+                    });
+                }
+                if let Some(index) = go_to_index {
+                    model.world.go_to_target(index);
+                }
+                if let Some(index) = delete_index {
+                    model.world.targets.remove(index);
+                    model.tour = None;
+                }
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut model.new_target_name);
+                    if ui.button("Add Current Target").clicked() {
+                        let color = TARGET_PALETTE[model.world.targets.len() % TARGET_PALETTE.len()];
+                        model.world.targets.push(NamedTarget::new(&model.new_target_name, model.world.target(), color));
+                        model.tour = None;
+                    }
+                });
+                if ui.button("Plan Pickup Tour")
+                    .on_hover_text("visits every named target in a short order from the robot, found by nearest neighbor and 2-opt over the grid path lengths between them. replaces the via points")
+                    .clicked()
+                {
+                    model.tour = model.world.plan_tour(model.new_via_tolerance);
+                    tour_failed = model.tour.is_none() && !model.world.targets.is_empty();
+                }
+                if let Some((order, length)) = &model.tour {
+                    let names: Vec<&str> = order.iter()
+                        .filter_map(|&i| model.world.targets.get(i).map(|target| target.name.as_str()))
+                        .collect();
+                    ui.label(format!("Tour: {} ({:.2} m)", names.join(" → "), length));
+                }
+                
+                ui.separator();
+                ui.heading("Robot Movement");
+                
+                let mut velocity_changed = false;
+                let mut speed_changed = false;
+                
+                ui.horizontal(|ui| {
+                    ui.label("X Velocity:");
+                    if ui.add(egui::Slider::new(&mut model.robot_velocity_x, -5.0..=5.0).text("X")).changed() {
+                        velocity_changed = true;
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("Y Velocity:");
+                    if ui.add(egui::Slider::new(&mut model.robot_velocity_y, -5.0..=5.0).text("Y")).changed() {
+                        velocity_changed = true;
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("Target Speed:");
+                    if ui.add(egui::Slider::new(&mut model.robot_target_speed, 0.1..=10.0).text("Speed")).changed() {
+                        speed_changed = true;
+                    }
+                });
+                
+                if velocity_changed {
+                    if let Some(robot) = &mut model.world.robot {
+                        robot.set_velocity(model.robot_velocity_x, model.robot_velocity_y);
+                    }
+                }
+                
+                if speed_changed {
+                    if let Some(robot) = &mut model.world.robot {
+                        robot.set_target_speed(model.robot_target_speed);
+                    }
+                }
+                
+                if let Some(robot) = &mut model.world.robot {
+                    ui.add(egui::Slider::new(&mut robot.turn_rate, 0.5..=20.0).text("Turn Rate (rad/s)"))
+                        .on_hover_text("how fast the robot turns to face the direction it drives");
+                    ui.checkbox(&mut robot.limit_acceleration, "Limit Acceleration")
+                        .on_hover_text("ramp the velocity instead of changing it instantly");
+                    if robot.limit_acceleration {
+                        ui.add(egui::Slider::new(&mut robot.max_acceleration, 0.5..=20.0).text("Max Acceleration (m/s²)"));
+                        ui.add(egui::Slider::new(&mut robot.max_deceleration, 0.5..=20.0).text("Max Deceleration (m/s²)"));
+                    }
+                    
+                    let noise = &mut robot.noise;
+                    ui.checkbox(&mut noise.enabled, "Execution Noise")
+                        .on_hover_text("disturb the velocity the robot actually drives at, like wheel slip");
+                    if noise.enabled {
+                        ui.add(egui::Slider::new(&mut noise.velocity_std_dev, 0.0..=2.0).text("Noise Std Dev (m/s)"));
+                        ui.add(egui::Slider::new(&mut noise.scale_error, -0.5..=0.5).text("Velocity Scale Error"));
+                        ui.horizontal(|ui| {
+                            ui.label("Seed:");
+                            ui.add(egui::DragValue::new(&mut noise.seed));
+                            if ui.button("Restart Noise").on_hover_text("replay the same noise sequence from the seed").clicked() {
+                                noise.reseed();
+                            }
+                        });
+                    }
+                    
+                    egui::CollapsingHeader::new("Clearance Speed Limits").show(ui, |ui| {
+                        let limits = &mut robot.speed_limits;
+                        ui.checkbox(&mut limits.enabled, "Slow Down Near Obstacles")
+                            .on_hover_text("scales the speed along the path by its clearance to the hard obstacles, the follower drives the profile below");
+                        ui.add(egui::Slider::new(&mut limits.slow_clearance, 0.0..=1.0).text("Slow Clearance (m)"))
+                            .on_hover_text("at or below this clearance the robot drives at the minimum speed");
+                        ui.add(egui::Slider::new(&mut limits.fast_clearance, 0.1..=3.0).text("Fast Clearance (m)"))
+                            .on_hover_text("from this clearance on the robot drives at the target speed");
+                        ui.add(egui::Slider::new(&mut limits.min_fraction, 0.05..=1.0).text("Min Speed (fraction)"));
+                        ui.checkbox(&mut model.color_path_by_speed, "Color Path by Speed")
+                            .on_hover_text("draws the path from the path_slow color where the robot drives slowest to the path color at the target speed");
+                        ui.label("Speed along the path (gray the target speed):");
+                        plot_speed_profile(ui, robot, &[]);
+                    });
+                    
+                    egui::CollapsingHeader::new("Time-Optimal Re-Timing").show(ui, |ui| {
+                        ui.add(egui::Slider::new(&mut robot.max_lateral_acceleration, 0.5..=20.0).text("Max Lateral Acceleration (m/s²)"))
+                            .on_hover_text("limits the speed through curves to the root of this over the curvature");
+                        let (optimal, optimal_time) = robot.time_optimal_profile();
+                        let length = robot.speed_limits.profile.last().map_or(0.0, |sample| sample.distance);
+                        let constant_time = length / robot.target_speed.max(1e-3);
+                        ui.label(format!("Time-optimal: {:.2} s", optimal_time))
+                            .on_hover_text("speeding up and slowing down as hard as the acceleration limits allow, under the speed limits and the lateral acceleration, from and to a standstill");
+                        ui.label(format!("Constant speed estimate: {:.2} s ({:+.2} s)", constant_time, optimal_time - constant_time))
+                            .on_hover_text("the path length over the target speed");
+                        ui.label("Speed along the path (green the limit, orange time-optimal):");
+                        plot_speed_profile(ui, robot, &optimal);
+                    });
+                }
+                
+                egui::CollapsingHeader::new("Speed Zones").show(ui, |ui| {
+                    ui.label("Draw a zone with the Speed Zone placement mode, clicking two opposite corners.");
+                    ui.add(egui::Slider::new(&mut model.new_zone_speed, 0.1..=5.0).text("New Zone Speed (m/s)"));
+                    let mut delete_index = None;
+                    let mut new_speed = None;
+                    for (i, zone) in model.world.speed_zones().iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}: ({:.1}, {:.1}) to ({:.1}, {:.1})", i + 1, zone.min[0], zone.min[1], zone.max[0], zone.max[1]));
+                            let mut max_speed = zone.max_speed;
+                            if ui.add(egui::DragValue::new(&mut max_speed).speed(0.05).clamp_range(0.05..=10.0).suffix(" m/s")).changed() {
+                                new_speed = Some((i, max_speed));
+                            }
+                            if ui.small_button("x").clicked() {
+                                delete_index = Some(i);
+                            }
+                        });
+                    }
+                    if let Some((index, max_speed)) = new_speed {
+                        model.world.set_zone_speed(index, max_speed);
+                    }
+                    if let Some(index) = delete_index {
+                        model.world.remove_speed_zone(index);
+                    }
+                });
+                
+                ui.separator();
+                ui.heading("Path Settings");
+                
+                let mut planner_changed = false;
+                let mut gradient_planner = true;
+                if let Some(robot) = &mut model.world.robot {
+                    ui.label("Planner:");
+                    for planner in Planner::ALL {
+                        planner_changed |= ui.radio_value(&mut robot.planner, planner, planner.label()).changed();
+                    }
+                    gradient_planner = robot.planner == Planner::Gradient;
+                    match robot.planner {
+                        Planner::Gradient => {},
+                        Planner::Dubins | Planner::ReedsShepp => {
+                            planner_changed |= ui.add(egui::Slider::new(&mut robot.turn_radius, 0.1..=3.0).text("Turn Radius (m)"))
+                                .on_hover_text("smallest arc the path turns on, obstacles are passed on arcs at least this wide")
+                                .changed();
+                        },
+                        Planner::HybridAStar => {
+                            // the costmap is rebuilt by every hybrid a* plan
+                            planner_changed |= ui.add(egui::Slider::new(&mut robot.turn_radius, 0.1..=3.0).text("Turn Radius (m)"))
+                                .on_hover_text("smallest arc the path turns on, every step of the search drives an arc at least this wide")
+                                .changed();
+                            planner_changed |= ui.checkbox(&mut robot.hybrid_reversing, "Allow Reversing")
+                                .on_hover_text("let the search back up as well, at a higher cost and with a penalty for every change of direction")
+                                .changed();
+                            ui.checkbox(&mut model.show_costmap, "Show Costmap");
+                            ui.checkbox(&mut model.show_hybrid_expanded, "Show Expanded Nodes")
+                                .on_hover_text("the steps the search drove from every pose it expanded");
+                            if !robot.hybrid_expanded.is_empty() {
+                                ui.label(format!("Last plan expanded {} poses", robot.hybrid_expanded.len()));
+                            }
+                        },
+                        Planner::Prm => {
+                            planner_changed |= ui.add(egui::Slider::new(&mut robot.roadmap_samples, 50..=2000).logarithmic(true).text("Roadmap Samples"))
+                                .on_hover_text("random free points the roadmap connects, it is rebuilt when the obstacles change")
+                                .changed();
+                            if let Some((nodes, edges)) = robot.roadmap.as_ref().map(|roadmap| roadmap.size()) {
+                                ui.label(format!("Roadmap of {} samples and {} edges", nodes, edges));
+                            }
+                            ui.checkbox(&mut model.show_roadmap, "Show Roadmap");
+                            if model.show_roadmap {
+                                ui.checkbox(&mut model.animate_roadmap, "Animate Growth")
+                                    .on_hover_text("draw the roadmap edge by edge as it was connected, then the edges the search explored");
+                                if model.animate_roadmap {
+                                    ui.add(egui::Slider::new(&mut model.roadmap_growth_speed, 10.0..=5000.0).logarithmic(true).text("Growth Speed (edges/s)"));
+                                    if ui.button("Replay Growth").clicked() {
+                                        if let Some(roadmap) = &mut robot.roadmap {
+                                            roadmap.restart_growth();
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        Planner::Grid => {
+                            // the costmap is rebuilt by every grid plan
+                            ui.checkbox(&mut model.show_costmap, "Show Costmap");
+                            planner_changed |= ui.checkbox(&mut robot.any_angle, "Any-Angle (Theta*)")
+                                .on_hover_text("let the search cut straight across cells at any angle instead of straightening an 8 connected route afterwards")
+                                .changed();
+                        },
+                        Planner::Wavefront => {
+                            // the costmap is rebuilt by every wavefront plan as well
+                            ui.checkbox(&mut model.show_costmap, "Show Costmap");
+                            ui.checkbox(&mut model.show_wavefront, "Show Wavefront")
+                                .on_hover_text("cells the wave from the goal reached, fading with their distance from it");
+                            if model.show_wavefront {
+                                ui.checkbox(&mut model.animate_wavefront, "Animate Expansion")
+                                    .on_hover_text("draw the wave one step at a time as it spreads out from the goal");
+                                if model.animate_wavefront {
+                                    ui.add(egui::Slider::new(&mut model.wavefront_speed, 1.0..=500.0).logarithmic(true).text("Expansion Speed (waves/s)"));
+                                    if ui.button("Replay Expansion").clicked() {
+                                        if let Some(wavefront) = &mut robot.wavefront {
+                                            wavefront.restart();
+                                        }
+                                    }
+                                }
+                            }
+                            if let Some(wavefront) = &robot.wavefront {
+                                ui.label(format!("{} waves", wavefront.last_wave + 1));
+                            }
+                        },
+                        Planner::DStarLite => {
+                            // the costmap is rebuilt by every d* lite plan, the search is repaired where it changed
+                            ui.checkbox(&mut model.show_costmap, "Show Costmap");
+                            ui.checkbox(&mut model.replan_moving_obstacles, "Re-plan As Obstacles Move")
+                                .on_hover_text("plan again from the robot every step while it follows the path and obstacles move on their trajectories");
+                            if let Some(search) = robot.dstar.first() {
+                                let expanded: usize = robot.dstar.iter().map(|search| search.expanded).sum();
+                                ui.label(format!("Last plan expanded {} cells, {} plans on this search", expanded, search.plans));
+                            }
+                        },
+                        Planner::SpaceTime => {
+                            planner_changed |= ui.add(egui::Slider::new(&mut robot.deadline, 1.0..=60.0).text("Deadline (s)"))
+                                .on_hover_text("seconds after leaving the start the robot must be at the target by")
+                                .changed();
+                            planner_changed |= ui.add(egui::Slider::new(&mut robot.space_time_resolution, 0.05..=0.5).logarithmic(true).text("Space-Time Resolution (m)"))
+                                .on_hover_text("cell size of the grid searched over time, every step drives one cell at the target speed or waits for as long")
+                                .changed();
+                            if let Some(arrival) = robot.timed_arrival {
+                                let verdict = if arrival <= robot.deadline { "deadline met" } else { "deadline missed" };
+                                ui.label(format!("Arrives after {:.1} s, {}", arrival, verdict));
+                            }
+                        },
+                        Planner::Harmonic => {
+                            planner_changed |= ui.add(egui::Slider::new(&mut robot.harmonic_resolution, 0.05..=0.5).logarithmic(true).text("Harmonic Resolution (m)"))
+                                .on_hover_text("cell size of the grid the potential is solved over, finer grids fit through narrower gaps but take longer to solve")
+                                .changed();
+                            ui.checkbox(&mut model.show_harmonic_field, "Show Harmonic Field");
+                            if let Some(harmonic) = &robot.harmonic {
+                                let state = if harmonic.converged { "converged" } else { "stopped" };
+                                ui.label(format!("Solved in {} sweeps, {}", harmonic.sweeps, state));
+                            }
+                        },
+                    }
+                    refresh_distance_field |= ui.checkbox(&mut robot.use_distance_field, "Distance Field Clearance")
+                        .on_hover_text("read clearance from the distance field grid instead of checking every obstacle, faster with many obstacles and accurate to half a cell")
+                        .changed();
+                }
+                if planner_changed {
+                    model.world.invalidate_path();
+                }
+                
+                ui.checkbox(&mut model.show_path, "Show Path");
+                ui.checkbox(&mut model.show_initial_path, "Show Initial Path")
+                    .on_hover_text("the straight line the path was seeded with");
+                ui.checkbox(&mut model.show_optimized_path, "Show Optimized Points")
+                    .on_hover_text("the control polygon after optimization, before it is smoothed into the spline");
+                
+                let mut pipeline_changed = false;
+                if gradient_planner {
+                    ui.collapsing("Pipeline", |ui| {
+                        let Some(robot) = &mut model.world.robot else {
+                            ui.label("No robot to plan for");
+                            return;
+                        };
+                        let pipeline = &mut robot.pipeline;
+                            
+                        for (i, stage) in PipelineStage::ALL.into_iter().enumerate() {
+                            let title = format!("{}. {}", i + 1, stage.label());
+                            let enabled = match pipeline.enabled_mut(stage) {
+                                Some(enabled) => {
+                                    pipeline_changed |= ui.checkbox(enabled, title).on_hover_text(stage.description()).changed();
+                                    *enabled
+                                },
+                                None => {
+                                    ui.label(title).on_hover_text(stage.description());
+                                    true
+                                }
+                            };
+                            if !enabled {
+                                continue;
+                            }
+                                
+                            match stage {
+                                PipelineStage::Seed => {
+                                    pipeline_changed |= ui.add(egui::Slider::new(&mut model.path_segments, 5..=200).text("Path Segments")).changed();
+                                },
+                                PipelineStage::Optimize => {
+                                    pipeline_changed |= ui.add(egui::Slider::new(&mut pipeline.max_iterations, 10..=10000).logarithmic(true).text("Max Iterations")).changed();
+                                    pipeline_changed |= ui.add(egui::Slider::new(&mut pipeline.optimization_threshold, 0.0001..=0.1).logarithmic(true).text("Height Threshold"))
+                                        .on_hover_text("points higher than this on the obstacle field keep moving")
+                                        .changed();
+                                    pipeline_changed |= ui.add(egui::Slider::new(&mut pipeline.soft_iterations, 0..=2000).text("Soft Iterations"))
+                                        .on_hover_text("iterations spent moving the path off soft obstacles once it is clear of the hard ones")
+                                        .changed();
+                                    ui.horizontal(|ui| {
+                                        ui.label("Overlapping Fields:").on_hover_text("how the fields of overlapping obstacles add up, for the path heights, the optimization and the overlay");
+                                        let mut combination = obstacle::field_combination();
+                                        for option in FieldCombination::ALL {
+                                            if ui.radio_value(&mut combination, option, option.label()).changed() {
+                                                obstacle::set_field_combination(combination);
+                                                pipeline_changed = true;
+                                                update_gradient_field = true;
+                                            }
+                                        }
+                                    });
+                                    let stepping = robot.stepped_optimization.is_some();
+                                    ui.collapsing("Advanced", |ui| {
+                                        let mut changed = ui.add(egui::Slider::new(&mut pipeline.learning_rate, 0.00001..=0.01).logarithmic(true).text("Learning Rate"))
+                                            .on_hover_text("scale of each gradient step, points overshoot when it is too high and need many iterations when it is too low")
+                                            .changed();
+                                        changed |= ui.add(egui::Slider::new(&mut pipeline.push_strength, 0.0..=2.0).text("Push Strength (m)"))
+                                            .on_hover_text("how far a point closer than the safe margin to an obstacle is moved each iteration")
+                                            .changed();
+                                        // a stepped optimization carries on with the new values
+                                        pipeline_changed |= changed && !stepping;
+                                    });
+                                    pipeline_changed |= ui.checkbox(&mut pipeline.step_through, "Step Through")
+                                        .on_hover_text("plan the seed, then run the optimization one iteration at a time")
+                                        .changed();
+                                    if pipeline.step_through {
+                                        match robot.stepped_optimization {
+                                            Some(stepped) => {
+                                                ui.label(format!("Iteration {} of at most {}", stepped.iterations, pipeline.max_iterations));
+                                                ui.horizontal(|ui| {
+                                                    if ui.button("Step").clicked() {
+                                                        optimization_steps = Some(1);
+                                                    }
+                                                    if ui.button("Step 10").clicked() {
+                                                        optimization_steps = Some(10);
+                                                    }
+                                                    if ui.button("Finish").clicked() {
+                                                        optimization_steps = Some(usize::MAX);
+                                                    }
+                                                });
+                                                ui.checkbox(&mut model.play_optimization, "Play");
+                                                ui.add(egui::Slider::new(&mut model.optimization_steps_per_frame, 1..=100).text("Steps per Frame"));
+                                            },
+                                            None => { ui.label("Optimization finished, generate the path to step through it again"); },
+                                        }
+                                        
+                                        ui.collapsing("Breakpoints", |ui| {
+                                            let mut removed = None;
+                                            for (i, breakpoint) in model.breakpoints.iter_mut().enumerate() {
+                                                ui.horizontal(|ui| {
+                                                    ui.checkbox(&mut breakpoint.enabled, breakpoint.condition.label());
+                                                    match &mut breakpoint.condition {
+                                                        BreakCondition::Iteration(at) => { ui.add(egui::DragValue::new(at).clamp_range(1..=100000)); },
+                                                        BreakCondition::HeightAbove(limit) => { ui.add(egui::DragValue::new(limit).speed(0.01).clamp_range(0.0..=100.0)); },
+                                                        BreakCondition::InsideObstacle => {},
+                                                    }
+                                                    if ui.small_button("x").on_hover_text("Remove").clicked() {
+                                                        removed = Some(i);
+                                                    }
+                                                });
+                                            }
+                                            if let Some(i) = removed {
+                                                model.breakpoints.remove(i);
+                                            }
+                                            
+                                            ui.horizontal(|ui| {
+                                                egui::ComboBox::from_id_source("break_condition")
+                                                    .selected_text(model.new_break_condition.label())
+                                                    .show_ui(ui, |ui| {
+                                                        for condition in BreakCondition::ALL {
+                                                            ui.selectable_value(&mut model.new_break_condition, condition, condition.label());
+                                                        }
+                                                    });
+                                                if ui.button("Add").clicked() {
+                                                    model.breakpoints.push(Breakpoint { condition: model.new_break_condition, enabled: true });
+                                                }
+                                            });
+                                            
+                                            if let Some(hit) = &model.breakpoint_hit {
+                                                ui.colored_label(egui::Color32::RED, format!("Paused at iteration {}: {}", hit.iteration, hit.reason));
+                                                for &(index, height) in &hit.obstacles {
+                                                    let name = model.world.obstacles.get(index).map_or("?", |obstacle| obstacle.name.as_str());
+                                                    ui.label(format!("{}: {:.3}", name, height));
+                                                }
+                                            }
+                                        });
+                                    }
+                                },
+                                PipelineStage::Clean => {
+                                    pipeline_changed |= ui.add(egui::Slider::new(&mut pipeline.clean_spacing, 0.5..=3.0).text("Spacing (seed steps)")).changed();
+                                    pipeline_changed |= ui.add(egui::Slider::new(&mut pipeline.clean_min_points, 3..=50).text("Min Points")).changed();
+                                },
+                                PipelineStage::Prune => {
+                                    pipeline_changed |= ui.add(egui::Slider::new(&mut pipeline.prune_distance, 0.05..=1.0).text("Distance (robot sizes)")).changed();
+                                    ui.checkbox(&mut model.show_pruned_points, "Show Pruned Points");
+                                },
+                                PipelineStage::Smooth => {},
+                            }
+                        }
+                    });
+                }
+                if pipeline_changed {
+                    model.world.invalidate_path();
+                }
+                
+                ui.horizontal(|ui| {
+                    if ui.button("Generate Path").clicked() {
+                        model.world.invalidate_path();
+                    }
+                    
+                    if ui.button("Follow Path").clicked() {
+                        start_following = true;
+                    }
+                });
+                
+                if let Some(robot) = &mut model.world.robot {
+                    ui.horizontal(|ui| {
+                        ui.label("Follower:");
+                        ui.radio_value(&mut robot.follower_mode, FollowerMode::OpenLoop, "Open Loop");
+                        ui.radio_value(&mut robot.follower_mode, FollowerMode::Pid, "PID");
+                    });
+                    
+                    if robot.follower_mode == FollowerMode::Pid {
+                        egui::Grid::new("pid_gains").show(ui, |ui| {
+                            ui.label("");
+                            ui.label("Kp");
+                            ui.label("Ki");
+                            ui.label("Kd");
+                            ui.end_row();
+                            
+                            for (name, pid) in [("Along-track", &mut robot.pid.along), ("Cross-track", &mut robot.pid.cross)] {
+                                ui.label(name);
+                                ui.add(egui::DragValue::new(&mut pid.kp).speed(0.05).clamp_range(0.0..=50.0));
+                                ui.add(egui::DragValue::new(&mut pid.ki).speed(0.05).clamp_range(0.0..=50.0));
+                                ui.add(egui::DragValue::new(&mut pid.kd).speed(0.01).clamp_range(0.0..=10.0));
+                                ui.end_row();
+                            }
+                        });
+                        
+                        ui.label("Errors (blue along-track, orange cross-track):");
+                        plot_errors(ui, &robot.pid.history);
+                    }
+                    
+                    egui::CollapsingHeader::new("Local Avoidance").show(ui, |ui| {
+                        let avoidance = &mut robot.local_avoidance;
+                        ui.checkbox(&mut avoidance.enabled, "Avoid Moving Obstacles")
+                            .on_hover_text("changes the follower's velocity to stay out of the velocity obstacles of obstacles on trajectories, the planned path is kept");
+                        ui.add(egui::Slider::new(&mut avoidance.time_horizon, 0.5..=5.0).text("Time Horizon (s)"))
+                            .on_hover_text("collisions further ahead than this do not change the velocity");
+                        ui.add(egui::Slider::new(&mut avoidance.tracking_gain, 0.0..=5.0).text("Path Tracking"))
+                            .on_hover_text("how hard the robot is pulled back to the path after avoiding");
+                        if avoidance.enabled && robot.follow_path {
+                            let status = match (avoidance.is_avoiding(), avoidance.lagging) {
+                                (true, _) => "avoiding a moving obstacle",
+                                (false, true) => "catching up with the path",
+                                (false, false) => "tracking the path",
+                            };
+                            ui.label(format!("Status: {}", status));
+                        }
+                    });
+                    
+                    let point_count = robot.path_points.len();
+                    let locked_count = robot.path_points.iter().filter(|point| point.locked).count();
+                    ui.label(format!("{} of {} path points locked", locked_count, point_count));
+                    
+                    ui.horizontal(|ui| {
+                        if ui.button("Re-optimize Path").on_hover_text("optimizes the current path again, keeping locked points").clicked() {
+                            reoptimize = true;
+                        }
+                        if ui.button("Unlock All").clicked() {
+                            robot.unlock_all_path_points();
+                        }
+                    });
+                    
+                    if let Some(index) = model.inspected_path_point.filter(|&index| index < point_count) {
+                        ui.collapsing("Point Cost", |ui| {
+                            let contributions = robot.field_contributions(index, &model.world.obstacles);
+                            let total: f32 = contributions.iter().map(|&(_, height)| height).sum();
+                            ui.label(format!("Point {}: height {:.3} from {} obstacle(s)", index, total, contributions.len()));
+                            for (rank, &(obstacle, height)) in contributions.iter().enumerate() {
+                                let name = model.world.obstacles.get(obstacle).map_or("?", |obstacle| obstacle.name.as_str());
+                                let text = format!("{}: {:.3} ({:.0}%)", name, height, 100.0 * height / total.max(f32::EPSILON));
+                                if rank == 0 {
+                                    ui.colored_label(egui::Color32::YELLOW, format!("{}, dominant", text));
+                                } else {
+                                    ui.label(text);
+                                }
+                            }
+                            if ui.button("Stop Inspecting").clicked() {
+                                model.inspected_path_point = None;
+                            }
+                        });
+                    }
+                    
+                    egui::CollapsingHeader::new("Path Points").show(ui, |ui| {
+                        egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                            // the start and end points are always fixed
+                            for i in 1..point_count.saturating_sub(1) {
+                                let point = &mut robot.path_points[i];
+                                let mut locked = point.locked;
+                                let label = format!("Point {} ({:.2}, {:.2})", i, point.position.x, point.position.y);
+                                let lock_changed = ui.horizontal(|ui| {
+                                    ui.checkbox(&mut point.reversed, "Reverse").on_hover_text("drive backwards on the way to this point");
+                                    let inspected = model.inspected_path_point == Some(i);
+                                    if ui.selectable_label(inspected, "Cost").on_hover_text("show the obstacles this point's height comes from").clicked() {
+                                        model.inspected_path_point = if inspected { None } else { Some(i) };
+                                    }
+                                    ui.checkbox(&mut locked, label).changed()
+                                }).inner;
+                                if lock_changed {
+                                    robot.set_path_point_locked(i, locked);
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+            
+            ui.collapsing("Team Robots", |ui| {
+                ui.label("More robots with their own targets, planned after the main robot.");
+                egui::ComboBox::from_label("Coordination")
+                    .selected_text(model.coordination_mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in CoordinationMode::ALL {
+                            if ui.selectable_value(&mut model.coordination_mode, mode, mode.label()).changed() {
+                                model.world.invalidate_path();
+                            }
+                        }
+                    });
+                
+                let mut team_changed = false;
+                let mut member_to_remove = None;
+                for (i, member) in model.world.team.iter_mut().enumerate() {
+                    ui.separator();
+                    let [r, g, b] = [member.color.red, member.color.green, member.color.blue];
+                    ui.colored_label(egui::Color32::from_rgb(r, g, b), &member.name);
+                    
+                    let mut start = member.robot.model.config.position;
+                    ui.horizontal(|ui| {
+                        ui.label("Start X:");
+                        let x_changed = ui.add(egui::DragValue::new(&mut start.x).speed(0.1)).changed();
+                        ui.label("Y:");
+                        let y_changed = ui.add(egui::DragValue::new(&mut start.y).speed(0.1)).changed();
+                        if x_changed || y_changed {
+                            member.robot.model.move_to(start);
+                            team_changed = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Target X:");
+                        team_changed |= ui.add(egui::DragValue::new(&mut member.target.position.x).speed(0.1)).changed();
+                        ui.label("Y:");
+                        team_changed |= ui.add(egui::DragValue::new(&mut member.target.position.y).speed(0.1)).changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Speed:");
+                        team_changed |= ui.add(egui::DragValue::new(&mut member.robot.target_speed).speed(0.05).clamp_range(0.1..=5.0).suffix(" m/s")).changed();
+                        if ui.button("Remove").clicked() {
+                            member_to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = member_to_remove {
+                    model.world.team.remove(i);
+                    team_changed = true;
+                }
+                if team_changed {
+                    model.world.invalidate_path();
+                }
+                
+                ui.separator();
+                if ui.button("Add Robot").clicked() {
+                    add_team_robot = true;
+                }
+                
+                if !model.world.team.is_empty() {
+                    if model.robot_conflicts.is_empty() {
+                        ui.label("No robots meet on their paths");
+                    }
+                    for conflict in &model.robot_conflicts {
+                        ui.colored_label(egui::Color32::RED, format!("{} and {} meet {:.1}s after leaving", conflict.robots.0, conflict.robots.1, conflict.time));
+                    }
+                }
+            });
+            
+            ui.separator();
+            ui.label("Controls:");
+            if model.wasd_drives_robot {
+                ui.label("Camera movement keys drive the robot");
+            }
+            for action in Action::ALL {
+                if model.key_bindings.key(action).is_some() {
+                    ui.label(format!("{} - {}", model.key_bindings.describe(action), action.label()));
+                }
+            }
+            ui.label("F1-F9 - Camera Bookmarks");
+        });
+    model.panel_width = panel.response.rect.width();
+    drop(ctx);
+    
+    if let Some(index) = bookmark_to_open {
+        model.go_to_bookmark(index);
+    }
+    if let Some(config) = obstacle_to_add {
+        model.add_obstacle(config);
+    }
+    if refresh_entity_preview {
+        model.refresh_entity_preview();
+    }
+    if add_entity {
+        model.add_entity();
+    }
+    if record_run {
+        model.record_run();
+    }
+    if generate_scene {
+        model.generate_random_scene();
+    }
+    if run_evaluation {
+        model.run_evaluation();
+    }
+    if let Some(layout) = field_layout_to_load {
+        model.load_field_layout(&layout);
+    }
+    if let Some(scene) = scene_to_load {
+        model.load_scene(&scene);
+    }
+    if reoptimize {
+        model.reoptimize_path();
+    }
+    if let Some(e) = path_import_error {
+        model.report_error(e);
+    }
+    if let Some(point) = go_to_clearance_point {
+        model.go_to_point(point);
+    }
+    if frame_scene {
+        model.frame_scene();
+    }
+    if let Some(colors) = colors_to_set {
+        model.set_colors(colors);
+    }
+    if tour_failed {
+        model.report_error(String::from("Pickup tour: a named target can not be reached from the robot"));
+    }
+    if let Some(index) = dismissed_error {
+        model.errors.remove(index);
+    }
+    if let Some((name, center, rotation)) = group_transform {
+        model.transform_group(&name, center, rotation);
+    }
+    if let Some(name) = group_to_dissolve {
+        model.ungroup(&name);
+    }
+    if update_gradient_field {
+        model.update_gradient_field();
+    }
+    if let Some(steps) = optimization_steps {
+        model.step_optimization(steps);
+    }
+    if start_following {
+        model.start_following();
+    }
+    if start_remote {
+        model.start_remote_server();
+    }
+    if run_script {
+        model.run_script();
+    }
+    if record_session {
+        model.start_session_recording();
+    }
+    if replay_session {
+        model.start_session_replay();
+    }
+    if step_replay {
+        model.step_session_replay();
+    }
+    if add_team_robot {
+        model.add_team_robot();
+    }
+    if refresh_distance_field {
+        model.refresh_distance_field();
+    }
+    if refresh_costmap {
+        model.refresh_costmap();
+        if model.world.robot.as_ref().is_some_and(|robot| robot.planner.uses_costmap()) {
+            model.world.invalidate_path();
+        }
+    }
+    match restore_autosave {
+        Some(true) => {
+            if let Some(scene) = model.autosave.recovered.take() {
+                model.load_scene(&scene);
+            }
+        },
+        Some(false) => { model.autosave.recovered = None; },
+        None => {},
+    }
+    if save_scene {
+        match scene::save(&model.scene(), &model.scene_path) {
+            Ok(()) => println!("Saved scene to {}", model.scene_path),
+            Err(e) => eprintln!("Failed to save scene to {}: {}", model.scene_path, e),
+        }
+    }
+    model.dispatch_world_events();
+    
+    if let (Some(index), Some(cursor)) = (model.dragging_path_point, model.cursor_field_position) {
+        let cursor = model.snap_position(cursor);
+        if let Some(robot) = &mut model.world.robot {
+            robot.move_path_point(index, cursor, &model.world.obstacles);
+        }
+    }
+    
+    if let Some(transition) = &mut model.camera_transition {
+        let pose = transition.step(update.since_last.as_secs_f32());
+        let finished = transition.is_finished();
+        model.set_camera_pose(pose);
+        model.camera_motion.stop();
+        if finished {
+            model.camera_transition = None;
+        }
+    } else if model.follow_robot {
+        update_follow_camera(model, update.since_last.as_secs_f32());
+        model.camera_motion.stop();
+    } else {
+        move_camera_manually(app, model, &update);
+    }
+    model.constrain_camera();
+    
+    if !model.egui.ctx().wants_keyboard_input() {
+        teleop_robot(app, model);
+    }
+    
+    if !model.egui.ctx().wants_keyboard_input() && model.key_bindings.is_down(Action::FollowPath, app) { 
+        if let Some(robot) = &mut model.world.robot {
+            robot.follow_path();
+        } 
+    }
+}
+
+fn move_camera_manually(app: &App, model: &mut AppModel, update: &Update) {
+    let dt = update.since_last.as_secs_f32();
+    let camera_keys = !model.wasd_drives_robot;
+    let down = |action: Action| model.key_bindings.is_down(action, app);
+    let axis = |positive: Action, negative: Action| down(positive) as i32 as f32 - down(negative) as i32 as f32;
+    
+    let (forward, left) = if camera_keys {
+        (axis(Action::CameraForward, Action::CameraBack), axis(Action::CameraLeft, Action::CameraRight))
+    } else {
+        (0.0, 0.0)
+    };
+    let (sin, cos) = model.direction.sin_cos();
+    let velocity = [
+        (forward * cos - left * sin) * model.camera_speed,
+        (forward * sin + left * cos) * model.camera_speed,
+        axis(Action::CameraUp, Action::CameraDown) * model.camera_speed,
+    ];
+    let turn = [
+        axis(Action::RotateLeft, Action::RotateRight) * model.rotation_speed,
+        axis(Action::RotateUp, Action::RotateDown) * model.rotation_speed,
+    ];
+    
+    model.camera_motion.step(velocity, turn, model.camera_damping, dt);
+    let motion = model.camera_motion;
+    model.camera_position.move_by(motion.velocity[0] * dt, motion.velocity[1] * dt, motion.velocity[2] * dt);
+    model.direction += motion.turn[0] * dt;
+    model.rotation_y += motion.turn[1] * dt;
+}
+
+/// drives the robot with its teleop keys, or the camera movement keys when they are focused on the robot,
+/// relative to the camera view
+fn teleop_robot(app: &App, model: &mut AppModel) {
+    let down = |action: Action| model.key_bindings.is_down(action, app);
+    let camera = model.wasd_drives_robot;
+    let pressed = |robot_action: Action, camera_action: Action| down(robot_action) || (camera && down(camera_action));
+    
+    let forward = pressed(Action::RobotForward, Action::CameraForward) as i32 - pressed(Action::RobotBack, Action::CameraBack) as i32;
+    let left = pressed(Action::RobotLeft, Action::CameraLeft) as i32 - pressed(Action::RobotRight, Action::CameraRight) as i32;
+    
+    let Some(robot) = &mut model.world.robot else { return; };
+    
+    if forward == 0 && left == 0 {
+        // stop once when the keys are released so the velocity sliders keep working otherwise
+        if model.teleop_active {
+            robot.set_velocity(0.0, 0.0);
+            model.teleop_active = false;
+        }
+        return;
+    }
+    
+    let (sin, cos) = model.direction.sin_cos();
+    let x = forward as f32 * cos - left as f32 * sin;
+    let y = forward as f32 * sin + left as f32 * cos;
+    let length = (x * x + y * y).sqrt();
+    
+    robot.follow_path = false;
+    robot.set_velocity(x / length * model.robot_target_speed, y / length * model.robot_target_speed);
+    model.teleop_active = true;
+}
+
+/// eases the camera toward a chase position behind the robot, looking down at it
+fn update_follow_camera(model: &mut AppModel, dt: f32) {
+    let Some(robot) = &model.world.robot else { return; };
+    let robot_position = robot.model.config.position;
+    
+    // only update the heading while moving so the camera does not spin when the robot stops
+    let speed = (robot.velocity_x * robot.velocity_x + robot.velocity_y * robot.velocity_y).sqrt();
+    if speed > FOLLOW_MIN_HEADING_SPEED {
+        model.follow_heading = robot.velocity_y.atan2(robot.velocity_x);
+    }
+    
+    let target_position = Position::new(
+        robot_position.x - model.follow_distance * model.follow_heading.cos(),
+        robot_position.y - model.follow_distance * model.follow_heading.sin(),
+        robot_position.z + model.follow_height,
+    );
+    
+    // exponential smoothing, a smoothing of 0 snaps straight to the target
+    let blend = if model.follow_smoothing <= 0.0 { 1.0 } else { 1.0 - (-dt / model.follow_smoothing).exp() };
+    
+    model.camera_position.move_by(
+        (target_position.x - model.camera_position.x) * blend,
+        (target_position.y - model.camera_position.y) * blend,
+        (target_position.z - model.camera_position.z) * blend,
+    );
+    
+    let dx = robot_position.x - model.camera_position.x;
+    let dy = robot_position.y - model.camera_position.y;
+    let dz = robot_position.z - model.camera_position.z;
+    let target_direction = dy.atan2(dx);
+    let target_rotation_y = dz.atan2((dx * dx + dy * dy).sqrt()); // negative when looking down
+    
+    model.direction += wrap_angle(target_direction - model.direction) * blend;
+    model.rotation_y += (target_rotation_y - model.rotation_y) * blend;
+}
+
+fn key_pressed(app: &App, model: &mut AppModel, key: Key) {
+    if model.egui.ctx().wants_keyboard_input() {
+        return; // typing into a text field
+    }
+    
+    // the settings window is waiting for a key to bind
+    if let Some(action) = model.rebinding_action.take() {
+        if keybindings::is_bindable(key) {
+            model.key_bindings.bind(action, Some(key));
+        }
+        return;
+    }
+    
+    match model.key_bindings.action(key) {
+        Some(Action::ToggleFullscreen) => {
+            model.fullscreen = !model.fullscreen;
+            app.main_window().set_fullscreen(model.fullscreen);
+        },
+        Some(Action::ToggleFollowRobot) => { model.follow_robot = !model.follow_robot; },
+        Some(Action::ToggleMoveFocus) => { model.wasd_drives_robot = !model.wasd_drives_robot; },
+        Some(Action::GeneratePath) => { model.world.invalidate_path(); },
+        Some(Action::ToggleGradient) => {
+            model.show_gradient_function = !model.show_gradient_function;
+            if model.show_gradient_function {
+                model.update_gradient_field();
+            }
+        },
+        Some(Action::ToggleMinimap) => { model.show_minimap = !model.show_minimap; },
+        Some(Action::ToggleLabels) => { model.show_labels = !model.show_labels; },
+        Some(Action::ToggleGrid) => { model.show_grid = !model.show_grid; },
+        Some(Action::FrameScene) => { model.frame_scene(); },
+        Some(Action::Screenshot) => { save_screenshot(app, &model.screenshot_directory); },
+        _ => {},
+    }
+    
+    let bookmark_keys = [Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9];
+    if let Some(index) = bookmark_keys.iter().position(|k| *k == key) {
+        model.go_to_bookmark(index);
+    }
+    
+    // number keys not bound to an action send the target to a named target
+    let target_keys = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
+    if let Some(index) = target_keys.iter().position(|k| *k == key) {
+        if model.key_bindings.action(key).is_none() {
+            model.world.go_to_target(index);
+        }
+    }
+}
+
+fn mouse_pressed(app: &App, model: &mut AppModel, button: MouseButton) {
+    if model.egui.ctx().is_pointer_over_area() {
+        return;
+    }
+    
+    // right click toggles whether the path point under the cursor is locked
+    if button == MouseButton::Right && model.click_action == ClickAction::EditPath {
+        let projection = Projection::from_window(app.window_rect(), model.fov);
+        if let Some(index) = pick_path_point(app.mouse.position(), model, &projection) {
+            if let Some(robot) = &mut model.world.robot {
+                let locked = robot.path_points[index].locked;
+                robot.set_path_point_locked(index, !locked);
+            }
+        }
+        return;
+    }
+    
+    if button != MouseButton::Left {
+        return;
+    }
+    
+    let Some(cursor) = model.cursor_field_position else { return; };
+    let cursor = model.snap_position(cursor);
+    
+    match model.click_action {
+        ClickAction::None => {},
+        ClickAction::PlaceObstacle => {
+            let position = Position::new(cursor.x, cursor.y, model.new_obstacle_position.z);
+            model.add_obstacle(model.new_obstacle_config(position));
+        },
+        ClickAction::MoveTarget => {
+            model.world.set_target(Position::new(cursor.x, cursor.y, 0.0));
+        },
+        ClickAction::Measure => {
+            if model.ruler_start.is_none() || model.ruler_end.is_some() {
+                model.ruler_start = Some(cursor);
+                model.ruler_end = None;
+            } else {
+                model.ruler_end = Some(cursor);
+            }
+        },
+        ClickAction::AddViaPoint => {
+            if let Some(robot) = &mut model.world.robot {
+                robot.via_points.push(ViaPoint::new(Position::new(cursor.x, cursor.y, 0.0), model.new_via_tolerance));
+                model.world.invalidate_path();
+            }
+        },
+        ClickAction::AddPathEvent => {
+            if let Some(robot) = &mut model.world.robot {
+                robot.events.push(PathEvent::new(Position::new(cursor.x, cursor.y, 0.0), model.new_event_action.clone()));
+                robot.update_event_progress();
+                robot.reset_events();
+            }
+        },
+        ClickAction::CrossSection => {
+            let position = Position::new(cursor.x, cursor.y, 0.0);
+            if model.section_start.is_none() || model.section_end.is_some() {
+                model.section_start = Some(position);
+                model.section_end = None;
+            } else {
+                model.section_end = Some(position);
+            }
+        },
+        ClickAction::Probe => {
+            model.probes.push(Position::new(cursor.x, cursor.y, 0.0));
+        },
+        ClickAction::SpeedZone => {
+            match model.zone_corner.take() {
+                None => { model.zone_corner = Some(Position::new(cursor.x, cursor.y, 0.0)); },
+                Some(corner) => {
+                    model.world.add_speed_zone(SpeedZone::from_corners([corner.x, corner.y], [cursor.x, cursor.y], model.new_zone_speed));
+                },
+            }
+        },
+        ClickAction::EditPath => {
+            let projection = Projection::from_window(app.window_rect(), model.fov);
+            model.dragging_path_point = pick_path_point(app.mouse.position(), model, &projection);
+            if model.dragging_path_point.is_some() {
+                model.inspected_path_point = model.dragging_path_point;
+            }
+        },
+    }
+}
+
+fn mouse_released(_app: &App, model: &mut AppModel, button: MouseButton) {
+    if button != MouseButton::Left {
+        return;
+    }
+    
+    // re-fit the rest of the path around the point that was just placed
+    if let Some(index) = model.dragging_path_point.take() {
+        model.log_path_point_move(index);
+        model.reoptimize_path();
+    }
+}
+
+/// saves the next frame, including the ui, as a png in the directory
+fn save_screenshot(app: &App, directory: &str) {
+    match capture::screenshot_path(directory) {
+        Ok(path) => {
+            println!("Saving screenshot to {}", path.display());
+            app.main_window().capture_frame(path);
+        },
+        Err(e) => eprintln!("Failed to save screenshot to {}: {}", directory, e),
+    }
+}
+
+/// a dropped mesh file is added at the field center as a model, or as an obstacle while shift is held
+fn dropped_file(app: &App, model: &mut AppModel, path: std::path::PathBuf) {
+    if !model::is_mesh_file(&path) {
+        eprintln!("Cannot load {}: only STL and OBJ files can be dropped", path.display());
+        return;
+    }
+    
+    let center = Position::new(field_length() / 2.0, field_width() / 2.0, 0.0);
+    
+    if app.keys.mods.shift() {
+        let config = ModelConfig {
+            name: path.to_string_lossy().into_owned(),
+            position: center,
+            scale: model.new_obstacle_radius * 2.0, // scale is diameter
+        };
+        model.add_obstacle(config);
+    } else {
+        let config = ModelConfig {
+            name: path.to_string_lossy().into_owned(),
+            position: center,
+            scale: model.new_model_scale,
+        };
+        model.add_model(config);
+    }
+}
+
+/// index of the path point handle under the cursor, the start and end points cannot be dragged
+fn pick_path_point(mouse: Vec2, model: &AppModel, projection: &Projection) -> Option<usize> {
+    let robot = model.world.robot.as_ref()?;
+    let last = robot.path_points.len().checked_sub(1)?;
+    
+    robot.path_points.iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 0 && *i != last)
+        .filter_map(|(i, point)| {
+            let screen = project_point(point.position, model, projection)?;
+            Some((i, screen.distance(mouse)))
+        })
+        .filter(|(_, distance)| *distance <= PATH_HANDLE_PICK_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// the main window with every event handler, sized from the command line
+fn window_builder<'a>(app: &'a App, cli: &Cli) -> nannou::window::Builder<'a> {
+    let (window_width, window_height) = cli.window.unwrap_or((SCREENWIDTH + 250, SCREENHEIGHT));
+    
+    app
+        .new_window()
+        .size(window_width, window_height)
+        .msaa_samples(MSAA_SAMPLES)
+        .view(view)
+        .key_pressed(key_pressed)
+        .mouse_pressed(mouse_pressed)
+        .mouse_released(mouse_released)
+        .dropped_file(dropped_file)
+        .raw_event(|_app: &App, model: &mut AppModel, event: &nannou::winit::event::WindowEvent| model.egui.handle_raw_event(event))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn model(app: &App) -> AppModel {
+    let cli = Cli::parse();
+    let window_id = window_builder(app, &cli).build().unwrap();
+    setup(app, cli, window_id)
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn model_async(app: &App) -> AppModel {
+    let cli = Cli::parse();
+    let window_id = window_builder(app, &cli).build_async().await.unwrap();
+    setup(app, cli, window_id)
+}
+
+/// the app state around a built window
+fn setup(app: &App, cli: Cli, window_id: window::Id) -> AppModel {
+    let window = app.window(window_id).unwrap();
+    
+    let egui = Egui::from_window(&window);
+    
+    // shown in a dialog once the app is built
+    let mut robot_error = None;
+    let robot = match Robot::create_default() {
+        Ok(mut robot) => {
+            robot.planner = cli.planner;
+            Some(robot)
+        },
+        Err(e) => {
+            robot_error = Some(format!("Failed to load robot model: {}", e));
+            None
+        }
+    };
+    
+    // saved settings also set the gradient field resolution, otherwise keep the coarse startup overlay
+    let (mut settings, gradient_resolution) = match Settings::load(SETTINGS_PATH) {
+        Ok(settings) => {
+            let resolution = (settings.gradient_x_resolution, settings.gradient_y_resolution, settings.gradient_line_resolution);
+            (settings, resolution)
+        },
+        Err(e) => {
+            println!("Using default settings: {}", e);
+            (Settings::default(), (3.0, 3.0, 0.12))
+        }
+    };
+    if let Some(segments) = cli.segments {
+        settings.path_segments = segments;
+    }
+    config::set_colors(settings.colors);
+    
+    // the first update plans the path and builds the gradient field for the empty world
+    let world = World::new(robot, TargetPosition::create_default());
+    
+    let key_bindings = match KeyBindings::load(KEYBINDINGS_PATH) {
+        Ok(key_bindings) => key_bindings,
+        Err(e) => {
+            println!("Using default key bindings: {}", e);
+            KeyBindings::default()
+        }
+    };
+    
+    let gradient_field = Some(gradient_field::GradientWire::new(
+        gradient_field::obstacle_sum,
+        gradient_resolution.0,
+        gradient_resolution.1,
+        gradient_resolution.2
+    ).with_color(config::color(config::colors().gradient))
+        .with_adaptive(settings.gradient_adaptive.then_some(settings.gradient_tolerance))
+        .with_influence(settings.gradient_influence_only.then_some(settings.gradient_influence_margin))
+        .with_surface(settings.gradient_rendering.surface()));
+    
+    let model_watcher = match ModelWatcher::new(&config::get().models.search_paths) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            eprintln!("Model hot reload disabled: {}", e);
+            None
+        }
+    };
+    
+    let mut app_model = AppModel {
+        _window: window_id,
+        camera_position: Position::new(0.0, -2.0, 0.0),
+        direction: PI / 8.0,
+        rotation_y: 0.0,
+        fov: settings.fov,
+        fullscreen: false,
+        follow_robot: false,
+        follow_distance: settings.follow_distance,
+        follow_height: settings.follow_height,
+        follow_smoothing: settings.follow_smoothing,
+        follow_heading: 0.0,
+        camera_bookmarks: camera::default_bookmarks(),
+        camera_transition: None,
+        camera_transition_time: settings.camera_transition_time,
+        camera_motion: CameraMotion::default(),
+        camera_damping: settings.camera_damping,
+        keep_horizon_level: settings.keep_horizon_level,
+        new_bookmark_name: String::from("Bookmark"),
+        new_target_name: String::from("Pickup"),
+        tour: None,
+        cursor_field_position: None,
+        click_action: ClickAction::None,
+        dragging_path_point: None,
+        inspected_path_point: None,
+        new_via_tolerance: 0.3,
+        new_event_action: PathAction::Wait { seconds: 1.0 },
+        path_export_path: settings.path_export_path.clone(),
+        ros_export_path: settings.ros_export_path.clone(),
+        setpoint_export_path: settings.setpoint_export_path.clone(),
+        path_import_path: settings.path_import_path.clone(),
+        path_check: None,
+        clearance_report: Vec::new(),
+        worst_point_count: DEFAULT_WORST_POINTS,
+        control_rate: settings.control_rate,
+        svg_export_path: settings.svg_export_path.clone(),
+        scene_path: settings.scene_path.clone(),
+        session_path: settings.session_path.clone(),
+        session_recorder: None,
+        session_replay: None,
+        snap_to_grid: false,
+        ruler_start: None,
+        ruler_end: None,
+        probes: Vec::new(),
+        section_start: None,
+        zone_corner: None,
+        new_zone_speed: DEFAULT_ZONE_SPEED,
+        section_end: None,
+        section_gaussian: false,
+        world,
+        egui,
+        camera_speed: settings.camera_speed,
+        rotation_speed: settings.rotation_speed,
+        show_path: settings.show_path,
+        show_initial_path: settings.show_initial_path,
+        show_optimized_path: settings.show_optimized_path,
+        show_pruned_points: settings.show_pruned_points,
+        show_roadmap: settings.show_roadmap,
+        animate_roadmap: settings.animate_roadmap,
+        roadmap_growth_speed: settings.roadmap_growth_speed,
+        show_wavefront: settings.show_wavefront,
+        animate_wavefront: settings.animate_wavefront,
+        wavefront_speed: settings.wavefront_speed,
+        replan_moving_obstacles: settings.replan_moving_obstacles,
+        show_hybrid_expanded: settings.show_hybrid_expanded,
+        color_path_by_speed: settings.color_path_by_speed,
+        show_points: settings.show_points,
+        path_markers: settings.path_markers.clone(),
+        line_weights: settings.line_weights,
+        custom_colors: settings.colors,
+        show_gradient_function: settings.show_gradient_function,
+        show_influence_rings: settings.show_influence_rings,
+        show_minimap: settings.show_minimap,
+        show_grid: settings.show_grid,
+        show_axis_gizmo: settings.show_axis_gizmo,
+        show_labels: settings.show_labels,
+        grid_spacing: settings.grid_spacing,
+        grid_extent: settings.grid_extent,
+        field_image: None,
+        field_image_path: settings.field_image_path.clone(),
+        field_image_columns: settings.field_image_columns,
+        field_image_brightness: settings.field_image_brightness,
+        show_field_image: settings.show_field_image,
+        show_costmap: settings.show_costmap,
+        show_distance_heatmap: settings.show_distance_heatmap,
+        show_distance_contours: settings.show_distance_contours,
+        distance_contour_spacing: settings.distance_contour_spacing,
+        distance_max: settings.distance_max,
+        show_harmonic_field: settings.show_harmonic_field,
+        path_segments: settings.path_segments,
+        fixed_timestep: settings.fixed_timestep,
+        timestep: settings.timestep,
+        sim_time: 0.0,
+        time_accumulator: 0.0,
+        play_optimization: false,
+        optimization_steps_per_frame: 1,
+        breakpoints: Vec::new(),
+        new_break_condition: BreakCondition::ALL[0],
+        breakpoint_hit: None,
+        obstacle_time: 0.0,
+        animate_obstacles: true,
+        coordination_mode: CoordinationMode::Priority,
+        robot_conflicts: Vec::new(),
+        
+        robot_velocity_x: 0.0,
+         robot_velocity_y: 0.0,
+         robot_target_speed: 2.0,
+        wasd_drives_robot: false,
+        key_bindings,
+        show_settings: false,
+        rebinding_action: None,
+        teleop_active: false,
+         new_model_name: String::from("cube"),
+        new_model_scale: 1.0,
+        new_model_position: Position::new(0.0, 0.0, 0.0),
+        selected_model_index: None,
+        selected_obstacle_index: None,
+        selected_obstacle_indices: Vec::new(),
+        group_move_offset: Position::new(0.0, 0.0, 0.0),
+        duplicate_offset: Position::new(0.5, 0.5, 0.0),
+        create_as_obstacle: false,
+        show_add_entity: false,
+        show_script_console: false,
+        script_console: ScriptConsole::new(settings.script_path.clone()),
+        entity_preview: Err(String::from("not loaded")),
+        
+        new_obstacle_name: OBSTACLE_PALETTE[2].model.to_string(),
+        new_obstacle_label: OBSTACLE_PALETTE[2].name.to_string(),
+        new_obstacle_radius: OBSTACLE_PALETTE[2].radius,
+        new_obstacle_position: Position::new(0.0, 0.0, 0.0),
+        new_obstacle_layer: DEFAULT_LAYER.to_string(),
+        
+        obstacle_layers: vec![ObstacleLayer::new(DEFAULT_LAYER), ObstacleLayer::new(FIELD_ELEMENTS_LAYER)],
+        obstacle_groups: Vec::new(),
+        new_group_name: String::new(),
+        selected_group: None,
+        new_layer_name: String::from("opponents"),
+        
+        field_layout_preset: FieldLayout::preset_names()[0].to_string(),
+        field_layout_path: settings.field_layout_path.clone(),
+        replace_obstacles_on_load: true,
+        robot_height: config::get().robot.scale,
+        step_height: 0.0,
+        random_scene: RandomSceneConfig::default(),
+        collision_count: 0,
+        pause_on_collision: false,
+        auto_replan: false,
+        replan_threshold: 0.5,
+        replan_count: 0,
+        evaluation_runs: 20,
+        evaluation: None,
+        
+        gradient_field,
+        gradient_x_resolution: settings.gradient_x_resolution,
+        gradient_y_resolution: settings.gradient_y_resolution,
+        gradient_line_resolution: settings.gradient_line_resolution,
+        gradient_adaptive: settings.gradient_adaptive,
+        gradient_tolerance: settings.gradient_tolerance,
+        gradient_gpu: settings.gradient_gpu,
+        gradient_rendering: settings.gradient_rendering,
+        gradient_influence_only: settings.gradient_influence_only,
+        gradient_influence_margin: settings.gradient_influence_margin,
+        gpu_field: None,
+        panel_width: settings.panel_width,
+        model_watcher,
+        remote: None,
+        remote_port: cli.remote_port.unwrap_or(settings.remote_port),
+        screenshot_directory: String::from("screenshots"),
+        frame_recorder: FrameRecorder::new("frames"),
+        autosave: Autosave::start(),
+        errors: Vec::new(),
+    };
+    
+    if let Some(message) = robot_error {
+        app_model.report_error(message);
+    }
+    if cli.remote_port.is_some() {
+        app_model.start_remote_server();
+    }
+    
+    if let Some(path) = cli.scene {
+        // older field layout json is not a scene, so it is tried when the file does not load as one
+        match scene::load(&path) {
+            Ok(scene) => {
+                app_model.load_scene(&scene);
+                app_model.scene_path = path;
+            },
+            Err(scene_error) => match FieldLayout::from_file(&path) {
+                Ok(layout) => {
+                    app_model.load_field_layout(&layout);
+                    app_model.field_layout_path = path;
+                },
+                Err(layout_error) => eprintln!("Failed to load scene {}: {}, nor as a field layout: {}", path, scene_error, layout_error),
+            },
+        }
+    }
+    
+    app_model
+}
+
+/// saves the ui settings so the next session starts where this one left off
+fn exit(_app: &App, model: AppModel) {
+    match model.settings().save(SETTINGS_PATH) {
+        Ok(()) => { println!("Saved settings to {}", SETTINGS_PATH); },
+        Err(e) => { eprintln!("Failed to save settings to {}: {}", SETTINGS_PATH, e); }
+    }
+    model.autosave.finish();
+}
+
+fn view(app: &App, model: &AppModel, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(config::color(config::colors().background));
+    
+    // projection is rebuilt every frame so resizing or going fullscreen never distorts the scene
+    let window_rect = app.window_rect();
+    let projection = Projection::from_window(window_rect, model.fov);
+    
+    if model.show_field_image {
+        if let Some(field_image) = &model.field_image {
+            for (corners, color) in field_image.cells(model.field_image_brightness) {
+                if let Some([a, b, c, d]) = project_quad(&corners, model, &projection) {
+                    draw.quad().points(a, b, c, d).color(color);
+                }
+            }
+        }
+    }
+    
+    if model.show_wavefront {
+        if let Some(wavefront) = model.world.robot.as_ref().filter(|robot| robot.planner == Planner::Wavefront).and_then(|robot| robot.wavefront.as_ref()) {
+            let colors = config::colors();
+            for (corners, color) in wavefront.cells(config::color(colors.wavefront), config::color(colors.wavefront_front)) {
+                if let Some([a, b, c, d]) = project_quad(&corners, model, &projection) {
+                    draw.quad().points(a, b, c, d).color(color);
+                }
+            }
+        }
+    }
+    
+    // speed zones are tinted on the ground, with the one being drawn following the cursor
+    let zone_color = config::colors().speed_zone;
+    let tint = nannou::color::rgb(zone_color[0] / 4, zone_color[1] / 4, zone_color[2] / 4);
+    let drawing = match (model.click_action, model.zone_corner, model.cursor_field_position) {
+        (ClickAction::SpeedZone, Some(corner), Some(cursor)) => Some(SpeedZone::from_corners([corner.x, corner.y], [cursor.x, cursor.y], model.new_zone_speed)),
+        _ => None,
+    };
+    for zone in model.world.speed_zones().iter().chain(drawing.iter()) {
+        let corners = zone.corners();
+        if let Some([a, b, c, d]) = project_quad(&corners, model, &projection) {
+            draw.quad().points(a, b, c, d).color(tint);
+        }
+        for i in 0..4 {
+            let wire = Wire::with_color(corners[i], corners[(i + 1) % 4], config::color(zone_color));
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(wire.color);
+            }
+        }
+    }
+    
+    if model.show_costmap {
+        if let Some(costmap) = model.world.robot.as_ref().and_then(|robot| robot.costmap.as_ref()) {
+            let colors = config::colors();
+            for (corners, color) in costmap.cells(config::color(colors.costmap_lethal), config::color(colors.costmap_inflated)) {
+                if let Some([a, b, c, d]) = project_quad(&corners, model, &projection) {
+                    draw.quad().points(a, b, c, d).color(color);
+                }
+            }
+        }
+    }
+    
+    if model.show_harmonic_field {
+        if let Some(harmonic) = model.world.robot.as_ref().and_then(|robot| robot.harmonic.as_ref()) {
+            let color = config::color(config::colors().harmonic_field);
+            for wire in harmonic.wires(model.gradient_x_resolution, model.gradient_y_resolution, color) {
+                if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(wire.color);
+                }
+            }
+        }
+    }
+    
+    if let Some(distance_field) = model.world.robot.as_ref().and_then(|robot| robot.distance_field.as_ref()) {
+        let colors = config::colors();
+        if model.show_distance_heatmap {
+            for (corners, color) in distance_field.heatmap(model.distance_max, config::color(colors.distance_near), config::color(colors.distance_far)) {
+                if let Some([a, b, c, d]) = project_quad(&corners, model, &projection) {
+                    draw.quad().points(a, b, c, d).color(color);
+                }
+            }
+        }
+        if model.show_distance_contours {
+            for wire in distance_field.contours(model.distance_contour_spacing, model.distance_max, config::color(colors.distance_contour)) {
+                if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(wire.color);
+                }
+            }
+        }
+    }
+    
+    let weights = model.line_weights;
+    if model.show_grid {
+        for wire in ground_grid(model.grid_spacing, model.grid_extent) {
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(wire.color).weight(weights.field);
+            }
+        }
+    }
+    
+    for wire in field_border() {
+        if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+            draw.line().start(draw_start).end(draw_end).color(wire.color).weight(weights.field);
+        }
+    }
+    
+    if let Some(robot) = &model.world.robot {
+        let color = config::color(config::colors().robot);
+        for wire in &robot.model.wires {
+            if let Some((draw_start, draw_end)) = project_wire(wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(color);
+            }
+        }
+    }
+    
+    if model.show_gradient_function {
+        if let Some(gradient_field) = &model.gradient_field {
+            if model.gradient_rendering.surface() {
+                draw_field_surface(&draw, model, &gradient_field.surface, &projection);
+            }
+            if model.gradient_rendering.wires() {
+                for wire in gradient_field.get_all_wires() {
+                    if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                        draw.line().start(draw_start).end(draw_end).color(wire.color).weight(weights.gradient);
+                    }
+                }
+            }
+        }
+    }
+    
+    for loaded_model in &model.world.models {
+        for wire in &loaded_model.wires {
+            if let Some((draw_start, draw_end)) = project_wire(wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(wire.color);
+            }
+        }
+    }
+    
+    // preview of the model the add entity dialog would create, with its bounding box
+    if model.show_add_entity {
+        if let Ok(preview) = &model.entity_preview {
+            for wire in &preview.wires {
+                if let Some((draw_start, draw_end)) = project_wire(wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(GRAY);
+                }
+            }
+            
+            let (min, max) = preview.bounds();
+            for wire in Wire::cuboid(min, max, YELLOW) {
+                if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(wire.color);
+                }
+            }
+        }
+    }
+    
+    for obstacle in model.world.obstacles.iter().filter(|obstacle| obstacle.visible) {
+        let color = obstacle_color(obstacle);
+        for wire in &obstacle.wires {
+            if let Some((draw_start, draw_end)) = project_wire(wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(color).weight(weights.obstacles);
+            }
+        }
+        if model.show_influence_rings && obstacle.is_planned() {
+            for wire in obstacle.influence_outline(color) {
+                if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(wire.color).weight(weights.obstacles);
+                }
+            }
+        }
+        for wire in obstacle.trajectory_wires(color) {
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(wire.color).weight(weights.obstacles);
+            }
+        }
+    }
+    
+    let target_region = model.world.robot.as_ref()
+        .map(|robot| robot.goal_region.wires(model.world.target(), model.world.target_marker().color))
+        .unwrap_or_default();
+    let named_targets = model.world.targets.iter().flat_map(|target| target.marker.get_wires());
+    for wire in model.world.target_marker().get_wires().into_iter().chain(target_region).chain(named_targets) {
+        if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+            draw.line().start(draw_start).end(draw_end).color(wire.color);
+        }
+    }
+    
+    if let Some(robot) = &model.world.robot {
+        let mut stage_wires = Vec::new();
+        if model.show_roadmap && robot.planner == Planner::Prm {
+            if let Some(roadmap) = &robot.roadmap {
+                stage_wires.extend(roadmap.get_wires(
+                    config::color(config::colors().roadmap),
+                    config::color(config::colors().roadmap_search),
+                ));
+            }
+        }
+        if model.show_hybrid_expanded && robot.planner == Planner::HybridAStar {
+            let color = config::color(config::colors().hybrid_expanded);
+            stage_wires.extend(robot.hybrid_expanded.iter()
+                .map(|[from, to]| Wire::with_color(Position::new(from[0], from[1], 0.0), Position::new(to[0], to[1], 0.0), color)));
+        }
+        if model.show_initial_path {
+            stage_wires.extend(robot.get_initial_path_wires());
+        }
+        if model.show_optimized_path {
+            stage_wires.extend(robot.get_optimized_path_wires());
+        }
+        for wire in stage_wires {
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(wire.color);
+            }
+        }
+        
+        if model.show_pruned_points {
+            let color = config::color(config::colors().pruned_points);
+            for &position in &robot.pruned_points {
+                if let Some(marker) = project_point(position, model, &projection) {
+                    draw.ellipse().xy(marker).radius(PATH_HANDLE_RADIUS).color(color);
+                }
+            }
+        }
+    }
+    
+    if model.show_path && model.world.robot.is_some() {
+        if let Some(robot) = &model.world.robot {
+            let path_wires = if model.color_path_by_speed { robot.get_speed_path_wires() } else { robot.get_path_wires() };
+            // round caps close the gaps between the short spline segments of a thick path
+            for wire in path_wires {
+                if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(wire.color).weight(weights.path).caps_round();
+                }
+            }
+        }
+    }
+    
+    // team robots with their paths and targets in their own color
+    for member in &model.world.team {
+        let mut wires = member.robot.model.wires.clone();
+        wires.extend(member.target.get_wires());
+        if model.show_path {
+            wires.extend(member.robot.get_path_wires());
+        }
+        for wire in wires {
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(member.color);
+            }
+        }
+    }
+    
+    // the path point a breakpoint paused the stepped optimization on
+    if let (Some(hit), Some(robot)) = (&model.breakpoint_hit, &model.world.robot) {
+        if let Some(point) = robot.path_points.get(hit.point).filter(|_| robot.stepped_optimization.is_some()) {
+            // the offending point circled, with lines to the obstacles whose fields it is on
+            let color = config::color(config::colors().breakpoint);
+            let center = Position::new(point.position.x, point.position.y, 0.0);
+            let mut wires = Wire::circle(center, robot.footprint_radius(), 24, color);
+            for &(index, _) in &hit.obstacles {
+                if let Some(obstacle) = model.world.obstacles.get(index) {
+                    wires.push(Wire { start: center, end: obstacle.model.config.position, color });
+                }
+            }
+            for wire in wires {
+                if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(wire.color).weight(2.0);
                 }
-                
-                ui.separator();
-                ui.heading("Path Settings");
-                
-                ui.checkbox(&mut model.show_path, "Show Path");
-                
-                let mut segments = model.path_segments;
-                if ui.add(egui::Slider::new(&mut segments, 5..=200).text("Path Segments")).changed() {
-                    model.path_segments = segments;
-                    
-                    if let Some(robot) = &mut model.robot {
-                        let target_pos = model.target_position.get_position();
-                        robot.generate_path(&target_pos, model.path_segments, &model.obstacles);
-                    }
+            }
+        }
+    }
+    
+    // where two robots would meet, sized to the robot footprint
+    for conflict in &model.robot_conflicts {
+        let radius = model.world.robot.as_ref().map_or(0.5, |robot| robot.footprint_radius());
+        for wire in Wire::circle(conflict.position, radius, 24, RED) {
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(wire.color).weight(2.0);
+            }
+        }
+    }
+    
+    if let Some(robot) = &model.world.robot {
+        // short line from the center towards the front of the robot
+        let center = robot.model.config.position;
+        let front = Position::new(
+            center.x + robot.model.config.scale * robot.heading.cos(),
+            center.y + robot.model.config.scale * robot.heading.sin(),
+            center.z,
+        );
+        if let Some((draw_start, draw_end)) = project_wire(&Wire::with_color(center, front, ORANGE), model, &projection) {
+            draw.line().start(draw_start).end(draw_end).color(ORANGE).weight(2.0);
+        }
+        
+        // velocity the follower wanted and the one the local avoidance chose instead, a second ahead
+        if robot.follow_path && robot.local_avoidance.is_avoiding() {
+            let avoidance = &robot.local_avoidance;
+            let velocities = [
+                (avoidance.preferred, GRAY),
+                (avoidance.chosen, config::color(config::colors().avoidance_velocity)),
+            ];
+            for ([vx, vy], color) in velocities {
+                let end = Position::new(center.x + vx, center.y + vy, center.z);
+                if let Some((draw_start, draw_end)) = project_wire(&Wire::with_color(center, end, color), model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(color).weight(2.0);
                 }
-                
-                ui.horizontal(|ui| {
-                    if ui.button("Generate Path").clicked() {
-                        if let Some(robot) = &mut model.robot {
-                            let target_pos = model.target_position.get_position();
-                            robot.generate_path(&target_pos, model.path_segments, &model.obstacles);
-                            
-                            if model.models.iter().any(|m| m.config.name == "point") {
-                                model.models.retain(|m| m.config.name != "point");
-                                
-                                if robot.path_points.len() > 1 {
-                                    for path_point in &robot.path_points {
-                                        let position = path_point.position;
-                                        
-                                        let config = ModelConfig {
-                                            name: "point".to_string(),
-                                            position: position,
-                                            scale: 0.05,
-                                        };
-                                        
-                                        match Model::from_config(&config) {
-                                            Ok(point_model) => {
-                                                model.models.push(point_model);
-                                            },
-                                            Err(e) => { eprintln!("Failed to create point model: {}", e); }
-                                        }
-                                    }
-                                    println!("Updated {} points after path optimization", robot.path_points.len());
-                                }
-                            }
-                        }
-                    }
-                    
-                    if ui.button("Follow Path").clicked() {
-                        if let Some(robot) = &mut model.robot {
-                            robot.follow_path = true;
-                        }
-                    }
-                });
-                
-                
-                if ui.button("Place Points Along Path").clicked() {
-                    model.models.retain(|m| m.config.name != "point");
-                    
-                    if let Some(robot) = &model.robot {
-                        if robot.path_points.len() > 1 {
-                            for path_point in &robot.path_points {
-                                let mut position = path_point.position;
-                                
-                                let mut height = 0.0;
-                                for obstacle in &model.obstacles {
-                                    height += obstacle.cosine_field_function(position);
-                                }
-                                position.z = height;
-                                
-                                let config = ModelConfig {
-                                    name: "point".to_string(),
-                                    position: position,
-                                    scale: 0.05,
-                                };
-                                
-                                match Model::from_config(&config) {
-                                    Ok(point_model) => {
-                                        model.models.push(point_model);
-                                    },
-                                    Err(e) => { eprintln!("Failed to create point model: {}", e); }
-                                }
-                            }
-                            println!("Placed {} points along path", robot.path_points.len());
-                        }
-                    }
+            }
+        }
+        
+        for event in &robot.events {
+            let (x, y) = robot.catmull_rom_spline(event.progress);
+            if let Some(marker) = project_point(Position::new(x, y, 0.0), model, &projection) {
+                draw.ellipse().xy(marker).radius(PATH_HANDLE_RADIUS + 1.0).color(MAGENTA);
+            }
+        }
+        
+        for via in &robot.via_points {
+            for wire in Wire::circle(via.position, via.tolerance, 24, config::color(config::colors().via_point)) {
+                if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                    draw.line().start(draw_start).end(draw_end).color(wire.color);
                 }
-                
-                if ui.button("Clear All Path Points").clicked() {
-                    model.models.retain(|m| m.config.name != "point");
+            }
+        }
+    }
+    
+    if model.show_points && model.click_action != ClickAction::EditPath {
+        if let Some(robot) = &model.world.robot {
+            for marker in model.path_markers.markers(robot, &model.world.obstacles) {
+                if let Some(screen) = project_point(marker.position, model, &projection) {
+                    let radius = model.path_markers.radius(camera_depth(marker.position, model), projection.focal_length());
+                    draw.ellipse().xy(screen).radius(radius).color(marker.color);
                 }
-            });
-            
-            ui.separator();
-            ui.label("Controls:");
-            ui.label("WASD - Move Camera");
-            ui.label("E - Up, Q - Down");
-            ui.label("Arrow Keys - Rotate Camera");
-        });
+            }
+        }
+    }
     
-    let step_size = (update.since_last.secs() * model.camera_speed as f64) as f32;
-
-    if app.keys.down.contains(&Key::W) {
-        model.camera_position.move_by(
-            step_size * model.direction.cos(),
-            step_size * model.direction.sin(),
-            0.0,
-        );
+    if model.click_action == ClickAction::EditPath {
+        if let Some(robot) = &model.world.robot {
+            for (i, point) in robot.path_points.iter().enumerate() {
+                if let Some(handle) = project_point(point.position, model, &projection) {
+                    let color = if model.dragging_path_point == Some(i) {
+                        YELLOW
+                    } else if point.locked {
+                        ORANGE
+                    } else {
+                        WHITE
+                    };
+                    draw.ellipse().xy(handle).radius(PATH_HANDLE_RADIUS).color(color);
+                }
+            }
+        }
     }
-    if app.keys.down.contains(&Key::S) {
-        model.camera_position.move_by(
-            -step_size * model.direction.cos(),
-            -step_size * model.direction.sin(),
-            0.0,
-        );
+    
+    draw_point_cost(&draw, model, &projection);
+    
+    if model.show_labels {
+        draw_labels(&draw, model, &projection);
     }
-    if app.keys.down.contains(&Key::A) {
-        model.camera_position.move_by(
-            -step_size * model.direction.sin(),
-            step_size * model.direction.cos(),
-            0.0,
-        );
+    
+    if let Some(robot) = &model.world.robot {
+        if robot.follow_path {
+            draw_path_metrics(&draw, robot, window_rect);
+        }
     }
-    if app.keys.down.contains(&Key::D) {
-        model.camera_position.move_by(
-            step_size * model.direction.sin(),
-            -step_size * model.direction.cos(),
-            0.0,
-        );
+    
+    if let Some(start) = model.ruler_start {
+        // follow the cursor until the second point is placed
+        let end = model.ruler_end.or(model.cursor_field_position.map(|c| model.snap_position(c)));
+        if let Some(end) = end {
+            let wire = Wire::with_color(start, end, YELLOW);
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(wire.color).weight(2.0);
+            }
+            
+            let midpoint = Position::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0, 0.0);
+            if let Some(label_position) = project_point(midpoint, model, &projection) {
+                draw.text(&format!("{:.2} m", start.distance_to(&end)))
+                    .xy(label_position + vec2(0.0, 12.0))
+                    .color(YELLOW)
+                    .font_size(14);
+            }
+        }
     }
-
-    if app.keys.down.contains(&Key::E) {
-        model.camera_position.move_by(
-            0.0,
-            0.0,
-            step_size,
-        );
+    
+    draw_probes(&draw, model, &projection);
+    draw_clearance_report(&draw, model, &projection);
+    
+    if let Some(start) = model.section_start {
+        // follow the cursor until the second point is placed
+        let end = match model.section_end {
+            None if model.click_action == ClickAction::CrossSection => model.cursor_field_position.map(|c| Position::new(c.x, c.y, 0.0)),
+            end => end,
+        };
+        if let Some(end) = end {
+            let color = config::color(config::colors().cross_section);
+            if let Some((draw_start, draw_end)) = project_wire(&Wire::with_color(start, end, color), model, &projection) {
+                draw.line().start(draw_start).end(draw_end).color(color).weight(2.0);
+            }
+        }
     }
-    if app.keys.down.contains(&Key::Q) {
-        model.camera_position.move_by(
-            0.0,
-            0.0,
-            -step_size,
-        );
+    
+    if model.show_minimap {
+        draw_minimap(&draw, model, &Minimap::new(window_rect.pad_bottom(STATUS_BAR_HEIGHT)));
+    }
+    
+    if model.show_axis_gizmo {
+        let origin = window_rect.top_left() + vec2(AXIS_GIZMO_MARGIN, -AXIS_GIZMO_MARGIN);
+        draw_axis_gizmo(&draw, model, origin);
     }
+    
+    draw.to_frame(app, &frame).unwrap();
+    model.egui.draw_to_frame(&frame).unwrap();
+}
 
-    let rot_step = (update.since_last.secs() * model.rotation_speed as f64) as f32;
-    let rot_y_step = (update.since_last.secs() * model.rotation_speed as f64) as f32;
+/// the shaded field surface, farthest triangles first so nearer ones are painted over them
+fn draw_field_surface(draw: &Draw, model: &AppModel, surface: &[gradient_field::SurfaceTriangle], projection: &Projection) {
+    let base = config::colors().gradient_surface;
+    let mut visible: Vec<(f32, [Vec2; 3], f32)> = surface.iter()
+        .filter_map(|triangle| {
+            let [a, b, c] = triangle.corners.map(|corner| project_point(corner, model, projection));
+            Some((camera_depth(triangle.center(), model), [a?, b?, c?], triangle.shade))
+        })
+        .collect();
+    visible.sort_by(|a, b| b.0.total_cmp(&a.0));
+    
+    for (_, [a, b, c], shade) in visible {
+        let [red, green, blue] = base.map(|channel| (channel as f32 * shade).min(255.0) as u8);
+        draw.tri().points(a, b, c).color(rgb(red, green, blue));
+    }
+}
 
-    if app.keys.down.contains(&Key::Left) { model.direction += rot_step; }
-    if app.keys.down.contains(&Key::Right) { model.direction -= rot_step; }
-    if app.keys.down.contains(&Key::Up) { model.rotation_y += rot_y_step; }
-    if app.keys.down.contains(&Key::Down) { model.rotation_y -= rot_y_step; }
+/// pinned field probes and the one under the cursor in probe mode, each a marker on the field surface
+/// with a line down its gradient, the way the optimizer pushes a path point there
+fn draw_probes(draw: &Draw, model: &AppModel, projection: &Projection) {
+    let color = config::color(config::colors().probe);
+    let hovered = match (model.click_action, model.cursor_field_position) {
+        (ClickAction::Probe, Some(cursor)) if !model.egui.ctx().is_pointer_over_area() => Some(cursor),
+        _ => None,
+    };
+    
+    for (i, position) in model.probes.iter().copied().map(Some).chain([hovered]).enumerate() {
+        let Some(position) = position else { continue; };
+        let probe = FieldProbe::at(&model.world.obstacles, position);
+        let surface = Position::new(probe.position.x, probe.position.y, probe.potential);
+        let Some(marker) = project_point(surface, model, projection) else { continue; };
+        draw.ellipse().xy(marker).radius(PATH_HANDLE_RADIUS).no_fill().stroke(color).stroke_weight(2.0);
+        
+        let slope = probe.slope();
+        if slope > f32::EPSILON {
+            let length = PROBE_ARROW_LENGTH * slope.min(1.0);
+            let end = Position::new(
+                surface.x - probe.gradient[0] / slope * length,
+                surface.y - probe.gradient[1] / slope * length,
+                surface.z,
+            );
+            if let Some((draw_start, draw_end)) = project_wire(&Wire::with_color(surface, end, color), model, projection) {
+                draw.line().start(draw_start).end(draw_end).color(color).weight(2.0);
+            }
+        }
+        
+        let label = if i < model.probes.len() { format!("{}: h = {:.3}, |grad| = {:.3}", i + 1, probe.potential, slope) } else { probe.readout() };
+        draw.text(&label)
+            .xy(marker + vec2(0.0, 14.0))
+            .color(color)
+            .font_size(12);
+    }
+}
 
-    if app.keys.down.contains(&Key::Space) { 
-        if let Some(robot) = &mut model.robot {
-            robot.follow_path();
-        } 
+/// the spots of the clearance report, each a marker on the path with a ring as wide as its clearance
+fn draw_clearance_report(draw: &Draw, model: &AppModel, projection: &Projection) {
+    let color = config::color(config::colors().clearance_marker);
+    for (i, point) in model.clearance_report.iter().enumerate() {
+        for wire in Wire::circle(point.position, point.clearance.max(0.0), 24, color) {
+            if let Some((draw_start, draw_end)) = project_wire(&wire, model, projection) {
+                draw.line().start(draw_start).end(draw_end).color(color);
+            }
+        }
+        let Some(marker) = project_point(point.position, model, projection) else { continue; };
+        draw.ellipse().xy(marker).radius(PATH_HANDLE_RADIUS).no_fill().stroke(color).stroke_weight(2.0);
+        draw.text(&format!("#{} {:.3} m", i + 1, point.clearance))
+            .xy(marker + vec2(0.0, 14.0))
+            .color(color)
+            .font_size(12);
     }
 }
 
-fn model(app: &App) -> AppModel {
-    let window_id = app
-        .new_window()
-        .size(SCREENWIDTH + 250, SCREENHEIGHT)
-        .view(view)
-        .raw_event(|_app: &App, model: &mut AppModel, event: &nannou::winit::event::WindowEvent| model.egui.handle_raw_event(event))
-        .build()
-        .unwrap();
+/// draws the world axes as seen from the camera, pinned to a point on the screen
+fn draw_axis_gizmo(draw: &Draw, model: &AppModel, origin: Vec2) {
+    let axes = [
+        (Position::new(1.0, 0.0, 0.0), RED, "X"),
+        (Position::new(0.0, 1.0, 0.0), GREEN, "Y"),
+        (Position::new(0.0, 0.0, 1.0), BLUE, "Z"),
+    ];
     
-    let window = app.window(window_id).unwrap();
+    for (axis, color, label) in axes {
+        // rotate only, the gizmo ignores the camera position and uses an orthographic projection
+        let cam_axis = to_cam_coords(axis, position::ORIGIN, model.direction, model.rotation_y);
+        let end = origin + vec2(-cam_axis.y, -cam_axis.z) * AXIS_GIZMO_LENGTH;
+        draw.line().start(origin).end(end).color(color).weight(2.0);
+        draw.text(label).xy(origin + (end - origin) * 1.3).color(color).font_size(12);
+    }
+}
+
+/// line chart of the follower's recent along-track and cross-track errors, centered on zero
+fn plot_errors(ui: &mut egui::Ui, history: &std::collections::VecDeque<(f32, f32)>) {
+    let (response, painter) = ui.allocate_painter(egui::vec2(ui.available_width(), 80.0), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::DARK_GRAY));
+    painter.hline(rect.x_range(), rect.center().y, egui::Stroke::new(1.0, egui::Color32::DARK_GRAY));
     
-    let egui = Egui::from_window(&window);
+    let max_error = history.iter()
+        .map(|(along, cross)| along.abs().max(cross.abs()))
+        .fold(0.1, f32::max);
     
-    let models = Vec::new();
-    let obstacles = Vec::new();
+    let to_screen = |i: usize, error: f32| {
+        egui::pos2(
+            rect.left() + i as f32 / history.len().max(2).saturating_sub(1) as f32 * rect.width(),
+            rect.center().y - error / max_error * rect.height() / 2.0,
+        )
+    };
     
-    let mut robot = match Robot::create_default() {
-        Ok(robot) => Some(robot),
-        Err(e) => {
-            eprintln!("Failed to load robot model: {}", e);
-            None
-        }
+    let along: Vec<egui::Pos2> = history.iter().enumerate().map(|(i, (error, _))| to_screen(i, *error)).collect();
+    let cross: Vec<egui::Pos2> = history.iter().enumerate().map(|(i, (_, error))| to_screen(i, *error)).collect();
+    painter.add(egui::Shape::line(along, egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE)));
+    painter.add(egui::Shape::line(cross, egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 165, 0))));
+    painter.text(rect.left_top(), egui::Align2::LEFT_TOP, format!("±{:.2} m", max_error), egui::FontId::monospace(10.0), egui::Color32::GRAY);
+}
+
+/// speed limit against distance along the path, under the target speed, with the time optimal speeds at the
+/// profile samples when there are any
+fn plot_speed_profile(ui: &mut egui::Ui, robot: &Robot, optimal: &[f32]) {
+    let (response, painter) = ui.allocate_painter(egui::vec2(ui.available_width(), 80.0), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::DARK_GRAY));
+    let profile = &robot.speed_limits.profile;
+    let (Some(last), true) = (profile.last(), robot.target_speed > 0.0) else { return; };
+    
+    let to_screen = |distance: f32, speed: f32| egui::pos2(
+        rect.left() + distance / last.distance.max(1e-3) * rect.width(),
+        rect.bottom() - speed / (robot.target_speed * 1.1) * rect.height(),
+    );
+    let top = to_screen(0.0, robot.target_speed).y;
+    painter.hline(rect.x_range(), top, egui::Stroke::new(1.0, egui::Color32::GRAY));
+    let speeds: Vec<egui::Pos2> = profile.iter()
+        .map(|sample| to_screen(sample.distance, robot.speed_limit_at(sample.progress)))
+        .collect();
+    painter.add(egui::Shape::line(speeds, egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN)));
+    let retimed: Vec<egui::Pos2> = profile.iter().zip(optimal)
+        .map(|(sample, &speed)| to_screen(sample.distance, speed))
+        .collect();
+    painter.add(egui::Shape::line(retimed, egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 165, 0))));
+    painter.text(rect.left_top(), egui::Align2::LEFT_TOP, format!("{:.2} m/s", robot.target_speed), egui::FontId::monospace(10.0), egui::Color32::GRAY);
+    painter.text(rect.right_bottom(), egui::Align2::RIGHT_BOTTOM, format!("{:.2} m", last.distance), egui::FontId::monospace(10.0), egui::Color32::GRAY);
+}
+
+/// field height against distance along the cross section line, the total in white over each obstacle's own
+/// field so overlaps stand out
+fn plot_cross_section(ui: &mut egui::Ui, section: &CrossSection, gaussian: bool) {
+    let (response, painter) = ui.allocate_painter(egui::vec2(ui.available_width(), 120.0), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::DARK_GRAY));
+    
+    let mut max_height = section.cosine.iter().copied().fold(0.1, f32::max);
+    if gaussian {
+        max_height = section.gaussian.iter().copied().fold(max_height, f32::max);
+    }
+    let to_screen = |i: usize, height: f32| {
+        egui::pos2(
+            rect.left() + i as f32 / (section.cosine.len() - 1) as f32 * rect.width(),
+            rect.bottom() - height / max_height * rect.height(),
+        )
     };
+    let line = |heights: &[f32]| heights.iter().enumerate().map(|(i, &height)| to_screen(i, height)).collect::<Vec<_>>();
     
-    let target_position = TargetPosition::create_default();
+    for (n, (_, heights)) in section.obstacles.iter().enumerate() {
+        let hue = (n as f32 * 0.618).fract();
+        let color = egui::Color32::from(egui::ecolor::Hsva::new(hue, 0.6, 0.8, 1.0));
+        painter.add(egui::Shape::line(line(heights), egui::Stroke::new(1.0, color)));
+    }
+    if gaussian {
+        painter.add(egui::Shape::line(line(&section.gaussian), egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE)));
+    }
+    painter.add(egui::Shape::line(line(&section.cosine), egui::Stroke::new(2.0, egui::Color32::WHITE)));
     
-    if let Some(robot_ref) = &mut robot {
-        let target_pos = target_position.get_position();
-        robot_ref.generate_path(&target_pos, 160, &obstacles);
+    let font = egui::FontId::monospace(10.0);
+    painter.text(rect.left_top(), egui::Align2::LEFT_TOP, format!("h {:.2}", max_height), font.clone(), egui::Color32::GRAY);
+    painter.text(rect.right_bottom(), egui::Align2::RIGHT_BOTTOM, format!("{:.2} m", section.length()), font, egui::Color32::GRAY);
+    
+    if let Some(hover) = response.hover_pos() {
+        let i = (((hover.x - rect.left()) / rect.width()) * (section.cosine.len() - 1) as f32).round() as usize;
+        if let Some(&height) = section.cosine.get(i) {
+            let distance = i as f32 / (section.cosine.len() - 1) as f32 * section.length();
+            painter.vline(to_screen(i, 0.0).x, rect.y_range(), egui::Stroke::new(1.0, egui::Color32::GRAY));
+            response.on_hover_text(format!("{:.2} m: h = {:.3}", distance, height));
+        }
     }
+}
+
+/// the collision color while the robot is touching the obstacle, blinking it for a moment after a collision,
+/// and the passable, soft or hard obstacle color otherwise
+fn obstacle_color(obstacle: &Obstacle) -> Rgb<u8> {
+    let colors = config::colors();
+    let blink_on = (obstacle.collision_flash * 8.0) as i32 % 2 == 0;
+    let color = if obstacle.colliding || (obstacle.collision_flash > 0.0 && blink_on) {
+        colors.collision
+    } else if obstacle.passable {
+        colors.passable_obstacle
+    } else if obstacle.soft {
+        colors.soft_obstacle
+    } else {
+        colors.obstacle
+    };
+    config::color(color)
+}
+
+/// overlay with progress, distance left, ETA and curvature while the robot follows its path
+fn draw_path_metrics(draw: &Draw, robot: &Robot, window_rect: Rect) {
+    let progress = robot.current_path_progress;
+    let remaining = robot.remaining_path_length(progress);
+    let eta = if robot.target_speed > 0.0 {
+        format!("{:.1} s", remaining / robot.target_speed)
+    } else {
+        String::from("-")
+    };
+    let curvature = robot.curvature_at(progress);
     
-    gradient_field::set_obstacles_ref(&obstacles);
+    let last_marker = robot.triggered_markers.last().map_or("-", |name| name.as_str());
+    let text = format!(
+        "Progress: {:.0}%\nRemaining: {:.2} m\nETA: {}\nCurvature: {:.2} 1/m\nSpeed: {:.2} m/s\nLast marker: {}",
+        progress * 100.0, remaining, eta, curvature, robot.forward_speed(), last_marker
+    );
     
-    let gradient_field = Some(gradient_field::GradientWire::new(
-        gradient_field::obstacle_sum,
-        3.0,
-        3.0,
-        0.12
-    ).with_color(nannou::color::rgb(0, 255, 255)));
+    let size = vec2(170.0, 108.0);
+    let center = vec2(0.0, window_rect.top() - AXIS_GIZMO_MARGIN);
+    draw.rect().xy(center).wh(size).color(rgba(0.0, 0.0, 0.0, 0.6));
+    draw.text(&text)
+        .xy(center)
+        .wh(size)
+        .left_justify()
+        .color(WHITE)
+        .font_size(13);
+}
+
+/// draws obstacle names and indices, matching the side panel list, above each obstacle and the target
+/// lines from the inspected path point to the obstacles its height comes from, the dominant one thickest,
+/// each labelled with its share
+fn draw_point_cost(draw: &Draw, model: &AppModel, projection: &Projection) {
+    let Some(robot) = &model.world.robot else { return; };
+    let Some(point) = model.inspected_path_point.and_then(|index| robot.path_points.get(index).map(|point| (index, point))) else { return; };
+    let (index, point) = point;
     
-    AppModel {
-        _window: window_id,
-        camera_position: Position::new(0.0, -2.0, 0.0),
-        direction: PI / 8.0,
-        rotation_y: 0.0,
-        models,
-        obstacles,
-        robot,
-        target_position,
-        egui,
-        camera_speed: SPEED as f32,
-        rotation_speed: 1.0,
-        show_path: true,
-        show_points: true,
-        show_gradient_function: true,
-        path_segments: 160,
-        
-        robot_velocity_x: 0.0,
-         robot_velocity_y: 0.0,
-         robot_target_speed: 2.0,
-         new_model_name: String::from("cube"),
-        new_model_scale: 1.0,
-        new_model_position: Position::new(0.0, 0.0, 0.0),
-        selected_model_index: None,
-        selected_obstacle_index: None,
-        create_as_obstacle: false,
-        
-        new_obstacle_name: String::from("robot_base"),
-        new_obstacle_radius: 0.6,
-        new_obstacle_position: Position::new(0.0, 0.0, 0.0),
+    let color = config::color(config::colors().point_cost);
+    let contributions = robot.field_contributions(index, &model.world.obstacles);
+    let total: f32 = contributions.iter().map(|&(_, height)| height).sum();
+    let start = Position::new(point.position.x, point.position.y, 0.0);
+    for (rank, &(obstacle, height)) in contributions.iter().enumerate() {
+        let Some(obstacle) = model.world.obstacles.get(obstacle) else { continue; };
+        let end = obstacle.model.config.position;
+        let wire = Wire { start, end, color };
+        if let Some((draw_start, draw_end)) = project_wire(&wire, model, projection) {
+            let weight = if rank == 0 { 3.0 } else { 1.0 };
+            draw.line().start(draw_start).end(draw_end).color(color).weight(weight);
+        }
         
-        gradient_field,
-        gradient_x_resolution: 0.5,
-        gradient_y_resolution: 0.5,
-        gradient_line_resolution: 0.5,
+        let middle = Position::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0, LABEL_HEIGHT);
+        if let Some(label_position) = project_point(middle, model, projection) {
+            draw.text(&format!("{:.3} ({:.0}%)", height, 100.0 * height / total.max(f32::EPSILON)))
+                .xy(label_position)
+                .color(color)
+                .font_size(12);
+        }
+    }
+    
+    if let Some(handle) = project_point(point.position, model, projection) {
+        draw.ellipse().xy(handle).radius(PATH_HANDLE_RADIUS).no_fill().stroke(color).stroke_weight(2.0);
     }
 }
 
-fn view(app: &App, model: &AppModel, frame: Frame) {
-    let draw = app.draw();
-    draw.background().color(BLACK);
-    
-    for wire in field_border() {
-        let cam_pos_start: Position = to_cam_coords(
-            wire.start,
-            model.camera_position,
-            model.direction,
-            model.rotation_y,
-        );
-        let cam_pos_end: Position = to_cam_coords(
-            wire.end,
-            model.camera_position,
-            model.direction,
-            model.rotation_y,
-        );
-        if let Some((s, e)) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01) {
-            let draw_start: Vec2 = point_on_canvas(s);
-            let draw_end: Vec2 = point_on_canvas(e);
-            draw.line().start(draw_start).end(draw_end).color(wire.color);
+fn draw_labels(draw: &Draw, model: &AppModel, projection: &Projection) {
+    for (i, obstacle) in model.world.obstacles.iter().enumerate() {
+        if !obstacle.visible {
+            continue;
+        }
+        
+        let center = obstacle.model.config.position;
+        let top = Position::new(center.x, center.y, center.z + obstacle.model.config.scale + LABEL_HEIGHT);
+        if let Some(label_position) = project_point(top, model, projection) {
+            let color = if model.selected_obstacle_indices.contains(&i) { YELLOW } else { config::color(config::colors().obstacle) };
+            draw.text(&format!("{}: {}", i + 1, obstacle.name))
+                .xy(label_position)
+                .color(color)
+                .font_size(12);
         }
     }
     
-    if let Some(robot) = &model.robot {
-        for wire in &robot.model.wires {
-            let cam_pos_start: Position = to_cam_coords(
-                wire.start,
-                model.camera_position,
-                model.direction,
-                model.rotation_y,
-            );
-            let cam_pos_end: Position = to_cam_coords(
-                wire.end,
-                model.camera_position,
-                model.direction,
-                model.rotation_y,
-            );
-            if let Some((s, e)) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01) {
-                let draw_start: Vec2 = point_on_canvas(s);
-                let draw_end: Vec2 = point_on_canvas(e);
-                draw.line().start(draw_start).end(draw_end).color(nannou::color::rgb::<u8>(0, 255, 0));
+    if let Some(robot) = &model.world.robot {
+        for (i, via) in robot.via_points.iter().enumerate() {
+            let top = Position::new(via.position.x, via.position.y, via.position.z + LABEL_HEIGHT);
+            if let Some(label_position) = project_point(top, model, projection) {
+                draw.text(&format!("Via {}", i + 1)).xy(label_position).color(config::color(config::colors().via_point)).font_size(12);
             }
         }
     }
     
-    if model.show_gradient_function {
-        if let Some(gradient_field) = &model.gradient_field {
-            for wire in gradient_field.get_all_wires() {
-                let cam_pos_start: Position = to_cam_coords(
-                    wire.start,
-                    model.camera_position,
-                    model.direction,
-                    model.rotation_y,
-                );
-                let cam_pos_end: Position = to_cam_coords(
-                    wire.end,
-                    model.camera_position,
-                    model.direction,
-                    model.rotation_y,
-                );
-                if let Some((s, e)) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01) {
-                    let draw_start: Vec2 = point_on_canvas(s);
-                    let draw_end: Vec2 = point_on_canvas(e);
-                    draw.line().start(draw_start).end(draw_end).color(wire.color);
-                }
+    if let Some(robot) = &model.world.robot {
+        for event in &robot.events {
+            let (x, y) = robot.catmull_rom_spline(event.progress);
+            if let Some(label_position) = project_point(Position::new(x, y, LABEL_HEIGHT), model, projection) {
+                draw.text(&event.action.description()).xy(label_position).color(MAGENTA).font_size(12);
             }
         }
     }
     
-    for loaded_model in &model.models {
-        for wire in &loaded_model.wires {
-            let cam_pos_start: Position = to_cam_coords(
-                wire.start,
-                model.camera_position,
-                model.direction,
-                model.rotation_y,
-            );
-            let cam_pos_end: Position = to_cam_coords(
-                wire.end,
-                model.camera_position,
-                model.direction,
-                model.rotation_y,
-            );
-            if let Some((s, e)) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01) {
-                let draw_start: Vec2 = point_on_canvas(s);
-                let draw_end: Vec2 = point_on_canvas(e);
-                draw.line().start(draw_start).end(draw_end).color(wire.color);
-            }
+    let zone_color = config::color(config::colors().speed_zone);
+    for zone in model.world.speed_zones() {
+        let center = Position::new((zone.min[0] + zone.max[0]) / 2.0, (zone.min[1] + zone.max[1]) / 2.0, LABEL_HEIGHT);
+        if let Some(label_position) = project_point(center, model, projection) {
+            draw.text(&format!("{:.1} m/s", zone.max_speed)).xy(label_position).color(zone_color).font_size(12);
         }
     }
     
-    for obstacle in &model.obstacles {
-        for wire in &obstacle.wires {
-            let cam_pos_start: Position = to_cam_coords(
-                wire.start,
-                model.camera_position,
-                model.direction,
-                model.rotation_y,
-            );
-            let cam_pos_end: Position = to_cam_coords(
-                wire.end,
-                model.camera_position,
-                model.direction,
-                model.rotation_y,
-            );
-            if let Some((s, e)) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01) {
-                let draw_start: Vec2 = point_on_canvas(s);
-                let draw_end: Vec2 = point_on_canvas(e);
-                draw.line().start(draw_start).end(draw_end).color(GREEN);
-            }
+    let target = model.world.target();
+    let top = Position::new(target.x, target.y, target.z + LABEL_HEIGHT);
+    if let Some(label_position) = project_point(top, model, projection) {
+        draw.text("Target").xy(label_position).color(WHITE).font_size(12);
+    }
+    
+    for (i, target) in model.world.targets.iter().enumerate() {
+        let position = target.marker.position;
+        let top = Position::new(position.x, position.y, position.z + LABEL_HEIGHT);
+        if let Some(label_position) = project_point(top, model, projection) {
+            let hotkey = if i < 9 { format!("{}: ", i + 1) } else { String::new() };
+            draw.text(&format!("{}{}", hotkey, target.name)).xy(label_position).color(target.marker.color).font_size(12);
         }
     }
+}
+
+fn draw_minimap(draw: &Draw, model: &AppModel, minimap: &Minimap) {
+    minimap.draw_background(draw);
+    
+    for wire in field_border() {
+        minimap.draw_wire(draw, &wire, wire.color);
+    }
     
-    for wire in model.target_position.get_wires() {
-        let cam_pos_start: Position = to_cam_coords(
-            wire.start,
-            model.camera_position,
-            model.direction,
-            model.rotation_y,
-        );
-        let cam_pos_end: Position = to_cam_coords(
-            wire.end,
-            model.camera_position,
-            model.direction,
-            model.rotation_y,
-        );
-        if let Some((s, e)) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01) {
-            let draw_start: Vec2 = point_on_canvas(s);
-            let draw_end: Vec2 = point_on_canvas(e);
-            draw.line().start(draw_start).end(draw_end).color(wire.color);
+    for obstacle in model.world.obstacles.iter().filter(|obstacle| obstacle.visible) {
+        for wire in obstacle.footprint_outline(obstacle_color(obstacle)) {
+            minimap.draw_wire(draw, &wire, wire.color);
         }
     }
     
-    if model.show_path && model.robot.is_some() {
-        if let Some(robot) = &model.robot {
+    if model.show_path {
+        if let Some(robot) = &model.world.robot {
             for wire in robot.get_path_wires() {
-                let cam_pos_start: Position = to_cam_coords(
-                    wire.start,
-                    model.camera_position,
-                    model.direction,
-                    model.rotation_y,
-                );
-                let cam_pos_end: Position = to_cam_coords(
-                    wire.end,
-                    model.camera_position,
-                    model.direction,
-                    model.rotation_y,
-                );
-                if let Some((s, e)) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01) {
-                    let draw_start: Vec2 = point_on_canvas(s);
-                    let draw_end: Vec2 = point_on_canvas(e);
-                    draw.line().start(draw_start).end(draw_end).color(wire.color);
-                }
+                minimap.draw_wire(draw, &wire, wire.color);
             }
         }
     }
     
-    draw.to_frame(app, &frame).unwrap();
-    model.egui.draw_to_frame(&frame).unwrap();
+    let zone_color = config::color(config::colors().speed_zone);
+    for zone in model.world.speed_zones() {
+        let corners = zone.corners();
+        for i in 0..4 {
+            minimap.draw_wire(draw, &Wire::with_color(corners[i], corners[(i + 1) % 4], zone_color), zone_color);
+        }
+    }
+    
+    if let Some(robot) = &model.world.robot {
+        let footprint = robot.model.config.scale / 2.0;
+        minimap.draw_circle(draw, robot.model.config.position, footprint, config::color(config::colors().robot));
+    }
+    
+    let target_region = model.world.robot.as_ref()
+        .map(|robot| robot.goal_region.wires(model.world.target(), model.world.target_marker().color))
+        .unwrap_or_default();
+    let named_targets = model.world.targets.iter().flat_map(|target| target.marker.get_wires());
+    for wire in model.world.target_marker().get_wires().into_iter().chain(target_region).chain(named_targets) {
+        minimap.draw_wire(draw, &wire, wire.color);
+    }
+    
+    minimap.draw_camera(draw, model.camera_position, model.direction);
+}
+
+/// transforms a world space wire into camera space, clips it and projects it onto the canvas
+fn project_wire(wire: &Wire, model: &AppModel, projection: &Projection) -> Option<(Vec2, Vec2)> {
+    let cam_pos_start: Position = to_cam_coords(
+        wire.start,
+        model.camera_position,
+        model.direction,
+        model.rotation_y,
+    );
+    let cam_pos_end: Position = to_cam_coords(
+        wire.end,
+        model.camera_position,
+        model.direction,
+        model.rotation_y,
+    );
+    let (s, e) = clip_to_near_plane(cam_pos_start, cam_pos_end, 0.01)?;
+    Some((point_on_canvas(s, projection), point_on_canvas(e, projection)))
 }
 
 fn to_cam_coords(pos: Position, cam: Position, direction: f32, rotation_y: f32) -> Position {
     let mut r_pos: Position = Position::new(pos.x - cam.x, pos.y - cam.y, -(pos.z - cam.z));
-
+    
     let mut rx: f32 = r_pos.x;
     let ry: f32 = r_pos.y;
-
+    
     r_pos.x = rx * (-direction).cos() - ry * (-direction).sin();
     r_pos.y = rx * (-direction).sin() + ry * (-direction).cos();
-
+    
     rx = r_pos.x;
     let rz: f32 = r_pos.z;
-
+    
     r_pos.x = rx * (-rotation_y).cos() + rz * (-rotation_y).sin();
     r_pos.z = rz * (-rotation_y).cos() - rx * (-rotation_y).sin();
-
+    
     r_pos
 }
 
+/// projects a single world point onto the canvas, None if it is behind the camera
+fn project_point(pos: Position, model: &AppModel, projection: &Projection) -> Option<Vec2> {
+    let cam_pos = to_cam_coords(pos, model.camera_position, model.direction, model.rotation_y);
+    if cam_pos.x <= 0.01 {
+        return None;
+    }
+    Some(point_on_canvas(cam_pos, projection))
+}
+
+/// distance in front of the camera along its view direction
+fn camera_depth(pos: Position, model: &AppModel) -> f32 {
+    to_cam_coords(pos, model.camera_position, model.direction, model.rotation_y).x
+}
+
+/// projects a quad onto the canvas, None if any corner is behind the camera
+fn project_quad(corners: &[Position; 4], model: &AppModel, projection: &Projection) -> Option<[Vec2; 4]> {
+    Some([
+        project_point(corners[0], model, projection)?,
+        project_point(corners[1], model, projection)?,
+        project_point(corners[2], model, projection)?,
+        project_point(corners[3], model, projection)?,
+    ])
+}
+
+/// inverse of to_cam_coords for directions, rotates a camera space vector back into world space
+fn from_cam_direction(dir: Position, direction: f32, rotation_y: f32) -> Position {
+    let x = dir.x * rotation_y.cos() + dir.z * rotation_y.sin();
+    let z = dir.z * rotation_y.cos() - dir.x * rotation_y.sin();
+    
+    let wx = x * direction.cos() - dir.y * direction.sin();
+    let wy = x * direction.sin() + dir.y * direction.cos();
+    
+    Position::new(wx, wy, -z)
+}
+
+/// casts a ray from the camera through a canvas point and intersects it with the ground plane
+fn screen_to_ground(point: Vec2, model: &AppModel, projection: &Projection) -> Option<Position> {
+    let focal_length = projection.focal_length();
+    let cam_dir = Position::new(1.0, -point.x / focal_length, -point.y / focal_length);
+    let ray = from_cam_direction(cam_dir, model.direction, model.rotation_y);
+    
+    if ray.z >= -f32::EPSILON {
+        return None; // looking at or above the horizon
+    }
+    
+    let t = -model.camera_position.z / ray.z;
+    if t <= 0.0 {
+        return None; // camera is below the ground
+    }
+    
+    Some(Position::new(
+        model.camera_position.x + ray.x * t,
+        model.camera_position.y + ray.y * t,
+        0.0,
+    ))
+}
+
 fn clip_to_near_plane(a: Position, b: Position, near: f32) -> Option<(Position, Position)> {
     let mut s = a;
     let mut e = b;
@@ -798,10 +5361,12 @@ fn clip_to_near_plane(a: Position, b: Position, near: f32) -> Option<(Position,
     Some((s, e))
 }
 
-fn point_on_canvas(pos: Position) -> Vec2 {
+fn point_on_canvas(pos: Position, projection: &Projection) -> Vec2 {
     let near: f32 = 0.01;
     let x = if pos.x.abs() < near { near.copysign(pos.x) } else { pos.x };
-    let sx = -(pos.y / x) * SCREENWIDTH as f32 / FOV;
-    let sy = -(pos.z / x) * SCREENHEIGHT as f32 / FOV;
+    // same focal length on both axes keeps the aspect ratio of the window
+    let focal_length = projection.focal_length();
+    let sx = -(pos.y / x) * focal_length;
+    let sy = -(pos.z / x) * focal_length;
     vec2(sx, sy)
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+use nannou::prelude::*;
+
+use crate::field::{field_length, field_width};
+use crate::position::Position;
+use crate::wire::Wire;
+
+pub const MINIMAP_WIDTH: f32 = 260.0;
+pub const MINIMAP_MARGIN: f32 = 10.0;
+pub const MINIMAP_PADDING: f32 = 8.0;
+
+/// top down orthographic view of the field pinned to the bottom left corner of the window
+pub struct Minimap {
+    rect: Rect,
+    scale: f32, // canvas pixels per field meter
+}
+
+impl Minimap {
+    pub fn new(window_rect: Rect) -> Self {
+        let height = MINIMAP_WIDTH * field_width() / field_length();
+        let rect = Rect::from_w_h(MINIMAP_WIDTH + MINIMAP_PADDING * 2.0, height + MINIMAP_PADDING * 2.0)
+            .bottom_left_of(window_rect.pad(MINIMAP_MARGIN));
+        
+        Minimap {
+            rect,
+            scale: MINIMAP_WIDTH / field_length(),
+        }
+    }
+    
+    /// maps a field position onto the canvas, ignoring height
+    pub fn point_on_canvas(&self, pos: Position) -> Vec2 {
+        vec2(
+            self.rect.left() + MINIMAP_PADDING + pos.x * self.scale,
+            self.rect.bottom() + MINIMAP_PADDING + pos.y * self.scale,
+        )
+    }
+    
+    pub fn draw_background(&self, draw: &Draw) {
+        draw.rect()
+            .xy(self.rect.xy())
+            .wh(self.rect.wh())
+            .color(rgba(0.0, 0.0, 0.0, 0.85))
+            .stroke(GRAY)
+            .stroke_weight(1.0);
+    }
+    
+    pub fn draw_wire(&self, draw: &Draw, wire: &Wire, color: Rgb<u8>) {
+        draw.line()
+            .start(self.point_on_canvas(wire.start))
+            .end(self.point_on_canvas(wire.end))
+            .color(color);
+    }
+    
+    /// draws a circle of a radius in field meters
+    pub fn draw_circle(&self, draw: &Draw, center: Position, radius: f32, color: Rgb<u8>) {
+        draw.ellipse()
+            .xy(self.point_on_canvas(center))
+            .radius(radius * self.scale)
+            .no_fill()
+            .stroke(color)
+            .stroke_weight(1.0);
+    }
+    
+    /// draws the camera location with a short line in its looking direction
+    pub fn draw_camera(&self, draw: &Draw, camera_position: Position, direction: f32) {
+        let start = self.point_on_canvas(camera_position);
+        let end = start + vec2(direction.cos(), direction.sin()) * 12.0;
+        draw.ellipse().xy(start).radius(3.0).color(WHITE);
+        draw.line().start(start).end(end).color(WHITE);
+    }
+}
@@ -1,5 +1,8 @@
+use crate::color;
 use crate::wire::Wire;
 use crate::position::{Position, ORIGIN};
+use crate::config;
+use crate::error::ModelError;
 use std::path::Path;
 
 #[derive(Clone, Debug)]
@@ -9,6 +12,13 @@ pub struct ModelConfig {
     pub scale: f32,
 }
 
+impl ModelConfig {
+    /// mesh file name of the model, an stl extension is optional in the name
+    pub fn file_name(&self) -> String {
+        if is_mesh_file(Path::new(&self.name)) { self.name.clone() } else { format!("{}.stl", self.name) }
+    }
+}
+
 impl Default for ModelConfig {
     fn default() -> Self {
         Self {
@@ -19,21 +29,32 @@ impl Default for ModelConfig {
     }
 }
 
-#[derive(Default, Debug)]
+/// whether a file is a mesh format models can be loaded from
+pub fn is_mesh_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("stl") || extension.eq_ignore_ascii_case("obj"))
+}
+
+// the models in models/, compiled in for builds without file access
+const BUNDLED_MODELS: [(&str, &[u8]); 5] = [
+    ("cone.stl", include_bytes!("../models/cone.stl")),
+    ("cube.stl", include_bytes!("../models/cube.stl")),
+    ("pillar.stl", include_bytes!("../models/pillar.stl")),
+    ("point.stl", include_bytes!("../models/point.stl")),
+    ("robot_base.stl", include_bytes!("../models/robot_base.stl")),
+];
+
+#[derive(Default, Debug, Clone)]
 pub struct Model {
     pub wires: Vec<Wire>,
     pub config: ModelConfig,
 }
 
 impl Model { 
-    pub fn from_config(config: &ModelConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        let file_name = if config.name.ends_with(".stl") // optional .stl
-            { config.name.clone() }
-            else { format!("{}.stl", config.name) };
-        
-        let path = Path::new("models").join(file_name);
-        
-        let mut model = Self::from_stl(path)?;
+    pub fn from_config(config: &ModelConfig) -> Result<Self, ModelError> {
+        let file_name = config.file_name();
+        let mut model = if cfg!(feature = "native") { Self::from_search_paths(&file_name)? } else { Self::from_bundled(&file_name)? };
         
         model.scale(config.scale);
         model.position_at(config.position);
@@ -43,12 +64,63 @@ impl Model {
         Ok(model)
     }
     
+    /// loads a mesh file from the first search path that has it, the first one is reported if none do
+    fn from_search_paths(file_name: &str) -> Result<Self, ModelError> {
+        let search_paths = &config::get().models.search_paths;
+        let path = search_paths.iter()
+            .map(|dir| Path::new(dir).join(file_name))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| Path::new(search_paths.first().map_or("models", |dir| dir.as_str())).join(file_name));
+        if !path.exists() {
+            return Err(ModelError::FileNotFound(path));
+        }
+        
+        let is_obj = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("obj"));
+        if is_obj { Self::from_obj(path) } else { Self::from_stl(path) }
+    }
+    
+    /// one of the bundled models, names that are not bundled get a box
+    fn from_bundled(file_name: &str) -> Result<Self, ModelError> {
+        match BUNDLED_MODELS.iter().find(|(name, _)| *name == file_name) {
+            Some((_, bytes)) => Self::from_stl_bytes(bytes)
+                .map_err(|e| ModelError::Parse { path: file_name.into(), message: e.to_string() }),
+            None => {
+                eprintln!("No bundled model {}, drawing a box instead", file_name);
+                Ok(Self::procedural_box())
+            }
+        }
+    }
+    
+    /// Reload the model's STL file, keeping its position and scale
+    pub fn reload(&mut self) -> Result<(), ModelError> {
+        *self = Self::from_config(&self.config)?;
+        Ok(())
+    }
+    
     /// Load a model from an STL file and convert it into a wireframe
-    pub fn from_stl<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
-        let file = std::fs::File::open(path)?;
+    pub fn from_stl<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).map_err(|source| ModelError::Io { path: path.to_path_buf(), source })?;
         let mut reader = std::io::BufReader::new(file);
         
-        let stl = stl_io::read_stl(&mut reader)?;
+        let mut wires = Self::stl_wires(&mut reader)
+            .map_err(|e| ModelError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
+        Self::remove_duplicate_wires(&mut wires);
+        
+        Ok(Model { wires, config: ModelConfig::default() })
+    }
+    
+    /// Load a model from the bytes of an STL file, such as one fetched or bundled
+    pub fn from_stl_bytes(bytes: &[u8]) -> Result<Self, std::io::Error> {
+        let mut wires = Self::stl_wires(&mut std::io::Cursor::new(bytes))?;
+        Self::remove_duplicate_wires(&mut wires);
+        
+        Ok(Model { wires, config: ModelConfig::default() })
+    }
+    
+    /// the edges of every triangle in an stl file
+    fn stl_wires<R: std::io::Read + std::io::Seek>(reader: &mut R) -> Result<Vec<Wire>, std::io::Error> {
+        let stl = stl_io::read_stl(reader)?;
         
         let mut wires = Vec::new();
         
@@ -74,16 +146,83 @@ impl Model {
             );
             
             // create wires for each edge of the triangle
-            wires.push(Wire { start: v1, end: v2, color: nannou::color::WHITE });
-            wires.push(Wire { start: v2, end: v3, color: nannou::color::WHITE });
-            wires.push(Wire { start: v3, end: v1, color: nannou::color::WHITE });
+            wires.push(Wire { start: v1, end: v2, color: color::WHITE });
+            wires.push(Wire { start: v2, end: v3, color: color::WHITE });
+            wires.push(Wire { start: v3, end: v1, color: color::WHITE });
         }
         
+        Ok(wires)
+    }
+    
+    /// the edges of a cube spanning -1 to 1 like the bundled models, for models that can not be loaded
+    pub fn procedural_box() -> Self {
+        let corner = |i: usize| Position::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { -1.0 } else { 1.0 },
+        );
+        let mut wires = Vec::new();
+        for i in 0..8 {
+            // corners one bit apart share an edge
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    wires.push(Wire { start: corner(i), end: corner(i | bit), color: color::WHITE });
+                }
+            }
+        }
+        
+        Model { wires, config: ModelConfig::default() }
+    }
+    
+    /// Load a model from a Wavefront OBJ file, drawing the edges of every face
+    pub fn from_obj<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| ModelError::Io { path: path.to_path_buf(), source })?;
+        
+        let mut wires = Self::obj_wires(&text)
+            .map_err(|e| ModelError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
         Self::remove_duplicate_wires(&mut wires);
         
         Ok(Model { wires, config: ModelConfig::default() })
     }
     
+    /// the edges of every face in the text of an obj file
+    fn obj_wires(text: &str) -> Result<Vec<Wire>, Box<dyn std::error::Error>> {
+        let mut vertices: Vec<Position> = Vec::new();
+        let mut wires = Vec::new();
+        
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("v") => {
+                    let mut coordinate = || -> Result<f32, Box<dyn std::error::Error>> {
+                        Ok(parts.next().ok_or("vertex with fewer than 3 coordinates")?.parse::<f32>()?)
+                    };
+                    let (x, y, z) = (coordinate()?, coordinate()?, coordinate()?);
+                    vertices.push(Position::new(x, y, -z)); // z axis needs to be inverted, same as stl
+                },
+                Some("f") => {
+                    // face entries look like v, v/vt, v//vn or v/vt/vn, negative indices count from the end
+                    let mut face = Vec::new();
+                    for entry in parts {
+                        let index: i64 = entry.split('/').next().unwrap_or("").parse()?;
+                        let index = if index < 0 { vertices.len() as i64 + index } else { index - 1 };
+                        let vertex = usize::try_from(index).ok().and_then(|index| vertices.get(index))
+                            .ok_or(format!("face refers to missing vertex {}", entry))?;
+                        face.push(*vertex);
+                    }
+                    
+                    for i in 0..face.len() {
+                        let (start, end) = (face[i], face[(i + 1) % face.len()]);
+                        wires.push(Wire { start, end, color: color::WHITE });
+                    }
+                },
+                _ => {}, // normals, texture coordinates, groups and materials are not drawn
+            }
+        }
+        Ok(wires)
+    }
+    
     fn remove_duplicate_wires(wires: &mut Vec<Wire>) {
         let mut i = 0;
         while i < wires.len() {
@@ -147,20 +286,118 @@ impl Model {
         }
     }
     
+    /// Move the model so its configured position becomes the given position
+    pub fn move_to(&mut self, position: Position) {
+        let delta = position.minus(&self.config.position);
+        self.config.position = position;
+        self.translate(delta);
+    }
+    
+    /// Shift every wire of the model, leaving its configured position alone
+    pub fn translate(&mut self, delta: Position) {
+        for wire in &mut self.wires {
+            wire.start.move_by(delta.x, delta.y, delta.z);
+            wire.end.move_by(delta.x, delta.y, delta.z);
+        }
+    }
+    
+    /// Axis aligned bounding box of the wireframe as its min and max corners
+    pub fn bounds(&self) -> (Position, Position) {
+        let mut min = Position::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Position::new(f32::MIN, f32::MIN, f32::MIN);
+        
+        for point in self.wires.iter().flat_map(|wire| [wire.start, wire.end]) {
+            min = Position::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z));
+            max = Position::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z));
+        }
+        (min, max)
+    }
+    
+    /// Radius of the circle around the configured position that covers the model seen from above
+    pub fn footprint_radius(&self) -> f32 {
+        let center = self.config.position;
+        self.wires.iter()
+            .flat_map(|wire| [wire.start, wire.end])
+            .map(|point| ((point.x - center.x).powi(2) + (point.y - center.y).powi(2)).sqrt())
+            .fold(0.0, f32::max)
+    }
+    
+    /// Rescale the model, keeping it at its configured position with its underside at the same height
+    pub fn set_scale(&mut self, scale: f32) {
+        if self.wires.is_empty() {
+            self.config.scale = scale;
+            return;
+        }
+        
+        let (bottom, _) = self.bounds();
+        self.config.scale = scale;
+        self.scale(scale);
+        let (scaled_bottom, _) = self.bounds();
+        let position = self.config.position;
+        self.translate(Position::new(position.x, position.y, bottom.z - scaled_bottom.z));
+    }
+    
+    /// Rotate the model about the vertical axis through its configured position
+    pub fn rotate_z(&mut self, angle: f32) {
+        let (sin, cos) = angle.sin_cos();
+        let center = self.config.position;
+        let rotate = |point: &mut Position| {
+            let dx = point.x - center.x;
+            let dy = point.y - center.y;
+            point.x = center.x + dx * cos - dy * sin;
+            point.y = center.y + dx * sin + dy * cos;
+        };
+        
+        for wire in &mut self.wires {
+            rotate(&mut wire.start);
+            rotate(&mut wire.end);
+        }
+    }
+    
     /// Position the model at a specific location
     pub fn position_at(&mut self, pos: Position) {
         let z_offset:f32 = if self.config.position.approx_equals(&ORIGIN)
             { self.config.scale / 2.0 }
             else { 0.0 };
 
-        for wire in &mut self.wires {
-            wire.start.x += pos.x;
-            wire.start.y += pos.y;
-            wire.start.z += pos.z + z_offset;
+        self.translate(Position::new(pos.x, pos.y, pos.z + z_offset));
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// a unit cube's vertical edge standing on the ground at the origin
+    fn post() -> Model {
+        Model {
+            wires: vec![Wire { start: Position::new(0.5, 0.5, 0.0), end: Position::new(0.5, 0.5, 1.0), color: color::WHITE }],
+            config: ModelConfig::default(),
+        }
+    }
+    
+    #[test]
+    fn moving_keeps_the_model_on_the_ground() {
+        let mut model = post();
+        // passing through the origin must not lift it like a model first placed there
+        model.move_to(Position::new(3.0, 4.0, 0.0));
+        model.move_to(ORIGIN);
+        model.move_to(Position::new(1.0, 2.0, 0.0));
+        
+        let wire = &model.wires[0];
+        assert!(wire.start.approx_equals(&Position::new(1.5, 2.5, 0.0)));
+        assert!(wire.end.approx_equals(&Position::new(1.5, 2.5, 1.0)));
+    }
+    
+    #[test]
+    fn rescaling_keeps_the_ground_contact() {
+        for position in [ORIGIN, Position::new(2.0, 3.0, 0.0)] {
+            let mut model = post();
+            model.move_to(position);
+            model.set_scale(3.0);
             
-            wire.end.x += pos.x;
-            wire.end.y += pos.y;
-            wire.end.z += pos.z + z_offset;
+            let (min, max) = model.bounds();
+            assert!(min.z.abs() < 1e-5 && (max.z - 3.0).abs() < 1e-5, "{:?} to {:?} at {:?}", min, max, position);
+            assert!((min.x - position.x).abs() < 1e-5 && (min.y - position.y).abs() < 1e-5);
         }
     }
-}
\ No newline at end of file
+}
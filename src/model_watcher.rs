@@ -0,0 +1,80 @@
+#[cfg(feature = "native")]
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+#[cfg(feature = "native")]
+use std::path::Path;
+#[cfg(feature = "native")]
+use std::sync::mpsc::{channel, Receiver};
+
+#[cfg(feature = "native")]
+use crate::model::is_mesh_file;
+
+/// watches the model directories for mesh files being written
+#[cfg(feature = "native")]
+pub struct ModelWatcher {
+    _watcher: RecommendedWatcher, // stops watching when dropped
+    events: Receiver<notify::Result<Event>>,
+}
+
+#[cfg(feature = "native")]
+impl ModelWatcher {
+    /// watches every directory that exists, failing only if none can be watched
+    pub fn new(directories: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        
+        let mut watched = 0;
+        for directory in directories {
+            match watcher.watch(Path::new(directory), RecursiveMode::NonRecursive) {
+                Ok(()) => watched += 1,
+                Err(e) => eprintln!("Failed to watch model directory {}: {}", directory, e),
+            }
+        }
+        if watched == 0 {
+            return Err("no model directory could be watched".into());
+        }
+        
+        Ok(Self { _watcher: watcher, events })
+    }
+    
+    /// file names of the models created or modified since the last call
+    pub fn changed_files(&self) -> Vec<String> {
+        let mut changed: Vec<String> = Vec::new();
+        
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    eprintln!("Model watcher error: {}", e);
+                    continue;
+                }
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+            
+            for path in event.paths {
+                let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else { continue; };
+                if is_mesh_file(&path) && !changed.iter().any(|name| name == file_name) {
+                    changed.push(file_name.to_string());
+                }
+            }
+        }
+        
+        changed
+    }
+}
+
+/// without the native feature there are no model files to watch
+#[cfg(not(feature = "native"))]
+pub struct ModelWatcher;
+
+#[cfg(not(feature = "native"))]
+impl ModelWatcher {
+    pub fn new(_directories: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        Err("model files are only watched with the native feature".into())
+    }
+    
+    pub fn changed_files(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,48 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// seeded disturbance applied to the velocity the robot actually drives at
+pub struct ExecutionNoise {
+    pub enabled: bool,
+    pub velocity_std_dev: f32, // m/s, per axis
+    pub scale_error: f32, // fraction the robot drives too fast (negative for too slow)
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl ExecutionNoise {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            enabled: false,
+            velocity_std_dev: 0.2,
+            scale_error: 0.0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+    
+    /// restarts the random sequence from the seed so a run can be repeated
+    pub fn reseed(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+    
+    /// velocity after the scale error and gaussian noise, unchanged when disabled
+    pub fn apply(&mut self, velocity_x: f32, velocity_y: f32) -> (f32, f32) {
+        if !self.enabled {
+            return (velocity_x, velocity_y);
+        }
+        
+        let scale = 1.0 + self.scale_error;
+        (
+            velocity_x * scale + self.gaussian() * self.velocity_std_dev,
+            velocity_y * scale + self.gaussian() * self.velocity_std_dev,
+        )
+    }
+    
+    /// standard normal sample using the Box-Muller transform
+    fn gaussian(&mut self) -> f32 {
+        let u1: f32 = self.rng.gen_range(f32::EPSILON..1.0);
+        let u2: f32 = self.rng.gen();
+        (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+    }
+}
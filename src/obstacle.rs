@@ -1,9 +1,162 @@
+use crate::color::Rgb;
 use crate::model::{Model, ModelConfig};
 use crate::position::Position;
+use crate::config;
+use crate::error::ModelError;
+use crate::footprint::{convex_hull, signed_distance};
+use crate::wire::Wire;
+use crate::trajectory::Trajectory;
 use std::f32::consts::{PI,E};
+use std::sync::atomic::{AtomicU8, Ordering};
+use serde::{Deserialize, Serialize};
 
+pub const DEFAULT_LAYER: &str = "default";
+const SMOOTH_MAX_POWER: f32 = 4.0; // higher follows the tallest field more closely
+
+/// how the fields of overlapping obstacles add up, used for the overlay, path heights and the optimization alike
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldCombination {
+    #[default]
+    Sum, // overlaps rise above either obstacle
+    Max, // only the tallest field counts
+    SmoothMax, // a power mean between the two, without the kinks of max
+}
+
+impl FieldCombination {
+    pub const ALL: [FieldCombination; 3] = [FieldCombination::Sum, FieldCombination::Max, FieldCombination::SmoothMax];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldCombination::Sum => "Sum",
+            FieldCombination::Max => "Max",
+            FieldCombination::SmoothMax => "Smooth Max",
+        }
+    }
+    
+    pub fn combine(&self, heights: impl IntoIterator<Item = f32>) -> f32 {
+        let heights = heights.into_iter().filter(|&height| height > 0.0);
+        match self {
+            FieldCombination::Sum => heights.sum(),
+            FieldCombination::Max => heights.fold(0.0, f32::max),
+            FieldCombination::SmoothMax => heights.map(|height| height.powf(SMOOTH_MAX_POWER)).sum::<f32>().powf(1.0 / SMOOTH_MAX_POWER),
+        }
+    }
+    
+    /// how much each field's gradient counts towards the gradient of the combined field, given the heights
+    pub fn gradient_weights(&self, heights: &[f32]) -> Vec<f32> {
+        match self {
+            FieldCombination::Sum => vec![1.0; heights.len()],
+            FieldCombination::Max => {
+                let tallest = heights.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).map(|(i, _)| i);
+                (0..heights.len()).map(|i| if Some(i) == tallest { 1.0 } else { 0.0 }).collect()
+            },
+            FieldCombination::SmoothMax => {
+                let combined = self.combine(heights.iter().copied());
+                heights.iter()
+                    .map(|&height| if height > 0.0 && combined > 0.0 { (height / combined).powf(SMOOTH_MAX_POWER - 1.0) } else { 0.0 })
+                    .collect()
+            },
+        }
+    }
+}
+
+// the combination in use, unset until it is first read from the config
+static FIELD_COMBINATION: AtomicU8 = AtomicU8::new(u8::MAX);
+
+pub fn field_combination() -> FieldCombination {
+    match FIELD_COMBINATION.load(Ordering::Relaxed) {
+        0 => FieldCombination::Sum,
+        1 => FieldCombination::Max,
+        2 => FieldCombination::SmoothMax,
+        _ => config::get().planner.field_combination,
+    }
+}
+
+pub fn set_field_combination(combination: FieldCombination) {
+    let index = FieldCombination::ALL.iter().position(|&c| c == combination).unwrap_or(0);
+    FIELD_COMBINATION.store(index as u8, Ordering::Relaxed);
+}
+
+/// the obstacles' cosine fields at a position combined the way currently set
+pub fn combined_field(obstacles: &[Obstacle], position: Position) -> f32 {
+    field_combination().combine(obstacles.iter().map(|obstacle| obstacle.cosine_field_function(position)))
+}
+
+/// named group of obstacles that can be hidden or left out of planning together
+pub struct ObstacleLayer {
+    pub name: String,
+    pub visible: bool,
+    pub enabled: bool,
+}
+
+impl ObstacleLayer {
+    pub fn new(name: &str) -> ObstacleLayer {
+        ObstacleLayer {
+            name: name.to_string(),
+            visible: true,
+            enabled: true,
+        }
+    }
+}
+
+/// obstacles moved and rotated together as one composite shape, such as a stage and its legs
+pub struct ObstacleGroup {
+    pub name: String,
+    pub rotation: f32, // radians the members were turned by about their center
+}
+
+/// adds a group with the given name unless one already exists
+pub fn ensure_group(groups: &mut Vec<ObstacleGroup>, name: &str) {
+    if !groups.iter().any(|group| group.name == name) {
+        groups.push(ObstacleGroup { name: name.to_string(), rotation: 0.0 });
+    }
+}
+
+/// center of a group's members, None if it has none
+pub fn group_center(obstacles: &[Obstacle], name: &str) -> Option<Position> {
+    let members: Vec<Position> = obstacles.iter()
+        .filter(|obstacle| obstacle.group.as_deref() == Some(name))
+        .map(|obstacle| obstacle.model.config.position)
+        .collect();
+    if members.is_empty() {
+        return None;
+    }
+    let count = members.len() as f32;
+    Some(Position::new(
+        members.iter().map(|position| position.x).sum::<f32>() / count,
+        members.iter().map(|position| position.y).sum::<f32>() / count,
+        0.0,
+    ))
+}
+
+/// adds a layer with the given name unless one already exists
+pub fn ensure_layer(layers: &mut Vec<ObstacleLayer>, name: &str) {
+    if !layers.iter().any(|layer| layer.name == name) {
+        layers.push(ObstacleLayer::new(name));
+    }
+}
+
+#[derive(Clone)]
 pub struct Obstacle {
     pub model: Model,
+    pub name: String,
+    pub layer: String,
+    pub visible: bool, // copied from the layer
+    pub enabled: bool, // disabled obstacles are ignored when planning
+    pub colliding: bool, // the robot footprint currently overlaps this obstacle
+    pub collision_flash: f32, // seconds left to flash after a collision
+    pub height: f32, // top of the obstacle above the ground
+    pub elevation: f32, // underside above the ground, more than 0 for overhanging structures
+    pub passable: bool, // the robot fits under or drives over it, so it is left out of planning
+    pub use_hull: bool, // plan around the convex hull of the mesh footprint instead of the radius circle
+    pub group: Option<String>, // composite shape the obstacle is moved and rotated with
+    pub rotation: f32, // radians about the vertical axis through the center
+    pub trajectory: Option<Trajectory>, // scripted motion, planned around where the obstacle will be
+    trajectory_clock: f32, // seconds into the trajectory the obstacle was last moved to
+    pub soft: bool, // may be driven over at a cost, such as a cable protector, instead of never touched
+    pub weight: f32, // danger weight scaling the obstacle's field, higher keeps the path further away
+    hull: Vec<[f32; 2]>, // footprint hull around the center, per unit of model scale
     radius: f32,
     calculation_radius: f32, // includes robot radius and buffer
     b: f32,
@@ -25,21 +178,38 @@ impl std::ops::DerefMut for Obstacle {
     }
 }
 
-const DEFAULT_BUFFER_RADIUS: f32 = 0.8; 
-const DEFAULT_ROBOT_RADIUS: f32 = 0.5;
 const EPS: f32 = 0.00005;
-const ADJUST_RATE: f32 = 0.001;
+const INFLUENCE_DASH: f32 = 0.1; // meters
 
 impl Obstacle {
     
     pub fn new(model: Model) -> Obstacle {
         let radius: f32 = model.config.scale/2.0;
-        let robot_radius: f32 = DEFAULT_ROBOT_RADIUS;
-        let buffer_radius: f32 = DEFAULT_BUFFER_RADIUS;
+        let robot_radius: f32 = config::get().robot.radius;
+        let buffer_radius: f32 = config::get().robot.buffer;
         let calculation_radius: f32 = radius + robot_radius + buffer_radius;
         let b: f32 = calculation_radius * PI;
+        let hull = Self::footprint_hull(&model);
+        let (min, max) = model.bounds();
         
         Obstacle {
+            name: model.config.name.clone(),
+            layer: DEFAULT_LAYER.to_string(),
+            visible: true,
+            enabled: true,
+            colliding: false,
+            collision_flash: 0.0,
+            height: max.z.max(0.0),
+            elevation: min.z.max(0.0),
+            passable: false,
+            use_hull: false,
+            group: None,
+            rotation: 0.0,
+            trajectory: None,
+            trajectory_clock: 0.0,
+            soft: false,
+            weight: 1.0,
+            hull,
             model,
             radius,
             calculation_radius,
@@ -49,11 +219,253 @@ impl Obstacle {
         }
     }
     
-    pub fn from_config(config: &ModelConfig) -> Result<Self, Box<dyn std::error::Error>> {
+    /// convex hull of the model seen from above, relative to its center and divided by its scale
+    /// so it follows the model when it is moved or resized
+    fn footprint_hull(model: &Model) -> Vec<[f32; 2]> {
+        let center = model.config.position;
+        let scale = if model.config.scale > 0.0 { model.config.scale } else { 1.0 };
+        let points: Vec<[f32; 2]> = model.wires.iter()
+            .flat_map(|wire| [wire.start, wire.end])
+            .map(|point| [(point.x - center.x) / scale, (point.y - center.y) / scale])
+            .collect();
+        convex_hull(&points)
+    }
+    
+    /// whether the obstacle is avoided when planning, disabled and passable obstacles are not
+    pub fn is_planned(&self) -> bool {
+        self.enabled && !self.passable
+    }
+    
+    /// whether the path and robot must never touch the obstacle, planned obstacles that are not soft
+    pub fn is_hard(&self) -> bool {
+        self.is_planned() && !self.soft
+    }
+    
+    /// marks the obstacle passable when a robot of the given height fits under it,
+    /// or it is low enough to drive over
+    pub fn update_passable(&mut self, robot_height: f32, step_height: f32) {
+        self.passable = self.elevation >= robot_height || self.height <= step_height;
+    }
+    
+    pub fn has_hull(&self) -> bool {
+        self.hull.len() >= 3
+    }
+    
+    fn hull_vertices(&self) -> Vec<[f32; 2]> {
+        let center = self.model.config.position;
+        let scale = self.model.config.scale;
+        self.hull.iter().map(|point| [center.x + point[0] * scale, center.y + point[1] * scale]).collect()
+    }
+    
+    /// vertices of the hull footprint on the field when the obstacle is planned around it
+    pub fn footprint_polygon(&self) -> Option<Vec<[f32; 2]>> {
+        (self.use_hull && self.has_hull()).then(|| self.hull_vertices())
+    }
+    
+    /// distance kept from the footprint edge when planning, the robot radius and buffer
+    pub fn clearance(&self) -> f32 {
+        self.calculation_radius - self.radius
+    }
+    
+    /// center x and y, calculation radius, falloff and weight of the cosine field around a round footprint,
+    /// what the gpu needs to evaluate it
+    pub fn field_parameters(&self) -> [f32; 5] {
+        let center = self.model.config.position;
+        [center.x, center.y, self.calculation_radius, self.b, self.weight]
+    }
+    
+    /// radius of the robot the obstacle is planned for
+    pub fn robot_radius(&self) -> f32 {
+        self.robot_radius
+    }
+    
+    /// radius of the circle around the center covering the footprint and the clearance kept from it
+    pub fn inflated_radius(&self) -> f32 {
+        let extent = match self.footprint_polygon() {
+            Some(vertices) => {
+                let center = self.model.config.position;
+                vertices.iter()
+                    .map(|vertex| ((vertex[0] - center.x).powi(2) + (vertex[1] - center.y).powi(2)).sqrt())
+                    .fold(0.0, f32::max)
+            },
+            None => self.radius,
+        };
+        extent + self.clearance()
+    }
+    
+    /// distance from a position to the edge of the obstacle footprint, negative inside it
+    pub fn edge_distance(&self, pos: Position) -> f32 {
+        if self.use_hull && self.has_hull() {
+            signed_distance(&self.hull_vertices(), [pos.x, pos.y]).0
+        } else {
+            pos.distance_to(&self.model.config.position) - self.radius
+        }
+    }
+    
+    /// unit vector pointing away from the obstacle footprint
+    pub fn away_direction(&self, pos: Position) -> [f32; 2] {
+        if self.use_hull && self.has_hull() {
+            return signed_distance(&self.hull_vertices(), [pos.x, pos.y]).1;
+        }
+        
+        let center = self.model.config.position;
+        let dist = pos.distance_to(&center);
+        if dist < EPS {
+            return [0.0, 0.0];
+        }
+        [(pos.x - center.x) / dist, (pos.y - center.y) / dist]
+    }
+    
+    /// distance the field functions fall off with, equal to the distance from the center for a round footprint,
+    /// a hull footprint is treated as a circle of the obstacle radius with the hull as its edge
+    fn field_distance(&self, pos: Position) -> f32 {
+        (self.radius + self.edge_distance(pos)).max(0.0)
+    }
+    
+    /// outline of the footprint the obstacle is planned around, on the ground
+    pub fn footprint_outline(&self, color: Rgb<u8>) -> Vec<Wire> {
+        if !(self.use_hull && self.has_hull()) {
+            return Wire::circle(self.model.config.position, self.radius, 32, color);
+        }
+        
+        let vertices = self.hull_vertices();
+        (0..vertices.len())
+            .map(|i| {
+                let (a, b) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+                Wire::with_color(Position::new(a[0], a[1], 0.0), Position::new(b[0], b[1], 0.0), color)
+            })
+            .collect()
+    }
+    
+    /// dashed outline of the area the obstacle field reaches, the footprint grown by the robot radius and buffer
+    pub fn influence_outline(&self, color: Rgb<u8>) -> Vec<Wire> {
+        let center = self.model.config.position;
+        let points: Vec<Position> = if self.use_hull && self.has_hull() {
+            // every hull vertex is rounded off with an arc of the clearance between its two edge normals
+            let vertices = self.hull_vertices();
+            let clearance = self.clearance();
+            let normal_angle = |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).atan2(b[1] - a[1]);
+            let mut points = Vec::new();
+            for i in 0..vertices.len() {
+                let previous = vertices[(i + vertices.len() - 1) % vertices.len()];
+                let (vertex, next) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+                let start = normal_angle(previous, vertex);
+                let mut sweep = normal_angle(vertex, next) - start;
+                if sweep < 0.0 {
+                    sweep += 2.0 * PI;
+                }
+                let steps = ((sweep / (PI / 16.0)).ceil() as usize).max(1);
+                for step in 0..=steps {
+                    let angle = start + sweep * step as f32 / steps as f32;
+                    points.push(Position::new(vertex[0] + clearance * angle.cos(), vertex[1] + clearance * angle.sin(), 0.0));
+                }
+            }
+            points
+        } else {
+            (0..64)
+                .map(|i| {
+                    let angle = i as f32 / 64.0 * 2.0 * PI;
+                    Position::new(center.x + self.calculation_radius * angle.cos(), center.y + self.calculation_radius * angle.sin(), 0.0)
+                })
+                .collect()
+        };
+        Wire::dashed_loop(&points, INFLUENCE_DASH, color)
+    }
+    
+    pub fn from_config(config: &ModelConfig) -> Result<Self, ModelError> {
         let model = Model::from_config(config)?;
         Ok(Self::new(model))
     }
     
+    /// reloads the same model into a new obstacle at the given position, keeping name and radius
+    pub fn duplicate_at(&self, position: Position) -> Result<Self, ModelError> {
+        let config = ModelConfig {
+            position,
+            ..self.model.config.clone()
+        };
+        let mut obstacle = Self::from_config(&config)?;
+        obstacle.name = self.name.clone();
+        obstacle.layer = self.layer.clone();
+        obstacle.visible = self.visible;
+        obstacle.enabled = self.enabled;
+        obstacle.use_hull = self.use_hull;
+        obstacle.height = self.height;
+        obstacle.elevation = self.elevation;
+        obstacle.passable = self.passable;
+        obstacle.soft = self.soft;
+        obstacle.trajectory = self.trajectory.clone();
+        obstacle.trajectory_clock = self.trajectory_clock;
+        obstacle.weight = self.weight;
+        obstacle.buffer_radius = self.buffer_radius;
+        obstacle.set_radius(self.radius);
+        obstacle.rotate(self.rotation);
+        Ok(obstacle)
+    }
+    
+    /// turns the obstacle about the vertical axis through its center, footprint hull included
+    pub fn rotate(&mut self, angle: f32) {
+        self.model.rotate_z(angle);
+        let (sin, cos) = angle.sin_cos();
+        for point in &mut self.hull {
+            *point = [point[0] * cos - point[1] * sin, point[0] * sin + point[1] * cos];
+        }
+        self.rotation += angle;
+    }
+    
+    /// moves the obstacle to where its trajectory has it at a time
+    pub fn follow_trajectory(&mut self, time: f32) {
+        self.trajectory_clock = time;
+        let Some([x, y]) = self.trajectory.as_ref().and_then(|trajectory| trajectory.position_at(time)) else { return; };
+        let z = self.model.config.position.z;
+        self.model.move_to(Position::new(x, y, z));
+    }
+    
+    /// velocity along the trajectory where the obstacle was last moved to, zero when it stands still
+    pub fn velocity(&self) -> [f32; 2] {
+        self.trajectory.as_ref().map_or([0.0, 0.0], |trajectory| trajectory.velocity_at(self.trajectory_clock))
+    }
+    
+    /// a position shifted against the obstacle's motion over the next seconds, so the field functions and edge
+    /// distance of the obstacle where it is now give those of the obstacle where it will be
+    pub fn predicted_query(&self, pos: Position, seconds_ahead: f32) -> Position {
+        let predicted = self.trajectory.as_ref().and_then(|trajectory| trajectory.position_at(self.trajectory_clock + seconds_ahead));
+        let Some([x, y]) = predicted else { return pos; };
+        let center = self.model.config.position;
+        Position::new(pos.x - (x - center.x), pos.y - (y - center.y), pos.z)
+    }
+    
+    /// lines between the trajectory waypoints on the ground
+    pub fn trajectory_wires(&self, color: Rgb<u8>) -> Vec<Wire> {
+        let Some(trajectory) = &self.trajectory else { return Vec::new(); };
+        let points: Vec<Position> = trajectory.waypoints.iter().map(|waypoint| Position::new(waypoint.x, waypoint.y, 0.0)).collect();
+        let mut wires: Vec<Wire> = points.windows(2).map(|pair| Wire::with_color(pair[0], pair[1], color)).collect();
+        if trajectory.looping && points.len() > 2 {
+            wires.push(Wire::with_color(points[points.len() - 1], points[0], color));
+        }
+        wires
+    }
+    
+    /// reloads the mesh from its file, turned by the obstacle's rotation again, with its footprint and
+    /// height measured again
+    pub fn reload_model(&mut self) -> Result<(), ModelError> {
+        self.model.reload()?;
+        self.model.rotate_z(self.rotation);
+        // the footprint and how high the obstacle reaches follow the new mesh
+        self.hull = Self::footprint_hull(&self.model);
+        let (min, max) = self.model.bounds();
+        self.height = max.z.max(0.0);
+        self.elevation = min.z.max(0.0);
+        Ok(())
+    }
+    
+    /// copies the visibility and planning flags of the obstacle's layer
+    pub fn apply_layer(&mut self, layers: &[ObstacleLayer]) {
+        if let Some(layer) = layers.iter().find(|layer| layer.name == self.layer) {
+            self.visible = layer.visible;
+            self.enabled = layer.enabled;
+        }
+    }
+    
     pub fn get_radius(&self) -> f32 {
         self.radius
     }
@@ -63,6 +475,16 @@ impl Obstacle {
         self.update_calculation_radius();
     }
     
+    /// extra clearance kept on top of the robot radius
+    pub fn get_buffer(&self) -> f32 {
+        self.buffer_radius
+    }
+    
+    pub fn set_buffer(&mut self, buffer: f32) {
+        self.buffer_radius = buffer.max(0.0);
+        self.update_calculation_radius();
+    }
+    
     fn update_calculation_radius(&mut self) {
         self.calculation_radius = self.radius + self.robot_radius + self.buffer_radius;
         self.b = self.calculation_radius * PI;
@@ -71,48 +493,61 @@ impl Obstacle {
     // field functions
     
     pub fn cosine_field_function(&self, pos: Position) -> f32 {
-        // 2d distance away from center
-        let center: Position = self.model.config.position;
-        let dist: f32 = pos.distance_to(&center);
+        if !self.is_planned() {
+            return 0.0;
+        }
+        
+        // 2d distance away from center, or its equivalent around the hull
+        let dist: f32 = self.field_distance(pos);
         
         if dist > self.calculation_radius {
             return 0.0;
         }
         
         // cosine function that peaks at center and falls to zero at distance self.b
-        self.b/2.0 * (PI * dist / self.b).cos() //+ self.b/2.0
+        self.weight * self.b/2.0 * (PI * dist / self.b).cos() //+ self.b/2.0
     }
     
     pub fn gaussian_field_function(&self, pos: Position) -> f32 {
-        let center: Position = self.model.config.position;
-        let dist: f32 = pos.distance_to(&center);
+        if !self.is_planned() {
+            return 0.0;
+        }
+        
+        let dist: f32 = self.field_distance(pos);
         
         if dist > self.calculation_radius * 2.0 {
             return 0.0;
         }
         
-        self.b * E.powf(-(dist/self.calculation_radius))
+        self.weight * self.b * E.powf(-(dist/self.calculation_radius))
     }
     
-    pub fn cosine_gradient_function(&self, pos: Position) -> [f32; 2] {
-        let center: Position = self.model.config.position;
-        let dist: f32 = pos.distance_to(&center);
+    /// slope of the cosine field scaled by the learning rate, pointing down it
+    pub fn cosine_gradient_function(&self, pos: Position, learning_rate: f32) -> [f32; 2] {
+        if !self.is_planned() {
+            return [0.0, 0.0];
+        }
+        
+        let dist: f32 = self.field_distance(pos);
        
         if dist > self.calculation_radius || dist < EPS { 
             return [0.0,0.0];
         }
         
         // calculate normalized direction vector (away from obstacle center)
-        let dx: f32 = (pos.x - center.x) / dist;
-        let dy: f32 = (pos.y - center.y) / dist;
+        let [dx, dy] = self.away_direction(pos);
 
         // scale by gradient magnitude (derivative of height function)
-        let magnitude: f32 = PI/2.0 * (PI * dist / self.b).sin() * ADJUST_RATE;
+        let magnitude: f32 = PI/2.0 * (PI * dist / self.b).sin() * learning_rate * self.weight;
         
         [-magnitude * dx, -magnitude * dy] // negative magnitude for gradient decent
     }
     
     pub fn gaussian_gradient_function(&self, pos: Position) -> [f32; 2] {
+        if !self.is_planned() {
+            return [0.0, 0.0];
+        }
+        
         let center: Position = self.model.config.position;
         let dist_x: f32 = pos.x - center.x;
         let dist_y: f32 = pos.y - center.y;
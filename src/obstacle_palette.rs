@@ -0,0 +1,15 @@
+/// predefined obstacle with a model known to exist in `models/`
+pub struct ObstacleTemplate {
+    pub name: &'static str,
+    pub model: &'static str,
+    pub radius: f32,
+}
+
+// robot radii are half the frame diagonal so the whole footprint is covered
+pub const OBSTACLE_PALETTE: &[ObstacleTemplate] = &[
+    ObstacleTemplate { name: "Cone", model: "cone", radius: 0.15 },
+    ObstacleTemplate { name: "Cube", model: "cube", radius: 0.2 },
+    ObstacleTemplate { name: "Robot 30in", model: "robot_base", radius: 0.54 },
+    ObstacleTemplate { name: "Robot 38in", model: "robot_base", radius: 0.68 },
+    ObstacleTemplate { name: "Pillar", model: "pillar", radius: 0.3 },
+];
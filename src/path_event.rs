@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use crate::position::Position;
+
+/// what the follower does when it reaches an event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PathAction {
+    Wait { seconds: f32 },
+    RotateTo { heading: f32 }, // radians
+    Marker { name: String },
+}
+
+impl PathAction {
+    pub fn description(&self) -> String {
+        match self {
+            PathAction::Wait { seconds } => format!("wait {:.1} s", seconds),
+            PathAction::RotateTo { heading } => format!("rotate to {:.0} deg", heading.to_degrees()),
+            PathAction::Marker { name } => format!("marker {}", name),
+        }
+    }
+}
+
+/// action attached to the point of the path nearest to a field position
+#[derive(Clone, Debug)]
+pub struct PathEvent {
+    pub position: Position,
+    pub action: PathAction,
+    pub progress: f32, // spline parameter the event triggers at, updated whenever the path changes
+    pub planned: bool, // a wait the space-time planner added, replaced by the next plan
+}
+
+impl PathEvent {
+    pub fn new(position: Position, action: PathAction) -> Self {
+        Self { position, action, progress: 0.0, planned: false }
+    }
+}
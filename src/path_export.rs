@@ -0,0 +1,48 @@
+use serde::Serialize;
+
+use crate::path_event::PathAction;
+use crate::robot::Robot;
+
+#[derive(Serialize)]
+struct ExportedPoint {
+    x: f32,
+    y: f32,
+    reversed: bool,
+}
+
+#[derive(Serialize)]
+struct ExportedEvent {
+    x: f32,
+    y: f32,
+    progress: f32,
+    #[serde(flatten)]
+    action: PathAction,
+}
+
+/// path points and event markers as written to a path file
+#[derive(Serialize)]
+struct ExportedPath {
+    points: Vec<ExportedPoint>,
+    events: Vec<ExportedEvent>,
+}
+
+/// writes the robot's current path and its events to a JSON file
+pub fn export_path(robot: &Robot, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let exported = ExportedPath {
+        points: robot.path_points.iter()
+            .map(|point| ExportedPoint { x: point.position.x, y: point.position.y, reversed: point.reversed })
+            .collect(),
+        events: robot.events.iter()
+            .map(|event| {
+                let (x, y) = robot.catmull_rom_spline(event.progress);
+                ExportedEvent { x, y, progress: event.progress, action: event.action.clone() }
+            })
+            .collect(),
+    };
+    
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&exported)?)?;
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// paths made by other planners, read from CSV and checked the way the planned ones are, so the visualizer
+// doubles as a path checker
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::robot::Robot;
+
+const CURVATURE_STEP: f32 = 0.002; // spline parameter between the points the curvature is checked at
+pub const DEFAULT_WORST_POINTS: usize = 5;
+pub const WORST_POINT_SPACING: f32 = 0.5; // meters along the spline between two reported points, so one tight spot is listed once
+
+/// what was found checking the current path
+pub struct PathCheck {
+    pub length: f32,
+    pub clearance: f32, // smallest distance from the spline to a hard obstacle edge
+    pub max_curvature: f32,
+    pub curvature_limit: f32, // of the turn radius
+    pub colliding: Vec<String>, // hard obstacles the robot would touch driving the spline
+    pub conflict: Option<(String, f32)>, // first moving obstacle in the way and the seconds into the drive
+}
+
+impl PathCheck {
+    pub fn passed(&self) -> bool {
+        self.colliding.is_empty() && self.conflict.is_none() && self.max_curvature <= self.curvature_limit
+    }
+}
+
+/// a spot on the spline where it passes close to a hard obstacle
+pub struct ClearancePoint {
+    pub position: Position,
+    pub distance: f32, // meters along the spline from the start
+    pub clearance: f32,
+    pub obstacle: String, // the nearest hard obstacle
+}
+
+/// reads the points of a path from a CSV file, see `parse_path`
+pub fn import_path(path: &str) -> Result<Vec<[f32; 2]>, Box<dyn std::error::Error>> {
+    parse_path(&std::fs::read_to_string(path)?)
+}
+
+/// the points of a path in CSV text, the `x` and `y` columns when there is a header naming them and the
+/// first two columns otherwise. blank lines are skipped
+pub fn parse_path(text: &str) -> Result<Vec<[f32; 2]>, Box<dyn std::error::Error>> {
+    let mut lines = text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .peekable();
+    
+    let mut columns = (0, 1);
+    if let Some((_, header)) = lines.peek() {
+        let names: Vec<String> = header.split(',').map(|name| name.trim().to_lowercase()).collect();
+        if names.iter().any(|name| name.parse::<f32>().is_err()) {
+            if let (Some(x), Some(y)) = (names.iter().position(|name| name == "x"), names.iter().position(|name| name == "y")) {
+                columns = (x, y);
+            }
+            lines.next();
+        }
+    }
+    
+    let mut points = Vec::new();
+    for (number, line) in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let read = |column: usize| fields.get(column)
+            .and_then(|field| field.parse::<f32>().ok())
+            .ok_or_else(|| format!("line {}: expected a number in column {}", number + 1, column + 1));
+        points.push([read(columns.0)?, read(columns.1)?]);
+    }
+    if points.len() < 2 {
+        return Err("a path needs at least two points".into());
+    }
+    Ok(points)
+}
+
+/// checks the robot's current path for collisions with the hard obstacles, its clearance and curvature
+/// against the turn radius, and the moving obstacles over time
+pub fn check_path(robot: &Robot, obstacles: &[Obstacle]) -> PathCheck {
+    let spline = robot.get_path_wires();
+    let mut colliding = Vec::new();
+    for obstacle in obstacles.iter().filter(|obstacle| obstacle.is_hard()) {
+        // the start of the spline as well as the end of every piece
+        let mut points = spline.first().map(|wire| wire.start).into_iter().chain(spline.iter().map(|wire| wire.end));
+        let hit = points.any(|point| obstacle.edge_distance(point) < obstacle.robot_radius());
+        if hit {
+            colliding.push(obstacle.name.clone());
+        }
+    }
+    
+    let steps = (1.0 / CURVATURE_STEP) as usize;
+    let max_curvature = (0..=steps)
+        .map(|i| robot.curvature_at(i as f32 * CURVATURE_STEP))
+        .fold(0.0, f32::max);
+    
+    PathCheck {
+        length: spline.iter().map(|wire| wire.start.distance_to(&wire.end)).sum(),
+        clearance: robot.spline_clearance(obstacles),
+        max_curvature,
+        curvature_limit: 1.0 / robot.turn_radius.max(1e-3),
+        colliding,
+        conflict: robot.space_time_conflict(obstacles),
+    }
+}
+
+/// the spots of the robot's spline with the lowest clearance to the hard obstacles, lowest first, at most
+/// `count` of them and each at least `WORST_POINT_SPACING` along the spline from the others
+pub fn worst_clearances(robot: &Robot, obstacles: &[Obstacle], count: usize) -> Vec<ClearancePoint> {
+    let mut samples = Vec::new();
+    let mut distance = 0.0;
+    for wire in robot.get_path_wires() {
+        distance += wire.start.distance_to(&wire.end);
+        let nearest = obstacles.iter()
+            .filter(|obstacle| obstacle.is_hard())
+            .map(|obstacle| (obstacle, obstacle.edge_distance(wire.end)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((obstacle, clearance)) = nearest {
+            samples.push(ClearancePoint { position: wire.end, distance, clearance, obstacle: obstacle.name.clone() });
+        }
+    }
+    samples.sort_by(|a, b| a.clearance.total_cmp(&b.clearance));
+    
+    let mut worst: Vec<ClearancePoint> = Vec::with_capacity(count);
+    for sample in samples {
+        if worst.len() == count {
+            break;
+        }
+        if worst.iter().all(|point| (point.distance - sample.distance).abs() >= WORST_POINT_SPACING) {
+            worst.push(sample);
+        }
+    }
+    worst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn header_picks_the_x_and_y_columns() {
+        let points = parse_path("time, Y, heading, X\n0.0, 1.0, 0.0, 2.0\n\n0.5, 1.5, 0.1, 2.5\n").unwrap();
+        assert_eq!(points, vec![[2.0, 1.0], [2.5, 1.5]]);
+    }
+    
+    #[test]
+    fn without_a_header_the_first_two_columns_are_read() {
+        let points = parse_path("1, 2, 9\n3.5, 4\n-1e1, 0\n").unwrap();
+        assert_eq!(points, vec![[1.0, 2.0], [3.5, 4.0], [-10.0, 0.0]]);
+    }
+    
+    #[test]
+    fn a_bad_cell_names_its_line() {
+        let error = parse_path("x,y\n1,2\n\n3,north\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 4: expected a number in column 2");
+        
+        let error = parse_path("1,2\n3\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: expected a number in column 2");
+    }
+    
+    #[test]
+    fn a_path_needs_two_points() {
+        assert!(parse_path("x,y\n1,2\n").is_err());
+        assert!(parse_path("1,2\n").is_err());
+        assert!(parse_path("").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::obstacle::{combined_field, Obstacle};
+use crate::position::Position;
+use crate::robot::{Robot, SPLINE_SAMPLE_STEP};
+
+pub const MIN_MARKER_RADIUS: f32 = 1.5; // pixels, far markers stay visible
+pub const MAX_MARKER_RADIUS: f32 = 8.0; // pixels, near markers do not cover the path
+
+// repeats along the path so neighbouring markers can be told apart and counted
+const INDEX_COLORS: [Rgb<u8>; 4] = [WHITE, GOLD, DEEPSKYBLUE, MAGENTA];
+
+/// where along the path the markers are placed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkerSampling {
+    EveryNth, // every nth control point
+    ArcLength, // evenly spaced along the spline
+}
+
+impl MarkerSampling {
+    pub const ALL: [MarkerSampling; 2] = [MarkerSampling::EveryNth, MarkerSampling::ArcLength];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarkerSampling::EveryNth => "Every Nth Point",
+            MarkerSampling::ArcLength => "Arc Length",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkerColoring {
+    Plain,
+    Index, // cycles through a few colors
+    Progress, // fades from the start color to the goal color
+}
+
+impl MarkerColoring {
+    pub const ALL: [MarkerColoring; 3] = [MarkerColoring::Plain, MarkerColoring::Index, MarkerColoring::Progress];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarkerColoring::Plain => "Plain",
+            MarkerColoring::Index => "By Index",
+            MarkerColoring::Progress => "By Progress",
+        }
+    }
+}
+
+/// a marker on the obstacle field surface
+pub struct PathMarker {
+    pub position: Position,
+    pub color: Rgb<u8>,
+}
+
+/// how path point markers are sampled and drawn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathMarkers {
+    pub sampling: MarkerSampling,
+    pub every_nth: usize,
+    pub spacing: f32, // meters between markers along the spline
+    pub coloring: MarkerColoring,
+    pub size: f32, // meters across at the marker's depth
+}
+
+impl Default for PathMarkers {
+    fn default() -> Self {
+        Self {
+            sampling: MarkerSampling::EveryNth,
+            every_nth: 1,
+            spacing: 0.25,
+            coloring: MarkerColoring::Plain,
+            size: 0.05,
+        }
+    }
+}
+
+impl PathMarkers {
+    /// markers along the robot's path, colored by their order or progress from the start to the goal
+    pub fn markers(&self, robot: &Robot, obstacles: &[Obstacle]) -> Vec<PathMarker> {
+        if robot.path_points.len() < 2 {
+            return Vec::new();
+        }
+        
+        let samples = match self.sampling {
+            MarkerSampling::EveryNth => {
+                let last = (robot.path_points.len() - 1) as f32;
+                robot.path_points.iter().enumerate()
+                    .step_by(self.every_nth.max(1))
+                    .map(|(i, point)| (point.position, i as f32 / last))
+                    .collect()
+            },
+            MarkerSampling::ArcLength => arc_length_samples(robot, self.spacing),
+        };
+        
+        samples.into_iter().enumerate()
+            .map(|(i, (mut position, progress))| {
+                position.z = combined_field(obstacles, position);
+                PathMarker {
+                    position,
+                    color: self.color(i, progress),
+                }
+            })
+            .collect()
+    }
+    
+    fn color(&self, index: usize, progress: f32) -> Rgb<u8> {
+        match self.coloring {
+            MarkerColoring::Plain => WHITE,
+            MarkerColoring::Index => INDEX_COLORS[index % INDEX_COLORS.len()],
+            MarkerColoring::Progress => {
+                let t = progress.clamp(0.0, 1.0);
+                rgb((255.0 * t) as u8, (255.0 * (1.0 - t)) as u8, 0)
+            },
+        }
+    }
+    
+    /// screen radius of a marker at the given camera depth, nearer markers are drawn larger
+    pub fn radius(&self, depth: f32, focal_length: f32) -> f32 {
+        (self.size / 2.0 * focal_length / depth).clamp(MIN_MARKER_RADIUS, MAX_MARKER_RADIUS)
+    }
+}
+
+/// points spaced evenly along the spline from the start, with their progress
+fn arc_length_samples(robot: &Robot, spacing: f32) -> Vec<(Position, f32)> {
+    let spacing = spacing.max(0.01);
+    let point_at = |t: f32| {
+        let (x, y) = robot.catmull_rom_spline(t);
+        Position::new(x, y, 0.0)
+    };
+    
+    let mut samples = vec![(point_at(0.0), 0.0)];
+    let mut previous = point_at(0.0);
+    let mut travelled = 0.0;
+    let mut t = 0.0;
+    while t < 1.0 {
+        t = (t + SPLINE_SAMPLE_STEP).min(1.0);
+        let current = point_at(t);
+        travelled += current.distance_to(&previous);
+        previous = current;
+        
+        if travelled >= spacing {
+            samples.push((current, t));
+            travelled = 0.0;
+        }
+    }
+    
+    // the goal gets a marker unless the last one is already next to it
+    if travelled > spacing / 2.0 {
+        samples.push((previous, 1.0));
+    }
+    samples
+}
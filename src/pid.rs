@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+
+const MAX_INTEGRAL: f32 = 2.0; // limits windup while the robot is held back
+const HISTORY_LENGTH: usize = 300;
+
+pub struct Pid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    integral: f32,
+    previous_error: Option<f32>,
+}
+
+impl Pid {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self { kp, ki, kd, integral: 0.0, previous_error: None }
+    }
+    
+    pub fn update(&mut self, error: f32, dt: f32) -> f32 {
+        if dt <= 0.0 {
+            return self.kp * error;
+        }
+        
+        self.integral = (self.integral + error * dt).clamp(-MAX_INTEGRAL, MAX_INTEGRAL);
+        let derivative = self.previous_error.map_or(0.0, |previous| (error - previous) / dt);
+        self.previous_error = Some(error);
+        
+        self.kp * error + self.ki * self.integral + self.kd * derivative
+    }
+    
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.previous_error = None;
+    }
+}
+
+/// how the robot turns the path into velocity commands
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FollowerMode {
+    OpenLoop, // drives along the spline direction regardless of where the robot actually is
+    Pid, // corrects along-track and cross-track error to a reference point moving along the spline
+}
+
+/// pair of controllers for the along-track and cross-track error, keeping recent errors for plotting
+pub struct PidFollower {
+    pub along: Pid,
+    pub cross: Pid,
+    pub history: VecDeque<(f32, f32)>, // (along-track, cross-track) error
+}
+
+impl PidFollower {
+    pub fn new() -> Self {
+        Self {
+            along: Pid::new(2.0, 0.0, 0.1),
+            cross: Pid::new(4.0, 0.5, 0.2),
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+        }
+    }
+    
+    /// speed correction along the path and velocity across it
+    pub fn update(&mut self, along_error: f32, cross_error: f32, dt: f32) -> (f32, f32) {
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back((along_error, cross_error));
+        
+        (self.along.update(along_error, dt), self.cross.update(cross_error, dt))
+    }
+    
+    pub fn reset(&mut self) {
+        self.along.reset();
+        self.cross.reset();
+        self.history.clear();
+    }
+}
+
+impl Default for PidFollower {
+    fn default() -> Self {
+        Self::new()
+    }
+}
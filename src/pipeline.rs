@@ -0,0 +1,92 @@
+use crate::config;
+
+/// a step of path planning, run in this order
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PipelineStage {
+    Seed, // straight line through the via points, always runs
+    Optimize,
+    Clean,
+    Prune,
+    Smooth,
+}
+
+impl PipelineStage {
+    pub const ALL: [PipelineStage; 5] = [
+        PipelineStage::Seed,
+        PipelineStage::Optimize,
+        PipelineStage::Clean,
+        PipelineStage::Prune,
+        PipelineStage::Smooth,
+    ];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            PipelineStage::Seed => "Seed",
+            PipelineStage::Optimize => "Optimize",
+            PipelineStage::Clean => "Clean",
+            PipelineStage::Prune => "Prune Curvature",
+            PipelineStage::Smooth => "Smooth",
+        }
+    }
+    
+    pub fn description(&self) -> &'static str {
+        match self {
+            PipelineStage::Seed => "place evenly spaced points on straight lines from the start through the via points to the target",
+            PipelineStage::Optimize => "move the points down the obstacle field gradient until they are clear of every hard obstacle",
+            PipelineStage::Clean => "remove points that bunch up too close to the point before them",
+            PipelineStage::Prune => "remove the points around where the path starts or stops turning, kept if the spline would then come closer to an obstacle than the robot radius",
+            PipelineStage::Smooth => "follow a Catmull-Rom spline through the points instead of straight lines between them",
+        }
+    }
+}
+
+/// which planning stages run and their parameters
+#[derive(Debug, Clone)]
+pub struct PathPipeline {
+    pub optimize: bool,
+    pub max_iterations: usize,
+    pub optimization_threshold: f32, // path points higher than this are still inside an obstacle field
+    pub learning_rate: f32, // scale of the gradient step each iteration
+    pub push_strength: f32, // meters a point closer than the safe margin to an obstacle is moved per iteration
+    pub soft_iterations: usize, // iterations spent moving the path off soft obstacles once it clears the hard ones
+    pub step_through: bool, // optimize one iteration at a time from the ui instead of all at once
+    pub clean: bool,
+    pub clean_spacing: f32, // points closer than this many seed steps to the previous one are removed
+    pub clean_min_points: usize, // paths with this many points or fewer are left alone
+    pub prune: bool,
+    pub prune_distance: f32, // points within this fraction of the robot size of a turn are removed
+    pub smooth: bool,
+}
+
+impl Default for PathPipeline {
+    fn default() -> Self {
+        Self {
+            optimize: true,
+            max_iterations: config::get().planner.max_iterations,
+            optimization_threshold: config::get().planner.optimization_threshold,
+            learning_rate: config::get().planner.adjust_rate,
+            push_strength: 0.5,
+            soft_iterations: 200,
+            step_through: false,
+            clean: true,
+            clean_spacing: 1.3,
+            clean_min_points: 12,
+            prune: true,
+            prune_distance: 0.25,
+            smooth: true,
+        }
+    }
+}
+
+impl PathPipeline {
+    /// the flag switching a stage on or off, None for the seed every other stage works on
+    pub fn enabled_mut(&mut self, stage: PipelineStage) -> Option<&mut bool> {
+        match stage {
+            PipelineStage::Seed => None,
+            PipelineStage::Optimize => Some(&mut self.optimize),
+            PipelineStage::Clean => Some(&mut self.clean),
+            PipelineStage::Prune => Some(&mut self.prune),
+            PipelineStage::Smooth => Some(&mut self.smooth),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+/// path planner used to generate the path to the target
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "window", derive(clap::ValueEnum))]
+pub enum Planner {
+    /// spline seeded straight to the target, pushed away from obstacles by gradient descent
+    Gradient,
+    /// straight segments and arcs of at least the turn radius tangent to the inflated obstacles, driving forwards only
+    Dubins,
+    /// like dubins, but the robot may back up and change direction once
+    ReedsShepp,
+    /// shortest route through a probabilistic roadmap of the free field, built once per obstacle layout
+    Prm,
+    /// cheapest route over the costmap grid with a*, kept out of the inflated cells where it can be
+    Grid,
+    /// descent of a harmonic potential solved over a grid of the field, which has no minimum but the goal
+    Harmonic,
+    /// counts down a breadth first wave expanded from the goal over the free costmap cells
+    Wavefront,
+    /// cheapest route over the costmap with d* lite, repairing the last search instead of starting over
+    DStarLite,
+    /// hybrid a* over the costmap, short arcs no tighter than the turn radius searched over position and heading
+    HybridAStar,
+    /// earliest arrival over grid cells and time, waiting for moving obstacles to pass, checked against a deadline
+    SpaceTime,
+}
+
+impl Planner {
+    pub const ALL: [Planner; 10] = [Planner::Gradient, Planner::Dubins, Planner::ReedsShepp, Planner::Prm, Planner::Grid, Planner::Harmonic, Planner::Wavefront, Planner::DStarLite, Planner::HybridAStar, Planner::SpaceTime];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            Planner::Gradient => "Gradient Descent",
+            Planner::Dubins => "Dubins",
+            Planner::ReedsShepp => "Reeds-Shepp",
+            Planner::Prm => "Roadmap (PRM)",
+            Planner::Grid => "Grid A* (Costmap)",
+            Planner::Harmonic => "Harmonic Field",
+            Planner::Wavefront => "Wavefront (Brushfire)",
+            Planner::DStarLite => "D* Lite (Incremental)",
+            Planner::HybridAStar => "Hybrid A* (Kinematic)",
+            Planner::SpaceTime => "Space-Time A* (Timed)",
+        }
+    }
+    
+    /// whether the planner searches the costmap, which it rebuilds for every plan
+    pub fn uses_costmap(&self) -> bool {
+        matches!(self, Planner::Grid | Planner::Wavefront | Planner::DStarLite | Planner::HybridAStar)
+    }
+}
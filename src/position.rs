@@ -1,4 +1,5 @@
 use std::default::Default;
+use std::f32::consts::PI;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Position {
@@ -65,3 +66,8 @@ impl Position {
         }
     }
 }
+
+/// wraps an angle into the range -PI to PI
+pub fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
@@ -0,0 +1,191 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::color::Rgb;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::dubins::{Circle, PathSample, segment_is_clear, segment_length, segment_samples};
+use crate::field::{field_length, field_width};
+use crate::position::Position;
+use crate::wire::Wire;
+
+pub const DEFAULT_ROADMAP_SAMPLES: usize = 300;
+const NEIGHBORS: usize = 10; // nearest samples each sample, start and goal try to connect to
+const ROADMAP_SEED: u64 = 1; // the same scene always gets the same roadmap
+
+/// graph of collision free straight edges between random samples of the free field, built once
+/// per obstacle layout and searched for every start and goal
+pub struct Roadmap {
+    circles: Vec<Circle>, // obstacles the roadmap was built around
+    samples: usize,
+    nodes: Vec<[f32; 2]>,
+    edges: Vec<Vec<(usize, f32)>>, // neighbors and distances of every node
+    build_order: Vec<(usize, usize)>, // edges in the order they were connected
+    search: Vec<([f32; 2], [f32; 2])>, // edges the searches of the last plan explored, in order
+    revealed: f32, // how many build and search edges are drawn so far
+}
+
+impl Roadmap {
+    pub fn build(circles: &[Circle], samples: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(ROADMAP_SEED);
+        let mut nodes = Vec::with_capacity(samples);
+        // give up on a field that is mostly covered by obstacles
+        let mut attempts = 0;
+        while nodes.len() < samples && attempts < samples * 20 {
+            attempts += 1;
+            let point = [rng.gen_range(0.0..field_length()), rng.gen_range(0.0..field_width())];
+            // a point is a segment of no length
+            if segment_is_clear(point, point, circles) {
+                nodes.push(point);
+            }
+        }
+        
+        let mut edges = vec![Vec::new(); nodes.len()];
+        let mut build_order = Vec::new();
+        for i in 0..nodes.len() {
+            for (j, distance) in nearest(&nodes, nodes[i], NEIGHBORS + 1) {
+                if j != i && !edges[i].iter().any(|&(k, _)| k == j) && segment_is_clear(nodes[i], nodes[j], circles) {
+                    edges[i].push((j, distance));
+                    edges[j].push((i, distance));
+                    build_order.push((i, j));
+                }
+            }
+        }
+        
+        Self {
+            circles: circles.to_vec(),
+            samples,
+            nodes,
+            edges,
+            build_order,
+            search: Vec::new(),
+            revealed: 0.0,
+        }
+    }
+    
+    /// whether the roadmap was built for these obstacles with this many samples
+    pub fn matches(&self, circles: &[Circle], samples: usize) -> bool {
+        self.circles == circles && self.samples == samples
+    }
+    
+    /// shortest route through the roadmap from start to goal, returns the points of the path after the start
+    /// and the heading the robot arrives with
+    pub fn query(&mut self, start: [f32; 2], goal: [f32; 2]) -> Option<(Vec<PathSample>, f32)> {
+        let corners = self.route(start, goal)?;
+        
+        let mut samples = Vec::new();
+        let mut from = start;
+        for &corner in &corners {
+            samples.extend(segment_samples(from, corner).into_iter()
+                .map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed: false }));
+            from = corner;
+        }
+        
+        let before_goal = if corners.len() > 1 { corners[corners.len() - 2] } else { start };
+        let heading = (goal[1] - before_goal[1]).atan2(goal[0] - before_goal[0]);
+        Some((samples, heading))
+    }
+    
+    /// the corner points of the shortest route after the start
+    fn route(&mut self, start: [f32; 2], goal: [f32; 2]) -> Option<Vec<[f32; 2]>> {
+        if segment_is_clear(start, goal, &self.circles) {
+            return Some(vec![goal]);
+        }
+        
+        // start and goal join the graph as two extra nodes
+        let start_index = self.nodes.len();
+        let goal_index = start_index + 1;
+        let connect = |point: [f32; 2]| -> Vec<(usize, f32)> {
+            nearest(&self.nodes, point, NEIGHBORS).into_iter()
+                .filter(|&(i, _)| segment_is_clear(point, self.nodes[i], &self.circles))
+                .collect()
+        };
+        let from_start = connect(start);
+        let to_goal = connect(goal);
+        
+        let point_of = |i: usize| if i == start_index { start } else if i == goal_index { goal } else { self.nodes[i] };
+        let neighbors = |i: usize| -> Vec<(usize, f32)> {
+            let mut neighbors = if i == start_index { from_start.clone() } else { self.edges[i].clone() };
+            if let Some(&(_, distance)) = to_goal.iter().find(|&&(j, _)| j == i) {
+                neighbors.push((goal_index, distance));
+            }
+            neighbors
+        };
+        
+        // a* with the straight line distance to the goal, the positive costs keep their order as bits
+        let mut cost = vec![f32::MAX; self.nodes.len() + 2];
+        let mut previous = vec![usize::MAX; self.nodes.len() + 2];
+        let mut queue = BinaryHeap::new();
+        cost[start_index] = 0.0;
+        queue.push(Reverse((segment_length(start, goal).to_bits(), start_index)));
+        
+        while let Some(Reverse((_, i))) = queue.pop() {
+            if i == goal_index {
+                let mut route = vec![goal];
+                let mut current = previous[goal_index];
+                while current != start_index {
+                    route.push(self.nodes[current]);
+                    current = previous[current];
+                }
+                route.reverse();
+                return Some(route);
+            }
+            
+            for (j, distance) in neighbors(i) {
+                let next_cost = cost[i] + distance;
+                if next_cost < cost[j] {
+                    cost[j] = next_cost;
+                    previous[j] = i;
+                    self.search.push((point_of(i), point_of(j)));
+                    let estimate = next_cost + segment_length(point_of(j), goal);
+                    queue.push(Reverse((estimate.to_bits(), j)));
+                }
+            }
+        }
+        None
+    }
+    
+    /// how many free points the roadmap connects and the edges between them
+    pub fn size(&self) -> (usize, usize) {
+        (self.nodes.len(), self.build_order.len())
+    }
+    
+    /// forgets the explored edges before a new plan, the roadmap itself stays drawn
+    pub fn clear_search(&mut self) {
+        self.search.clear();
+        self.revealed = self.revealed.min(self.build_order.len() as f32);
+    }
+    
+    /// draws this many more edges, the roadmap as it was connected and then the search over it
+    pub fn grow(&mut self, edges: f32) {
+        self.revealed = (self.revealed + edges).min((self.build_order.len() + self.search.len()) as f32);
+    }
+    
+    /// hides every edge so the growth is shown again from the first one
+    pub fn restart_growth(&mut self) {
+        self.revealed = 0.0;
+    }
+    
+    /// the edges revealed so far, the explored search edges drawn in their own color
+    pub fn get_wires(&self, color: Rgb<u8>, search_color: Rgb<u8>) -> Vec<Wire> {
+        let wire = |a: [f32; 2], b: [f32; 2], color: Rgb<u8>| Wire::with_color(Position::new(a[0], a[1], 0.0), Position::new(b[0], b[1], 0.0), color);
+        let revealed = self.revealed as usize;
+        self.build_order.iter()
+            .map(|&(i, j)| wire(self.nodes[i], self.nodes[j], color))
+            .chain(self.search.iter().map(|&(a, b)| wire(a, b, search_color)))
+            .take(revealed)
+            .collect()
+    }
+}
+
+/// the closest nodes to a point with their distances, nearest first
+fn nearest(nodes: &[[f32; 2]], point: [f32; 2], count: usize) -> Vec<(usize, f32)> {
+    let mut by_distance: Vec<(usize, f32)> = nodes.iter()
+        .enumerate()
+        .map(|(i, &node)| (i, segment_length(point, node)))
+        .collect();
+    by_distance.sort_by(|a, b| a.1.total_cmp(&b.1));
+    by_distance.truncate(count);
+    by_distance
+}
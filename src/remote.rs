@@ -0,0 +1,105 @@
+// websocket server for driving the app from scripts or a dashboard, every text message is one json
+// command such as {"command": "set_target", "x": 12.0, "y": 4.0} and gets one json reply
+use serde::Deserialize;
+use std::sync::mpsc::{Receiver, Sender};
+
+pub const DEFAULT_REMOTE_PORT: u16 = 9001;
+
+fn default_obstacle_model() -> String {
+    String::from("cone")
+}
+
+fn default_obstacle_radius() -> f32 {
+    0.5
+}
+
+/// what a remote client can ask the app to do
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    AddObstacle {
+        #[serde(default = "default_obstacle_model")]
+        model: String,
+        x: f32,
+        y: f32,
+        #[serde(default = "default_obstacle_radius")]
+        radius: f32,
+    },
+    MoveObstacle { index: usize, x: f32, y: f32 },
+    SetTarget { x: f32, y: f32 },
+    Plan,
+    StartFollowing,
+    GetPose,
+}
+
+/// a command waiting for the app to run it, the client waits for the reply before sending the next one
+pub struct RemoteRequest {
+    pub command: Result<RemoteCommand, String>, // the parse error for messages that are not a command
+    reply: Sender<String>,
+}
+
+impl RemoteRequest {
+    pub fn reply(self, reply: serde_json::Value) {
+        // the client may have disconnected while waiting
+        let _ = self.reply.send(reply.to_string());
+    }
+}
+
+/// listens on a local port until the app exits, every client is served on its own thread
+pub struct RemoteServer {
+    pub port: u16,
+    requests: Receiver<RemoteRequest>,
+}
+
+impl RemoteServer {
+    #[cfg(feature = "native")]
+    pub fn start(port: u16) -> Result<Self, Box<dyn std::error::Error>> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+        let (sender, requests) = std::sync::mpsc::channel();
+        
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue; };
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = serve(stream, sender) {
+                        eprintln!("Remote client disconnected: {}", e);
+                    }
+                });
+            }
+        });
+        
+        println!("Remote control listening on ws://127.0.0.1:{}", port);
+        Ok(Self { port, requests })
+    }
+    
+    #[cfg(not(feature = "native"))]
+    pub fn start(_port: u16) -> Result<Self, Box<dyn std::error::Error>> {
+        Err("the remote control server needs the native feature".into())
+    }
+    
+    /// commands received since the last call
+    pub fn requests(&self) -> Vec<RemoteRequest> {
+        self.requests.try_iter().collect()
+    }
+}
+
+/// reads commands from one client until it closes the connection
+#[cfg(feature = "native")]
+fn serve(stream: std::net::TcpStream, sender: Sender<RemoteRequest>) -> Result<(), Box<dyn std::error::Error>> {
+    use tungstenite::Message;
+    
+    let mut socket = tungstenite::accept(stream)?;
+    loop {
+        let text = match socket.read()? {
+            Message::Text(text) => text,
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+        
+        let (reply, replies) = std::sync::mpsc::channel();
+        let command = serde_json::from_str(&text).map_err(|e| e.to_string());
+        sender.send(RemoteRequest { command, reply })?;
+        socket.send(Message::Text(replies.recv()?))?;
+    }
+}
@@ -1,13 +1,56 @@
 use crate::model::{Model, ModelConfig};
-use crate::position::{self, Position, ORIGIN};
+use crate::position::{self, wrap_angle, Position, ORIGIN};
 use crate::wire::Wire;
-use crate::obstacle::Obstacle;
-use clearscreen;
-use nannou::color::rgb;
+use crate::obstacle::{combined_field, field_combination, Obstacle};
+use crate::path_event::{PathAction, PathEvent};
+use crate::noise::ExecutionNoise;
+use crate::pid::{FollowerMode, PidFollower};
+use crate::config;
+use crate::pipeline::PathPipeline;
+use crate::planner::Planner;
+use crate::dubins::{self, Circle, Pose, DEFAULT_TURN_RADIUS};
+use crate::prm::{Roadmap, DEFAULT_ROADMAP_SAMPLES};
+use crate::costmap::{Costmap, DEFAULT_COSTMAP_RESOLUTION};
+use crate::harmonic::{HarmonicField, DEFAULT_HARMONIC_RESOLUTION};
+use crate::wavefront::Wavefront;
+use crate::dstar_lite::DStarLite;
+use crate::hybrid_astar;
+use crate::space_time::{SpaceTimeGrid, DEFAULT_DEADLINE, DEFAULT_SPACE_TIME_RESOLUTION};
+use crate::distance_field::{DistanceField, DEFAULT_DISTANCE_RESOLUTION};
+use crate::local_avoidance::LocalAvoidance;
+use crate::goal_region::GoalRegion;
+use crate::speed_limit::{self, SpeedLimits, SpeedSample, SpeedZone, DEFAULT_MAX_LATERAL_ACCELERATION, PROFILE_SAMPLES};
+use crate::color::{self, Rgb};
+use crate::error::{ModelError, PlanError};
 
+/// progress of an optimization run one iteration at a time
+#[derive(Debug, Copy, Clone)]
+pub struct SteppedOptimization {
+    pub iterations: usize,
+    step_distance: f32, // seed spacing the clean stage compares against
+}
+
+/// position the path has to pass within tolerance of
+#[derive(Debug, Copy, Clone)]
+pub struct ViaPoint {
+    pub position: Position,
+    pub tolerance: f32,
+    pub reversed: bool, // drive backwards from this via point to the next waypoint
+}
+
+impl ViaPoint {
+    pub fn new(position: Position, tolerance: f32) -> Self {
+        Self { position, tolerance, reversed: false }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct PathPoint {
     pub position: Position,
     pub height: f32,
+    pub locked: bool, // left in place by the optimizer, set when the point is moved by hand
+    pub via: Option<ViaPoint>, // kept within the via point's tolerance by the optimizer
+    pub reversed: bool, // the robot drives backwards on the way to this point
 }
 
 impl PathPoint {
@@ -15,6 +58,9 @@ impl PathPoint {
         Self {
             position: Position::new(x, y, 0.0),
             height: 0.0,
+            locked: false,
+            via: None,
+            reversed: false,
         }
     }
     
@@ -22,6 +68,27 @@ impl PathPoint {
         Self {
             position: Position::new(position.x, position.y, 0.0),
             height: position.z,
+            locked: false,
+            via: None,
+            reversed: false,
+        }
+    }
+    
+    /// locked and via points are never removed when the path is cleaned or pruned
+    pub fn is_constrained(&self) -> bool {
+        self.locked || self.via.is_some()
+    }
+    
+    /// pulls the point back inside its via point's tolerance
+    fn clamp_to_via(&mut self) {
+        if let Some(via) = self.via {
+            let offset = self.position.minus(&via.position);
+            let dist = self.position.distance_to(&via.position);
+            if dist > via.tolerance {
+                let direction = offset.norm2D();
+                self.position.x = via.position.x + direction.x * via.tolerance;
+                self.position.y = via.position.y + direction.y * via.tolerance;
+            }
         }
     }
     
@@ -34,15 +101,69 @@ impl PathPoint {
     }
 }
 
+/// event that is currently holding the robot in place
+#[derive(Debug, Copy, Clone)]
+enum ActiveEvent {
+    Waiting(f32), // seconds left
+    Rotating(f32), // target heading
+}
+
 pub struct Robot {
     pub model: Model,
     pub path_points: Vec<PathPoint>,
     pub velocity_x: f32,
     pub velocity_y: f32,
+    commanded_velocity_x: f32, // velocity the robot ramps towards when acceleration is limited
+    commanded_velocity_y: f32,
+    pub limit_acceleration: bool,
+    pub max_acceleration: f32, // m/s^2 while speeding up or turning
+    pub max_deceleration: f32, // m/s^2 while slowing down
+    pub noise: ExecutionNoise,
+    pub follower_mode: FollowerMode,
+    pub pid: PidFollower,
+    pub local_avoidance: LocalAvoidance, // steers around moving obstacles while following
+    pub speed_limits: SpeedLimits, // slower where the path passes close to obstacles
+    pub max_lateral_acceleration: f32, // m/s^2, limits the speed through curves when re-timing the path
     pub current_path_progress: f32,
     pub target_speed: f32,
     pub follow_path: bool,
     pub velocity_update_timer: f32,
+    pub via_points: Vec<ViaPoint>, // passed through in order on the way to the target
+    pub goal_region: GoalRegion, // around the target, the path ends at its nearest point and the follower stops inside
+    goal_target: Position, // target of the last plan
+    stop_progress: f32, // spline parameter after the last via point, where the follower may stop in the goal region
+    pub start_reversed: bool, // drive backwards from the start to the first waypoint
+    pub initial_path: Vec<Position>, // straight line seed of the last planned path
+    pub optimized_path: Vec<Position>, // control points after the last optimization, before curvature pruning
+    pub planner: Planner,
+    pub pipeline: PathPipeline, // stages of the gradient planner
+    pub stepped_optimization: Option<SteppedOptimization>, // optimization waiting for the next step
+    pub turn_radius: f32, // smallest arc the dubins, reeds-shepp and hybrid a* planners drive
+    pub roadmap: Option<Roadmap>, // kept between plans until the obstacles change
+    pub roadmap_samples: usize,
+    pub costmap: Option<Costmap>, // rebuilt for every grid plan, or by the ui while it is shown
+    pub costmap_resolution: f32,
+    pub any_angle: bool, // the grid planner searches any angle segments with theta* instead of straightening its route
+    pub harmonic: Option<HarmonicField>, // solved for the last leg of the last harmonic plan
+    pub harmonic_resolution: f32,
+    pub wavefront: Option<Wavefront>, // expanded for the last leg of the last wavefront plan
+    pub dstar: Vec<DStarLite>, // search of every leg, carried on by the next plan to the same goals
+    pub hybrid_reversing: bool, // the hybrid a* planner may back up
+    pub hybrid_expanded: Vec<[[f32; 2]; 2]>, // steps the last hybrid a* plan expanded, from the parent pose to the node
+    pub space_time_resolution: f32,
+    pub deadline: f32, // seconds the space-time planner must reach the target in
+    pub timed_arrival: Option<f32>, // seconds the last space-time plan reaches the target in, speeding up and slowing down included
+    pub distance_field: Option<DistanceField>, // only read while it matches the obstacles it is asked about
+    pub distance_resolution: f32,
+    pub use_distance_field: bool, // clearance is read from the distance field instead of checking every obstacle
+    pub pruned_points: Vec<Position>, // removed by the last curvature pruning
+    pub heading: f32, // direction the front of the robot faces, radians
+    pub turn_rate: f32, // radians per second the robot turns to face its velocity
+    travel_reversed: bool, // the path segment being followed is driven backwards
+    pub events: Vec<PathEvent>, // sorted by progress
+    pub triggered_markers: Vec<String>,
+    next_event: usize,
+    active_event: Option<ActiveEvent>,
 }
 
 impl std::ops::Deref for Robot {
@@ -65,12 +186,14 @@ pub const ROBOT_INITIAL_POSITION: Position = Position {
     z: 0.0,
 };
 
-pub const ROBOT_MODEL_NAME: &str = "robot_base";
-pub const ROBOT_SCALE: f32 = 1.0;
 
-pub const MIN_ADJUST_RATE: f32 = 0.0001;
-pub const MAX_ITERATIONS: usize = 2000;
-pub const PATH_OPTIMIZATION_THRESHOLD: f32 = 0.001;
+pub const SPLINE_SAMPLE_STEP: f32 = 0.001; // same step follow_path advances t by
+pub const DEFAULT_MAX_ACCELERATION: f32 = 4.0;
+pub const DEFAULT_MAX_DECELERATION: f32 = 6.0;
+pub const EVENT_ROTATION_SPEED: f32 = 3.0; // radians per second
+pub const DEFAULT_TURN_RATE: f32 = 6.0; // radians per second
+const MIN_TURN_SPEED: f32 = 0.05; // m/s, slower than this the velocity direction is just noise
+pub const MIN_SAFE_MARGIN: f32 = 0.1; // path points closer than this to an obstacle edge are pushed out
 
 impl Robot {
     pub fn new(model: Model) -> Robot {
@@ -79,31 +202,163 @@ impl Robot {
             path_points: Vec::new(),
             velocity_x: 0.0,
             velocity_y: 0.0,
+            commanded_velocity_x: 0.0,
+            commanded_velocity_y: 0.0,
+            limit_acceleration: true,
+            max_acceleration: DEFAULT_MAX_ACCELERATION,
+            max_deceleration: DEFAULT_MAX_DECELERATION,
+            noise: ExecutionNoise::new(1),
+            follower_mode: FollowerMode::OpenLoop,
+            pid: PidFollower::new(),
+            local_avoidance: LocalAvoidance::new(),
+            speed_limits: SpeedLimits::new(),
+            max_lateral_acceleration: DEFAULT_MAX_LATERAL_ACCELERATION,
             current_path_progress: 0.0,
             target_speed: 2.0,
             follow_path: false,
             velocity_update_timer: 0.0,
+            via_points: Vec::new(),
+            goal_region: GoalRegion::Point,
+            goal_target: ORIGIN,
+            stop_progress: 0.0,
+            start_reversed: false,
+            initial_path: Vec::new(),
+            optimized_path: Vec::new(),
+            planner: Planner::Gradient,
+            pipeline: PathPipeline::default(),
+            stepped_optimization: None,
+            turn_radius: DEFAULT_TURN_RADIUS,
+            roadmap: None,
+            roadmap_samples: DEFAULT_ROADMAP_SAMPLES,
+            costmap: None,
+            costmap_resolution: DEFAULT_COSTMAP_RESOLUTION,
+            any_angle: false,
+            harmonic: None,
+            harmonic_resolution: DEFAULT_HARMONIC_RESOLUTION,
+            wavefront: None,
+            dstar: Vec::new(),
+            hybrid_reversing: false,
+            hybrid_expanded: Vec::new(),
+            space_time_resolution: DEFAULT_SPACE_TIME_RESOLUTION,
+            deadline: DEFAULT_DEADLINE,
+            timed_arrival: None,
+            distance_field: None,
+            distance_resolution: DEFAULT_DISTANCE_RESOLUTION,
+            use_distance_field: false,
+            pruned_points: Vec::new(),
+            heading: 0.0,
+            turn_rate: DEFAULT_TURN_RATE,
+            travel_reversed: false,
+            events: Vec::new(),
+            triggered_markers: Vec::new(),
+            next_event: 0,
+            active_event: None,
         }
     }
     
+    /// commands a velocity, reached instantly unless acceleration is limited
     pub fn set_velocity(&mut self, x: f32, y: f32) {
-        self.velocity_x = x;
-        self.velocity_y = y;
+        self.commanded_velocity_x = x;
+        self.commanded_velocity_y = y;
+        
+        if !self.limit_acceleration {
+            self.velocity_x = x;
+            self.velocity_y = y;
+        }
+    }
+    
+    /// moves the velocity towards the commanded one, no faster than the acceleration limits allow
+    fn ramp_velocity(&mut self, dt: f32) {
+        let dvx = self.commanded_velocity_x - self.velocity_x;
+        let dvy = self.commanded_velocity_y - self.velocity_y;
+        let change = (dvx * dvx + dvy * dvy).sqrt();
+        if change <= f32::EPSILON {
+            return;
+        }
+        
+        let speed = (self.velocity_x * self.velocity_x + self.velocity_y * self.velocity_y).sqrt();
+        let commanded_speed = (self.commanded_velocity_x * self.commanded_velocity_x 
+            + self.commanded_velocity_y * self.commanded_velocity_y).sqrt();
+        let limit = if commanded_speed >= speed { self.max_acceleration } else { self.max_deceleration };
+        
+        let scale = if self.limit_acceleration { (limit * dt / change).min(1.0) } else { 1.0 };
+        self.velocity_x += dvx * scale;
+        self.velocity_y += dvy * scale;
+    }
+    
+    /// velocity the robot was last told to drive at
+    pub fn commanded_velocity(&self) -> [f32; 2] {
+        [self.commanded_velocity_x, self.commanded_velocity_y]
     }
     
     pub fn set_target_speed(&mut self, speed: f32) {
         self.target_speed = speed;
     }
-
-    pub fn update_position(&mut self, dt: f32) {
+    
+    /// radius of the circle covering the robot, matches the robot radius obstacles plan around
+    pub fn footprint_radius(&self) -> f32 {
+        self.model.config.scale / 2.0
+    }
+    
+    /// reloads the robot model from disk, keeping its position and heading
+    pub fn reload_model(&mut self) -> Result<(), ModelError> {
+        self.model.reload()?;
+        self.model.rotate_z(self.heading);
+        Ok(())
+    }
+    
+    /// turns the robot model to face the given heading
+    pub fn set_heading(&mut self, heading: f32) {
+        self.model.rotate_z(heading - self.heading);
+        self.heading = heading;
+    }
+    
+    /// turns the front towards the velocity, or away from it on reversed segments, no faster than the turn rate
+    fn turn_towards_velocity(&mut self, dt: f32) {
+        let speed = (self.velocity_x * self.velocity_x + self.velocity_y * self.velocity_y).sqrt();
+        let commanded_speed = (self.commanded_velocity_x * self.commanded_velocity_x 
+            + self.commanded_velocity_y * self.commanded_velocity_y).sqrt();
+        // keep the heading while coasting to a stop
+        if speed < MIN_TURN_SPEED || commanded_speed < MIN_TURN_SPEED {
+            return;
+        }
+        
+        let mut target = self.velocity_y.atan2(self.velocity_x);
+        if self.follow_path && self.travel_reversed {
+            target += std::f32::consts::PI;
+        }
+        
+        let error = wrap_angle(target - self.heading);
+        let step = self.turn_rate * dt;
+        if error.abs() > step {
+            self.set_heading(self.heading + step * error.signum());
+        } else {
+            self.set_heading(self.heading + error);
+        }
+    }
+    
+    /// speed along the heading, negative while driving backwards
+    pub fn forward_speed(&self) -> f32 {
+        self.velocity_x * self.heading.cos() + self.velocity_y * self.heading.sin()
+    }
+    
+    pub fn update_position(&mut self, dt: f32, obstacles: &[Obstacle]) {
         self.velocity_update_timer += dt;
         
         if self.follow_path {
+            if self.local_avoidance.enabled {
+                self.local_avoidance.observe(obstacles);
+            }
             self.follow_path_with_dt(dt);
         }
         
-        let new_x = self.model.config.position.x + self.velocity_x * dt;
-        let new_y = self.model.config.position.y + self.velocity_y * dt;
+        self.ramp_velocity(dt);
+        self.turn_towards_velocity(dt);
+        
+        // the robot drives at a disturbed velocity while the controller keeps its own command
+        let (driven_x, driven_y) = self.noise.apply(self.velocity_x, self.velocity_y);
+        let new_x = self.model.config.position.x + driven_x * dt;
+        let new_y = self.model.config.position.y + driven_y * dt;
         
         let old_position = self.model.config.position;
         
@@ -123,49 +378,586 @@ impl Robot {
     
     pub fn create_default() -> Result<Self, Box<dyn std::error::Error>> {
         let config = ModelConfig {
-            name: ROBOT_MODEL_NAME.to_string(),
+            name: config::get().robot.model.clone(),
             position: ROBOT_INITIAL_POSITION,
-            scale: ROBOT_SCALE,
+            scale: config::get().robot.scale,
         };
         
         let model = Model::from_config(&config)?;
         Ok(Self::new(model))
     }
     
-    pub fn generate_path(&mut self, target_position: &Position, segments_count: usize, obstacles: &[Obstacle]) {
+    /// plans a new path to the target, the path is still generated when an error is returned
+    /// so there is something to show, but it is not safe to drive
+    pub fn generate_path(&mut self, target_position: &Position, segments_count: usize, obstacles: &[Obstacle]) -> Result<(), PlanError> {
+        self.plan_to_region(target_position, segments_count, obstacles)?;
+        match self.space_time_conflict(obstacles) {
+            Some((name, time)) => Err(PlanError::MovingObstacle { name, time }),
+            None => Ok(()),
+        }
+    }
+    
+    /// plans again from where the robot is against where the obstacles are now, without checking the path
+    /// against their predicted motion, for replanning every frame while they move
+    pub fn replan(&mut self, target_position: &Position, segments_count: usize, obstacles: &[Obstacle]) -> Result<(), PlanError> {
+        self.plan_to_region(target_position, segments_count, obstacles)
+    }
+    
+    /// plans to the point of the goal region around the target the robot reaches first, the target itself
+    /// without a region
+    fn plan_to_region(&mut self, target_position: &Position, segments_count: usize, obstacles: &[Obstacle]) -> Result<(), PlanError> {
+        let approach = self.via_points.last().map_or(self.model.config.position, |via| via.position);
+        let end = self.goal_region.goal_from(*target_position, approach, obstacles);
+        self.goal_target = *target_position;
+        let planned = self.plan(&end, segments_count, obstacles);
+        self.stop_progress = self.via_points.last().map_or(0.0, |via| self.progress_nearest_to(&via.position));
+        self.update_speed_profile(obstacles);
+        planned
+    }
+    
+    /// whether the robot is in the goal region on the last leg of its path with no events left to run
+    pub fn in_goal_region(&self) -> bool {
+        self.goal_region != GoalRegion::Point
+            && self.current_path_progress >= self.stop_progress
+            && self.next_event >= self.events.len()
+            && self.goal_region.contains(self.goal_target, self.model.config.position)
+    }
+    
+    fn plan(&mut self, target_position: &Position, segments_count: usize, obstacles: &[Obstacle]) -> Result<(), PlanError> {
         let start = self.model.config.position;
         let end = *target_position;
+        let goal_obstacle = obstacles.iter()
+            .find(|obstacle| obstacle.is_hard() && obstacle.edge_distance(end) < 0.0)
+            .map(|obstacle| obstacle.name.clone());
+        self.stepped_optimization = None;
+        if self.use_distance_field {
+            self.update_distance_field(obstacles);
+        }
+        
+        if self.planner != Planner::Gradient {
+            let planned = self.generate_geometric_path(end, obstacles);
+            return match goal_obstacle {
+                Some(name) => Err(PlanError::GoalInObstacle(name)),
+                None => planned,
+            };
+        }
         
-        self.path_points = Vec::with_capacity(segments_count + 2);
+        // the path is seeded through the via points in order, locked points of the previous path are kept
+        // and inserted into the leg they are closest to
+        let mut waypoints = vec![PathPoint::from_position(start)];
+        for via in &self.via_points {
+            let mut waypoint = PathPoint::from_position(via.position);
+            waypoint.via = Some(*via);
+            waypoints.push(waypoint);
+        }
+        waypoints.push(PathPoint::from_position(end));
+        
+        let locked: Vec<Position> = self.path_points.iter()
+            .filter(|point| point.locked)
+            .map(|point| point.position)
+            .collect();
+        for position in locked {
+            let nearest_leg = (0..waypoints.len() - 1)
+                .min_by(|&a, &b| {
+                    let dist_a = distance_to_segment(&position, &waypoints[a].position, &waypoints[a + 1].position);
+                    let dist_b = distance_to_segment(&position, &waypoints[b].position, &waypoints[b + 1].position);
+                    dist_a.total_cmp(&dist_b)
+                })
+                .unwrap_or(0);
+            
+            let mut waypoint = PathPoint::from_position(position);
+            waypoint.locked = true;
+            waypoints.insert(nearest_leg + 1, waypoint);
+        }
+        
+        let total_length: f32 = waypoints.windows(2).map(|leg| leg[0].position.distance_to(&leg[1].position)).sum();
+        
+        // each leg is driven backwards if the via point it starts at says so, legs starting at a
+        // locked point keep the direction of the leg before them
+        let mut leg_reversed = Vec::with_capacity(waypoints.len() - 1);
+        leg_reversed.push(self.start_reversed);
+        for waypoint in &waypoints[1..waypoints.len() - 1] {
+            let previous = *leg_reversed.last().unwrap_or(&false);
+            leg_reversed.push(waypoint.via.map_or(previous, |via| via.reversed));
+        }
+        
+        self.path_points = Vec::with_capacity(segments_count + waypoints.len());
         
         self.current_path_progress = 0.0;
+        self.reset_events();
+        self.pid.reset();
         
         self.path_points.push(PathPoint::from_position(start));
         
-        let dx = (end.x - start.x) / segments_count as f32;
-        let dy = (end.y - start.y) / segments_count as f32;
-        
-        for i in 1..=segments_count {
-            let prev_point = &self.path_points[i-1];
-            let mut p = PathPoint::new(
-                prev_point.position.x + dx,
-                prev_point.position.y + dy
-            );
+        let leg_count = waypoints.len() - 1;
+        for (leg_index, leg) in waypoints.windows(2).enumerate() {
+            let (from, to) = (leg[0].position, leg[1].position);
+            let leg_segments = if total_length > 0.0 {
+                ((from.distance_to(&to) / total_length * segments_count as f32).round() as usize).max(1)
+            } else {
+                segments_count
+            };
             
-            p.height = 0.0;
-            for obstacle in obstacles {
-                p.height += obstacle.cosine_field_function(p.position);
-            }
+            let dx = (to.x - from.x) / leg_segments as f32;
+            let dy = (to.y - from.y) / leg_segments as f32;
             
-            self.path_points.push(p);
+            for i in 1..=leg_segments {
+                let mut p = PathPoint::new(
+                    from.x + dx * i as f32,
+                    from.y + dy * i as f32
+                );
+                
+                p.reversed = leg_reversed[leg_index];
+                
+                if i == leg_segments && leg_index < leg_count - 1 {
+                    p.locked = leg[1].locked;
+                    p.via = leg[1].via;
+                }
+                
+                self.path_points.push(p);
+            }
         }
         
-        self.path_points.push(PathPoint::from_position(end));
-
-        let step_distance = (dx*dx + dy*dy).sqrt();
-        let _ = self.clean_path(step_distance);
-        self.optimize_path(obstacles);
-        self.get_points_of_curvature(); // remove points that are too sharp of a turn
+        let mut end_point = PathPoint::from_position(end);
+        end_point.reversed = leg_reversed[leg_count - 1];
+        self.path_points.push(end_point);
+        self.initial_path = self.path_points.iter().map(|point| point.position).collect();
+        
+        let arrival_times = self.arrival_times();
+        let last = self.path_points.len() - 1;
+        for (point, ahead) in self.path_points[1..last].iter_mut().zip(&arrival_times[1..last]) {
+            point.height = field_height(obstacles, point.position, *ahead);
+        }
+        
+        let step_distance = total_length / segments_count as f32;
+        if self.pipeline.clean {
+            let _ = self.clean_path(step_distance);
+        }
+        let optimized = if self.pipeline.optimize && self.pipeline.step_through {
+            // pruning waits for the last step
+            self.start_stepped_optimization();
+            if self.stepped_optimization.is_some() {
+                self.update_event_progress();
+                return match goal_obstacle {
+                    Some(name) => Err(PlanError::GoalInObstacle(name)),
+                    None => Ok(()),
+                };
+            }
+            Ok(())
+        } else if self.pipeline.optimize {
+            self.optimize_path(obstacles)
+        } else {
+            self.optimized_path = self.path_points.iter().map(|point| point.position).collect();
+            Ok(())
+        };
+        self.prune_points_of_curvature(obstacles);
+        self.update_event_progress();
+        
+        match goal_obstacle {
+            Some(name) => Err(PlanError::GoalInObstacle(name)),
+            None => optimized,
+        }
+    }
+    
+    /// rebuilds the costmap around the obstacles at the current resolution
+    pub fn update_costmap(&mut self, obstacles: &[Obstacle]) {
+        self.costmap = Some(Costmap::build(obstacles, self.costmap_resolution));
+    }
+    
+    /// length of the grid path between two points over the costmap, None when there is no costmap or no path
+    pub fn path_cost(&self, from: Position, to: Position) -> Option<f32> {
+        let start = [from.x, from.y];
+        let (samples, _) = self.costmap.as_ref()?.query(start, [to.x, to.y], self.any_angle)?;
+        let mut previous = start;
+        let mut length = 0.0;
+        for sample in samples {
+            let point = [sample.position.x, sample.position.y];
+            length += (point[0] - previous[0]).hypot(point[1] - previous[1]);
+            previous = point;
+        }
+        Some(length)
+    }
+    
+    /// rebuilds the distance field when the obstacles or the resolution changed since it was built
+    pub fn update_distance_field(&mut self, obstacles: &[Obstacle]) {
+        let current = self.distance_field.as_ref()
+            .is_some_and(|field| field.resolution == self.distance_resolution.max(0.01) && field.matches(obstacles));
+        if !current {
+            self.distance_field = Some(DistanceField::build(obstacles, self.distance_resolution));
+        }
+    }
+    
+    /// the distance field when clearance should be read from it and it was built from these obstacles
+    fn distance_field_for(&self, obstacles: &[Obstacle]) -> Option<&DistanceField> {
+        self.distance_field.as_ref().filter(|field| self.use_distance_field && field.matches(obstacles))
+    }
+    
+    /// plans each leg through the via points with the dubins, reeds-shepp, roadmap, grid, harmonic, wavefront, d* lite, hybrid a* or space-time planner, starting the
+    /// next leg with the heading the last one arrived with. a leg without a path is driven as a straight line
+    fn generate_geometric_path(&mut self, end: Position, obstacles: &[Obstacle]) -> Result<(), PlanError> {
+        let start = self.model.config.position;
+        let circles: Vec<Circle> = obstacles.iter()
+            .filter(|obstacle| obstacle.is_hard())
+            .map(|obstacle| {
+                let center = obstacle.model.config.position;
+                Circle { x: center.x, y: center.y, radius: obstacle.inflated_radius() }
+            })
+            .collect();
+        
+        if self.planner == Planner::Prm && !self.roadmap.as_ref().is_some_and(|roadmap| roadmap.matches(&circles, self.roadmap_samples)) {
+            self.roadmap = Some(Roadmap::build(&circles, self.roadmap_samples));
+        }
+        if let Some(roadmap) = &mut self.roadmap {
+            roadmap.clear_search();
+        }
+        if self.planner.uses_costmap() {
+            self.update_costmap(obstacles);
+        }
+        
+        let mut waypoints: Vec<Position> = self.via_points.iter().map(|via| via.position).collect();
+        waypoints.push(end);
+        
+        self.path_points = vec![PathPoint::from_position(start)];
+        let mut pose = Pose { x: start.x, y: start.y, heading: self.heading };
+        let mut found_all = true;
+        let mut searches: Vec<Option<DStarLite>> = std::mem::take(&mut self.dstar).into_iter().map(Some).collect();
+        self.hybrid_expanded.clear();
+        // the space-time planner's waits are planned again with the path, every leg leaves when the last arrived
+        self.events.retain(|event| !event.planned);
+        let space_time = (self.planner == Planner::SpaceTime).then(|| SpaceTimeGrid::build(obstacles, self.space_time_resolution));
+        let mut waits = Vec::new();
+        let mut leg_time = 0.0;
+        
+        for (leg_index, goal) in waypoints.iter().enumerate() {
+            // the harmonic field is solved again for every leg since the goal is one of its boundaries
+            // and so is the wave, which expands from it
+            if self.planner == Planner::Harmonic {
+                self.harmonic = Some(HarmonicField::solve(obstacles, self.harmonic_resolution, [goal.x, goal.y]));
+            }
+            if let (Some(costmap), Planner::Wavefront) = (&self.costmap, self.planner) {
+                self.wavefront = Some(Wavefront::expand(costmap, [goal.x, goal.y]));
+            }
+            let leg = match (&mut self.roadmap, &self.costmap, &self.harmonic, &self.wavefront, self.planner) {
+                (Some(roadmap), _, _, _, Planner::Prm) => roadmap.query([pose.x, pose.y], [goal.x, goal.y]),
+                (_, Some(costmap), _, _, Planner::Grid) => costmap.query([pose.x, pose.y], [goal.x, goal.y], self.any_angle),
+                (_, _, Some(harmonic), _, Planner::Harmonic) => harmonic.query([pose.x, pose.y], [goal.x, goal.y]),
+                (_, _, _, Some(wavefront), Planner::Wavefront) => wavefront.query([pose.x, pose.y], [goal.x, goal.y]),
+                (_, Some(costmap), _, _, Planner::DStarLite) => {
+                    let carried = searches.get_mut(leg_index).and_then(Option::take)
+                        .filter(|search| search.continues(costmap, [goal.x, goal.y]));
+                    match carried.or_else(|| DStarLite::new(costmap, [goal.x, goal.y])) {
+                        Some(mut search) => {
+                            let leg = search.plan(costmap, [pose.x, pose.y]);
+                            self.dstar.push(search);
+                            leg
+                        },
+                        None => None,
+                    }
+                },
+                (_, Some(costmap), _, _, Planner::HybridAStar) => {
+                    hybrid_astar::plan(costmap, pose, [goal.x, goal.y], self.turn_radius, self.hybrid_reversing, &mut self.hybrid_expanded)
+                },
+                (_, _, _, _, Planner::SpaceTime) => space_time.as_ref()
+                    .and_then(|grid| grid.plan(obstacles, [pose.x, pose.y], leg_time, [goal.x, goal.y], self.target_speed))
+                    .map(|leg| {
+                        leg_time = leg.arrival;
+                        waits.extend(leg.waits);
+                        (leg.samples, leg.heading)
+                    }),
+                _ => dubins::plan(pose, [goal.x, goal.y], &circles, self.turn_radius, self.planner == Planner::ReedsShepp),
+            };
+            match leg {
+                Some((samples, heading)) => {
+                    for sample in samples {
+                        let mut point = PathPoint::from_position(sample.position);
+                        point.reversed = sample.reversed;
+                        self.path_points.push(point);
+                    }
+                    pose = Pose { x: goal.x, y: goal.y, heading };
+                },
+                None => {
+                    found_all = false;
+                    self.path_points.push(PathPoint::from_position(*goal));
+                    pose = Pose { x: goal.x, y: goal.y, heading: (goal.y - pose.y).atan2(goal.x - pose.x) };
+                }
+            }
+        }
+        
+        // a catmull-rom spline needs 4 points
+        if self.path_points.len() < 4 {
+            let (first, last) = (start, end);
+            self.path_points = (0..4)
+                .map(|i| {
+                    let t = i as f32 / 3.0;
+                    PathPoint::new(first.x + (last.x - first.x) * t, first.y + (last.y - first.y) * t)
+                })
+                .collect();
+        }
+        
+        let stops = waits.len() + 1;
+        for ([x, y], seconds) in waits {
+            let mut event = PathEvent::new(Position::new(x, y, 0.0), PathAction::Wait { seconds });
+            event.planned = true;
+            self.events.push(event);
+        }
+        // the grid is driven at the target speed throughout, a robot that has to speed up and slow down loses
+        // half the time it takes to reach the speed at every start and stop
+        self.timed_arrival = (self.planner == Planner::SpaceTime && found_all).then(|| {
+            let ramps = if self.limit_acceleration {
+                self.target_speed / (2.0 * self.max_acceleration) + self.target_speed / (2.0 * self.max_deceleration)
+            } else {
+                0.0
+            };
+            leg_time + stops as f32 * ramps
+        });
+        
+        self.initial_path = std::iter::once(start).chain(waypoints).collect();
+        self.optimized_path.clear();
+        self.pruned_points.clear();
+        self.current_path_progress = 0.0;
+        self.reset_events();
+        self.pid.reset();
+        self.update_event_progress();
+        
+        match self.timed_arrival {
+            _ if !found_all => Err(PlanError::NoPath),
+            Some(arrival) if arrival > self.deadline => Err(PlanError::DeadlineMissed { arrival, deadline: self.deadline }),
+            _ => Ok(()),
+        }
+    }
+    
+    /// takes a path made elsewhere as the current path, moving the robot to its start facing along it, so it
+    /// is checked, drawn and followed like a planned one until the next plan
+    pub fn set_external_path(&mut self, points: &[[f32; 2]], obstacles: &[Obstacle]) {
+        let Some(&[x, y]) = points.first() else { return; };
+        self.model.move_to(Position::new(x, y, self.model.config.position.z));
+        if let Some(next) = points.iter().find(|point| point[0] != x || point[1] != y) {
+            self.set_heading((next[1] - y).atan2(next[0] - x));
+        }
+        
+        self.path_points = points.iter().map(|&[x, y]| PathPoint::new(x, y)).collect();
+        // a catmull-rom spline needs 4 points
+        while self.path_points.len() < 4 {
+            self.path_points.push(self.path_points[self.path_points.len() - 1].clone());
+        }
+        self.initial_path = points.iter().map(|&[x, y]| Position::new(x, y, 0.0)).collect();
+        self.optimized_path.clear();
+        self.pruned_points.clear();
+        self.stepped_optimization = None;
+        self.stop_progress = 0.0;
+        if let Some(&[x, y]) = points.last() {
+            self.goal_target = Position::new(x, y, 0.0);
+        }
+        self.current_path_progress = 0.0;
+        self.reset_events();
+        self.pid.reset();
+        self.update_event_progress();
+        self.update_speed_profile(obstacles);
+    }
+    
+    /// places every event at the point of the current path nearest to it and sorts them along the path
+    pub fn update_event_progress(&mut self) {
+        for i in 0..self.events.len() {
+            self.events[i].progress = self.progress_nearest_to(&self.events[i].position);
+        }
+        self.events.sort_by(|a, b| a.progress.total_cmp(&b.progress));
+    }
+    
+    /// lets every event trigger again the next time the path is followed
+    pub fn reset_events(&mut self) {
+        self.next_event = 0;
+        self.active_event = None;
+        self.triggered_markers.clear();
+    }
+    
+    /// distance from the robot to the nearest point of its path
+    pub fn cross_track_error(&self) -> f32 {
+        let position = self.model.config.position;
+        let (x, y) = self.catmull_rom_spline(self.progress_nearest_to(&position));
+        ((x - position.x).powi(2) + (y - position.y).powi(2)).sqrt()
+    }
+    
+    /// spline parameter of the sampled path point closest to a position
+    pub fn progress_nearest_to(&self, position: &Position) -> f32 {
+        let samples = (1.0 / (SPLINE_SAMPLE_STEP * 10.0)) as usize;
+        (0..=samples)
+            .map(|i| i as f32 / samples as f32)
+            .min_by(|&a, &b| {
+                let point_a = self.catmull_rom_spline(a);
+                let point_b = self.catmull_rom_spline(b);
+                let dist_a = (point_a.0 - position.x).powi(2) + (point_a.1 - position.y).powi(2);
+                let dist_b = (point_b.0 - position.x).powi(2) + (point_b.1 - position.y).powi(2);
+                dist_a.total_cmp(&dist_b)
+            })
+            .unwrap_or(0.0)
+    }
+    
+    /// sets whether a path point is kept in place, the start and end points are always fixed
+    pub fn set_path_point_locked(&mut self, index: usize, locked: bool) {
+        if index == 0 || index + 1 >= self.path_points.len() {
+            return;
+        }
+        self.path_points[index].locked = locked;
+    }
+    
+    pub fn unlock_all_path_points(&mut self) {
+        for point in &mut self.path_points {
+            point.locked = false;
+        }
+    }
+    
+    /// moves a path point by hand and locks it so later optimization passes leave it there
+    pub fn move_path_point(&mut self, index: usize, position: Position, obstacles: &[Obstacle]) {
+        if let Some(point) = self.path_points.get_mut(index) {
+            point.position = Position::new(position.x, position.y, 0.0);
+            point.height = combined_field(obstacles, point.position);
+            point.locked = true;
+        }
+    }
+    
+    /// total length of the straight segments between path points
+    pub fn path_length(&self) -> f32 {
+        self.path_points.windows(2)
+            .map(|pair| pair[0].position.distance_to(&pair[1].position))
+            .sum()
+    }
+    
+    /// samples the clearance of the spline for the speed limits, evenly in progress
+    pub fn update_speed_profile(&mut self, obstacles: &[Obstacle]) {
+        let field = self.distance_field_for(obstacles);
+        let clearance = |position: Position| match field {
+            Some(field) => field.distance_at(position),
+            None => obstacles.iter()
+                .filter(|obstacle| obstacle.is_hard())
+                .map(|obstacle| obstacle.edge_distance(position))
+                .fold(f32::INFINITY, f32::min),
+        };
+        
+        let mut profile = Vec::with_capacity(PROFILE_SAMPLES + 1);
+        let mut distance = 0.0;
+        let mut previous = self.catmull_rom_spline(0.0);
+        for i in 0..=PROFILE_SAMPLES {
+            let progress = i as f32 / PROFILE_SAMPLES as f32;
+            let (x, y) = self.catmull_rom_spline(progress);
+            distance += (x - previous.0).hypot(y - previous.1);
+            previous = (x, y);
+            profile.push(SpeedSample { progress, distance, point: [x, y], clearance: clearance(Position::new(x, y, 0.0)) });
+        }
+        self.speed_limits.profile = profile;
+    }
+    
+    /// the slow zones the speed limits keep to, a copy of the ones on the field
+    pub fn set_speed_zones(&mut self, zones: &[SpeedZone]) {
+        self.speed_limits.zones = zones.to_vec();
+    }
+    
+    /// speed the follower drives at a spline parameter, the target speed unless a speed limit is lower
+    pub fn speed_limit_at(&self, t: f32) -> f32 {
+        self.speed_limits.speed_at(t, self.target_speed)
+    }
+    
+    /// the time optimal speed at every sample of the speed profile, under the speed limits, the lateral
+    /// acceleration through curves and the acceleration limits when they are on, with the seconds the path takes
+    pub fn time_optimal_profile(&self) -> (Vec<f32>, f32) {
+        let distances: Vec<f32> = self.speed_limits.profile.iter().map(|sample| sample.distance).collect();
+        let limits: Vec<f32> = self.speed_limits.profile.iter()
+            .map(|sample| {
+                let curvature = self.curvature_at(sample.progress);
+                let turning = if curvature > 1e-6 { (self.max_lateral_acceleration / curvature).sqrt() } else { f32::INFINITY };
+                self.speed_limit_at(sample.progress).min(turning)
+            })
+            .collect();
+        let (acceleration, deceleration) = if self.limit_acceleration {
+            (self.max_acceleration, self.max_deceleration)
+        } else {
+            (f32::INFINITY, f32::INFINITY)
+        };
+        speed_limit::time_optimal(&distances, &limits, acceleration, deceleration)
+    }
+    
+    /// smallest distance from the spline to the edge of an enabled obstacle, negative when it cuts through one
+    pub fn spline_clearance(&self, obstacles: &[Obstacle]) -> f32 {
+        if let Some(field) = self.distance_field_for(obstacles) {
+            return self.generate_catmull_rom_spline().into_iter().map(|position| field.distance_at(position)).fold(f32::MAX, f32::min);
+        }
+        let mut min_clearance = f32::MAX;
+        for position in self.generate_catmull_rom_spline() {
+            for obstacle in obstacles.iter().filter(|obstacle| obstacle.is_hard()) {
+                min_clearance = min_clearance.min(obstacle.edge_distance(position));
+            }
+        }
+        min_clearance
+    }
+    
+    /// smallest distance from a path point to the edge of a hard obstacle, negative when inside one
+    pub fn min_clearance(&self, obstacles: &[Obstacle]) -> f32 {
+        if let Some(field) = self.distance_field_for(obstacles) {
+            return self.path_points.iter().map(|point| field.distance_at(point.position)).fold(f32::MAX, f32::min);
+        }
+        let mut min_clearance = f32::MAX;
+        for point in &self.path_points {
+            for obstacle in obstacles.iter().filter(|obstacle| obstacle.is_hard()) {
+                let clearance = obstacle.edge_distance(point.position);
+                min_clearance = min_clearance.min(clearance);
+            }
+        }
+        min_clearance
+    }
+    
+    /// the first moving hard obstacle the robot would hit driving the spline at the target speed, with
+    /// the seconds after leaving the start it happens
+    pub fn space_time_conflict(&self, obstacles: &[Obstacle]) -> Option<(String, f32)> {
+        let moving: Vec<&Obstacle> = obstacles.iter()
+            .filter(|obstacle| obstacle.is_hard() && obstacle.trajectory.is_some())
+            .collect();
+        if moving.is_empty() {
+            return None;
+        }
+        
+        let radius = self.footprint_radius();
+        for (time, position) in self.timed_path() {
+            for obstacle in &moving {
+                if obstacle.edge_distance(obstacle.predicted_query(position, time)) < radius {
+                    return Some((obstacle.name.clone(), time));
+                }
+            }
+        }
+        None
+    }
+    
+    /// points along the spline with the seconds after leaving the start the robot reaches them at the target speed
+    /// or the speed limit there, standing still at the wait events on the way
+    pub fn timed_path(&self) -> Vec<(f32, Position)> {
+        let spline = self.generate_catmull_rom_spline();
+        // every wait is taken at the spline point nearest to it
+        let mut waits: Vec<f32> = vec![0.0; spline.len()];
+        for event in &self.events {
+            let PathAction::Wait { seconds } = event.action else { continue; };
+            let nearest = spline.iter().enumerate()
+                .min_by(|a, b| a.1.distance_to(&event.position).total_cmp(&b.1.distance_to(&event.position)))
+                .map(|(i, _)| i);
+            if let Some(i) = nearest {
+                waits[i] += seconds;
+            }
+        }
+        
+        let mut travelled = 0.0;
+        let mut time = 0.0;
+        let mut previous: Option<Position> = None;
+        let mut timed = Vec::new();
+        for (position, wait) in spline.into_iter().zip(waits) {
+            let step = previous.map_or(0.0, |previous| previous.distance_to(&position));
+            travelled += step;
+            time += step / self.speed_limits.speed_at_distance(travelled, self.target_speed).max(0.1);
+            previous = Some(position);
+            timed.push((time, position));
+            // the point is listed again when the robot leaves it, so the wait is checked at both ends
+            if wait > 0.0 {
+                time += wait;
+                timed.push((time, position));
+            }
+        }
+        timed
     }
     
     pub fn get_path_wires(&self) -> Vec<Wire> {
@@ -183,16 +975,48 @@ impl Robot {
             wires.push(Wire {
                 start,
                 end,
-                color: rgb(0, 255, 0),
+                color: config::color(config::colors().path),
             });
         }
         
         wires
     }
     
+    /// the spline colored by the speed the robot drives along it, from the slow path color at no speed to the
+    /// path color at the target speed
+    pub fn get_speed_path_wires(&self) -> Vec<Wire> {
+        let colors = config::colors();
+        let (slow, fast) = (colors.path_slow, colors.path);
+        let blend = |amount: f32| {
+            let channel = |i: usize| (slow[i] as f32 + (fast[i] as f32 - slow[i] as f32) * amount) as u8;
+            color::rgb(channel(0), channel(1), channel(2))
+        };
+        
+        let spline_points = self.generate_catmull_rom_spline();
+        let mut travelled = 0.0;
+        spline_points.windows(2)
+            .map(|pair| {
+                let middle = travelled + pair[0].distance_to(&pair[1]) / 2.0;
+                travelled += pair[0].distance_to(&pair[1]);
+                let amount = self.speed_limits.speed_at_distance(middle, self.target_speed) / self.target_speed.max(1e-3);
+                Wire::with_color(pair[0], pair[1], blend(amount.clamp(0.0, 1.0)))
+            })
+            .collect()
+    }
+    
+    /// the straight line seed of the last planned path
+    pub fn get_initial_path_wires(&self) -> Vec<Wire> {
+        polyline_wires(&self.initial_path, config::color(config::colors().initial_path))
+    }
+    
+    /// the control polygon after the last optimization, the spline is smoothed from it
+    pub fn get_optimized_path_wires(&self) -> Vec<Wire> {
+        polyline_wires(&self.optimized_path, config::color(config::colors().optimized_path))
+    }
+    
     fn generate_catmull_rom_spline(&self) -> Vec<Position> {
         let segments = self.path_points.len();
-
+        
         if segments < 2 {
             return Vec::new();
         }
@@ -219,6 +1043,11 @@ impl Robot {
     }
     
     fn catmull_rom_point(&self, p0: Position, p1: Position, p2: Position, p3: Position, t: f32) -> Position {
+        // straight line between the points when the smoothing stage is off
+        if !self.pipeline.smooth {
+            return Position::new(p1.x + (p2.x - p1.x) * t, p1.y + (p2.y - p1.y) * t, p1.z + (p2.z - p1.z) * t);
+        }
+        
         // matrix coefficients
         let t2 = t * t;
         let t3 = t2 * t;
@@ -265,18 +1094,91 @@ impl Robot {
         (point.x, point.y)
     }
     
+    /// length of the spline from t to the end of the path, sampled in small steps of t
+    pub fn remaining_path_length(&self, t: f32) -> f32 {
+        let mut length = 0.0;
+        let mut previous = self.catmull_rom_spline(t);
+        let mut current_t = t;
+        
+        while current_t < 1.0 {
+            current_t = (current_t + SPLINE_SAMPLE_STEP).min(1.0);
+            let point = self.catmull_rom_spline(current_t);
+            length += ((point.0 - previous.0).powi(2) + (point.1 - previous.1).powi(2)).sqrt();
+            previous = point;
+        }
+        length
+    }
+    
+    /// curvature (1/m) of the spline at t, from the circle through three nearby samples
+    pub fn curvature_at(&self, t: f32) -> f32 {
+        let a = self.catmull_rom_spline((t - SPLINE_SAMPLE_STEP).max(0.0));
+        let b = self.catmull_rom_spline(t);
+        let c = self.catmull_rom_spline((t + SPLINE_SAMPLE_STEP).min(1.0));
+        
+        let ab = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        let bc = ((c.0 - b.0).powi(2) + (c.1 - b.1).powi(2)).sqrt();
+        let ca = ((a.0 - c.0).powi(2) + (a.1 - c.1).powi(2)).sqrt();
+        if ab * bc * ca < 1e-9 {
+            return 0.0;
+        }
+        
+        let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+        2.0 * cross.abs() / (ab * bc * ca)
+    }
+    
+    /// index of the path point the spline at t is heading towards
+    pub fn path_point_index_at(&self, t: f32) -> usize {
+        if self.path_points.len() < 4 {
+            return self.path_points.len().saturating_sub(1);
+        }
+        
+        let num_segments = self.path_points.len() - 3;
+        let segment_idx = ((t * num_segments as f32).floor() as usize).min(num_segments - 1);
+        segment_idx + 2
+    }
+    
+    /// seconds after leaving the start the robot reaches each path point at the target speed, moving
+    /// obstacles are planned around where they will be by then
+    fn arrival_times(&self) -> Vec<f32> {
+        let speed = self.target_speed.max(0.1);
+        let mut travelled = 0.0;
+        let mut previous = self.path_points.first().map(|point| point.position);
+        self.path_points.iter()
+            .map(|point| {
+                travelled += previous.map_or(0.0, |previous| previous.distance_to(&point.position));
+                previous = Some(point.position);
+                travelled / speed
+            })
+            .collect()
+    }
+    
+    /// the obstacles whose fields a path point is on and how high each one lifts it, highest first
+    pub fn field_contributions(&self, index: usize, obstacles: &[Obstacle]) -> Vec<(usize, f32)> {
+        let Some(point) = self.path_points.get(index) else { return Vec::new(); };
+        let ahead = self.arrival_times()[index];
+        let mut contributions: Vec<(usize, f32)> = obstacles.iter().enumerate()
+            .map(|(i, obstacle)| (i, obstacle.cosine_field_function(obstacle.predicted_query(point.position, ahead))))
+            .filter(|&(_, height)| height > 0.0)
+            .collect();
+        contributions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        contributions
+    }
+    
     fn is_path_optimized(&self, obstacles: &[Obstacle]) -> bool {
-        for i in 1..self.path_points.len() - 1 {
-            if self.path_points[i].get_height() > PATH_OPTIMIZATION_THRESHOLD {
+        let arrival_times = self.arrival_times();
+        let last = self.path_points.len() - 1;
+        for (point, &ahead) in self.path_points[1..last].iter().zip(&arrival_times[1..last]) {
+            if point.is_constrained() {
+                continue;
+            }
+            
+            let position = point.position;
+            if hard_field_height(obstacles, position, ahead) > self.pipeline.optimization_threshold {
                 return false;
             }
             
-            for obstacle in obstacles {
-                let obstacle_pos = obstacle.model.config.position;
-                let min_safe_distance = obstacle.get_radius() + 0.1; // Add small buffer
-                
-                let dist = self.path_points[i].position.distance_to(&obstacle_pos);
-                if dist < min_safe_distance {
+            for obstacle in obstacles.iter().filter(|obstacle| obstacle.is_hard()) {
+                if obstacle.edge_distance(obstacle.predicted_query(position, ahead)) < MIN_SAFE_MARGIN {
                     return false;
                 }
             }
@@ -284,62 +1186,115 @@ impl Robot {
         true
     }
     
-    pub fn optimize_path(&mut self, obstacles: &[Obstacle]) {
+    /// whether another iteration is needed, while a hard obstacle field is crossed, or for the first
+    /// soft iterations while a soft one is
+    fn needs_iteration(&self, obstacles: &[Obstacle], iterations: usize) -> bool {
+        if !self.is_path_optimized(obstacles) {
+            return true;
+        }
+        iterations < self.pipeline.soft_iterations && self.path_points.iter().zip(self.arrival_times())
+            .filter(|(point, _)| !point.is_constrained())
+            .any(|(point, ahead)| point.get_height() - hard_field_height(obstacles, point.position, ahead) > self.pipeline.optimization_threshold)
+    }
+    
+    pub fn optimize_path(&mut self, obstacles: &[Obstacle]) -> Result<(), PlanError> {
         if self.path_points.len() <= 2 {
-            return;
+            self.optimized_path = self.path_points.iter().map(|point| point.position).collect();
+            return Ok(());
         }
         
         let mut iterations = 0;
+        let max_iterations = self.pipeline.max_iterations;
         
         let first_point = &self.path_points[0].position;
         let second_point = &self.path_points[1].position;
         let original_step_distance = first_point.distance_to(second_point);
         
-        while !self.is_path_optimized(obstacles) && iterations < MAX_ITERATIONS {
+        while self.needs_iteration(obstacles, iterations) && iterations < max_iterations {
             iterations += 1;
             self.optimize_path_single_iteration(obstacles);
-            self.clean_path(original_step_distance);
+            if self.pipeline.clean {
+                self.clean_path(original_step_distance);
+            }
+        }
+        
+        self.finish_optimization(obstacles, iterations)
+    }
+    
+    /// starts an optimization that is run by step_optimization, one iteration at a time
+    fn start_stepped_optimization(&mut self) {
+        self.optimized_path = self.path_points.iter().map(|point| point.position).collect();
+        if self.path_points.len() <= 2 {
+            return;
+        }
+        let step_distance = self.path_points[0].position.distance_to(&self.path_points[1].position);
+        self.stepped_optimization = Some(SteppedOptimization { iterations: 0, step_distance });
+    }
+    
+    /// runs up to this many iterations of a stepped optimization, finishing the path the way a
+    /// full plan would once it converges or runs out of iterations
+    pub fn step_optimization(&mut self, obstacles: &[Obstacle], steps: usize) -> Option<Result<(), PlanError>> {
+        let mut stepped = self.stepped_optimization?;
+        for _ in 0..steps {
+            if !self.needs_iteration(obstacles, stepped.iterations) || stepped.iterations >= self.pipeline.max_iterations {
+                self.stepped_optimization = None;
+                let optimized = self.finish_optimization(obstacles, stepped.iterations);
+                self.prune_points_of_curvature(obstacles);
+                self.update_event_progress();
+                self.update_speed_profile(obstacles);
+                return Some(optimized);
+            }
+            
+            stepped.iterations += 1;
+            self.optimize_path_single_iteration(obstacles);
+            if self.pipeline.clean {
+                self.clean_path(stepped.step_distance);
+            }
         }
         
+        self.stepped_optimization = Some(stepped);
+        self.optimized_path = self.path_points.iter().map(|point| point.position).collect();
+        self.update_event_progress();
+        None
+    }
+    
+    /// pushes the points still inside an obstacle field out of it if the optimization did not converge
+    /// and keeps the optimized control points
+    fn finish_optimization(&mut self, obstacles: &[Obstacle], iterations: usize) -> Result<(), PlanError> {
+        let max_iterations = self.pipeline.max_iterations;
+        let threshold = self.pipeline.optimization_threshold;
+        
         for point in &mut self.path_points {
             point.position.z = 0.0;
         }
-
-        if iterations >= MAX_ITERATIONS && !self.is_path_optimized(obstacles) {
-            println!("Warning: Path optimization did not converge after {} iterations", MAX_ITERATIONS);
-            
-            for i in 1..self.path_points.len() - 1 {
-                if self.path_points[i].get_height() > PATH_OPTIMIZATION_THRESHOLD {
+        
+        let converged = iterations < max_iterations || self.is_path_optimized(obstacles);
+        if !converged {
+            // push the points that are still inside an obstacle field straight out of it
+            let arrival_times = self.arrival_times();
+            for (i, &ahead) in arrival_times.iter().enumerate().take(self.path_points.len() - 1).skip(1) {
+                if !self.path_points[i].is_constrained() && hard_field_height(obstacles, self.path_points[i].position, ahead) > threshold {
                     let mut nearest_obstacle_idx = 0;
                     let mut min_dist = f32::MAX;
                     
-                    for (idx, obstacle) in obstacles.iter().enumerate() {
-                        let dist = self.path_points[i].position.distance_to(&obstacle.model.config.position);
+                    for (idx, obstacle) in obstacles.iter().enumerate().filter(|(_, obstacle)| obstacle.is_hard()) {
+                        let dist = obstacle.edge_distance(obstacle.predicted_query(self.path_points[i].position, ahead));
                         if dist < min_dist {
                             min_dist = dist;
                             nearest_obstacle_idx = idx;
                         }
                     }
                     
-                    if obstacles.len() > 0 {
-                        let obstacle_pos = obstacles[nearest_obstacle_idx].model.config.position;
+                    if min_dist < f32::MAX {
                         let point_pos = &mut self.path_points[i].position;
+                        let nearest = &obstacles[nearest_obstacle_idx];
+                        let [nx, ny] = nearest.away_direction(nearest.predicted_query(*point_pos, ahead));
                         
-                        let dx = point_pos.x - obstacle_pos.x;
-                        let dy = point_pos.y - obstacle_pos.y;
-                        let dist = (dx*dx + dy*dy).sqrt();
-                        
-                        if dist > 0.001 {
-                            let nx = dx / dist;
-                            let ny = dy / dist;
-                            
+                        if nx != 0.0 || ny != 0.0 {
                             point_pos.x += nx * 0.5;
                             point_pos.y += ny * 0.5;
                             
-                            let mut height = 0.0;
-                            for obstacle in obstacles {
-                                height += obstacle.cosine_field_function(*point_pos);
-                            }
+                            let height = field_height(obstacles, *point_pos, ahead);
                             self.path_points[i].set_height(height);
                         }
                     }
@@ -347,7 +1302,11 @@ impl Robot {
             }
         }
         
+        self.optimized_path = self.path_points.iter().map(|point| point.position).collect();
         println!("Path optimized in {} iterations", iterations);
+        self.update_event_progress();
+        
+        if converged { Ok(()) } else { Err(PlanError::NoConvergence { iterations }) }
     }
     
     pub fn optimize_path_single_iteration(&mut self, obstacles: &[Obstacle]) -> bool {
@@ -355,39 +1314,50 @@ impl Robot {
             return true;
         }
         
+        let threshold = self.pipeline.optimization_threshold;
+        let min_adjust_rate = config::get().planner.min_adjust_rate;
         let mut all_points_optimized = true;
+        let arrival_times = self.arrival_times();
         
-        for i in 1..self.path_points.len() - 1 {
-            let point = &mut self.path_points[i];
+        let last = self.path_points.len() - 1;
+        for (point, &ahead) in self.path_points[1..last].iter_mut().zip(&arrival_times[1..last]) {
+            if point.locked {
+                continue;
+            }
+            
             let mut too_close_to_obstacle = false;
             
-            for obstacle in obstacles {
-                let obstacle_pos = obstacle.model.config.position;
-                let min_safe_distance = obstacle.get_radius() + 0.1;
-                
-                let dist = point.position.distance_to(&obstacle_pos);
-                if dist < min_safe_distance {
+            for obstacle in obstacles.iter().filter(|obstacle| obstacle.is_hard()) {
+                if obstacle.edge_distance(obstacle.predicted_query(point.position, ahead)) < MIN_SAFE_MARGIN {
                     too_close_to_obstacle = true;
                     break;
                 }
             }
             
-            if point.get_height() > PATH_OPTIMIZATION_THRESHOLD || too_close_to_obstacle {
+            // soft obstacle fields move the point too, but only hard ones keep the path from converging
+            if point.get_height() > threshold || too_close_to_obstacle {
                 all_points_optimized = false;
                 let mut total_delta = Position::new(0.0, 0.0, 0.0);
                 
-                for obstacle in obstacles {
-                    let gradient = obstacle.cosine_gradient_function(point.position);
+                // each field pulls as much as it adds to the combined field, all of them for a plain sum
+                let planned: Vec<&Obstacle> = obstacles.iter().filter(|obstacle| obstacle.is_planned()).collect();
+                let heights: Vec<f32> = planned.iter()
+                    .map(|obstacle| obstacle.cosine_field_function(obstacle.predicted_query(point.position, ahead)))
+                    .collect();
+                let weights = field_combination().gradient_weights(&heights);
+                
+                for (obstacle, weight) in planned.into_iter().zip(weights) {
+                    let query = obstacle.predicted_query(point.position, ahead);
+                    let gradient = obstacle.cosine_gradient_function(query, self.pipeline.learning_rate * weight);
                     
                     let obstacle_pos = obstacle.model.config.position;
-                    let dist = point.position.distance_to(&obstacle_pos);
-                    let min_safe_distance = obstacle.get_radius() + 0.1;
                     
-                    if dist < min_safe_distance {
-                        let diff = point.position.minus(&obstacle_pos);
-                        if diff.norm2D() > 0.001 {
+                    if obstacle.is_hard() && obstacle.edge_distance(query) < MIN_SAFE_MARGIN {
+                        let diff = query.minus(&obstacle_pos);
+                        if diff.x.hypot(diff.y) > 0.001 {
+                            // pushed straight out, away from the center
                             let mut push = diff.norm2D();
-                            total_delta = total_delta.minus(&push.scalar(0.5));
+                            total_delta = total_delta.minus(&push.scalar(-self.pipeline.push_strength));
                         }
                     } else {
                         let grad = Position::new(gradient[0], gradient[1], 0.0);
@@ -395,39 +1365,38 @@ impl Robot {
                     }
                 }
                 
-                if total_delta.x.abs() < MIN_ADJUST_RATE && total_delta.y.abs() < MIN_ADJUST_RATE {
+                if total_delta.x.abs() < min_adjust_rate && total_delta.y.abs() < min_adjust_rate {
                     if total_delta.x != 0.0 {
-                        total_delta.x = total_delta.x.signum() * MIN_ADJUST_RATE;
+                        total_delta.x = total_delta.x.signum() * min_adjust_rate;
                     }
                     if total_delta.y != 0.0 {
-                        total_delta.y = total_delta.y.signum() * MIN_ADJUST_RATE;
+                        total_delta.y = total_delta.y.signum() * min_adjust_rate;
                     }
                 }
                 
                 point.position.move_by(total_delta.x, total_delta.y, 0.0);
+                point.clamp_to_via();
                 
-                let mut height = 0.0;
-                for obstacle in obstacles {
-                    height += obstacle.cosine_field_function(point.position);
-                }
+                let height = field_height(obstacles, point.position, ahead);
                 point.set_height(height);
             }
         }
         
         return all_points_optimized;
     }
-
+    
     fn clean_path(&mut self, original_step_distance: f32) -> bool {
-        // remove points closer together than 1.3x step distance, avoid clumping
-        let threshold = original_step_distance * 1.3;
+        // remove points closer together than the clean spacing times the step distance, avoid clumping
+        let threshold = original_step_distance * self.pipeline.clean_spacing;
+        let min_points = self.pipeline.clean_min_points;
         let mut i = 1;
         let mut removed_any = false;
         // clearscreen::clear().expect("failedtoclear");
         
         while i < self.path_points.len() - 1 {
-            if self.path_points.len() <= 12 { break; }
+            if self.path_points.len() <= min_points { break; }
             if i == 0 { i = 1; continue; }
-            if self.path_points[i].position.distance_to(&self.path_points[i-1].position) < threshold {
+            if !self.path_points[i].is_constrained() && self.path_points[i].position.distance_to(&self.path_points[i-1].position) < threshold {
                 self.path_points.remove(i);
                 removed_any = true;
             } else {
@@ -436,9 +1405,40 @@ impl Robot {
         }
         removed_any
     }
-
-    // returns a Vec of points that are at the beginning or end of the path curving
-    fn get_points_of_curvature(&mut self) {
+    
+    /// removes the points around where the path starts or stops turning, unless that makes the
+    /// smoothed spline cut through an obstacle it did not cut through before
+    fn prune_points_of_curvature(&mut self, obstacles: &[Obstacle]) {
+        self.pruned_points.clear();
+        if !self.pipeline.prune || self.path_points.len() < 5 {
+            return;
+        }
+        
+        let clearance_before = self.spline_clearance(obstacles);
+        let unpruned = self.path_points.clone();
+        let removed = self.get_points_of_curvature();
+        if removed.is_empty() {
+            return;
+        }
+        
+        for &idx in removed.iter().rev() {
+            self.path_points.remove(idx);
+        }
+        
+        // the path was planned to keep the robot radius from the obstacles, so it may not bring the robot closer
+        let clearance_after = self.spline_clearance(obstacles);
+        if clearance_after < config::get().robot.radius && clearance_after < clearance_before {
+            println!("Curvature pruning brought the path within the robot radius of an obstacle, keeping the {} points", removed.len());
+            self.path_points = unpruned;
+            return;
+        }
+        
+        println!("Pruned {} points of curvature", removed.len());
+        self.pruned_points = removed.iter().map(|&idx| unpruned[idx].position).collect();
+    }
+    
+    // returns the indices of points that are at the beginning or end of the path curving, in order
+    fn get_points_of_curvature(&self) -> Vec<usize> {
         let mut i = 2;
         let mut points: Vec<usize> = Vec::new();
         
@@ -449,7 +1449,7 @@ impl Robot {
             let v2 = self.path_points[i].position.minus(&pos_i); // central point
             let v3 = self.path_points[i+1].position.minus(&pos_i);
             let v_path = self.path_points[self.path_points.len()-1].position.minus(&pos_i);
-
+            
             let is_colinear_to_path = |v: &Position| {
                 if v.approx_equals(&ORIGIN) { return false; } // check if is zero vector
                 v.dot(&v_path)/(v.distance_to(&ORIGIN) * v_path.distance_to(&ORIGIN)) > 0.999999 // 1-epsilon to account for floating point error                                                    
@@ -458,48 +1458,103 @@ impl Robot {
                 let mut dist = 0.0;
                 let mut remove_count = 1;
                 let center = self.path_points[i].position;
-                while dist < self.model.config.scale*0.25 as f32 && i+remove_count < self.path_points.len() && i-remove_count > 0 {  
+                let prune_distance = self.model.config.scale * self.pipeline.prune_distance;
+                while dist < prune_distance && i+remove_count < self.path_points.len() && i-remove_count > 0 {  
                     dist = self.path_points[i-remove_count].position.distance_to(&center);
-                    if dist < self.model.config.scale*0.25 && i+remove_count < self.path_points.len() && i-remove_count > 0 {
+                    if dist < prune_distance && i+remove_count < self.path_points.len() && i-remove_count > 0 {
                         remove_count += 1;
                     }
                 }
                 let start_idx = i - remove_count;
                 let end_idx = (i + remove_count).min(self.path_points.len() - 1);
                 for idx in start_idx..=end_idx {
-                    points.push(idx);
+                    if !self.path_points[idx].is_constrained() {
+                        points.push(idx);
+                    }
                 }
                 i = end_idx + 1;
             } else {
                 i += 1;
             }
         }
-
-        println!("points: {:?}", points);
-        for idx in points.into_iter().rev() {
-            self.path_points.remove(idx);    
-        }
+        
+        points
     }
-
+    
     pub fn follow_path(&mut self) {
         self.follow_path_with_dt(0.02); 
     }
     
+    /// starts events the robot has reached and runs the current one, true while an event holds the robot in place
+    fn run_events(&mut self, dt: f32) -> bool {
+        if let Some(active) = self.active_event {
+            let holding = match active {
+                ActiveEvent::Waiting(remaining) => {
+                    self.active_event = Some(ActiveEvent::Waiting(remaining - dt));
+                    remaining - dt > 0.0
+                },
+                ActiveEvent::Rotating(target) => {
+                    let error = wrap_angle(target - self.heading);
+                    let step = EVENT_ROTATION_SPEED * dt;
+                    if error.abs() > step {
+                        self.set_heading(self.heading + step * error.signum());
+                        true
+                    } else {
+                        self.set_heading(target);
+                        false
+                    }
+                },
+            };
+            
+            if holding {
+                self.set_velocity(0.0, 0.0);
+                return true;
+            }
+            self.active_event = None;
+        }
+        
+        while let Some(event) = self.events.get(self.next_event) {
+            if event.progress > self.current_path_progress {
+                break;
+            }
+            self.next_event += 1;
+            
+            match event.action.clone() {
+                PathAction::Wait { seconds } => { self.active_event = Some(ActiveEvent::Waiting(seconds)); },
+                PathAction::RotateTo { heading } => { self.active_event = Some(ActiveEvent::Rotating(heading)); },
+                PathAction::Marker { name } => {
+                    self.triggered_markers.push(name);
+                },
+            }
+            
+            if self.active_event.is_some() {
+                self.set_velocity(0.0, 0.0);
+                return true;
+            }
+        }
+        false
+    }
+    
     pub fn follow_path_with_dt(&mut self, dt: f32) {
-        if self.current_path_progress >= 1.0 {
+        if self.run_events(dt) {
+            return;
+        }
+        
+        if self.current_path_progress >= 1.0 || self.in_goal_region() {
             self.follow_path = false;
             self.set_velocity(0.0, 0.0);
             return;
         }
-
+        
         if self.velocity_update_timer >= 0.02 {
             let current_position = self.catmull_rom_spline(self.current_path_progress);
             let mut d = 0.0;
             let mut xv = 0.0;
             let mut yv = 0.0;
-
+            
             let mut ci = 0.0;
-            let target_distance = self.target_speed * dt;
+            let speed_limit = self.speed_limit_at(self.current_path_progress);
+            let target_distance = speed_limit * dt;
             
             if target_distance <= 0.001 {
                 ci = 0.001;
@@ -514,7 +1569,7 @@ impl Robot {
                 while d < target_distance && iteration_count < max_iterations {
                     ci += 0.001;
                     iteration_count += 1;
-
+                    
                     let focus_point = self.catmull_rom_spline(self.current_path_progress + ci);
                     xv = focus_point.0 - current_position.0;
                     yv = focus_point.1 - current_position.1;
@@ -538,8 +1593,42 @@ impl Robot {
             
             xv /= d;
             yv /= d;
-
-            self.set_velocity(xv * self.target_speed, yv * self.target_speed);
+            
+            let (command_x, command_y) = match self.follower_mode {
+                FollowerMode::OpenLoop => (xv * speed_limit, yv * speed_limit),
+                FollowerMode::Pid => {
+                    // error to the reference point in the path frame, (xv, yv) is the path direction
+                    let reference = self.catmull_rom_spline((self.current_path_progress + ci).min(1.0));
+                    let position = self.model.config.position;
+                    let ex = reference.0 - position.x;
+                    let ey = reference.1 - position.y;
+                    let along_error = ex * xv + ey * yv;
+                    let cross_error = ey * xv - ex * yv;
+                    
+                    let (speed_correction, cross_velocity) = self.pid.update(along_error, cross_error, self.velocity_update_timer);
+                    let speed = speed_limit + speed_correction;
+                    (xv * speed - yv * cross_velocity, yv * speed + xv * cross_velocity)
+                },
+            };
+            
+            if self.local_avoidance.enabled {
+                // the velocity is changed around moving obstacles and pulled back towards the path point the robot
+                // should be at, which waits while the robot falls behind
+                let reference = self.catmull_rom_spline(self.current_path_progress);
+                let position = self.model.config.position;
+                let [vx, vy] = self.local_avoidance.choose_velocity(
+                    [position.x, position.y], [command_x, command_y], [reference.0, reference.1], speed_limit);
+                self.set_velocity(vx, vy);
+                if self.local_avoidance.lagging {
+                    ci = 0.0;
+                }
+            } else {
+                self.set_velocity(command_x, command_y);
+            }
+            
+            // the front faces away from the direction of travel on reversed segments
+            self.travel_reversed = self.path_points.get(self.path_point_index_at(self.current_path_progress))
+                .is_some_and(|point| point.reversed);
             
             self.current_path_progress += ci;
             if self.current_path_progress >= 1.0 {
@@ -552,3 +1641,36 @@ impl Robot {
         }
     }
 }
+
+fn polyline_wires(points: &[Position], color: Rgb<u8>) -> Vec<Wire> {
+    points.windows(2)
+        .map(|pair| Wire::with_color(pair[0], pair[1], color))
+        .collect()
+}
+
+/// shortest 2d distance from a point to the segment a-b
+fn distance_to_segment(point: &Position, a: &Position, b: &Position) -> f32 {
+    let ab = b.minus(a);
+    let length_squared = ab.dot(&ab);
+    if length_squared <= f32::EPSILON {
+        return point.distance_to(a);
+    }
+    
+    let t = (point.minus(a).dot(&ab) / length_squared).clamp(0.0, 1.0);
+    let closest = Position::new(a.x + ab.x * t, a.y + ab.y * t, 0.0);
+    point.distance_to(&closest)
+}
+
+/// height of the obstacle fields at a position, with moving obstacles where they will be the given seconds from now
+fn field_height(obstacles: &[Obstacle], position: Position, seconds_ahead: f32) -> f32 {
+    field_combination().combine(obstacles.iter()
+        .filter(|obstacle| obstacle.is_planned())
+        .map(|obstacle| obstacle.cosine_field_function(obstacle.predicted_query(position, seconds_ahead))))
+}
+
+/// height of the hard obstacle fields alone, soft obstacles may be crossed
+fn hard_field_height(obstacles: &[Obstacle], position: Position, seconds_ahead: f32) -> f32 {
+    field_combination().combine(obstacles.iter()
+        .filter(|obstacle| obstacle.is_hard())
+        .map(|obstacle| obstacle.cosine_field_function(obstacle.predicted_query(position, seconds_ahead))))
+}
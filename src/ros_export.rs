@@ -0,0 +1,114 @@
+use serde::Serialize;
+
+use crate::robot::Robot;
+
+const ROS_FRAME_ID: &str = "map";
+const ROS_POSE_SPACING: f32 = 0.05; // meters of spline between exported poses
+
+/// std_msgs/Header, the stamp is the time the robot reaches the pose after leaving the start
+#[derive(Serialize)]
+struct Header {
+    seq: u32,
+    stamp: Stamp,
+    frame_id: String,
+}
+
+#[derive(Serialize)]
+struct Stamp {
+    secs: u32,
+    nsecs: u32,
+}
+
+/// geometry_msgs/Point
+#[derive(Serialize)]
+struct Point {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+/// geometry_msgs/Quaternion
+#[derive(Serialize)]
+struct Quaternion {
+    x: f64,
+    y: f64,
+    z: f64,
+    w: f64,
+}
+
+/// geometry_msgs/Pose
+#[derive(Serialize)]
+struct Pose {
+    position: Point,
+    orientation: Quaternion,
+}
+
+/// geometry_msgs/PoseStamped
+#[derive(Serialize)]
+struct PoseStamped {
+    header: Header,
+    pose: Pose,
+}
+
+/// nav_msgs/Path
+#[derive(Serialize)]
+struct RosPath {
+    header: Header,
+    poses: Vec<PoseStamped>,
+}
+
+fn header(seq: u32, seconds: f32) -> Header {
+    let seconds = seconds.max(0.0) as f64;
+    Header {
+        seq,
+        stamp: Stamp { secs: seconds.trunc() as u32, nsecs: (seconds.fract() * 1e9) as u32 },
+        frame_id: String::from(ROS_FRAME_ID),
+    }
+}
+
+/// writes the robot's current spline as a nav_msgs/Path of geometry_msgs/PoseStamped, YAML like
+/// `rostopic echo` prints it or JSON for a .json file, poses face the direction of travel
+pub fn export_ros_path(robot: &Robot, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // the robot covers the spacing in this many seconds at the target speed the times are for
+    let step = ROS_POSE_SPACING / robot.target_speed.max(0.1);
+    let timed = robot.timed_path();
+    let mut samples = Vec::new();
+    for (i, &(time, position)) in timed.iter().enumerate() {
+        let due = samples.last().is_none_or(|&(last, _)| time - last >= step);
+        if due || i == timed.len() - 1 {
+            samples.push((time, position));
+        }
+    }
+    
+    let poses = samples.iter().enumerate()
+        .map(|(i, &(time, position))| {
+            // heading towards the next pose it moves to, the last one keeps the heading from the one before it,
+            // as does a pose listed twice for a wait
+            let next = samples[i + 1..].iter().map(|&(_, next)| next).find(|next| next.distance_to(&position) > 0.0);
+            let previous = samples[..i].iter().rev().map(|&(_, previous)| previous).find(|previous| previous.distance_to(&position) > 0.0);
+            let (from, to) = match (previous, next) {
+                (_, Some(next)) => (position, next),
+                (Some(previous), None) => (previous, position),
+                (None, None) => (position, position),
+            };
+            let yaw = ((to.y - from.y) as f64).atan2((to.x - from.x) as f64);
+            PoseStamped {
+                header: header(i as u32, time),
+                pose: Pose {
+                    position: Point { x: position.x as f64, y: position.y as f64, z: 0.0 },
+                    orientation: Quaternion { x: 0.0, y: 0.0, z: (yaw / 2.0).sin(), w: (yaw / 2.0).cos() },
+                },
+            }
+        })
+        .collect();
+    let exported = RosPath { header: header(0, 0.0), poses };
+    
+    let is_json = std::path::Path::new(path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let text = if is_json { serde_json::to_string_pretty(&exported)? } else { serde_yaml::to_string(&exported)? };
+    
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::trajectory::Trajectory;
+use crate::goal_region::GoalRegion;
+use crate::speed_limit::SpeedZone;
+
+fn default_buffer() -> f32 {
+    crate::config::get().robot.buffer
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// file formats a scene can be written in, picked by the file extension
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SceneFormat {
+    Json,
+    Ron,
+    Yaml,
+}
+
+impl SceneFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let extension = path.as_ref().extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        
+        match extension.as_deref() {
+            Some("json") => Ok(SceneFormat::Json),
+            Some("ron") => Ok(SceneFormat::Ron),
+            Some("yaml") | Some("yml") => Ok(SceneFormat::Yaml),
+            _ => Err(format!("unknown scene format for {}, use .json, .ron or .yaml", path.as_ref().display()).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneRobot {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub heading: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenePoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneLayer {
+    pub name: String,
+    pub visible: bool,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneObstacle {
+    pub name: String,
+    pub model: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub z: f32,
+    pub scale: f32,
+    pub radius: f32,
+    pub layer: String,
+    #[serde(default)]
+    pub use_hull: bool,
+    #[serde(default = "default_buffer")]
+    pub buffer: f32,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    #[serde(default)]
+    pub soft: bool,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub trajectory: Option<Trajectory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneGroup {
+    pub name: String,
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+/// decorative model that is not planned around
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneModel {
+    pub model: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub z: f32,
+    pub scale: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneViaPoint {
+    pub x: f32,
+    pub y: f32,
+    pub tolerance: f32,
+    #[serde(default)]
+    pub reversed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneTarget {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub color: [u8; 3],
+}
+
+/// everything placed on the field, the robot, target, obstacles, models and via points
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub robot: SceneRobot,
+    pub target: ScenePoint,
+    #[serde(default)] // scenes saved before goal regions end at the target itself
+    pub target_region: GoalRegion,
+    #[serde(default)]
+    pub targets: Vec<SceneTarget>,
+    #[serde(default)]
+    pub layers: Vec<SceneLayer>,
+    #[serde(default)]
+    pub groups: Vec<SceneGroup>,
+    #[serde(default)]
+    pub obstacles: Vec<SceneObstacle>,
+    #[serde(default)]
+    pub models: Vec<SceneModel>,
+    #[serde(default)]
+    pub via_points: Vec<SceneViaPoint>,
+    #[serde(default)]
+    pub speed_zones: Vec<SpeedZone>,
+}
+
+/// writes the scene in the format matching the file extension
+pub fn save<P: AsRef<Path>>(scene: &Scene, path: P) -> Result<(), Box<dyn std::error::Error>> {
+    let text = match SceneFormat::from_path(&path)? {
+        SceneFormat::Json => serde_json::to_string_pretty(scene)?,
+        SceneFormat::Ron => ron::ser::to_string_pretty(scene, ron::ser::PrettyConfig::default())?,
+        SceneFormat::Yaml => serde_yaml::to_string(scene)?,
+    };
+    
+    if let Some(parent) = path.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+/// reads a scene in the format matching the file extension
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, Box<dyn std::error::Error>> {
+    let format = SceneFormat::from_path(&path)?;
+    let text = std::fs::read_to_string(path)?;
+    
+    let scene = match format {
+        SceneFormat::Json => serde_json::from_str(&text)?,
+        SceneFormat::Ron => ron::from_str(&text)?,
+        SceneFormat::Yaml => serde_yaml::from_str(&text)?,
+    };
+    Ok(scene)
+}
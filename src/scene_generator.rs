@@ -0,0 +1,85 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::field::{field_length, field_width};
+use crate::model::ModelConfig;
+use crate::position::Position;
+
+const MAX_ATTEMPTS_PER_OBSTACLE: usize = 100;
+
+/// settings for a randomly scattered set of obstacles
+#[derive(Clone, Debug)]
+pub struct RandomSceneConfig {
+    pub count: usize,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub seed: u64,
+    pub min_spacing: f32, // gap kept between obstacle edges
+    pub free_radius: f32, // kept clear around the start and goal
+    pub model: String,
+}
+
+impl Default for RandomSceneConfig {
+    fn default() -> Self {
+        Self {
+            count: 20,
+            min_radius: 0.2,
+            max_radius: 0.6,
+            seed: 1,
+            min_spacing: 0.3,
+            free_radius: 1.0,
+            model: String::from("cube"),
+        }
+    }
+}
+
+/// places obstacles uniformly on the field without overlapping each other, the start or the goal,
+/// the same seed always gives the same scene
+pub fn generate_random_scene(config: &RandomSceneConfig, start: Position, goal: Position) -> Vec<ModelConfig> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut placed: Vec<(Position, f32)> = Vec::with_capacity(config.count);
+    let max_radius = config.max_radius.max(config.min_radius);
+    
+    for _ in 0..config.count {
+        for _ in 0..MAX_ATTEMPTS_PER_OBSTACLE {
+            let radius = if max_radius > config.min_radius {
+                rng.gen_range(config.min_radius..max_radius)
+            } else {
+                config.min_radius
+            };
+            
+            if 2.0 * radius >= field_length().min(field_width()) {
+                break;
+            }
+            
+            let position = Position::new(
+                rng.gen_range(radius..field_length() - radius),
+                rng.gen_range(radius..field_width() - radius),
+                0.0,
+            );
+            
+            let blocks_endpoint = position.distance_to(&start) < radius + config.free_radius
+                || position.distance_to(&goal) < radius + config.free_radius;
+            let overlaps = placed.iter().any(|(other, other_radius)| {
+                position.distance_to(other) < radius + other_radius + config.min_spacing
+            });
+            
+            if !blocks_endpoint && !overlaps {
+                placed.push((position, radius));
+                break;
+            }
+        }
+    }
+    
+    if placed.len() < config.count {
+        println!("Random scene: only placed {} of {} obstacles, the field is too crowded", placed.len(), config.count);
+    }
+    
+    placed.into_iter()
+        .map(|(position, radius)| ModelConfig {
+            name: config.model.clone(),
+            position,
+            scale: radius * 2.0, // scale is diameter, radius*2
+        })
+        .collect()
+}
@@ -0,0 +1,220 @@
+// rhai scripting console, a script edits the world through the functions registered in `run_script`
+// and runs to completion within one frame
+use rhai::{Dynamic, Engine, EvalAltResult, Map};
+use std::cell::RefCell;
+use std::rc::Rc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant; // std clocks panic in the browser
+
+use crate::field::{field_length, field_width};
+use crate::model::ModelConfig;
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::world::World;
+
+const MAX_OPERATIONS: u64 = 50_000_000; // stops runaway loops before they freeze the window
+const MAX_OUTPUT_LINES: usize = 500;
+
+pub const DEFAULT_SCRIPT: &str = r#"// ten obstacles in a ring around the field center, then the target swept along the field
+clear_obstacles();
+let cx = field_length() / 2.0;
+let cy = field_width() / 2.0;
+for i in 0..10 {
+    let angle = i.to_float() / 10.0 * 2.0 * PI();
+    add_obstacle("cone", cx + 2.5 * angle.cos(), cy + 2.5 * angle.sin(), 0.3);
+}
+for step in 0..5 {
+    set_target(3.0 + step.to_float() * 2.5, cy);
+    let result = plan();
+    record("length", result.length);
+    record("clearance", result.clearance);
+}
+"#;
+
+/// a value a script recorded with `record`
+#[derive(Debug, Clone)]
+pub struct ScriptMetric {
+    pub name: String,
+    pub value: f64,
+}
+
+/// the source being edited, with what the runs so far printed and recorded
+pub struct ScriptConsole {
+    pub source: String,
+    pub path: String,
+    pub metrics_path: String,
+    pub output: Vec<String>,
+    pub metrics: Vec<ScriptMetric>,
+}
+
+impl ScriptConsole {
+    pub fn new(path: String) -> Self {
+        Self {
+            source: String::from(DEFAULT_SCRIPT),
+            path,
+            metrics_path: String::from("scripts/metrics.csv"),
+            output: Vec::new(),
+            metrics: Vec::new(),
+        }
+    }
+    
+    pub fn load(&mut self) -> Result<(), std::io::Error> {
+        self.source = std::fs::read_to_string(&self.path)?;
+        Ok(())
+    }
+    
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        if let Some(parent) = std::path::Path::new(&self.path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, &self.source)
+    }
+    
+    /// writes the recorded metrics as name,value rows
+    pub fn export_metrics(&self) -> Result<(), std::io::Error> {
+        let mut csv = String::from("name,value\n");
+        for metric in &self.metrics {
+            csv.push_str(&format!("{},{}\n", metric.name, metric.value));
+        }
+        if let Some(parent) = std::path::Path::new(&self.metrics_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.metrics_path, csv)
+    }
+    
+    /// runs the source against the world and hands it back with the script's edits
+    pub fn run(&mut self, world: World, path_segments: usize) -> World {
+        let (world, output, metrics, error) = run_script(&self.source, world, path_segments);
+        self.output.extend(output);
+        if let Some(error) = error {
+            self.output.push(format!("error: {}", error));
+        }
+        self.metrics.extend(metrics);
+        
+        let overflow = self.output.len().saturating_sub(MAX_OUTPUT_LINES);
+        self.output.drain(..overflow);
+        world
+    }
+}
+
+/// what a script works on while it runs
+struct ScriptState {
+    world: World,
+    path_segments: usize,
+    output: Vec<String>,
+    metrics: Vec<ScriptMetric>,
+}
+
+fn position(x: f64, y: f64) -> Position {
+    Position::new(x as f32, y as f32, 0.0)
+}
+
+fn obstacle_index(world: &World, index: i64) -> Result<usize, Box<EvalAltResult>> {
+    let count = world.obstacles.len();
+    usize::try_from(index).ok().filter(|&index| index < count)
+        .ok_or_else(|| format!("no obstacle {}, there are {}", index, count).into())
+}
+
+/// runs a script, returning the world with its edits, what it printed and recorded and the error that stopped it
+fn run_script(source: &str, world: World, path_segments: usize) -> (World, Vec<String>, Vec<ScriptMetric>, Option<String>) {
+    let state = Rc::new(RefCell::new(ScriptState { world, path_segments, output: Vec::new(), metrics: Vec::new() }));
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    
+    let printed = state.clone();
+    engine.on_print(move |text| printed.borrow_mut().output.push(text.to_string()));
+    let debugged = state.clone();
+    engine.on_debug(move |text, _, position| debugged.borrow_mut().output.push(format!("{} {}", position, text)));
+    
+    engine.register_fn("field_length", || field_length() as f64);
+    engine.register_fn("field_width", || field_width() as f64);
+    
+    let s = state.clone();
+    engine.register_fn("obstacle_count", move || s.borrow().world.obstacles.len() as i64);
+    let s = state.clone();
+    engine.register_fn("add_obstacle", move |model: &str, x: f64, y: f64, radius: f64| -> Result<i64, Box<EvalAltResult>> {
+        let config = ModelConfig { name: model.to_string(), position: position(x, y), scale: radius as f32 * 2.0 };
+        let obstacle = Obstacle::from_config(&config).map_err(|e| format!("failed to create obstacle {}: {}", model, e))?;
+        Ok(s.borrow_mut().world.add_obstacle(obstacle) as i64)
+    });
+    let s = state.clone();
+    engine.register_fn("move_obstacle", move |index: i64, x: f64, y: f64| -> Result<(), Box<EvalAltResult>> {
+        let mut state = s.borrow_mut();
+        let index = obstacle_index(&state.world, index)?;
+        state.world.move_obstacle(index, position(x, y));
+        Ok(())
+    });
+    let s = state.clone();
+    engine.register_fn("remove_obstacle", move |index: i64| -> Result<(), Box<EvalAltResult>> {
+        let mut state = s.borrow_mut();
+        let index = obstacle_index(&state.world, index)?;
+        state.world.remove_obstacle(index);
+        Ok(())
+    });
+    let s = state.clone();
+    engine.register_fn("clear_obstacles", move || s.borrow_mut().world.clear_obstacles());
+    
+    let s = state.clone();
+    engine.register_fn("set_target", move |x: f64, y: f64| s.borrow_mut().world.set_target(position(x, y)));
+    let s = state.clone();
+    engine.register_fn("target", move || {
+        let target = s.borrow().world.target();
+        vec![Dynamic::from(target.x as f64), Dynamic::from(target.y as f64)]
+    });
+    let s = state.clone();
+    engine.register_fn("set_start", move |x: f64, y: f64| -> Result<(), Box<EvalAltResult>> {
+        let mut state = s.borrow_mut();
+        let robot = state.world.robot.as_mut().ok_or("no robot is loaded")?;
+        robot.model.move_to(position(x, y));
+        Ok(())
+    });
+    let s = state.clone();
+    engine.register_fn("robot_pose", move || -> Result<Map, Box<EvalAltResult>> {
+        let state = s.borrow();
+        let robot = state.world.robot.as_ref().ok_or("no robot is loaded")?;
+        let mut pose = Map::new();
+        pose.insert("x".into(), Dynamic::from(robot.model.config.position.x as f64));
+        pose.insert("y".into(), Dynamic::from(robot.model.config.position.y as f64));
+        pose.insert("heading".into(), Dynamic::from(robot.heading as f64));
+        Ok(pose)
+    });
+    
+    // plans to the target, a failed plan does not stop the script so sweeps can record it
+    let s = state.clone();
+    engine.register_fn("plan", move || -> Result<Map, Box<EvalAltResult>> {
+        let mut state = s.borrow_mut();
+        let (target, segments) = (state.world.target(), state.path_segments);
+        let ScriptState { world, .. } = &mut *state;
+        let robot = world.robot.as_mut().ok_or("no robot is loaded")?;
+        
+        let started = Instant::now();
+        let result = robot.generate_path(&target, segments, &world.obstacles);
+        let time_ms = started.elapsed().as_secs_f64() * 1000.0;
+        
+        let clearance = if world.obstacles.is_empty() { 0.0 } else { robot.min_clearance(&world.obstacles) };
+        let mut plan = Map::new();
+        plan.insert("ok".into(), Dynamic::from(result.is_ok()));
+        plan.insert("error".into(), Dynamic::from(result.err().map_or(String::new(), |e| e.to_string())));
+        plan.insert("length".into(), Dynamic::from(robot.path_length() as f64));
+        plan.insert("clearance".into(), Dynamic::from(clearance as f64));
+        plan.insert("time_ms".into(), Dynamic::from(time_ms));
+        Ok(plan)
+    });
+    
+    let s = state.clone();
+    engine.register_fn("record", move |name: &str, value: f64| {
+        let mut state = s.borrow_mut();
+        state.output.push(format!("{} = {}", name, value));
+        state.metrics.push(ScriptMetric { name: name.to_string(), value });
+    });
+    
+    let error = engine.run(source).err().map(|e| e.to_string());
+    
+    // the registered functions hold the other references to the state
+    drop(engine);
+    let Ok(state) = Rc::try_unwrap(state) else { unreachable!("the script engine was dropped") };
+    let state = state.into_inner();
+    (state.world, state.output, state.metrics, error)
+}
@@ -0,0 +1,155 @@
+// session logs, one json entry per line written while recording, and their tick by tick replay
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::obstacle::FieldCombination;
+use crate::robot::Robot;
+use crate::scene::Scene;
+
+pub const DIVERGENCE_TOLERANCE: f32 = 0.001; // meters the replayed robot may be off the recorded one
+
+/// where the robot is and how it moves after a tick
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RobotState {
+    pub x: f32,
+    pub y: f32,
+    pub heading: f32,
+    pub velocity: [f32; 2],
+    pub progress: f32,
+    pub following: bool,
+}
+
+impl RobotState {
+    pub fn of(robot: &Robot) -> Self {
+        let position = robot.model.config.position;
+        RobotState {
+            x: position.x,
+            y: position.y,
+            heading: robot.heading,
+            velocity: [robot.velocity_x, robot.velocity_y],
+            progress: robot.current_path_progress,
+            following: robot.follow_path,
+        }
+    }
+    
+    pub fn distance_to(&self, other: &RobotState) -> f32 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+/// robot and planner settings outside the scene that change how the run plays out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSettings {
+    pub planner: String, // label of the planner
+    pub path_segments: usize,
+    pub target_speed: f32,
+    pub noise_enabled: bool,
+    pub noise_std_dev: f32,
+    pub noise_scale_error: f32,
+    pub noise_seed: u64,
+    #[serde(default)] // sessions recorded before it could be changed summed the fields
+    pub field_combination: FieldCombination,
+}
+
+/// one line of a session log, ticks count the simulation steps since recording started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEntry {
+    Start { settings: SessionSettings, scene: Scene },
+    Edit { tick: u64, scene: Scene }, // the field after an edit
+    Plan { tick: u64 },
+    Optimize { tick: u64, locked: Vec<usize> }, // the path was optimized again around the points locked then
+    PathPointMoved { tick: u64, index: usize, x: f32, y: f32 }, // a path point was dragged and let go here
+    Control { tick: u64, following: bool, velocity: [f32; 2] }, // the robot was started, stopped or driven by hand
+    Tick { tick: u64, dt: f32, robot: RobotState },
+}
+
+/// appends entries to a session log while recording, every line is flushed as it is written so the log
+/// survives a crash
+pub struct SessionRecorder {
+    writer: std::io::LineWriter<std::fs::File>,
+    pub tick: u64,
+    pub control: (bool, [f32; 2]), // following and commanded velocity the last tick left the robot with
+}
+
+impl SessionRecorder {
+    pub fn create<P: AsRef<Path>>(path: P, settings: SessionSettings, scene: Scene, control: (bool, [f32; 2])) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::File::create(path)?;
+        let mut recorder = Self { writer: std::io::LineWriter::new(file), tick: 0, control };
+        recorder.log(&SessionEntry::Start { settings, scene });
+        Ok(recorder)
+    }
+    
+    pub fn log(&mut self, entry: &SessionEntry) {
+        let written = serde_json::to_string(entry)
+            .map_err(std::io::Error::from)
+            .and_then(|line| writeln!(self.writer, "{}", line));
+        if let Err(e) = written {
+            eprintln!("Failed to write session log entry: {}", e);
+        }
+    }
+}
+
+/// a recorded session played back one tick at a time
+pub struct SessionReplay {
+    entries: Vec<SessionEntry>,
+    next: usize,
+    pub tick: u64,
+    pub ticks: u64,
+    pub playing: bool,
+    pub ticks_per_frame: usize,
+    pub divergence: Option<(u64, f32)>, // first tick the robot left the recorded state, and by how far
+}
+
+impl SessionReplay {
+    /// reads a session log, returning the replay with the settings and scene it starts from
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Self, SessionSettings, Scene), Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        let mut entries = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push(serde_json::from_str::<SessionEntry>(&line)?);
+            }
+        }
+        
+        let Some(SessionEntry::Start { settings, scene }) = entries.first().cloned() else {
+            return Err("the session log does not begin with a start entry".into());
+        };
+        let ticks = entries.iter().filter(|entry| matches!(entry, SessionEntry::Tick { .. })).count() as u64;
+        let replay = Self { entries, next: 1, tick: 0, ticks, playing: false, ticks_per_frame: 1, divergence: None };
+        Ok((replay, settings, scene))
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.ticks
+    }
+    
+    /// the entries logged before the next tick, with its time step and the robot state recorded after it
+    pub fn next_tick(&mut self) -> Option<(Vec<SessionEntry>, f32, RobotState)> {
+        let mut before = Vec::new();
+        while let Some(entry) = self.entries.get(self.next).cloned() {
+            self.next += 1;
+            match entry {
+                SessionEntry::Tick { dt, robot, .. } => {
+                    self.tick += 1;
+                    return Some((before, dt, robot));
+                },
+                entry => before.push(entry),
+            }
+        }
+        None
+    }
+    
+    /// compares the replayed robot with the recording, keeping the first tick they differ
+    pub fn check(&mut self, replayed: &RobotState, recorded: &RobotState) {
+        let distance = replayed.distance_to(recorded);
+        if self.divergence.is_none() && (distance > DIVERGENCE_TOLERANCE || replayed.following != recorded.following) {
+            self.divergence = Some((self.tick, distance));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use serde::Serialize;
+
+use crate::position::{wrap_angle, Position};
+use crate::robot::Robot;
+
+pub const DEFAULT_CONTROL_RATE: f32 = 50.0; // Hz
+
+/// the state a trajectory follower tracks at one control tick
+#[derive(Serialize)]
+struct Setpoint {
+    t: f32,
+    x: f32,
+    y: f32,
+    heading: f32,
+    vx: f32,
+    vy: f32,
+    omega: f32,
+}
+
+/// position on the timed path at a time, between the two points around it
+fn position_at(timed: &[(f32, Position)], time: f32) -> Position {
+    let after = timed.partition_point(|&(at, _)| at < time);
+    if after == 0 {
+        return timed[0].1;
+    }
+    let Some(&(next_time, next)) = timed.get(after) else { return timed[after - 1].1; };
+    let (previous_time, previous) = timed[after - 1];
+    let span = next_time - previous_time;
+    let amount = if span > 0.0 { (time - previous_time) / span } else { 1.0 };
+    Position::new(previous.x + (next.x - previous.x) * amount, previous.y + (next.y - previous.y) * amount, 0.0)
+}
+
+/// writes the robot's current path as setpoints at a fixed control rate, the time, pose and velocities a
+/// follower on the robot can be fed directly. the timing is the one the path is checked against moving
+/// obstacles with, waits included. CSV for a .csv file, JSON otherwise
+pub fn export_setpoints(robot: &Robot, path: &str, rate: f32) -> Result<(), Box<dyn std::error::Error>> {
+    let timed = robot.timed_path();
+    let Some(&(duration, _)) = timed.last() else { return Err("there is no path to export".into()); };
+    let dt = 1.0 / rate.max(1.0);
+    let ticks = (duration / dt).ceil() as usize;
+    let positions: Vec<Position> = (0..=ticks).map(|i| position_at(&timed, (i as f32 * dt).min(duration))).collect();
+    
+    // velocities are central differences, one sided at the ends
+    let velocity = |i: usize| {
+        let (before, after) = (i.saturating_sub(1), (i + 1).min(ticks));
+        let span = (after - before).max(1) as f32 * dt;
+        [(positions[after].x - positions[before].x) / span, (positions[after].y - positions[before].y) / span]
+    };
+    // the robot faces the way it drives and keeps its heading while it stands still
+    let mut heading = robot.heading;
+    let headings: Vec<f32> = (0..=ticks)
+        .map(|i| {
+            let [vx, vy] = velocity(i);
+            if vx.hypot(vy) > 1e-3 {
+                heading = vy.atan2(vx);
+            }
+            heading
+        })
+        .collect();
+    
+    let setpoints: Vec<Setpoint> = (0..=ticks)
+        .map(|i| {
+            let (before, after) = (i.saturating_sub(1), (i + 1).min(ticks));
+            let span = (after - before).max(1) as f32 * dt;
+            let [vx, vy] = velocity(i);
+            Setpoint {
+                t: i as f32 * dt,
+                x: positions[i].x,
+                y: positions[i].y,
+                heading: headings[i],
+                vx,
+                vy,
+                omega: wrap_angle(headings[after] - headings[before]) / span,
+            }
+        })
+        .collect();
+    
+    let is_csv = std::path::Path::new(path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let text = if is_csv {
+        let mut csv = String::from("t,x,y,heading,vx,vy,omega\n");
+        for setpoint in &setpoints {
+            csv.push_str(&format!("{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4}\n",
+                setpoint.t, setpoint.x, setpoint.y, setpoint.heading, setpoint.vx, setpoint.vy, setpoint.omega));
+        }
+        csv
+    } else {
+        serde_json::to_string_pretty(&setpoints)?
+    };
+    
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(())
+}
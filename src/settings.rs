@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::camera::{BOOKMARK_TRANSITION_TIME, DEFAULT_CAMERA_DAMPING};
+use crate::config::ColorConfig;
+use crate::gradient_field::FieldRendering;
+use crate::path_markers::PathMarkers;
+use crate::remote::DEFAULT_REMOTE_PORT;
+use crate::setpoint_export::DEFAULT_CONTROL_RATE;
+
+pub const SETTINGS_PATH: &str = "settings.json";
+const DEFAULT_CAMERA_SPEED: f32 = 3.0;
+const DEFAULT_FOV: f32 = std::f32::consts::PI / 2.0;
+
+/// ui state saved on exit and restored on startup, missing fields keep their defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub camera_speed: f32,
+    pub rotation_speed: f32,
+    pub camera_damping: f32, // seconds the camera eases its speed over
+    pub camera_transition_time: f32,
+    pub keep_horizon_level: bool,
+    pub fov: f32,
+    pub follow_distance: f32,
+    pub follow_height: f32,
+    pub follow_smoothing: f32,
+    
+    pub show_path: bool,
+    pub show_initial_path: bool,
+    pub show_optimized_path: bool,
+    pub show_pruned_points: bool,
+    pub show_roadmap: bool,
+    pub animate_roadmap: bool,
+    pub roadmap_growth_speed: f32,
+    pub show_wavefront: bool,
+    pub animate_wavefront: bool,
+    pub wavefront_speed: f32,
+    pub replan_moving_obstacles: bool,
+    pub show_hybrid_expanded: bool,
+    pub color_path_by_speed: bool,
+    pub show_points: bool,
+    pub path_markers: PathMarkers,
+    pub show_gradient_function: bool,
+    pub show_influence_rings: bool,
+    pub show_minimap: bool,
+    pub show_grid: bool,
+    pub show_axis_gizmo: bool,
+    pub show_labels: bool,
+    pub line_weights: LineWeights,
+    pub colors: Option<ColorConfig>, // replace the configured colors
+    pub show_field_image: bool,
+    pub show_costmap: bool,
+    pub show_harmonic_field: bool,
+    pub show_distance_heatmap: bool,
+    pub show_distance_contours: bool,
+    pub distance_contour_spacing: f32,
+    pub distance_max: f32,
+    pub grid_spacing: f32,
+    pub grid_extent: f32,
+    
+    pub gradient_x_resolution: f32,
+    pub gradient_y_resolution: f32,
+    pub gradient_line_resolution: f32,
+    pub gradient_adaptive: bool,
+    pub gradient_tolerance: f32, // height the adaptive grid may stray from the field between samples
+    pub gradient_gpu: bool, // evaluate the uniform grid heights with a compute shader
+    pub gradient_rendering: FieldRendering,
+    pub gradient_influence_only: bool, // skip the flat ground away from the obstacle fields
+    pub gradient_influence_margin: f32,
+    pub path_segments: usize,
+    pub fixed_timestep: bool,
+    pub timestep: f32, // seconds simulated per step with the fixed timestep
+    
+    pub field_image_path: String,
+    pub field_image_columns: usize,
+    pub field_image_brightness: f32,
+    pub field_layout_path: String,
+    pub path_export_path: String,
+    pub ros_export_path: String,
+    pub setpoint_export_path: String,
+    pub path_import_path: String,
+    pub control_rate: f32, // Hz the setpoints are exported at
+    pub remote_port: u16,
+    pub svg_export_path: String,
+    pub scene_path: String,
+    pub script_path: String,
+    pub session_path: String,
+    
+    pub panel_width: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            camera_speed: DEFAULT_CAMERA_SPEED,
+            rotation_speed: 1.0,
+            camera_damping: DEFAULT_CAMERA_DAMPING,
+            camera_transition_time: BOOKMARK_TRANSITION_TIME,
+            keep_horizon_level: false,
+            fov: DEFAULT_FOV,
+            follow_distance: 3.0,
+            follow_height: 2.0,
+            follow_smoothing: 0.3,
+            
+            show_path: true,
+            show_initial_path: false,
+            show_optimized_path: false,
+            show_pruned_points: false,
+            show_roadmap: true,
+            animate_roadmap: true,
+            roadmap_growth_speed: 400.0,
+            show_wavefront: true,
+            animate_wavefront: true,
+            wavefront_speed: 40.0,
+            replan_moving_obstacles: false,
+            show_hybrid_expanded: true,
+            color_path_by_speed: false,
+            show_points: true,
+            path_markers: PathMarkers::default(),
+            show_gradient_function: true,
+            show_influence_rings: false,
+            show_minimap: true,
+            show_grid: true,
+            show_axis_gizmo: true,
+            show_labels: true,
+            line_weights: LineWeights::default(),
+            colors: None,
+            show_field_image: true,
+            show_costmap: false,
+            show_harmonic_field: false,
+            show_distance_heatmap: false,
+            show_distance_contours: false,
+            distance_contour_spacing: 0.25,
+            distance_max: 1.5,
+            grid_spacing: 1.0,
+            grid_extent: 0.0,
+            
+            gradient_x_resolution: 0.5,
+            gradient_y_resolution: 0.5,
+            gradient_line_resolution: 0.5,
+            gradient_adaptive: false,
+            gradient_tolerance: 0.05,
+            gradient_gpu: false,
+            gradient_rendering: FieldRendering::Wires,
+            gradient_influence_only: false,
+            gradient_influence_margin: 0.25,
+            path_segments: 160,
+            fixed_timestep: false,
+            timestep: 1.0 / 60.0,
+            
+            field_image_path: String::from("fields/field.png"),
+            field_image_columns: 96,
+            field_image_brightness: 0.6,
+            field_layout_path: String::from("fields/layout.json"),
+            path_export_path: String::from("paths/path.json"),
+            ros_export_path: String::from("paths/path_ros.yaml"),
+            setpoint_export_path: String::from("paths/setpoints.csv"),
+            path_import_path: String::from("paths/external.csv"),
+            control_rate: DEFAULT_CONTROL_RATE,
+            remote_port: DEFAULT_REMOTE_PORT,
+            svg_export_path: String::from("paths/plan.svg"),
+            scene_path: String::from("scenes/scene.json"),
+            script_path: String::from("scripts/script.rhai"),
+            session_path: String::from("sessions/session.jsonl"),
+            
+            panel_width: 200.0,
+        }
+    }
+}
+
+/// widths in points of the lines of each part of the scene, so the path can stand out over a dense field mesh
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineWeights {
+    pub field: f32, // field border and ground grid
+    pub obstacles: f32,
+    pub path: f32,
+    pub gradient: f32,
+}
+
+impl Default for LineWeights {
+    fn default() -> Self {
+        Self {
+            field: 1.0,
+            obstacles: 1.0,
+            path: 2.5,
+            gradient: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+    
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+// space-time a* for goals with a deadline. the search is over grid cells and the time the robot is in them, every
+// step either drives to a neighbor cell at the robot's speed or waits where it is, and the moving obstacles are
+// checked where their trajectories put them at that time, so the path can let one pass instead of going around
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::dubins::{PathSample, segment_samples};
+use crate::field::{field_length, field_width};
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+
+pub const DEFAULT_SPACE_TIME_RESOLUTION: f32 = 0.1; // meters per cell
+pub const DEFAULT_DEADLINE: f32 = 10.0; // seconds
+const HORIZON: f32 = 120.0; // seconds ahead the search gives up at
+const MAX_EXPANSIONS: usize = 300_000;
+
+/// a leg through space and time, the points of the path after the start and where the robot stops on the way
+pub struct TimedLeg {
+    pub samples: Vec<PathSample>,
+    pub heading: f32, // the robot arrives with
+    pub waits: Vec<([f32; 2], f32)>, // where the robot stops and for how many seconds
+    pub arrival: f32, // seconds after the plan started
+}
+
+/// cells the robot center may never enter, the static hard obstacles grown by the robot radius. the moving
+/// obstacles are checked at the time of every step instead
+pub struct SpaceTimeGrid {
+    resolution: f32,
+    columns: usize,
+    rows: usize,
+    blocked: Vec<bool>,
+}
+
+impl SpaceTimeGrid {
+    pub fn build(obstacles: &[Obstacle], resolution: f32) -> Self {
+        let resolution = resolution.max(0.01);
+        let columns = (field_length() / resolution).ceil() as usize;
+        let rows = (field_width() / resolution).ceil() as usize;
+        let fixed: Vec<&Obstacle> = obstacles.iter()
+            .filter(|obstacle| obstacle.is_hard() && obstacle.trajectory.is_none())
+            .collect();
+        
+        let mut blocked = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let center = Position::new((column as f32 + 0.5) * resolution, (row as f32 + 0.5) * resolution, 0.0);
+                blocked.push(fixed.iter().any(|obstacle| obstacle.edge_distance(center) < obstacle.robot_radius()));
+            }
+        }
+        SpaceTimeGrid { resolution, columns, rows, blocked }
+    }
+    
+    fn cell_of(&self, point: [f32; 2]) -> Option<usize> {
+        if point[0] < 0.0 || point[1] < 0.0 {
+            return None;
+        }
+        let column = (point[0] / self.resolution) as usize;
+        let row = (point[1] / self.resolution) as usize;
+        (column < self.columns && row < self.rows).then(|| row * self.columns + column)
+    }
+    
+    fn center_of(&self, cell: usize) -> [f32; 2] {
+        let (row, column) = (cell / self.columns, cell % self.columns);
+        [(column as f32 + 0.5) * self.resolution, (row as f32 + 0.5) * self.resolution]
+    }
+    
+    fn neighbor(&self, column: i64, row: i64) -> Option<usize> {
+        (column >= 0 && row >= 0 && (column as usize) < self.columns && (row as usize) < self.rows)
+            .then(|| row as usize * self.columns + column as usize)
+    }
+    
+    /// octile distance in meters
+    fn distance(&self, a: usize, b: usize) -> f32 {
+        let d_column = (a % self.columns).abs_diff(b % self.columns) as f32;
+        let d_row = (a / self.columns).abs_diff(b / self.columns) as f32;
+        (d_column.max(d_row) + (std::f32::consts::SQRT_2 - 1.0) * d_column.min(d_row)) * self.resolution
+    }
+    
+    /// earliest arrival at the goal from start, leaving start_time seconds after the plan started and driving
+    /// at speed between the cells. None when no path gets there within the search horizon
+    pub fn plan(&self, obstacles: &[Obstacle], start: [f32; 2], start_time: f32, goal: [f32; 2], speed: f32) -> Option<TimedLeg> {
+        let speed = speed.max(0.1);
+        let moving: Vec<&Obstacle> = obstacles.iter()
+            .filter(|obstacle| obstacle.is_hard() && obstacle.trajectory.is_some())
+            .collect();
+        let hit = |point: [f32; 2], time: f32| moving.iter().any(|obstacle| {
+            let query = obstacle.predicted_query(Position::new(point[0], point[1], 0.0), time);
+            obstacle.edge_distance(query) < obstacle.robot_radius()
+        });
+        
+        let start_cell = self.cell_of(start)?;
+        let goal_cell = self.cell_of(goal).filter(|&cell| !self.blocked[cell])?;
+        // a step is the time to drive one cell across, time is binned by it so waiting always moves on a bin
+        let tick = self.resolution / speed;
+        let bin = |time: f32| ((time - start_time) / tick).round() as u32;
+        
+        let mut times: Vec<f32> = vec![start_time]; // per node
+        let mut cells: Vec<usize> = vec![start_cell];
+        let mut parents: Vec<Option<usize>> = vec![None];
+        let mut reached: HashMap<(usize, u32), f32> = HashMap::from([((start_cell, 0), start_time)]);
+        let mut open = BinaryHeap::new();
+        open.push(Reverse(((start_time + self.distance(start_cell, goal_cell) / speed).to_bits(), 0usize)));
+        
+        let mut expansions = 0;
+        let mut arrived = None;
+        while let Some(Reverse((_, node))) = open.pop() {
+            let (cell, time) = (cells[node], times[node]);
+            if cell == goal_cell {
+                arrived = Some(node);
+                break;
+            }
+            if reached.get(&(cell, bin(time))).is_some_and(|&best| best < time) {
+                continue;
+            }
+            expansions += 1;
+            if expansions > MAX_EXPANSIONS {
+                return None;
+            }
+            
+            let (row, column) = ((cell / self.columns) as i64, (cell % self.columns) as i64);
+            for (d_column, d_row) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let Some(next) = self.neighbor(column + d_column, row + d_row) else { continue; };
+                // the start cell may be blocked when the robot starts right next to an obstacle, it can still leave
+                if self.blocked[next] && next != start_cell {
+                    continue;
+                }
+                let diagonal = d_column != 0 && d_row != 0;
+                if diagonal {
+                    let beside = [self.neighbor(column + d_column, row), self.neighbor(column, row + d_row)];
+                    if beside.iter().any(|side| side.is_none_or(|side| self.blocked[side])) {
+                        continue;
+                    }
+                }
+                
+                // waiting takes one tick in place
+                let duration = if diagonal { tick * std::f32::consts::SQRT_2 } else { tick };
+                let arrival = time + duration;
+                if arrival - start_time > HORIZON {
+                    continue;
+                }
+                let (from, to) = (self.center_of(cell), self.center_of(next));
+                let midway = [(from[0] + to[0]) / 2.0, (from[1] + to[1]) / 2.0];
+                if hit(midway, time + duration / 2.0) || hit(to, arrival) {
+                    continue;
+                }
+                
+                let key = (next, bin(arrival));
+                if reached.get(&key).is_some_and(|&best| best <= arrival) {
+                    continue;
+                }
+                reached.insert(key, arrival);
+                times.push(arrival);
+                cells.push(next);
+                parents.push(Some(node));
+                open.push(Reverse(((arrival + self.distance(next, goal_cell) / speed).to_bits(), cells.len() - 1)));
+            }
+        }
+        
+        let mut chain = vec![arrived?];
+        while let Some(parent) = parents[*chain.last().unwrap()] {
+            chain.push(parent);
+        }
+        chain.reverse();
+        
+        // the points the robot drives through, with the seconds it stands at each before driving on. the center
+        // of the goal cell is swapped for the goal itself
+        let mut stops = vec![(start, 0.0)];
+        for pair in chain.windows(2) {
+            if cells[pair[1]] == cells[pair[0]] {
+                stops.last_mut().unwrap().1 += times[pair[1]] - times[pair[0]];
+            } else {
+                stops.push((self.center_of(cells[pair[1]]), 0.0));
+            }
+        }
+        if stops.len() > 1 {
+            stops.last_mut().unwrap().0 = goal;
+        } else {
+            stops.push((goal, 0.0));
+        }
+        
+        let mut samples = Vec::new();
+        for pair in stops.windows(2) {
+            samples.extend(segment_samples(pair[0].0, pair[1].0).into_iter()
+                .map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed: false }));
+        }
+        
+        let before_goal = stops[stops.len() - 2].0;
+        let heading = (goal[1] - before_goal[1]).atan2(goal[0] - before_goal[0]);
+        Some(TimedLeg {
+            samples,
+            heading,
+            waits: stops.into_iter().filter(|stop| stop.1 > 0.0).collect(),
+            arrival: times[*chain.last().unwrap()],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Model, ModelConfig};
+    use crate::trajectory::{Trajectory, TrajectoryWaypoint};
+    
+    // cell centers, so the times of the search match the distances driven
+    const START: [f32; 2] = [2.05, 4.05];
+    const GOAL: [f32; 2] = [6.05, 4.05];
+    const SPEED: f32 = 1.0;
+    
+    /// an obstacle crossing the straight line from the start to the goal at (4.05, 4.05) two seconds in, just
+    /// when a robot driving straight there would be
+    fn crossing_obstacle() -> Obstacle {
+        let config = ModelConfig { name: String::from("crossing"), position: Position::new(4.05, 2.05, 0.0), scale: 0.5 };
+        let mut obstacle = Obstacle::new(Model { wires: Vec::new(), config });
+        obstacle.trajectory = Some(Trajectory {
+            waypoints: vec![TrajectoryWaypoint { time: 0.0, x: 4.05, y: 2.05 }, TrajectoryWaypoint { time: 4.0, x: 4.05, y: 6.05 }],
+            looping: false,
+        });
+        obstacle
+    }
+    
+    /// how far the robot center is inside the clearance the obstacle needs at a time, negative when clear
+    fn overlap(obstacle: &Obstacle, point: [f32; 2], time: f32) -> f32 {
+        obstacle.robot_radius() - obstacle.edge_distance(obstacle.predicted_query(Position::new(point[0], point[1], 0.0), time))
+    }
+    
+    /// where the robot is along a leg driven at the speed, every sample and every few hundredths of a second
+    /// it waits
+    fn timed_points(leg: &TimedLeg) -> Vec<([f32; 2], f32)> {
+        let mut points = Vec::new();
+        let mut waits = leg.waits.iter().peekable();
+        let (mut at, mut time) = (START, 0.0);
+        let mut visit = |point: [f32; 2], time: &mut f32, points: &mut Vec<([f32; 2], f32)>| {
+            points.push((point, *time));
+            if let Some(&&(_, seconds)) = waits.peek().filter(|(stop, _)| (stop[0] - point[0]).hypot(stop[1] - point[1]) < 1e-4) {
+                waits.next();
+                let end = *time + seconds;
+                while *time < end {
+                    *time = (*time + 0.02).min(end);
+                    points.push((point, *time));
+                }
+            }
+        };
+        visit(at, &mut time, &mut points);
+        for sample in &leg.samples {
+            let point = [sample.position.x, sample.position.y];
+            time += (point[0] - at[0]).hypot(point[1] - at[1]) / SPEED;
+            visit(point, &mut time, &mut points);
+            at = point;
+        }
+        points
+    }
+    
+    #[test]
+    fn driving_straight_would_hit_the_crossing_obstacle() {
+        let obstacle = crossing_obstacle();
+        let hit = (0..=400).any(|i| {
+            let time = i as f32 / 100.0;
+            overlap(&obstacle, [START[0] + time * SPEED, START[1]], time) > 0.0
+        });
+        assert!(hit);
+    }
+    
+    #[test]
+    fn the_plan_lets_the_crossing_obstacle_pass() {
+        let obstacles = vec![crossing_obstacle()];
+        let grid = SpaceTimeGrid::build(&obstacles, DEFAULT_SPACE_TIME_RESOLUTION);
+        let leg = grid.plan(&obstacles, START, 0.0, GOAL, SPEED).unwrap();
+        
+        // waiting or driving around both take longer than the four seconds straight there
+        assert!(leg.arrival > 4.0 + 1e-3);
+        let end = leg.samples.last().unwrap().position;
+        assert!((end.x - GOAL[0]).abs() < 1e-4 && (end.y - GOAL[1]).abs() < 1e-4);
+        
+        // the search checks every step at its middle and end, so allow for a little between those
+        let points = timed_points(&leg);
+        assert!((points.last().unwrap().1 - leg.arrival).abs() < 1e-3);
+        for (point, time) in points {
+            let overlap = overlap(&obstacles[0], point, time);
+            assert!(overlap < DEFAULT_SPACE_TIME_RESOLUTION, "{} m into the obstacle at ({}, {}) after {} s", overlap, point[0], point[1], time);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+// speed limits along the path, from its clearance to the hard obstacles so the robot drives slowly where it
+// passes close to them and at the full target speed in the open field, and from slow zones drawn on the field
+use serde::{Deserialize, Serialize};
+
+use crate::position::Position;
+
+pub const DEFAULT_SLOW_CLEARANCE: f32 = 0.1; // meters, at or below which the robot drives at the minimum fraction
+pub const DEFAULT_FAST_CLEARANCE: f32 = 1.0; // meters, from which on the robot drives at the target speed
+pub const DEFAULT_MIN_FRACTION: f32 = 0.3; // of the target speed
+pub const DEFAULT_ZONE_SPEED: f32 = 1.0; // m/s
+pub const DEFAULT_MAX_LATERAL_ACCELERATION: f32 = 3.0; // m/s^2 the robot may turn with before it slides
+pub const PROFILE_SAMPLES: usize = 200; // spans of the spline the clearance is sampled over
+
+/// the clearance of the path at one point of the spline
+#[derive(Debug, Copy, Clone)]
+pub struct SpeedSample {
+    pub progress: f32, // spline parameter
+    pub distance: f32, // meters along the spline from the start
+    pub point: [f32; 2],
+    pub clearance: f32,
+}
+
+/// rectangle on the field the robot may not drive faster than a speed in, like over cable protectors or a ramp
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedZone {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    pub max_speed: f32,
+}
+
+impl SpeedZone {
+    /// the zone between two opposite corners
+    pub fn from_corners(a: [f32; 2], b: [f32; 2], max_speed: f32) -> Self {
+        SpeedZone { min: [a[0].min(b[0]), a[1].min(b[1])], max: [a[0].max(b[0]), a[1].max(b[1])], max_speed }
+    }
+    
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        point[0] >= self.min[0] && point[0] <= self.max[0] && point[1] >= self.min[1] && point[1] <= self.max[1]
+    }
+    
+    /// corners on the ground, counterclockwise from the lowest
+    pub fn corners(&self) -> [Position; 4] {
+        [
+            Position::new(self.min[0], self.min[1], 0.0),
+            Position::new(self.max[0], self.min[1], 0.0),
+            Position::new(self.max[0], self.max[1], 0.0),
+            Position::new(self.min[0], self.max[1], 0.0),
+        ]
+    }
+}
+
+/// the rules from clearance and zones to speed and the clearance along the current path, the speeds are worked
+/// out when they are asked for so the rules and the target speed can be changed without sampling the path again
+#[derive(Debug, Clone)]
+pub struct SpeedLimits {
+    pub enabled: bool, // the clearance rule, the zones always apply
+    pub slow_clearance: f32,
+    pub fast_clearance: f32,
+    pub min_fraction: f32,
+    pub(crate) zones: Vec<SpeedZone>, // set through `Robot::set_speed_zones`
+    pub profile: Vec<SpeedSample>, // evenly spaced in progress, empty before the first plan
+}
+
+impl SpeedLimits {
+    pub fn new() -> Self {
+        SpeedLimits {
+            enabled: false,
+            slow_clearance: DEFAULT_SLOW_CLEARANCE,
+            fast_clearance: DEFAULT_FAST_CLEARANCE,
+            min_fraction: DEFAULT_MIN_FRACTION,
+            zones: Vec::new(),
+            profile: Vec::new(),
+        }
+    }
+    
+    /// fraction of the target speed allowed at a clearance, rising linearly between the slow and fast clearances
+    pub fn fraction(&self, clearance: f32) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        let span = (self.fast_clearance - self.slow_clearance).max(1e-3);
+        let amount = ((clearance - self.slow_clearance) / span).clamp(0.0, 1.0);
+        self.min_fraction + (1.0 - self.min_fraction) * amount
+    }
+    
+    /// lowest max speed of the zones a point is in, infinite outside them
+    pub fn zone_speed(&self, point: [f32; 2]) -> f32 {
+        self.zones.iter()
+            .filter(|zone| zone.contains(point))
+            .map(|zone| zone.max_speed)
+            .fold(f32::INFINITY, f32::min)
+    }
+    
+    /// speed allowed at a spline parameter
+    pub fn speed_at(&self, progress: f32, target_speed: f32) -> f32 {
+        self.speed_of(self.sample_at(progress, |sample| sample.progress), target_speed)
+    }
+    
+    /// speed allowed a distance along the spline from the start
+    pub fn speed_at_distance(&self, distance: f32, target_speed: f32) -> f32 {
+        self.speed_of(self.sample_at(distance, |sample| sample.distance), target_speed)
+    }
+    
+    fn speed_of(&self, sample: Option<SpeedSample>, target_speed: f32) -> f32 {
+        match sample {
+            Some(sample) => (target_speed * self.fraction(sample.clearance)).min(self.zone_speed(sample.point)),
+            None => target_speed,
+        }
+    }
+    
+    /// the path interpolated between the two samples around a value of one of their fields, which rises along
+    /// the profile
+    fn sample_at(&self, value: f32, key: impl Fn(&SpeedSample) -> f32) -> Option<SpeedSample> {
+        let first = *self.profile.first()?;
+        let after = self.profile.partition_point(|sample| key(sample) < value);
+        if after == 0 {
+            return Some(first);
+        }
+        let Some(next) = self.profile.get(after) else { return Some(self.profile[after - 1]); };
+        let previous = self.profile[after - 1];
+        let span = key(next) - key(&previous);
+        let amount = if span > 0.0 { (value - key(&previous)) / span } else { 0.0 };
+        let blend = |a: f32, b: f32| a + (b - a) * amount;
+        Some(SpeedSample {
+            progress: blend(previous.progress, next.progress),
+            distance: blend(previous.distance, next.distance),
+            point: [blend(previous.point[0], next.point[0]), blend(previous.point[1], next.point[1])],
+            clearance: blend(previous.clearance, next.clearance),
+        })
+    }
+}
+
+/// the fastest speeds at points a distance along the path under a speed limit at each, starting and ending at
+/// a standstill. a forward pass speeds up as hard as the acceleration allows and a backward pass slows down in
+/// time for every limit ahead. returns the speeds and the seconds the path takes at them
+pub fn time_optimal(distances: &[f32], limits: &[f32], acceleration: f32, deceleration: f32) -> (Vec<f32>, f32) {
+    let mut speeds: Vec<f32> = limits.iter().map(|limit| limit.max(0.0)).collect();
+    let Some(last) = speeds.len().checked_sub(1) else { return (speeds, 0.0); };
+    speeds[0] = 0.0;
+    speeds[last] = 0.0;
+    
+    for i in 1..speeds.len() {
+        let step = distances[i] - distances[i - 1];
+        speeds[i] = speeds[i].min((speeds[i - 1].powi(2) + 2.0 * acceleration * step).sqrt());
+    }
+    for i in (0..last).rev() {
+        let step = distances[i + 1] - distances[i];
+        speeds[i] = speeds[i].min((speeds[i + 1].powi(2) + 2.0 * deceleration * step).sqrt());
+    }
+    
+    // the speed changes evenly over each step, so it is driven at the mean of its ends
+    let time = (1..speeds.len())
+        .map(|i| {
+            let mean = (speeds[i - 1] + speeds[i]) / 2.0;
+            let step = distances[i] - distances[i - 1];
+            if mean > 1e-6 { step / mean } else { 0.0 }
+        })
+        .sum();
+    (speeds, time)
+}
+
+impl Default for SpeedLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const ACCELERATION: f32 = 2.0;
+    const DECELERATION: f32 = 1.5;
+    
+    /// a straight 10 m path along x sampled every 10 cm, at 3 m/s except in a 1 m/s zone from 4 m to 6 m
+    fn zoned_path() -> (Vec<f32>, Vec<f32>) {
+        let limits = SpeedLimits { zones: vec![SpeedZone::from_corners([4.0, -1.0], [6.0, 1.0], 1.0)], ..SpeedLimits::new() };
+        let distances: Vec<f32> = (0..=100).map(|i| i as f32 * 0.1).collect();
+        let speeds = distances.iter().map(|&x| limits.zone_speed([x, 0.0]).min(3.0)).collect();
+        (distances, speeds)
+    }
+    
+    #[test]
+    fn the_profile_starts_and_ends_at_a_standstill() {
+        let (distances, limits) = zoned_path();
+        let (speeds, time) = time_optimal(&distances, &limits, ACCELERATION, DECELERATION);
+        
+        assert_eq!(speeds[0], 0.0);
+        assert_eq!(*speeds.last().unwrap(), 0.0);
+        assert!(speeds[50] > 0.0);
+        // slower than driving the whole way at the top speed
+        assert!(time > 10.0 / 3.0);
+    }
+    
+    #[test]
+    fn the_profile_keeps_to_the_zone_limit() {
+        let (distances, limits) = zoned_path();
+        let (speeds, _) = time_optimal(&distances, &limits, ACCELERATION, DECELERATION);
+        
+        for (i, (&speed, &limit)) in speeds.iter().zip(&limits).enumerate() {
+            assert!(speed <= limit + 1e-5, "sample {} at {} m/s over its limit of {}", i, speed, limit);
+        }
+        assert!(speeds[45..=55].iter().all(|&speed| speed <= 1.0 + 1e-5));
+    }
+    
+    #[test]
+    fn consecutive_samples_keep_to_the_acceleration_limits() {
+        let (distances, limits) = zoned_path();
+        let (speeds, _) = time_optimal(&distances, &limits, ACCELERATION, DECELERATION);
+        
+        for i in 1..speeds.len() {
+            let step = distances[i] - distances[i - 1];
+            let (before, after) = (speeds[i - 1].powi(2), speeds[i].powi(2));
+            assert!(after <= before + 2.0 * ACCELERATION * step + 1e-4, "speeds up too hard at sample {}", i);
+            assert!(before <= after + 2.0 * DECELERATION * step + 1e-4, "slows down too hard at sample {}", i);
+        }
+    }
+}
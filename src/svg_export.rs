@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use crate::field::{field_length, field_width};
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::robot::Robot;
+
+const PIXELS_PER_METER: f32 = 100.0;
+const MARGIN: f32 = 20.0; // pixels around the field border
+const SPLINE_SAMPLES: usize = 400;
+const MARKER_RADIUS: f32 = 0.15;
+
+/// maps field meters onto the drawing, y points down in svg so it is flipped
+fn to_svg(x: f32, y: f32) -> (f32, f32) {
+    (MARGIN + x * PIXELS_PER_METER, MARGIN + (field_width() - y) * PIXELS_PER_METER)
+}
+
+fn polygon_points(vertices: &[[f32; 2]]) -> String {
+    vertices.iter()
+        .map(|vertex| {
+            let (x, y) = to_svg(vertex[0], vertex[1]);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn write_marker(svg: &mut String, position: Position, color: &str, label: &str) -> std::fmt::Result {
+    let (x, y) = to_svg(position.x, position.y);
+    writeln!(svg, r#"  <circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}"/>"#, x, y, MARKER_RADIUS * PIXELS_PER_METER, color)?;
+    writeln!(svg, r#"  <text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="14" fill="{}">{}</text>"#,
+        x + MARKER_RADIUS * PIXELS_PER_METER + 4.0, y + 5.0, color, label)
+}
+
+/// top down vector drawing of the field, obstacles with their safety margins, the path spline and its endpoints
+fn plan_svg(robot: Option<&Robot>, obstacles: &[Obstacle], target: Position) -> Result<String, std::fmt::Error> {
+    let width = field_length() * PIXELS_PER_METER + MARGIN * 2.0;
+    let height = field_width() * PIXELS_PER_METER + MARGIN * 2.0;
+    
+    let mut svg = String::new();
+    writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}">"#, width, height, width, height)?;
+    writeln!(svg, r#"  <rect width="100%" height="100%" fill="white"/>"#)?;
+    
+    let (left, top) = to_svg(0.0, field_width());
+    writeln!(svg, r#"  <rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="none" stroke="black" stroke-width="2"/>"#,
+        left, top, field_length() * PIXELS_PER_METER, field_width() * PIXELS_PER_METER)?;
+    
+    // safety margins first so the footprints are drawn on top of them
+    for obstacle in obstacles.iter().filter(|obstacle| obstacle.visible && obstacle.is_planned()) {
+        let clearance = obstacle.clearance() * PIXELS_PER_METER;
+        match obstacle.footprint_polygon() {
+            // a polygon stroked with round joins covers exactly the area within the clearance of its edges
+            Some(vertices) => writeln!(svg, r##"  <polygon points="{}" fill="#f5a623" fill-opacity="0.2" stroke="#f5a623" stroke-opacity="0.2" stroke-width="{:.1}" stroke-linejoin="round"/>"##,
+                polygon_points(&vertices), clearance * 2.0)?,
+            None => {
+                let (x, y) = to_svg(obstacle.config.position.x, obstacle.config.position.y);
+                writeln!(svg, r##"  <circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="#f5a623" fill-opacity="0.2"/>"##,
+                    x, y, obstacle.get_radius() * PIXELS_PER_METER + clearance)?;
+            }
+        }
+    }
+    
+    for obstacle in obstacles.iter().filter(|obstacle| obstacle.visible) {
+        // passable obstacles are drawn dashed since the path ignores them
+        let style = if obstacle.is_planned() { r##"fill="#888888" stroke="black""## } else { r##"fill="none" stroke="#2e7d32" stroke-dasharray="6 4""## };
+        match obstacle.footprint_polygon() {
+            Some(vertices) => writeln!(svg, r#"  <polygon points="{}" {} stroke-width="1.5"/>"#, polygon_points(&vertices), style)?,
+            None => {
+                let (x, y) = to_svg(obstacle.config.position.x, obstacle.config.position.y);
+                writeln!(svg, r#"  <circle cx="{:.1}" cy="{:.1}" r="{:.1}" {} stroke-width="1.5"/>"#,
+                    x, y, obstacle.get_radius() * PIXELS_PER_METER, style)?;
+            }
+        }
+    }
+    
+    let start = robot.map(|robot| robot.path_points.first().map_or(robot.config.position, |point| point.position));
+    
+    if let Some(robot) = robot.filter(|robot| robot.path_points.len() >= 2) {
+        let points = if robot.path_points.len() >= 4 {
+            (0..=SPLINE_SAMPLES)
+                .map(|i| robot.catmull_rom_spline(i as f32 / SPLINE_SAMPLES as f32))
+                .map(|(x, y)| [x, y])
+                .collect::<Vec<_>>()
+        } else {
+            robot.path_points.iter().map(|point| [point.position.x, point.position.y]).collect()
+        };
+        writeln!(svg, r##"  <polyline points="{}" fill="none" stroke="#1565c0" stroke-width="3" stroke-linejoin="round"/>"##, polygon_points(&points))?;
+    }
+    
+    if let Some(start) = start {
+        write_marker(&mut svg, start, "#2e7d32", "Start")?;
+    }
+    write_marker(&mut svg, target, "#c62828", "Goal")?;
+    
+    writeln!(svg, "</svg>")?;
+    Ok(svg)
+}
+
+/// writes the top down plan as an svg file
+pub fn export_svg(robot: Option<&Robot>, obstacles: &[Obstacle], target: Position, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let svg = plan_svg(robot, obstacles, target)?;
+    
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, svg)?;
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use crate::position::Position;
 use crate::wire::Wire;
-use nannou::prelude::*;
+use crate::config;
 
 pub struct TargetPosition {
     pub position: Position,
@@ -15,17 +15,35 @@ pub const TARGET_INITIAL_POSITION: Position = Position {
 };
 
 pub const TARGET_HEIGHT: f32 = 1.0;
-pub const TARGET_COLOR_R: u8 = 255;
-pub const TARGET_COLOR_G: u8 = 0;
-pub const TARGET_COLOR_B: u8 = 0;
 pub const TARGET_MARKER_SIZE: f32 = 0.5;
 
+// colors given to new named targets in turn
+pub const TARGET_PALETTE: [[u8; 3]; 6] = [[255, 120, 0], [0, 200, 255], [255, 60, 160], [120, 255, 80], [255, 220, 0], [170, 120, 255]];
+
+/// a saved place the target can be sent to, like a scoring position or a pickup spot
+pub struct NamedTarget {
+    pub name: String,
+    pub marker: TargetPosition,
+}
+
+impl NamedTarget {
+    pub fn new(name: &str, position: Position, color: [u8; 3]) -> Self {
+        let mut marker = TargetPosition::new(position);
+        marker.color = nannou::color::rgb(color[0], color[1], color[2]);
+        NamedTarget { name: name.to_string(), marker }
+    }
+    
+    pub fn color(&self) -> [u8; 3] {
+        [self.marker.color.red, self.marker.color.green, self.marker.color.blue]
+    }
+}
+
 impl TargetPosition {
     pub fn new(position: Position) -> Self {
         TargetPosition {
             position,
             height: TARGET_HEIGHT,
-            color: nannou::color::rgb(TARGET_COLOR_R, TARGET_COLOR_G, TARGET_COLOR_B),
+            color: config::color(config::colors().target),
         }
     }
     
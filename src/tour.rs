@@ -0,0 +1,96 @@
+// visiting order for several targets, like collecting every game piece in autonomous. the order starts from
+// the nearest neighbor tour and is improved with 2-opt, reversing a stretch of the tour wherever that makes it
+// shorter, over the lengths of the paths planned between every pair of stops
+const MAX_PASSES: usize = 100;
+
+/// cost of a tour through the stops in order, starting at stop 0 and ending at the last one
+pub fn tour_cost(costs: &[Vec<f32>], order: &[usize]) -> f32 {
+    std::iter::once(0).chain(order.iter().copied()).collect::<Vec<_>>()
+        .windows(2)
+        .map(|leg| costs[leg[0]][leg[1]])
+        .sum()
+}
+
+/// a short order to visit stops 1.. in, starting at stop 0 without coming back. costs holds the cost of
+/// going between every pair of stops, infinite where there is no path
+pub fn order(costs: &[Vec<f32>]) -> Vec<usize> {
+    // the tour is open, so the stretch can run to the end and the last stop changes with it
+    let mut tour: Vec<usize> = std::iter::once(0).chain(nearest_neighbor(costs)).collect();
+    for _ in 0..MAX_PASSES {
+        let mut improved = false;
+        for i in 1..tour.len() {
+            for j in i + 1..tour.len() {
+                let before = costs[tour[i - 1]][tour[i]] + tour.get(j + 1).map_or(0.0, |&next| costs[tour[j]][next]);
+                let after = costs[tour[i - 1]][tour[j]] + tour.get(j + 1).map_or(0.0, |&next| costs[tour[i]][next]);
+                if after < before - 1e-4 {
+                    tour[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    tour.split_off(1)
+}
+
+/// stops 1.. in the order of always going on to the nearest one not visited yet, starting at stop 0
+fn nearest_neighbor(costs: &[Vec<f32>]) -> Vec<usize> {
+    let stops = costs.len();
+    let mut order = Vec::with_capacity(stops.saturating_sub(1));
+    let mut unvisited: Vec<usize> = (1..stops).collect();
+    let mut at = 0;
+    while !unvisited.is_empty() {
+        let nearest = (0..unvisited.len())
+            .min_by(|&a, &b| costs[at][unvisited[a]].total_cmp(&costs[at][unvisited[b]]))
+            .unwrap();
+        at = unvisited.swap_remove(nearest);
+        order.push(at);
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// straight line costs between points scattered over a field by a fixed linear congruential sequence
+    fn scattered(stops: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 40) as f32 / (1u64 << 24) as f32 * 16.0
+        };
+        let points: Vec<(f32, f32)> = (0..stops).map(|_| (next(), next() / 2.0)).collect();
+        points.iter()
+            .map(|a| points.iter().map(|b| (a.0 - b.0).hypot(a.1 - b.1)).collect())
+            .collect()
+    }
+    
+    #[test]
+    fn the_order_visits_every_stop_after_the_start_once() {
+        for seed in 0..20 {
+            let costs = scattered(9, seed);
+            let mut order = order(&costs);
+            order.sort_unstable();
+            assert_eq!(order, (1..9).collect::<Vec<_>>(), "seed {}", seed);
+        }
+    }
+    
+    #[test]
+    fn two_opt_never_lengthens_the_nearest_neighbor_tour() {
+        for seed in 0..20 {
+            let costs = scattered(9, seed);
+            let nearest = tour_cost(&costs, &nearest_neighbor(&costs));
+            let improved = tour_cost(&costs, &order(&costs));
+            assert!(improved <= nearest + 1e-4, "seed {}: {} after 2-opt, {} before", seed, improved, nearest);
+        }
+    }
+    
+    #[test]
+    fn a_single_stop_and_none_are_ordered() {
+        assert_eq!(order(&scattered(2, 1)), vec![1]);
+        assert!(order(&scattered(1, 1)).is_empty());
+    }
+}
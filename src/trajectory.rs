@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+const VELOCITY_STEP: f32 = 0.05; // seconds between the positions a velocity is measured from
+
+/// where a moving obstacle is at a time
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct TrajectoryWaypoint {
+    pub time: f32, // seconds from the start of the trajectory
+    pub x: f32,
+    pub y: f32,
+}
+
+/// scripted motion of an obstacle through timed waypoints, moving in straight lines between them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Trajectory {
+    pub waypoints: Vec<TrajectoryWaypoint>,
+    #[serde(default)]
+    pub looping: bool, // starts over after the last waypoint instead of stopping there
+}
+
+impl Trajectory {
+    /// seconds from the first waypoint to the last
+    pub fn duration(&self) -> f32 {
+        match (self.waypoints.first(), self.waypoints.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+    
+    /// position at a time since the trajectory started, None without waypoints
+    pub fn position_at(&self, time: f32) -> Option<[f32; 2]> {
+        let first = self.waypoints.first()?;
+        let duration = self.duration();
+        let time = if self.looping && duration > 0.0 {
+            first.time + (time - first.time).rem_euclid(duration)
+        } else {
+            time
+        };
+        
+        let Some(next) = self.waypoints.iter().position(|waypoint| waypoint.time > time) else {
+            let last = self.waypoints.last()?;
+            return Some([last.x, last.y]);
+        };
+        if next == 0 {
+            return Some([first.x, first.y]);
+        }
+        
+        let (from, to) = (self.waypoints[next - 1], self.waypoints[next]);
+        let t = (time - from.time) / (to.time - from.time);
+        Some([from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t])
+    }
+    
+    /// velocity in meters per second at a time, zero without waypoints
+    pub fn velocity_at(&self, time: f32) -> [f32; 2] {
+        match (self.position_at(time), self.position_at(time + VELOCITY_STEP)) {
+            (Some(from), Some(to)) => [(to[0] - from[0]) / VELOCITY_STEP, (to[1] - from[1]) / VELOCITY_STEP],
+            _ => [0.0, 0.0],
+        }
+    }
+    
+    /// keeps the waypoints in time order after one was edited
+    pub fn sort(&mut self) {
+        self.waypoints.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+}
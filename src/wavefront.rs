@@ -0,0 +1,256 @@
+// wavefront (brushfire) planner, a breadth first wave from the goal over the free cells of the costmap numbers
+// every cell with how many steps it is from the goal, and the path counts the numbers down from the start.
+// it finds a path whenever one exists on the grid, so it is the simple complete alternative to the fields
+use std::collections::VecDeque;
+
+use crate::color::Rgb;
+
+use crate::costmap::{CellCost, Costmap};
+use crate::dubins::{PathSample, segment_samples};
+use crate::position::Position;
+
+const UNREACHED: u32 = u32::MAX;
+
+/// wave number of every cell for one goal, with how far the expansion is drawn
+pub struct Wavefront {
+    resolution: f32,
+    columns: usize,
+    rows: usize,
+    waves: Vec<u32>, // steps from the goal, UNREACHED for lethal cells and cells walled off from it
+    goal_cell: Option<usize>,
+    pub last_wave: u32,
+    revealed: f32, // waves drawn so far
+}
+
+impl Wavefront {
+    /// expands the wave from the goal over the cells of the costmap the robot center may enter, 8 connected
+    /// without cutting the corners of lethal cells
+    pub fn expand(costmap: &Costmap, goal: [f32; 2]) -> Self {
+        let mut wavefront = Wavefront {
+            resolution: costmap.resolution,
+            columns: costmap.columns,
+            rows: costmap.rows,
+            waves: vec![UNREACHED; costmap.cells.len()],
+            goal_cell: None,
+            last_wave: 0,
+            revealed: 0.0,
+        };
+        let blocked = |cell: usize| costmap.cells[cell] == CellCost::Lethal;
+        let Some(goal_cell) = wavefront.cell_of(goal).filter(|&cell| !blocked(cell)) else {
+            return wavefront;
+        };
+        wavefront.goal_cell = Some(goal_cell);
+        wavefront.waves[goal_cell] = 0;
+        
+        let mut queue = VecDeque::from([goal_cell]);
+        while let Some(cell) = queue.pop_front() {
+            let wave = wavefront.waves[cell];
+            wavefront.last_wave = wave;
+            for next in wavefront.neighbors(cell, &blocked) {
+                if wavefront.waves[next] == UNREACHED {
+                    wavefront.waves[next] = wave + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        wavefront
+    }
+    
+    fn cell_of(&self, point: [f32; 2]) -> Option<usize> {
+        if point[0] < 0.0 || point[1] < 0.0 {
+            return None;
+        }
+        let column = (point[0] / self.resolution) as usize;
+        let row = (point[1] / self.resolution) as usize;
+        (column < self.columns && row < self.rows).then(|| row * self.columns + column)
+    }
+    
+    fn center_of(&self, cell: usize) -> [f32; 2] {
+        let (row, column) = (cell / self.columns, cell % self.columns);
+        [(column as f32 + 0.5) * self.resolution, (row as f32 + 0.5) * self.resolution]
+    }
+    
+    /// the unblocked cells around a cell, diagonals only where neither cell beside the step is blocked
+    fn neighbors(&self, cell: usize, blocked: &dyn Fn(usize) -> bool) -> Vec<usize> {
+        let (row, column) = ((cell / self.columns) as i64, (cell % self.columns) as i64);
+        let at = |column: i64, row: i64| (column >= 0 && row >= 0 && (column as usize) < self.columns && (row as usize) < self.rows)
+            .then(|| row as usize * self.columns + column as usize);
+        
+        let mut neighbors = Vec::with_capacity(8);
+        for (d_column, d_row) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let Some(next) = at(column + d_column, row + d_row) else { continue; };
+            if blocked(next) {
+                continue;
+            }
+            if d_column != 0 && d_row != 0 {
+                let beside = [at(column + d_column, row), at(column, row + d_row)];
+                if beside.iter().any(|side| side.is_none_or(blocked)) {
+                    continue;
+                }
+            }
+            neighbors.push(next);
+        }
+        neighbors
+    }
+    
+    /// counts the waves down from start to the goal, returns the points of the path after the start and the
+    /// heading the robot arrives with. None when the wave never reached the start
+    pub fn query(&self, start: [f32; 2], goal: [f32; 2]) -> Option<(Vec<PathSample>, f32)> {
+        let corners = self.descend(start, goal)?;
+        
+        let mut samples = Vec::new();
+        let mut from = start;
+        for &corner in &corners {
+            samples.extend(segment_samples(from, corner).into_iter()
+                .map(|[x, y]| PathSample { position: Position::new(x, y, 0.0), reversed: false }));
+            from = corner;
+        }
+        
+        let before_goal = if corners.len() > 1 { corners[corners.len() - 2] } else { start };
+        let heading = (goal[1] - before_goal[1]).atan2(goal[0] - before_goal[0]);
+        Some((samples, heading))
+    }
+    
+    /// steps to a neighbor one wave lower until the goal, of the lower neighbors the one heading most directly
+    /// at the goal so the path does not zigzag, keeping only the cells the direction changes at
+    fn descend(&self, start: [f32; 2], goal: [f32; 2]) -> Option<Vec<[f32; 2]>> {
+        let goal_cell = self.goal_cell?;
+        let mut cell = self.cell_of(start)?;
+        if self.waves[cell] == UNREACHED {
+            return None;
+        }
+        
+        let unreached = |cell: usize| self.waves[cell] == UNREACHED;
+        let mut cells = vec![cell];
+        while cell != goal_cell {
+            let (here, wave) = (self.center_of(cell), self.waves[cell]);
+            let toward = [goal[0] - here[0], goal[1] - here[1]];
+            cell = self.neighbors(cell, &unreached).into_iter()
+                .filter(|&next| self.waves[next] + 1 == wave)
+                .max_by(|&a, &b| {
+                    let alignment = |next: usize| {
+                        let [x, y] = self.center_of(next);
+                        let step = [x - here[0], y - here[1]];
+                        (step[0] * toward[0] + step[1] * toward[1]) / (step[0].hypot(step[1]) + f32::EPSILON)
+                    };
+                    alignment(a).total_cmp(&alignment(b))
+                })?;
+            cells.push(cell);
+        }
+        
+        let mut points: Vec<[f32; 2]> = cells.iter().map(|&cell| self.center_of(cell)).collect();
+        points[0] = start;
+        if points.len() == 1 {
+            return Some(vec![goal]);
+        }
+        *points.last_mut().unwrap() = goal;
+        
+        let mut corners = Vec::new();
+        for i in 1..points.len() {
+            let straight = points.get(i + 1).is_some_and(|next| {
+                let (a, b) = ([points[i][0] - points[i - 1][0], points[i][1] - points[i - 1][1]], [next[0] - points[i][0], next[1] - points[i][1]]);
+                (a[0] * b[1] - a[1] * b[0]).abs() < 1e-6 && a[0] * b[0] + a[1] * b[1] > 0.0
+            });
+            if !straight {
+                corners.push(points[i]);
+            }
+        }
+        Some(corners)
+    }
+    
+    /// draws this many more waves of the expansion
+    pub fn grow(&mut self, waves: f32) {
+        self.revealed = (self.revealed + waves).min(self.last_wave as f32 + 1.0);
+    }
+    
+    /// hides every wave so the expansion is shown again from the goal
+    pub fn restart(&mut self) {
+        self.revealed = 0.0;
+    }
+    
+    /// ground quads of the cells the wave has reached so far, fading with their distance from the goal, with
+    /// the cells of the newest wave in the front color
+    pub fn cells(&self, color: Rgb<u8>, front: Rgb<u8>) -> Vec<([Position; 4], Rgb<u8>)> {
+        let scale = |c: u8, amount: f32| (c as f32 * amount) as u8;
+        let newest = (self.revealed as u32).min(self.last_wave + 1).checked_sub(1);
+        let mut quads = Vec::new();
+        for (cell, &wave) in self.waves.iter().enumerate() {
+            if newest.is_none_or(|newest| wave > newest) {
+                continue;
+            }
+            let quad_color = if Some(wave) == newest && newest != Some(self.last_wave) {
+                front
+            } else {
+                let amount = 1.0 - 0.8 * wave as f32 / self.last_wave.max(1) as f32;
+                crate::color::rgb(scale(color.red, amount), scale(color.green, amount), scale(color.blue, amount))
+            };
+            
+            let [x, y] = self.center_of(cell);
+            let half = self.resolution / 2.0;
+            let corners = [
+                Position::new(x - half, y - half, 0.0),
+                Position::new(x + half, y - half, 0.0),
+                Position::new(x + half, y + half, 0.0),
+                Position::new(x - half, y + half, 0.0),
+            ];
+            quads.push((corners, quad_color));
+        }
+        quads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const START: [f32; 2] = [0.25, 1.05];
+    const GOAL: [f32; 2] = [2.75, 1.05];
+    
+    /// a free 3 by 2 meter grid with a wall across most of it, so the path has to bend
+    fn walled_costmap() -> Costmap {
+        let (columns, rows) = (30, 20);
+        let mut cells = vec![CellCost::Free; columns * rows];
+        for row in 4..rows {
+            cells[row * columns + 12] = CellCost::Lethal;
+        }
+        Costmap { resolution: 0.1, columns, rows, cells }
+    }
+    
+    #[test]
+    fn counting_the_waves_down_reaches_the_goal_around_the_wall() {
+        let costmap = walled_costmap();
+        let wavefront = Wavefront::expand(&costmap, GOAL);
+        let (samples, _) = wavefront.query(START, GOAL).unwrap();
+        
+        let end = samples.last().unwrap().position;
+        assert!((end.x - GOAL[0]).abs() < 1e-4 && (end.y - GOAL[1]).abs() < 1e-4);
+        for sample in &samples {
+            let cost = costmap.cost_at([sample.position.x, sample.position.y]).unwrap();
+            assert_ne!(cost, CellCost::Lethal, "sample at ({}, {})", sample.position.x, sample.position.y);
+        }
+        
+        // every step goes one wave closer to the goal
+        let start = wavefront.cell_of(START).unwrap();
+        let goal = wavefront.cell_of(GOAL).unwrap();
+        assert_eq!(wavefront.waves[goal], 0);
+        assert!(wavefront.waves[start] != UNREACHED && wavefront.waves[start] > 0);
+    }
+    
+    #[test]
+    fn an_enclosed_goal_is_unreachable() {
+        let mut costmap = walled_costmap();
+        let goal = costmap.cell_of(GOAL).unwrap();
+        let (row, column) = (goal / costmap.columns, goal % costmap.columns);
+        for d_row in 0..5 {
+            for d_column in 0..5 {
+                if d_row == 0 || d_row == 4 || d_column == 0 || d_column == 4 {
+                    costmap.cells[(row + d_row - 2) * costmap.columns + column + d_column - 2] = CellCost::Lethal;
+                }
+            }
+        }
+        
+        let wavefront = Wavefront::expand(&costmap, GOAL);
+        assert_eq!(wavefront.waves[wavefront.cell_of(START).unwrap()], UNREACHED);
+        assert!(wavefront.query(START, GOAL).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use nannou::color::{Rgb, WHITE};
+use crate::color::{Rgb, WHITE};
 use crate::position::Position;
 
 #[derive(Debug, Copy, Clone)]
@@ -27,4 +27,63 @@ impl Wire {
     pub fn with_color(start: Position, end: Position, color: Rgb<u8>) -> Wire {
         Wire { start, end, color }
     }
-}
\ No newline at end of file
+    
+    /// flat circle on the ground plane made of straight segments
+    pub fn circle(center: Position, radius: f32, segments: usize, color: Rgb<u8>) -> Vec<Wire> {
+        let point = |i: usize| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            Position::new(center.x + radius * angle.cos(), center.y + radius * angle.sin(), center.z)
+        };
+        
+        (0..segments)
+            .map(|i| Wire::with_color(point(i), point(i + 1), color))
+            .collect()
+    }
+    
+    /// dashed line around a closed loop of points, dashes and gaps both this long
+    pub fn dashed_loop(points: &[Position], dash: f32, color: Rgb<u8>) -> Vec<Wire> {
+        let mut wires = Vec::new();
+        let mut drawing = true;
+        let mut left = dash; // of the current dash or gap
+        for i in 0..points.len() {
+            let (mut from, to) = (points[i], points[(i + 1) % points.len()]);
+            let mut length = from.distance_to(&to);
+            while length > 0.0 {
+                let step = left.min(length);
+                let t = step / length;
+                let next = Position::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t, from.z + (to.z - from.z) * t);
+                if drawing {
+                    wires.push(Wire::with_color(from, next, color));
+                }
+                from = next;
+                length -= step;
+                left -= step;
+                if left <= 0.0 {
+                    drawing = !drawing;
+                    left = dash;
+                }
+            }
+        }
+        wires
+    }
+    
+    /// the 12 edges of an axis aligned box between two corners
+    pub fn cuboid(min: Position, max: Position, color: Rgb<u8>) -> Vec<Wire> {
+        let corner = |i: usize| Position::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        );
+        
+        // corners that differ in exactly one axis are joined by an edge
+        let mut wires = Vec::with_capacity(12);
+        for i in 0..8 {
+            for axis in [1, 2, 4] {
+                if i & axis == 0 {
+                    wires.push(Wire::with_color(corner(i), corner(i | axis), color));
+                }
+            }
+        }
+        wires
+    }
+}
@@ -0,0 +1,274 @@
+use std::ops::Deref;
+
+use crate::coordination::TeamRobot;
+use crate::events::{EventBus, WorldEvent};
+use crate::model::Model;
+use crate::obstacle::Obstacle;
+use crate::position::Position;
+use crate::robot::{Robot, ViaPoint};
+use crate::speed_limit::SpeedZone;
+use crate::target_position::{NamedTarget, TargetPosition};
+use crate::tour;
+
+/// the obstacles of the world, read as a slice and edited through the world's methods
+pub struct Obstacles(Vec<Obstacle>);
+
+impl Deref for Obstacles {
+    type Target = [Obstacle];
+    
+    fn deref(&self) -> &[Obstacle] {
+        &self.0
+    }
+}
+
+impl<'a> IntoIterator for &'a Obstacles {
+    type Item = &'a Obstacle;
+    type IntoIter = std::slice::Iter<'a, Obstacle>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// everything placed on the field, edits made through its methods publish events
+/// so the gradient overlay, planner and ui are refreshed once afterwards
+pub struct World {
+    pub obstacles: Obstacles,
+    pub models: Vec<Model>,
+    pub robot: Option<Robot>,
+    pub team: Vec<TeamRobot>, // more robots, planned after the main one
+    target: TargetPosition,
+    speed_zones: Vec<SpeedZone>, // copied into every robot when they change
+    pub targets: Vec<NamedTarget>, // places the target can be sent to, the first nine by the number keys
+    events: EventBus,
+}
+
+impl World {
+    pub fn new(robot: Option<Robot>, target: TargetPosition) -> Self {
+        let mut events = EventBus::default();
+        // nothing is planned yet
+        events.publish(WorldEvent::ObstaclesCleared);
+        
+        Self {
+            obstacles: Obstacles(Vec::new()),
+            models: Vec::new(),
+            robot,
+            team: Vec::new(),
+            target,
+            speed_zones: Vec::new(),
+            targets: Vec::new(),
+            events,
+        }
+    }
+    
+    /// adds an obstacle, returning its index
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) -> usize {
+        self.obstacles.0.push(obstacle);
+        let index = self.obstacles.len() - 1;
+        self.events.publish(WorldEvent::ObstacleAdded);
+        index
+    }
+    
+    pub fn remove_obstacle(&mut self, index: usize) -> Option<Obstacle> {
+        if index >= self.obstacles.len() {
+            return None;
+        }
+        self.events.publish(WorldEvent::ObstacleRemoved(index));
+        Some(self.obstacles.0.remove(index))
+    }
+    
+    pub fn clear_obstacles(&mut self) {
+        self.obstacles.0.clear();
+        self.events.publish(WorldEvent::ObstaclesCleared);
+    }
+    
+    pub fn move_obstacle(&mut self, index: usize, position: Position) {
+        if let Some(obstacle) = self.obstacles.0.get_mut(index) {
+            obstacle.model.move_to(position);
+            self.events.publish(WorldEvent::ObstacleMoved);
+        }
+    }
+    
+    /// edits an obstacle in place, such as its radius, layer or footprint
+    pub fn edit_obstacle<R>(&mut self, index: usize, edit: impl FnOnce(&mut Obstacle) -> R) -> Option<R> {
+        let obstacle = self.obstacles.0.get_mut(index)?;
+        let result = edit(obstacle);
+        self.events.publish(WorldEvent::ObstaclesEdited);
+        Some(result)
+    }
+    
+    /// edits any number of obstacles in place at once
+    pub fn edit_obstacles<R>(&mut self, edit: impl FnOnce(&mut [Obstacle]) -> R) -> R {
+        let result = edit(&mut self.obstacles.0);
+        self.events.publish(WorldEvent::ObstaclesEdited);
+        result
+    }
+    
+    /// recomputes which obstacles the robot can pass under or over, publishing an edit only if that changed
+    pub fn update_passable(&mut self, robot_height: f32, step_height: f32) {
+        let mut changed = false;
+        for obstacle in &mut self.obstacles.0 {
+            let passable = obstacle.passable;
+            obstacle.update_passable(robot_height, step_height);
+            changed |= obstacle.passable != passable;
+        }
+        
+        if changed {
+            self.events.publish(WorldEvent::ObstaclesEdited);
+        }
+    }
+    
+    /// moves the obstacles with a trajectory to where they are at a time. nothing is published, moving
+    /// obstacles are planned around where they will be
+    pub fn follow_trajectories(&mut self, time: f32) {
+        for obstacle in &mut self.obstacles.0 {
+            obstacle.follow_trajectory(time);
+        }
+    }
+    
+    /// marks the hard obstacles a robot footprint touches and fades their flashes by a time step, flashing
+    /// the ones it just started touching. returns how many those are
+    pub fn update_contacts(&mut self, position: Position, footprint_radius: f32, dt: f32, flash_time: f32) -> usize {
+        let mut new_contacts = 0;
+        for obstacle in &mut self.obstacles.0 {
+            obstacle.collision_flash = (obstacle.collision_flash - dt).max(0.0);
+            
+            let colliding = obstacle.is_hard() && obstacle.edge_distance(position) < footprint_radius;
+            if colliding && !obstacle.colliding {
+                new_contacts += 1;
+                obstacle.collision_flash = flash_time;
+            }
+            obstacle.colliding = colliding;
+        }
+        new_contacts
+    }
+    
+    /// adds a decorative model, returning its index
+    pub fn add_model(&mut self, model: Model) -> usize {
+        self.models.push(model);
+        self.models.len() - 1
+    }
+    
+    pub fn remove_model(&mut self, index: usize) -> Option<Model> {
+        (index < self.models.len()).then(|| self.models.remove(index))
+    }
+    
+    pub fn clear_models(&mut self) {
+        self.models.clear();
+    }
+    
+    /// adds a team robot, which keeps to the speed zones like the main robot
+    pub fn add_team_robot(&mut self, mut member: TeamRobot) {
+        member.robot.set_speed_zones(&self.speed_zones);
+        self.team.push(member);
+        self.events.publish(WorldEvent::PathInvalidated);
+    }
+    
+    pub fn speed_zones(&self) -> &[SpeedZone] {
+        &self.speed_zones
+    }
+    
+    pub fn add_speed_zone(&mut self, zone: SpeedZone) {
+        self.speed_zones.push(zone);
+        self.speed_zones_changed();
+    }
+    
+    pub fn set_zone_speed(&mut self, index: usize, max_speed: f32) {
+        if let Some(zone) = self.speed_zones.get_mut(index) {
+            zone.max_speed = max_speed;
+            self.speed_zones_changed();
+        }
+    }
+    
+    pub fn remove_speed_zone(&mut self, index: usize) -> Option<SpeedZone> {
+        if index >= self.speed_zones.len() {
+            return None;
+        }
+        let zone = self.speed_zones.remove(index);
+        self.speed_zones_changed();
+        Some(zone)
+    }
+    
+    /// replaces all speed zones, such as from a scene
+    pub fn set_speed_zones(&mut self, zones: Vec<SpeedZone>) {
+        self.speed_zones = zones;
+        self.speed_zones_changed();
+    }
+    
+    fn speed_zones_changed(&mut self) {
+        let robots = self.robot.iter_mut().chain(self.team.iter_mut().map(|member| &mut member.robot));
+        for robot in robots {
+            robot.set_speed_zones(&self.speed_zones);
+        }
+        self.events.publish(WorldEvent::SpeedZonesChanged);
+    }
+    
+    pub fn target(&self) -> Position {
+        self.target.get_position()
+    }
+    
+    pub fn target_marker(&self) -> &TargetPosition {
+        &self.target
+    }
+    
+    pub fn set_target_color(&mut self, color: nannou::color::Rgb<u8>) {
+        self.target.color = color;
+    }
+    
+    pub fn set_target(&mut self, position: Position) {
+        self.target.set_position(position);
+        self.events.publish(WorldEvent::TargetChanged);
+    }
+    
+    /// moves the target to a named target, which plans again from where the robot is now
+    pub fn go_to_target(&mut self, index: usize) {
+        if let Some(position) = self.targets.get(index).map(|target| target.marker.position) {
+            self.set_target(position);
+        }
+    }
+    
+    /// orders the named targets into a short tour from the robot by the grid path lengths between them, and
+    /// plans it with the targets before the last as via points. returns the order and the tour's length, None
+    /// without a robot or targets or when one of them can not be reached
+    pub fn plan_tour(&mut self, tolerance: f32) -> Option<(Vec<usize>, f32)> {
+        let robot = self.robot.as_mut()?;
+        if self.targets.is_empty() {
+            return None;
+        }
+        robot.update_costmap(&self.obstacles);
+        let stops: Vec<Position> = std::iter::once(robot.model.config.position)
+            .chain(self.targets.iter().map(|target| target.marker.position))
+            .collect();
+        let mut costs = vec![vec![0.0; stops.len()]; stops.len()];
+        for i in 0..stops.len() {
+            for j in i + 1..stops.len() {
+                let cost = robot.path_cost(stops[i], stops[j]).unwrap_or(f32::INFINITY);
+                costs[i][j] = cost;
+                costs[j][i] = cost;
+            }
+        }
+        
+        let order = tour::order(&costs);
+        let length = tour::tour_cost(&costs, &order);
+        if !length.is_finite() {
+            return None;
+        }
+        robot.via_points = order[..order.len() - 1].iter()
+            .map(|&stop| ViaPoint::new(stops[stop], tolerance))
+            .collect();
+        let last = stops[*order.last().unwrap()];
+        let order = order.into_iter().map(|stop| stop - 1).collect();
+        self.set_target(last);
+        Some((order, length))
+    }
+    
+    /// asks for the path to be planned again, for changes the world does not see such as via points
+    pub fn invalidate_path(&mut self) {
+        self.events.publish(WorldEvent::PathInvalidated);
+    }
+    
+    /// the events published since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<WorldEvent> {
+        self.events.drain()
+    }
+}